@@ -1,19 +1,72 @@
-use crate::parser::{MessageType, Session};
+use crate::cost;
+use crate::exclusions;
+use crate::metrics;
+use crate::parser::{Message, MessageType, Session};
 use chrono::{DateTime, Utc};
 use colored::Colorize;
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
 
-/// A detected bottleneck in a session
-#[derive(Debug, Clone)]
+/// A detected bottleneck in a session. Serializes flat, with the variant
+/// name in a `type` field.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum Bottleneck {
     ErrorLoop(ErrorLoop),
     ExplorationSpiral(ExplorationSpiral),
     EditThrashing(EditThrashing),
     LongGap(LongGap),
+    FlakyTool(FlakyTool),
+    TestFailureLoop(TestFailureLoop),
+    ApprovalStall(ApprovalStall),
+    ContextSwitch(ContextSwitch),
+}
+
+/// Thresholds a session must meet to count as a focus session
+#[derive(Debug, Clone, Copy)]
+pub struct FocusCriteria {
+    pub min_minutes: f64,
+    pub min_efficiency: f64,
+}
+
+impl Default for FocusCriteria {
+    fn default() -> Self {
+        Self {
+            min_minutes: 30.0,
+            min_efficiency: 80.0,
+        }
+    }
+}
+
+/// Thresholds at which a pattern counts as a bottleneck
+#[derive(Debug, Clone, Copy)]
+pub struct BottleneckConfig {
+    /// Consecutive failures of one tool that make an error loop
+    pub error_loop_min_failures: usize,
+    /// Read/Grep/Glob calls without an edit that make an exploration spiral
+    pub exploration_min_calls: usize,
+    /// Minutes those calls must span
+    pub exploration_min_minutes: f64,
+    /// Edits to one file that count as thrashing
+    pub edit_thrash_min_edits: usize,
+    /// Gaps shorter than this are never flagged, however slow the session is
+    pub gap_floor_minutes: f64,
+}
+
+impl Default for BottleneckConfig {
+    fn default() -> Self {
+        Self {
+            error_loop_min_failures: 3,
+            exploration_min_calls: 10,
+            exploration_min_minutes: 10.0,
+            edit_thrash_min_edits: 5,
+            gap_floor_minutes: GAP_FLOOR_MINUTES,
+        }
+    }
 }
 
 /// Same tool fails 3+ times consecutively
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 #[allow(dead_code)] // Fields will be used in report generation
 pub struct ErrorLoop {
     pub session_id: String,
@@ -24,14 +77,22 @@ pub struct ErrorLoop {
     pub end_time: Option<DateTime<Utc>>,
     pub duration_minutes: f64,
     pub error_samples: Vec<String>,
+    /// Total size of the loop's error outputs
+    pub error_bytes: usize,
+    /// Times error output was sent back to the model as input during the loop
+    pub refeed_count: usize,
+    /// Estimated cost of those re-fed error tokens
+    pub refeed_cost: f64,
 }
 
 /// >10 Read/Grep calls with 0 Edit in 10+ minutes
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 #[allow(dead_code)] // Fields will be used in report generation
 pub struct ExplorationSpiral {
     pub session_id: String,
     pub project: String,
+    /// Full working directory of the session, for checking project files
+    pub project_path: String,
     pub read_count: usize,
     pub grep_count: usize,
     pub duration_minutes: f64,
@@ -40,17 +101,19 @@ pub struct ExplorationSpiral {
 }
 
 /// Same file edited 5+ times in a session
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct EditThrashing {
     pub session_id: String,
     pub project: String,
     pub file_path: String,
     pub edit_count: usize,
     pub duration_minutes: f64,
+    /// Time of the first edit
+    pub start_time: Option<DateTime<Utc>>,
 }
 
 /// >5 minutes between consecutive messages
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 #[allow(dead_code)] // Fields will be used in report generation
 pub struct LongGap {
     pub session_id: String,
@@ -60,6 +123,64 @@ pub struct LongGap {
     pub after_timestamp: Option<DateTime<Utc>>,
 }
 
+/// A tool whose results flip between failure and success 4+ times
+#[derive(Debug, Clone, Serialize)]
+pub struct FlakyTool {
+    pub session_id: String,
+    pub project: String,
+    pub tool_name: String,
+    /// Times consecutive results of this tool switched between error and success
+    pub flips: usize,
+    pub failure_count: usize,
+    pub result_count: usize,
+    /// Time from each failure to the result after it
+    pub duration_minutes: f64,
+    /// Time of the first failure
+    pub start_time: Option<DateTime<Utc>>,
+}
+
+/// A tool call left waiting on a permission prompt until it was approved or
+/// rejected
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalStall {
+    pub session_id: String,
+    pub project: String,
+    pub tool_name: String,
+    pub wait_minutes: f64,
+    pub requested_at: Option<DateTime<Utc>>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Back-to-back sessions hopping between 3+ projects with short breaks
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextSwitch {
+    /// The first session of the run
+    pub session_id: String,
+    pub project: String,
+    /// Distinct projects in the order they were first visited
+    pub projects: Vec<String>,
+    pub switch_count: usize,
+    pub session_count: usize,
+    pub start_time: Option<DateTime<Utc>>,
+    /// Breaks between the sessions, spent re-orienting. This falls outside
+    /// any session, so it isn't part of the time breakdown.
+    pub break_minutes: f64,
+}
+
+/// A test suite run via Bash fails 3+ times in a row, usually with edits in
+/// between: the fix-and-rerun cycle isn't converging
+#[derive(Debug, Clone, Serialize)]
+pub struct TestFailureLoop {
+    pub session_id: String,
+    pub project: String,
+    /// The first failing test command in the loop
+    pub command: String,
+    pub failure_count: usize,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub duration_minutes: f64,
+}
+
 #[allow(dead_code)] // Methods will be used in report generation
 impl Bottleneck {
     pub fn wasted_minutes(&self) -> f64 {
@@ -68,6 +189,10 @@ impl Bottleneck {
             Bottleneck::ExplorationSpiral(e) => e.duration_minutes,
             Bottleneck::EditThrashing(e) => e.duration_minutes,
             Bottleneck::LongGap(g) => g.gap_minutes,
+            Bottleneck::FlakyTool(f) => f.duration_minutes,
+            Bottleneck::TestFailureLoop(t) => t.duration_minutes,
+            Bottleneck::ApprovalStall(a) => a.wait_minutes,
+            Bottleneck::ContextSwitch(c) => c.break_minutes,
         }
     }
 
@@ -77,6 +202,10 @@ impl Bottleneck {
             Bottleneck::ExplorationSpiral(e) => &e.session_id,
             Bottleneck::EditThrashing(e) => &e.session_id,
             Bottleneck::LongGap(g) => &g.session_id,
+            Bottleneck::FlakyTool(f) => &f.session_id,
+            Bottleneck::TestFailureLoop(t) => &t.session_id,
+            Bottleneck::ApprovalStall(a) => &a.session_id,
+            Bottleneck::ContextSwitch(c) => &c.session_id,
         }
     }
 
@@ -86,112 +215,681 @@ impl Bottleneck {
             Bottleneck::ExplorationSpiral(e) => &e.project,
             Bottleneck::EditThrashing(e) => &e.project,
             Bottleneck::LongGap(g) => &g.project,
+            Bottleneck::FlakyTool(f) => &f.project,
+            Bottleneck::TestFailureLoop(t) => &t.project,
+            Bottleneck::ApprovalStall(a) => &a.project,
+            Bottleneck::ContextSwitch(c) => &c.project,
+        }
+    }
+
+    /// When the bottleneck began, if known
+    pub fn start_time(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Bottleneck::ErrorLoop(e) => e.start_time,
+            Bottleneck::ExplorationSpiral(e) => e.start_time,
+            Bottleneck::EditThrashing(e) => e.start_time,
+            Bottleneck::LongGap(g) => g.before_timestamp,
+            Bottleneck::FlakyTool(f) => f.start_time,
+            Bottleneck::TestFailureLoop(t) => t.start_time,
+            Bottleneck::ApprovalStall(a) => a.requested_at,
+            Bottleneck::ContextSwitch(c) => c.start_time,
         }
     }
 }
 
-/// Detect all bottlenecks in a set of sessions
+/// Detect all bottlenecks in a set of sessions with the default thresholds
 pub fn detect_all(sessions: &[Session]) -> Vec<Bottleneck> {
+    detect_all_with_config(sessions, &BottleneckConfig::default())
+}
+
+/// Detect all bottlenecks in a set of sessions
+pub fn detect_all_with_config(sessions: &[Session], config: &BottleneckConfig) -> Vec<Bottleneck> {
     let mut bottlenecks = Vec::new();
 
     for session in sessions {
-        bottlenecks.extend(detect_error_loops(session));
-        bottlenecks.extend(detect_exploration_spirals(session));
-        bottlenecks.extend(detect_edit_thrashing(session));
-        bottlenecks.extend(detect_long_gaps(session));
+        let test_loops = detect_test_failure_loops(session);
+        // A failing test run is also a failing Bash call. Count that time
+        // once, under the more specific test loop.
+        bottlenecks.extend(
+            detect_error_loops(session, config)
+                .into_iter()
+                .filter(|b| !test_loops.iter().any(|t| covers_bash_loop(t, b))),
+        );
+        bottlenecks.extend(test_loops);
+        bottlenecks.extend(detect_exploration_spirals(session, config));
+        bottlenecks.extend(detect_edit_thrashing(session, config));
+        bottlenecks.extend(detect_flaky_tools(session));
+        // Time spent on a permission prompt is reported as that, not as
+        // part of a long gap as well
+        let stalls = detect_approval_stalls(session);
+        bottlenecks.extend(
+            detect_long_gaps(session, config.gap_floor_minutes, GAP_MEDIAN_MULTIPLIER)
+                .into_iter()
+                .filter_map(|b| without_stalls(b, &stalls, config.gap_floor_minutes)),
+        );
+        bottlenecks.extend(stalls);
     }
+    bottlenecks.extend(detect_cross_session(sessions));
 
     // Sort by wasted time descending
     bottlenecks.sort_by(|a, b| {
         b.wasted_minutes()
             .partial_cmp(&a.wasted_minutes())
             .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.session_id().cmp(b.session_id()))
     });
 
     bottlenecks
 }
 
-/// Detect error loops: same tool fails 3+ times consecutively
-fn detect_error_loops(session: &Session) -> Vec<Bottleneck> {
+/// Percentage of a session's duration not lost to the given bottlenecks
+pub fn session_efficiency(session: &Session, bottlenecks: &[Bottleneck]) -> f64 {
+    if session.start_time.is_none() || session.end_time.is_none() {
+        return 100.0;
+    }
+    let duration = exclusions::active_minutes(session);
+    if duration <= 0.0 {
+        return 100.0;
+    }
+
+    let wasted: f64 = bottlenecks.iter().map(|b| b.wasted_minutes()).sum();
+    ((duration - wasted) / duration * 100.0).clamp(0.0, 100.0)
+}
+
+/// A focus session runs at least `min_minutes`, stays above
+/// `min_efficiency`, and has no long gaps
+pub fn is_focus_session(session: &Session, criteria: &FocusCriteria) -> bool {
+    let duration = match (session.start_time, session.end_time) {
+        (Some(start), Some(end)) => (end - start).num_seconds() as f64 / 60.0,
+        _ => return false,
+    };
+    if duration < criteria.min_minutes {
+        return false;
+    }
+
+    let bottlenecks = detect_all(std::slice::from_ref(session));
+    if bottlenecks
+        .iter()
+        .any(|b| matches!(b, Bottleneck::LongGap(_)))
+    {
+        return false;
+    }
+
+    session_efficiency(session, &bottlenecks) >= criteria.min_efficiency
+}
+
+/// Session duration bins for the length-vs-efficiency breakdown, as
+/// (label, lower bound in minutes)
+const DURATION_BINS: [(&str, f64); 4] = [
+    ("<30m", 0.0),
+    ("30m-1h", 30.0),
+    ("1-2h", 60.0),
+    ("2h+", 120.0),
+];
+
+/// Average efficiency of sessions within one duration range
+#[derive(Debug, Clone)]
+pub struct DurationBin {
+    pub label: &'static str,
+    pub min_minutes: f64,
+    pub session_count: usize,
+    pub avg_efficiency: f64,
+}
+
+/// Bin sessions by duration and average `session_efficiency` per bin.
+/// Empty bins are omitted.
+pub fn efficiency_by_duration(sessions: &[Session]) -> Vec<DurationBin> {
+    let mut totals = [(0usize, 0.0f64); DURATION_BINS.len()];
+
+    for session in sessions {
+        let Some(minutes) = session
+            .start_time
+            .zip(session.end_time)
+            .map(|(start, end)| (end - start).num_seconds() as f64 / 60.0)
+        else {
+            continue;
+        };
+        let bin = DURATION_BINS
+            .iter()
+            .rposition(|(_, min)| minutes >= *min)
+            .unwrap_or(0);
+        let efficiency = session_efficiency(session, &detect_all(std::slice::from_ref(session)));
+        totals[bin].0 += 1;
+        totals[bin].1 += efficiency;
+    }
+
+    DURATION_BINS
+        .iter()
+        .zip(totals)
+        .filter(|(_, (count, _))| *count > 0)
+        .map(|((label, min), (count, sum))| DurationBin {
+            label,
+            min_minutes: *min,
+            session_count: count,
+            avg_efficiency: sum / count as f64,
+        })
+        .collect()
+}
+
+/// "Efficiency drops to X% for sessions over Yh" when the longest bin is
+/// clearly less efficient than the shortest
+pub fn duration_efficiency_summary(bins: &[DurationBin]) -> Option<String> {
+    let (first, last) = (bins.first()?, bins.last()?);
+    if bins.len() < 2 || last.avg_efficiency >= first.avg_efficiency - 5.0 {
+        return None;
+    }
+
+    let over = if last.min_minutes >= 60.0 {
+        format!("{}h", last.min_minutes / 60.0)
+    } else {
+        format!("{}m", last.min_minutes)
+    };
+    Some(format!(
+        "Efficiency drops to {:.0}% for sessions over {} (vs {:.0}% for {})",
+        last.avg_efficiency, over, first.avg_efficiency, first.label
+    ))
+}
+
+/// Error samples kept per loop
+const ERROR_SAMPLE_LIMIT: usize = 3;
+
+/// Error samples are cut to this many characters
+const ERROR_SAMPLE_MAX_CHARS: usize = 80;
+
+/// One tool result, as seen by the error loop detector
+struct ToolOutcome<'a> {
+    is_error: bool,
+    timestamp: Option<DateTime<Utc>>,
+    content: &'a str,
+    /// The Bash command that produced the result, if any
+    command: Option<&'a str>,
+}
+
+/// Short description of a failed result: its first non-empty line,
+/// prefixed with the failing command for Bash
+fn error_sample(content: &str, command: Option<&str>) -> String {
+    let line = content
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or("");
+    let sample = match command {
+        Some(cmd) => format!("{}: {}", cmd.lines().next().unwrap_or(cmd).trim(), line),
+        None => line.to_string(),
+    };
+    if sample.chars().count() > ERROR_SAMPLE_MAX_CHARS {
+        format!(
+            "{}...",
+            sample
+                .chars()
+                .take(ERROR_SAMPLE_MAX_CHARS - 3)
+                .collect::<String>()
+        )
+    } else {
+        sample
+    }
+}
+
+/// Detect error loops: same tool fails 3+ times (by default) consecutively. Runs are
+/// counted over each tool's own results, so a Read failing between two Bash
+/// failures neither breaks nor extends the Bash loop.
+fn detect_error_loops(session: &Session, config: &BottleneckConfig) -> Vec<Bottleneck> {
     let mut bottlenecks = Vec::new();
 
-    // Build a list of (tool_name, is_error, timestamp) from tool results
-    let mut tool_results: Vec<(String, bool, Option<DateTime<Utc>>)> = Vec::new();
+    // tool name -> each of its results, in order
+    let mut tool_results: BTreeMap<&str, Vec<ToolOutcome>> = BTreeMap::new();
 
-    // Track tool_use_id -> tool_name mapping
-    let mut tool_id_to_name: HashMap<String, String> = HashMap::new();
+    // tool_use_id -> (tool name, Bash command)
+    let mut tool_calls: HashMap<&str, (&str, Option<&str>)> = HashMap::new();
 
     for msg in &session.messages {
         // Record tool calls
         if msg.msg_type == MessageType::Assistant {
             for tc in &msg.tool_calls {
-                // We don't have tool_use_id in our struct, so we'll match by order
-                tool_id_to_name.insert(tc.name.clone(), tc.name.clone());
+                let command = (tc.name == "Bash")
+                    .then(|| tc.input.get("command").and_then(|v| v.as_str()))
+                    .flatten();
+                tool_calls.insert(tc.id.as_str(), (tc.name.as_str(), command));
             }
         }
 
         // Record tool results
         if msg.msg_type == MessageType::User {
             for tr in &msg.tool_results {
-                let is_error = tr.is_error || is_error_content(&tr.content);
-                // Try to find the tool name from the mapping, fallback to generic
-                let tool_name = tool_id_to_name
-                    .get(&tr.tool_use_id)
-                    .cloned()
-                    .unwrap_or_else(|| "unknown".to_string());
-                tool_results.push((tool_name, is_error, msg.timestamp));
+                let (tool_name, command) = tool_calls
+                    .get(tr.tool_use_id.as_str())
+                    .copied()
+                    .unwrap_or(("unknown", None));
+                tool_results
+                    .entry(tool_name)
+                    .or_default()
+                    .push(ToolOutcome {
+                        is_error: tr.is_error || is_error_content(&tr.content),
+                        timestamp: msg.timestamp,
+                        content: &tr.content,
+                        command,
+                    });
             }
         }
     }
 
-    // Find consecutive failures
-    let mut i = 0;
-    while i < tool_results.len() {
-        if tool_results[i].1 {
-            // Found an error
-            let tool_name = &tool_results[i].0;
-            let start_time = tool_results[i].2;
-            let mut count = 1;
-            let error_samples: Vec<String> = Vec::new();
-
-            // Look ahead for consecutive errors of same tool (or any tool)
+    let pricing = cost::pricing_for(session.messages.iter().find_map(|m| m.model.as_deref()));
+
+    for (tool_name, results) in &tool_results {
+        // Find consecutive failures of this tool
+        let mut i = 0;
+        while i < results.len() {
+            if !results[i].is_error {
+                i += 1;
+                continue;
+            }
+
+            let start_time = results[i].timestamp;
             let mut j = i + 1;
-            let mut end_time = start_time;
-            while j < tool_results.len() && tool_results[j].1 {
-                count += 1;
-                end_time = tool_results[j].2;
+            while j < results.len() && results[j].is_error {
                 j += 1;
             }
+            let count = j - i;
+            let end_time = results[j - 1].timestamp;
 
-            if count >= 3 {
+            if count >= config.error_loop_min_failures {
                 let duration = match (start_time, end_time) {
                     (Some(s), Some(e)) => (e - s).num_seconds() as f64 / 60.0,
                     _ => 0.0,
                 };
 
+                let error_sizes: Vec<usize> =
+                    results[i..j].iter().map(|r| r.content.len()).collect();
+                let (refeed_count, refeed_tokens) = estimate_refeed(&error_sizes);
+
+                let mut error_samples: Vec<String> = Vec::new();
+                for r in &results[i..j] {
+                    let sample = error_sample(r.content, r.command);
+                    if error_samples.len() < ERROR_SAMPLE_LIMIT && !error_samples.contains(&sample)
+                    {
+                        error_samples.push(sample);
+                    }
+                }
+
                 bottlenecks.push(Bottleneck::ErrorLoop(ErrorLoop {
                     session_id: session.session_id.clone(),
                     project: extract_project_name(&session.project),
-                    tool_name: tool_name.clone(),
+                    tool_name: tool_name.to_string(),
                     failure_count: count,
                     start_time,
                     end_time,
                     duration_minutes: duration.max(1.0), // At least 1 minute
                     error_samples,
+                    error_bytes: error_sizes.iter().sum(),
+                    refeed_count,
+                    refeed_cost: refeed_tokens as f64 * pricing.input / 1_000_000.0,
                 }));
             }
 
             i = j;
-        } else {
+        }
+    }
+
+    bottlenecks
+}
+
+/// A tool must flip between error and success this many times to be flaky
+const FLAKY_MIN_FLIPS: usize = 4;
+
+/// Detect flaky tools: results for the same tool alternate between error and
+/// success. Unlike error loops the failures needn't be consecutive, which
+/// usually points at an unstable environment rather than a wrong approach.
+fn detect_flaky_tools(session: &Session) -> Vec<Bottleneck> {
+    let mut tool_names: HashMap<&str, &str> = HashMap::new();
+    // tool name -> (is_error, timestamp) for each of its results, in order
+    #[allow(clippy::type_complexity)]
+    let mut results: BTreeMap<&str, Vec<(bool, Option<DateTime<Utc>>)>> = BTreeMap::new();
+
+    for msg in &session.messages {
+        match msg.msg_type {
+            MessageType::Assistant => {
+                for tc in &msg.tool_calls {
+                    tool_names.insert(tc.id.as_str(), tc.name.as_str());
+                }
+            }
+            MessageType::User => {
+                for tr in &msg.tool_results {
+                    let Some(name) = tool_names.get(tr.tool_use_id.as_str()) else {
+                        continue;
+                    };
+                    let is_error = tr.is_error || is_error_content(&tr.content);
+                    results
+                        .entry(name)
+                        .or_default()
+                        .push((is_error, msg.timestamp));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut bottlenecks = Vec::new();
+
+    for (tool_name, outcomes) in results {
+        let flips = outcomes.windows(2).filter(|w| w[0].0 != w[1].0).count();
+        if flips < FLAKY_MIN_FLIPS {
+            continue;
+        }
+
+        let recovery_minutes: f64 = outcomes
+            .windows(2)
+            .filter(|w| w[0].0)
+            .filter_map(|w| match (w[0].1, w[1].1) {
+                (Some(s), Some(e)) => Some((e - s).num_seconds() as f64 / 60.0),
+                _ => None,
+            })
+            .sum();
+
+        bottlenecks.push(Bottleneck::FlakyTool(FlakyTool {
+            session_id: session.session_id.clone(),
+            project: extract_project_name(&session.project),
+            tool_name: tool_name.to_string(),
+            flips,
+            failure_count: outcomes.iter().filter(|(is_error, _)| *is_error).count(),
+            result_count: outcomes.len(),
+            duration_minutes: recovery_minutes.max(1.0),
+            start_time: outcomes
+                .iter()
+                .find(|(is_error, _)| *is_error)
+                .and_then(|o| o.1),
+        }));
+    }
+
+    bottlenecks
+}
+
+/// A test command must fail this many runs in a row to be a loop
+const TEST_LOOP_MIN_FAILURES: usize = 3;
+
+/// Detect test failure loops: consecutive failing runs of a test command.
+/// Other tool calls in between (typically edits) don't break the run; a
+/// passing test run does.
+fn detect_test_failure_loops(session: &Session) -> Vec<Bottleneck> {
+    // tool_use_id -> (command, call timestamp) for each test run
+    let mut pending: HashMap<&str, (&str, Option<DateTime<Utc>>)> = HashMap::new();
+    // (command, call timestamp, result timestamp, failed) in result order
+    #[allow(clippy::type_complexity)]
+    let mut runs: Vec<(&str, Option<DateTime<Utc>>, Option<DateTime<Utc>>, bool)> = Vec::new();
+
+    for msg in &session.messages {
+        match msg.msg_type {
+            MessageType::Assistant => {
+                for tc in msg.tool_calls.iter().filter(|tc| tc.name == "Bash") {
+                    let command = tc.input.get("command").and_then(|v| v.as_str());
+                    if let Some(command) = command.filter(|c| metrics::is_test_command(c)) {
+                        pending.insert(tc.id.as_str(), (command, msg.timestamp));
+                    }
+                }
+            }
+            MessageType::User => {
+                for tr in &msg.tool_results {
+                    if let Some((command, called)) = pending.remove(tr.tool_use_id.as_str()) {
+                        let failed = tr.is_error || mentions_test_failure(&tr.content);
+                        runs.push((command, called, msg.timestamp, failed));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut bottlenecks = Vec::new();
+    let mut i = 0;
+    while i < runs.len() {
+        if !runs[i].3 {
             i += 1;
+            continue;
+        }
+        let mut j = i + 1;
+        while j < runs.len() && runs[j].3 {
+            j += 1;
+        }
+
+        let count = j - i;
+        if count >= TEST_LOOP_MIN_FAILURES {
+            let start_time = runs[i].1;
+            // Until the passing run is started, or the last failure if none
+            let end_time = runs.get(j).map_or(runs[j - 1].2, |next| next.1);
+            let duration = match (start_time, end_time) {
+                (Some(s), Some(e)) => (e - s).num_seconds() as f64 / 60.0,
+                _ => 0.0,
+            };
+            bottlenecks.push(Bottleneck::TestFailureLoop(TestFailureLoop {
+                session_id: session.session_id.clone(),
+                project: extract_project_name(&session.project),
+                command: runs[i]
+                    .0
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string(),
+                failure_count: count,
+                start_time,
+                end_time,
+                duration_minutes: duration.max(1.0),
+            }));
+        }
+        i = j;
+    }
+
+    bottlenecks
+}
+
+/// Whether test output reports failures: `FAILED`, `--- FAIL`, a `FAIL`
+/// line, or a nonzero "N failed" count (so `0 failed` is a pass)
+fn mentions_test_failure(content: &str) -> bool {
+    if content.contains("FAILED")
+        || content.contains("--- FAIL")
+        || content.lines().any(|l| l.starts_with("FAIL"))
+    {
+        return true;
+    }
+    let words: Vec<&str> = content.split_whitespace().collect();
+    words.windows(2).any(|w| {
+        w[1].trim_end_matches([',', ';', '.']) == "failed"
+            && w[0].parse::<usize>().is_ok_and(|n| n > 0)
+    })
+}
+
+/// Whether a Bash error loop starts inside a test failure loop
+fn covers_bash_loop(test_loop: &Bottleneck, error_loop: &Bottleneck) -> bool {
+    let (Bottleneck::TestFailureLoop(t), Bottleneck::ErrorLoop(e)) = (test_loop, error_loop) else {
+        return false;
+    };
+    match (t.start_time, t.end_time, e.start_time) {
+        (Some(start), Some(end), Some(at)) => e.tool_name == "Bash" && at >= start && at <= end,
+        _ => false,
+    }
+}
+
+/// Sessions closer together than this count as one stretch of work
+const CONTEXT_SWITCH_MAX_BREAK_MINUTES: f64 = 30.0;
+
+/// A stretch of work must touch this many projects to be context switching
+const CONTEXT_SWITCH_MIN_PROJECTS: usize = 3;
+
+/// Detect patterns that span sessions rather than happen inside one.
+/// Currently context switching: sessions ordered by start time, each
+/// starting under 30 minutes after the previous one ended, that move
+/// between 3+ projects.
+pub fn detect_cross_session(sessions: &[Session]) -> Vec<Bottleneck> {
+    let mut timed: Vec<&Session> = sessions
+        .iter()
+        .filter(|s| s.start_time.is_some() && s.end_time.is_some())
+        .collect();
+    timed.sort_by_key(|s| s.start_time);
+
+    // Split into stretches of work separated by long breaks
+    let mut stretches: Vec<Vec<&Session>> = Vec::new();
+    for session in timed {
+        let continues = stretches.last().and_then(|s| s.last()).is_some_and(|prev| {
+            let gap = session.start_time.unwrap() - prev.end_time.unwrap();
+            (gap.num_seconds() as f64 / 60.0) < CONTEXT_SWITCH_MAX_BREAK_MINUTES
+        });
+        match stretches.last_mut() {
+            Some(stretch) if continues => stretch.push(session),
+            _ => stretches.push(vec![session]),
+        }
+    }
+
+    let mut bottlenecks = Vec::new();
+    for stretch in stretches {
+        let mut projects: Vec<String> = Vec::new();
+        for s in &stretch {
+            let name = extract_project_name(&s.project);
+            if !projects.contains(&name) {
+                projects.push(name);
+            }
+        }
+        if projects.len() < CONTEXT_SWITCH_MIN_PROJECTS {
+            continue;
         }
+
+        let switch_count = stretch
+            .windows(2)
+            .filter(|w| w[0].project != w[1].project)
+            .count();
+        let break_minutes: f64 = stretch
+            .windows(2)
+            .map(|w| {
+                let gap = w[1].start_time.unwrap() - w[0].end_time.unwrap();
+                (gap.num_seconds() as f64 / 60.0).max(0.0)
+            })
+            .sum();
+
+        bottlenecks.push(Bottleneck::ContextSwitch(ContextSwitch {
+            session_id: stretch[0].session_id.clone(),
+            project: projects[0].clone(),
+            projects,
+            switch_count,
+            session_count: stretch.len(),
+            start_time: stretch[0].start_time,
+            break_minutes,
+        }));
     }
 
     bottlenecks
 }
 
+/// A permission prompt must hold up a tool call this long to be a stall
+const APPROVAL_MIN_WAIT_MINUTES: f64 = 1.0;
+
+/// Phrases in a tool result that mean it waited on, or was refused, approval.
+/// A bare "permission" would also match errors like "Permission denied".
+const APPROVAL_PHRASES: &[&str] = &[
+    "requires approval",
+    "user rejected",
+    "tool use was rejected",
+];
+
+/// Detect approval stalls: tool calls whose result mentions an approval
+/// prompt or rejection and arrived a while after the call
+fn detect_approval_stalls(session: &Session) -> Vec<Bottleneck> {
+    // tool_use_id -> (tool name, call timestamp)
+    let mut calls: HashMap<&str, (&str, Option<DateTime<Utc>>)> = HashMap::new();
+    let mut bottlenecks = Vec::new();
+
+    for msg in &session.messages {
+        match msg.msg_type {
+            MessageType::Assistant => {
+                for tc in &msg.tool_calls {
+                    calls.insert(tc.id.as_str(), (tc.name.as_str(), msg.timestamp));
+                }
+            }
+            MessageType::User => {
+                for tr in &msg.tool_results {
+                    let Some((tool_name, requested_at)) = calls.get(tr.tool_use_id.as_str()) else {
+                        continue;
+                    };
+                    let content = tr.content.to_lowercase();
+                    if !APPROVAL_PHRASES.iter().any(|p| content.contains(p)) {
+                        continue;
+                    }
+                    let (Some(requested), Some(resolved)) = (*requested_at, msg.timestamp) else {
+                        continue;
+                    };
+                    let wait_minutes = (resolved - requested).num_seconds() as f64 / 60.0;
+                    if wait_minutes >= APPROVAL_MIN_WAIT_MINUTES {
+                        bottlenecks.push(Bottleneck::ApprovalStall(ApprovalStall {
+                            session_id: session.session_id.clone(),
+                            project: extract_project_name(&session.project),
+                            tool_name: tool_name.to_string(),
+                            wait_minutes,
+                            requested_at: Some(requested),
+                            resolved_at: Some(resolved),
+                        }));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    bottlenecks
+}
+
+/// Minutes of a long gap that fall within an approval stall's wait
+fn stall_overlap_minutes(stall: &Bottleneck, gap: &LongGap) -> f64 {
+    let Bottleneck::ApprovalStall(s) = stall else {
+        return 0.0;
+    };
+    match (
+        s.requested_at,
+        s.resolved_at,
+        gap.before_timestamp,
+        gap.after_timestamp,
+    ) {
+        (Some(s_start), Some(s_end), Some(g_start), Some(g_end)) => {
+            let overlap = s_end.min(g_end) - s_start.max(g_start);
+            (overlap.num_seconds() as f64 / 60.0).max(0.0)
+        }
+        _ => 0.0,
+    }
+}
+
+/// Take the minutes a long gap shares with approval stalls out of it,
+/// dropping the gap if less than `floor_minutes` is left
+fn without_stalls(
+    gap: Bottleneck,
+    stalls: &[Bottleneck],
+    floor_minutes: f64,
+) -> Option<Bottleneck> {
+    let Bottleneck::LongGap(mut g) = gap else {
+        return Some(gap);
+    };
+    let overlap: f64 = stalls.iter().map(|s| stall_overlap_minutes(s, &g)).sum();
+    if overlap <= 0.0 {
+        return Some(Bottleneck::LongGap(g));
+    }
+    g.gap_minutes -= overlap;
+    (g.gap_minutes >= floor_minutes).then_some(Bottleneck::LongGap(g))
+}
+
+/// Rough bytes per token for estimating the size of tool output
+const BYTES_PER_TOKEN: usize = 4;
+
+/// Estimate how often error output in a loop is sent back to the model.
+/// Each error stays in context for every retry after it, so the k-th of n
+/// errors is re-fed n - k times. Returns (total re-feeds, re-fed tokens).
+fn estimate_refeed(error_sizes: &[usize]) -> (usize, usize) {
+    let n = error_sizes.len();
+    error_sizes
+        .iter()
+        .enumerate()
+        .fold((0, 0), |(count, tokens), (k, bytes)| {
+            let times = n - k;
+            (count + times, tokens + times * bytes / BYTES_PER_TOKEN)
+        })
+}
+
 /// Detect exploration spirals: lots of reading without editing
-fn detect_exploration_spirals(session: &Session) -> Vec<Bottleneck> {
+fn detect_exploration_spirals(session: &Session, config: &BottleneckConfig) -> Vec<Bottleneck> {
     let mut bottlenecks = Vec::new();
 
     // Sliding window approach: look for periods of high read/grep with no edit
@@ -224,16 +922,17 @@ fn detect_exploration_spirals(session: &Session) -> Vec<Bottleneck> {
                     }
                     "Edit" | "Write" => {
                         // Check if we had a spiral before this edit
-                        if read_count + grep_count >= 10 {
+                        if read_count + grep_count >= config.exploration_min_calls {
                             if let Some(start) = window_start {
                                 let end = last_edit_time.or(msg.timestamp).unwrap_or(start);
                                 let duration = (end - start).num_seconds() as f64 / 60.0;
 
-                                if duration >= 10.0 {
+                                if duration >= config.exploration_min_minutes {
                                     bottlenecks.push(Bottleneck::ExplorationSpiral(
                                         ExplorationSpiral {
                                             session_id: session.session_id.clone(),
                                             project: extract_project_name(&session.project),
+                                            project_path: session.project.clone(),
                                             read_count,
                                             grep_count,
                                             duration_minutes: duration,
@@ -259,13 +958,14 @@ fn detect_exploration_spirals(session: &Session) -> Vec<Bottleneck> {
     }
 
     // Check for trailing spiral (session ended without edit)
-    if read_count + grep_count >= 10 {
+    if read_count + grep_count >= config.exploration_min_calls {
         if let (Some(start), Some(end)) = (window_start, session.end_time) {
             let duration = (end - start).num_seconds() as f64 / 60.0;
-            if duration >= 10.0 {
+            if duration >= config.exploration_min_minutes {
                 bottlenecks.push(Bottleneck::ExplorationSpiral(ExplorationSpiral {
                     session_id: session.session_id.clone(),
                     project: extract_project_name(&session.project),
+                    project_path: session.project.clone(),
                     read_count,
                     grep_count,
                     duration_minutes: duration,
@@ -279,8 +979,8 @@ fn detect_exploration_spirals(session: &Session) -> Vec<Bottleneck> {
     bottlenecks
 }
 
-/// Detect edit thrashing: same file edited 5+ times
-fn detect_edit_thrashing(session: &Session) -> Vec<Bottleneck> {
+/// Detect edit thrashing: same file edited 5+ times (by default)
+fn detect_edit_thrashing(session: &Session, config: &BottleneckConfig) -> Vec<Bottleneck> {
     let mut bottlenecks = Vec::new();
 
     // Count edits per file: (count, first_edit, last_edit)
@@ -308,9 +1008,9 @@ fn detect_edit_thrashing(session: &Session) -> Vec<Bottleneck> {
         }
     }
 
-    // Find files with 5+ edits
+    // Find files with enough edits to count as thrashing
     for (file_path, (count, start, end)) in edit_counts {
-        if count >= 5 {
+        if count >= config.edit_thrash_min_edits {
             let duration = match (start, end) {
                 (Some(s), Some(e)) => (e - s).num_seconds() as f64 / 60.0,
                 _ => 0.0,
@@ -322,6 +1022,7 @@ fn detect_edit_thrashing(session: &Session) -> Vec<Bottleneck> {
                 file_path: shorten_path(&file_path),
                 edit_count: count,
                 duration_minutes: duration.max(1.0),
+                start_time: start,
             }));
         }
     }
@@ -329,32 +1030,108 @@ fn detect_edit_thrashing(session: &Session) -> Vec<Bottleneck> {
     bottlenecks
 }
 
-/// Detect long gaps: >5 minutes between consecutive messages
-fn detect_long_gaps(session: &Session) -> Vec<Bottleneck> {
-    let mut bottlenecks = Vec::new();
+/// Pauses shorter than this are never long gaps, however slow the session
+/// is. The flamegraph draws `Gap` spans from the same threshold.
+pub const GAP_FLOOR_SECONDS: i64 = 300;
 
-    let mut prev_timestamp: Option<DateTime<Utc>> = None;
+/// `GAP_FLOOR_SECONDS` in the minutes `BottleneckConfig` works in
+const GAP_FLOOR_MINUTES: f64 = GAP_FLOOR_SECONDS as f64 / 60.0;
 
-    for msg in &session.messages {
-        if let Some(ts) = msg.timestamp {
-            if let Some(prev) = prev_timestamp {
-                let gap_minutes = (ts - prev).num_seconds() as f64 / 60.0;
-
-                if gap_minutes >= 5.0 {
-                    bottlenecks.push(Bottleneck::LongGap(LongGap {
-                        session_id: session.session_id.clone(),
-                        project: extract_project_name(&session.project),
-                        gap_minutes,
-                        before_timestamp: Some(prev),
-                        after_timestamp: Some(ts),
-                    }));
-                }
+/// A gap must exceed this multiple of the session's median interval
+const GAP_MEDIAN_MULTIPLIER: f64 = 3.0;
+
+/// Flagged gaps separated by less activity than this are reported as one,
+/// so a stray heartbeat message doesn't split a single break in two
+const GAP_MERGE_MAX_ACTIVITY_SECONDS: i64 = 60;
+
+/// Minutes between two messages, minus any time marked with `aist exclude`
+fn gap_minutes_excluding(session: &Session, prev: DateTime<Utc>, next: DateTime<Utc>) -> f64 {
+    let gap = (next - prev).num_seconds() as f64 / 60.0;
+    (gap - exclusions::excluded_minutes(&session.excluded_windows, prev, next)).max(0.0)
+}
+
+/// Detect long gaps: pauses that are outliers for the session's own pace.
+/// A gap is flagged only when it is at least `floor_minutes` long and more
+/// than `median_multiplier` times the median inter-message interval, so
+/// naturally slow sessions don't flag every think pause.
+fn detect_long_gaps(
+    session: &Session,
+    floor_minutes: f64,
+    median_multiplier: f64,
+) -> Vec<Bottleneck> {
+    let timestamps: Vec<DateTime<Utc>> = session
+        .messages
+        .iter()
+        .filter_map(|m| m.timestamp)
+        .collect();
+
+    let mut intervals: Vec<f64> = timestamps
+        .windows(2)
+        .map(|w| gap_minutes_excluding(session, w[0], w[1]))
+        .collect();
+    if intervals.is_empty() {
+        return vec![];
+    }
+    intervals.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mid = intervals.len() / 2;
+    let median = if intervals.len().is_multiple_of(2) {
+        (intervals[mid - 1] + intervals[mid]) / 2.0
+    } else {
+        intervals[mid]
+    };
+
+    let mut gaps: Vec<LongGap> = Vec::new();
+
+    let timed: Vec<&Message> = session
+        .messages
+        .iter()
+        .filter(|m| m.timestamp.is_some())
+        .collect();
+    for pair in timed.windows(2) {
+        let (Some(prev), Some(ts)) = (pair[0].timestamp, pair[1].timestamp) else {
+            continue;
+        };
+        // Waiting on a build or test run isn't idle time, but only the
+        // pause right after the command is that wait
+        if metrics::build_or_test_command(pair[0]).is_some() {
+            continue;
+        }
+        let gap_minutes = gap_minutes_excluding(session, prev, ts);
+
+        if gap_minutes < floor_minutes || gap_minutes <= median * median_multiplier {
+            continue;
+        }
+        if let Some(last) = gaps.last_mut() {
+            let activity = last
+                .after_timestamp
+                .map(|after| (prev - after).num_seconds());
+            if activity.is_some_and(|secs| secs < GAP_MERGE_MAX_ACTIVITY_SECONDS) {
+                last.gap_minutes += gap_minutes;
+                last.after_timestamp = Some(ts);
+                continue;
             }
-            prev_timestamp = Some(ts);
         }
+        gaps.push(LongGap {
+            session_id: session.session_id.clone(),
+            project: extract_project_name(&session.project),
+            gap_minutes,
+            before_timestamp: Some(prev),
+            after_timestamp: Some(ts),
+        });
     }
 
-    bottlenecks
+    // Messages can carry timestamps outside the session's start/end, so
+    // never report more idle time than the session lasted
+    if session.start_time.is_some() && session.end_time.is_some() {
+        let mut remaining = exclusions::active_minutes(session);
+        for gap in &mut gaps {
+            gap.gap_minutes = gap.gap_minutes.min(remaining);
+            remaining -= gap.gap_minutes;
+        }
+        gaps.retain(|g| g.gap_minutes > 0.0);
+    }
+
+    gaps.into_iter().map(Bottleneck::LongGap).collect()
 }
 
 /// Check if content looks like an error
@@ -385,131 +1162,323 @@ fn shorten_path(path: &str) -> String {
     path.replace(&home, "~")
 }
 
-/// Print bottlenecks to terminal
-pub fn print_bottlenecks(bottlenecks: &[Bottleneck], limit: usize) {
+/// A bottleneck as emitted by `--format json`: the variant's own fields
+/// plus its `type` and wasted minutes
+#[derive(Serialize)]
+struct BottleneckJson<'a> {
+    #[serde(flatten)]
+    bottleneck: &'a Bottleneck,
+    wasted_minutes: f64,
+}
+
+/// Render bottlenecks as a pretty-printed JSON array
+pub fn render_bottlenecks_json(bottlenecks: &[Bottleneck]) -> Result<String, String> {
+    let views: Vec<BottleneckJson> = bottlenecks
+        .iter()
+        .map(|b| BottleneckJson {
+            bottleneck: b,
+            wasted_minutes: b.wasted_minutes(),
+        })
+        .collect();
+    serde_json::to_string_pretty(&views)
+        .map_err(|e| format!("Error serializing bottlenecks: {}", e))
+}
+
+/// Print bottlenecks as JSON
+pub fn print_bottlenecks_json(bottlenecks: &[Bottleneck]) {
+    match render_bottlenecks_json(bottlenecks) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+/// The last prompt the user typed before `at`, ignoring hook injections
+pub fn preceding_prompt(session: &Session, at: DateTime<Utc>) -> Option<&str> {
+    session
+        .messages
+        .iter()
+        .take_while(|m| m.timestamp.is_none_or(|t| t <= at))
+        .filter(|m| m.msg_type == MessageType::User && !m.is_hook)
+        .filter_map(|m| m.text_content.as_deref())
+        .last()
+}
+
+/// The prompt preceding each bottleneck, in the same order
+pub fn preceding_prompts(sessions: &[Session], bottlenecks: &[Bottleneck]) -> Vec<Option<String>> {
+    let by_id: HashMap<&str, &Session> = sessions
+        .iter()
+        .map(|s| (s.session_id.as_str(), s))
+        .collect();
+    bottlenecks
+        .iter()
+        .map(|b| {
+            let session = by_id.get(b.session_id())?;
+            preceding_prompt(session, b.start_time()?).map(str::to_string)
+        })
+        .collect()
+}
+
+/// The first line of a prompt, shortened to fit one terminal row
+fn prompt_line(prompt: &str) -> String {
+    let first = prompt
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or("");
+    if first.chars().count() > 70 {
+        format!("\"{}...\"", first.chars().take(67).collect::<String>())
+    } else {
+        format!("\"{}\"", first)
+    }
+}
+
+/// Print bottlenecks to terminal. `prompts` holds the prompt preceding each
+/// bottleneck, or is empty to leave prompts out.
+pub fn print_bottlenecks(bottlenecks: &[Bottleneck], limit: usize, prompts: &[Option<String>]) {
+    for row in bottleneck_rows(bottlenecks, limit, prompts) {
+        println!("{}", row);
+    }
+}
+
+/// Lines printed by `print_bottlenecks`
+fn bottleneck_rows(
+    bottlenecks: &[Bottleneck],
+    limit: usize,
+    prompts: &[Option<String>],
+) -> Vec<String> {
+    let mut rows = Vec::new();
     if bottlenecks.is_empty() {
-        println!("{}", "No bottlenecks detected.".green());
-        return;
+        rows.push(format!("{}", "No bottlenecks detected.".green()));
+        return rows;
     }
 
     let total_wasted: f64 = bottlenecks.iter().map(|b| b.wasted_minutes()).sum();
 
-    println!("{}", "BOTTLENECKS DETECTED".bold());
-    println!("{}", "═".repeat(60));
-    println!(
+    rows.push(format!("{}", "BOTTLENECKS DETECTED".bold()));
+    rows.push("═".repeat(60));
+    rows.push(format!(
         "Found {} bottlenecks | ~{:.0} minutes potentially wasted\n",
         bottlenecks.len().to_string().bold(),
         total_wasted
-    );
+    ));
 
     for (i, bottleneck) in bottlenecks.iter().take(limit).enumerate() {
-        print_single_bottleneck(i + 1, bottleneck);
-        println!();
+        push_single_bottleneck(&mut rows, i + 1, bottleneck);
+        if let Some(prompt) = prompts.get(i).and_then(|p| p.as_deref()) {
+            rows.push(format!("   Prompt: {}", prompt_line(prompt).dimmed()));
+        }
+        rows.push(String::new());
     }
 
     if bottlenecks.len() > limit {
-        println!(
+        rows.push(format!(
             "... and {} more (use --limit to see more)",
             bottlenecks.len() - limit
-        );
+        ));
     }
+    rows
 }
 
-fn print_single_bottleneck(num: usize, bottleneck: &Bottleneck) {
+fn push_single_bottleneck(rows: &mut Vec<String>, num: usize, bottleneck: &Bottleneck) {
     match bottleneck {
         Bottleneck::ErrorLoop(e) => {
-            println!(
+            rows.push(format!(
                 "{}. {} {}",
                 num,
                 "ERROR LOOP".red().bold(),
                 format!("(~{:.0} min wasted)", e.duration_minutes).dimmed()
-            );
-            println!("   {}", "─".repeat(50).dimmed());
-            println!(
+            ));
+            rows.push(format!("   {}", "─".repeat(50).dimmed()));
+            rows.push(format!(
                 "   Session: {} ({})",
-                &e.session_id[..10.min(e.session_id.len())],
+                metrics::short_id(&e.session_id, 10),
                 e.project
-            );
-            println!(
+            ));
+            rows.push(format!(
                 "   Pattern: {} failed {} times in a row",
                 e.tool_name.yellow(),
                 e.failure_count
-            );
-            println!(
+            ));
+            for sample in &e.error_samples {
+                rows.push(format!("   Sample: {}", format!("\"{}\"", sample).dimmed()));
+            }
+            if e.error_bytes > 0 {
+                rows.push(format!(
+                    "   Error output re-fed ~{} times (~{})",
+                    e.refeed_count,
+                    cost::format_cost(e.refeed_cost)
+                ));
+            }
+            rows.push(format!(
                 "   {}",
                 "Suggestion: Check tool availability and inputs before running".cyan()
-            );
+            ));
         }
         Bottleneck::ExplorationSpiral(e) => {
-            println!(
+            rows.push(format!(
                 "{}. {} {}",
                 num,
                 "EXPLORATION SPIRAL".yellow().bold(),
                 format!("(~{:.0} min)", e.duration_minutes).dimmed()
-            );
-            println!("   {}", "─".repeat(50).dimmed());
-            println!(
+            ));
+            rows.push(format!("   {}", "─".repeat(50).dimmed()));
+            rows.push(format!(
                 "   Session: {} ({})",
-                &e.session_id[..10.min(e.session_id.len())],
+                metrics::short_id(&e.session_id, 10),
                 e.project
-            );
-            println!(
+            ));
+            rows.push(format!(
                 "   Pattern: {} Read + {} Grep calls with no Edit",
                 e.read_count, e.grep_count
-            );
-            println!(
+            ));
+            rows.push(format!(
                 "   Files searched: {}",
                 e.files_searched.len().to_string().yellow()
-            );
-            println!(
+            ));
+            rows.push(format!(
                 "   {}",
                 "Suggestion: Provide better context upfront (CLAUDE.md, file hints)".cyan()
-            );
+            ));
         }
         Bottleneck::EditThrashing(e) => {
-            println!(
+            rows.push(format!(
                 "{}. {} {}",
                 num,
                 "EDIT THRASHING".magenta().bold(),
                 format!("(~{:.0} min)", e.duration_minutes).dimmed()
-            );
-            println!("   {}", "─".repeat(50).dimmed());
-            println!(
+            ));
+            rows.push(format!("   {}", "─".repeat(50).dimmed()));
+            rows.push(format!(
                 "   Session: {} ({})",
-                &e.session_id[..10.min(e.session_id.len())],
+                metrics::short_id(&e.session_id, 10),
                 e.project
-            );
-            println!(
+            ));
+            rows.push(format!(
                 "   Pattern: {} edited {} times",
                 e.file_path.yellow(),
                 e.edit_count
-            );
-            println!(
+            ));
+            rows.push(format!(
                 "   {}",
                 "Suggestion: Break down complex changes into smaller tasks".cyan()
-            );
+            ));
         }
         Bottleneck::LongGap(g) => {
-            println!(
+            rows.push(format!(
                 "{}. {} {}",
                 num,
                 "LONG GAP".blue().bold(),
                 format!("({:.0} min pause)", g.gap_minutes).dimmed()
-            );
-            println!("   {}", "─".repeat(50).dimmed());
-            println!(
+            ));
+            rows.push(format!("   {}", "─".repeat(50).dimmed()));
+            rows.push(format!(
                 "   Session: {} ({})",
-                &g.session_id[..10.min(g.session_id.len())],
+                metrics::short_id(&g.session_id, 10),
                 g.project
-            );
-            println!(
+            ));
+            rows.push(format!(
                 "   Pattern: {:.0} minute gap between actions",
                 g.gap_minutes
-            );
-            println!(
+            ));
+            rows.push(format!(
                 "   {}",
                 "Suggestion: Review what caused the pause - unclear requirements?".cyan()
-            );
+            ));
+        }
+        Bottleneck::FlakyTool(f) => {
+            rows.push(format!(
+                "{}. {} {}",
+                num,
+                "FLAKY TOOL".red().bold(),
+                format!("(~{:.0} min recovering)", f.duration_minutes).dimmed()
+            ));
+            rows.push(format!("   {}", "─".repeat(50).dimmed()));
+            rows.push(format!(
+                "   Session: {} ({})",
+                metrics::short_id(&f.session_id, 10),
+                f.project
+            ));
+            rows.push(format!(
+                "   Pattern: {} failed {} of {} runs, flipping pass/fail {} times",
+                f.tool_name.yellow(),
+                f.failure_count,
+                f.result_count,
+                f.flips
+            ));
+            rows.push(format!(
+                "   {}",
+                "Suggestion: Stabilize the environment (pin versions, fix shared state, retry network calls)".cyan()
+            ));
+        }
+        Bottleneck::ApprovalStall(a) => {
+            rows.push(format!(
+                "{}. {} {}",
+                num,
+                "APPROVAL STALL".blue().bold(),
+                format!("({:.0} min waiting)", a.wait_minutes).dimmed()
+            ));
+            rows.push(format!("   {}", "─".repeat(50).dimmed()));
+            rows.push(format!(
+                "   Session: {} ({})",
+                metrics::short_id(&a.session_id, 10),
+                a.project
+            ));
+            rows.push(format!(
+                "   Pattern: {} waited {:.0} minutes for tool approval",
+                a.tool_name.yellow(),
+                a.wait_minutes
+            ));
+            rows.push(format!(
+                "   {}",
+                "Suggestion: Allow-list tools you always approve in your Claude settings".cyan()
+            ));
+        }
+        Bottleneck::ContextSwitch(c) => {
+            rows.push(format!(
+                "{}. {} {}",
+                num,
+                "CONTEXT SWITCHING".magenta().bold(),
+                format!("({:.0} min between sessions)", c.break_minutes).dimmed()
+            ));
+            rows.push(format!("   {}", "─".repeat(50).dimmed()));
+            rows.push(format!(
+                "   Starting: {} ({})",
+                metrics::short_id(&c.session_id, 10),
+                c.project
+            ));
+            rows.push(format!(
+                "   Pattern: {} switches across {} sessions: {}",
+                c.switch_count,
+                c.session_count,
+                c.projects.join(", ").yellow()
+            ));
+            rows.push(format!(
+                "   {}",
+                "Suggestion: Batch work per project instead of hopping between repos".cyan()
+            ));
+        }
+        Bottleneck::TestFailureLoop(t) => {
+            rows.push(format!(
+                "{}. {} {}",
+                num,
+                "TEST FAILURE LOOP".red().bold(),
+                format!("(~{:.0} min)", t.duration_minutes).dimmed()
+            ));
+            rows.push(format!("   {}", "─".repeat(50).dimmed()));
+            rows.push(format!(
+                "   Session: {} ({})",
+                metrics::short_id(&t.session_id, 10),
+                t.project
+            ));
+            rows.push(format!(
+                "   Pattern: {} failed {} runs in a row",
+                t.command.yellow(),
+                t.failure_count
+            ));
+            rows.push(format!(
+                "   {}",
+                "Suggestion: Rerun just the failing test and read its full output before editing again".cyan()
+            ));
         }
     }
 }
@@ -517,6 +1486,9 @@ fn print_single_bottleneck(num: usize, bottleneck: &Bottleneck) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::{Message, ToolCall, ToolResult};
+    use chrono::TimeZone;
+    use std::path::PathBuf;
 
     #[test]
     fn test_is_error_content() {
@@ -552,7 +1524,682 @@ mod tests {
             end_time: None,
             duration_minutes: 5.0,
             error_samples: vec![],
+            error_bytes: 0,
+            refeed_count: 0,
+            refeed_cost: 0.0,
         });
         assert_eq!(error_loop.wasted_minutes(), 5.0);
     }
+
+    fn paced_session(intervals_secs: &[i64]) -> Session {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0).unwrap();
+        let mut ts = start;
+        let mut messages = vec![Message {
+            msg_type: MessageType::User,
+            timestamp: Some(ts),
+            ..Default::default()
+        }];
+        for secs in intervals_secs {
+            ts += chrono::Duration::seconds(*secs);
+            messages.push(Message {
+                msg_type: MessageType::Assistant,
+                timestamp: Some(ts),
+                ..Default::default()
+            });
+        }
+        Session {
+            session_id: "paced".to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: PathBuf::from("/test/session.jsonl"),
+            git_branch: None,
+            start_time: Some(start),
+            end_time: Some(ts),
+            messages,
+            models_used: vec![],
+            excluded_windows: vec![],
+            was_compacted: false,
+            line_count: 0,
+            skipped_lines: 0,
+            git_branches: vec![],
+        }
+    }
+
+    #[test]
+    fn test_long_gap_relative_to_session_pace() {
+        // Fast session: 30s turns, then a 6 minute stall
+        let fast = paced_session(&[30, 30, 30, 360, 30, 30]);
+        let gaps = detect_long_gaps(&fast, GAP_FLOOR_MINUTES, GAP_MEDIAN_MULTIPLIER);
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].wasted_minutes(), 6.0);
+
+        // Consistently slow session: the same 6 minute gap is normal pace
+        let slow = paced_session(&[240, 270, 240, 360, 270, 240]);
+        let gaps = detect_long_gaps(&slow, GAP_FLOOR_MINUTES, GAP_MEDIAN_MULTIPLIER);
+        assert!(gaps.is_empty());
+    }
+
+    #[test]
+    fn test_focus_session_requires_clean_long_run() {
+        let criteria = FocusCriteria::default();
+
+        // 45 minutes of steady one-minute turns
+        let clean = paced_session(&[60; 45]);
+        assert!(is_focus_session(&clean, &criteria));
+
+        // Same length, but broken up by a 20 minute stall
+        let mut intervals = vec![60; 25];
+        intervals.insert(10, 1200);
+        let choppy = paced_session(&intervals);
+        assert!(!is_focus_session(&choppy, &criteria));
+
+        // Clean but too short
+        let short = paced_session(&[60; 10]);
+        assert!(!is_focus_session(&short, &criteria));
+    }
+
+    #[test]
+    fn test_efficiency_by_duration_trend() {
+        // Short, clean sessions
+        let short = paced_session(&[60; 15]);
+        // Long sessions with an hour-long stall in the middle
+        let mut intervals = vec![60; 70];
+        intervals.insert(35, 3600);
+        let long = paced_session(&intervals);
+
+        let bins = efficiency_by_duration(&[short.clone(), short, long.clone(), long]);
+
+        assert_eq!(bins.len(), 2);
+        assert_eq!(bins[0].label, "<30m");
+        assert_eq!(bins[0].session_count, 2);
+        assert_eq!(bins[0].avg_efficiency, 100.0);
+        assert_eq!(bins[1].label, "2h+");
+        assert!(bins[1].avg_efficiency < 60.0);
+
+        let summary = duration_efficiency_summary(&bins).unwrap();
+        assert!(summary.starts_with("Efficiency drops to"));
+        assert!(summary.contains("over 2h"));
+    }
+
+    #[test]
+    fn test_excluded_window_raises_efficiency() {
+        // 60 minutes of steady work with a 30-minute meeting in the middle
+        let mut intervals = vec![60; 60];
+        intervals.insert(30, 1800);
+        let mut session = paced_session(&intervals);
+        let before = session_efficiency(&session, &detect_all(std::slice::from_ref(&session)));
+
+        let meeting_start = session.start_time.unwrap() + chrono::Duration::minutes(30);
+        session.excluded_windows = vec![crate::exclusions::ExcludedWindow {
+            start: meeting_start,
+            end: meeting_start + chrono::Duration::minutes(30),
+        }];
+        let after = session_efficiency(&session, &detect_all(std::slice::from_ref(&session)));
+
+        assert!(before < 70.0);
+        assert_eq!(after, 100.0);
+    }
+
+    #[test]
+    fn test_adjacent_long_gaps_merge() {
+        // A 40 minute break split in two by a single stray message
+        let session = paced_session(&[30, 30, 30, 1200, 1200, 30, 30]);
+        let gaps = detect_long_gaps(&session, GAP_FLOOR_MINUTES, GAP_MEDIAN_MULTIPLIER);
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].wasted_minutes(), 40.0);
+        assert!(gaps[0].wasted_minutes() <= crate::exclusions::active_minutes(&session));
+    }
+
+    #[test]
+    fn test_long_gap_floor_applies_to_fast_sessions() {
+        // 3 minutes is an outlier here but below the absolute floor
+        let fast = paced_session(&[10, 10, 180, 10]);
+        let gaps = detect_long_gaps(&fast, GAP_FLOOR_MINUTES, GAP_MEDIAN_MULTIPLIER);
+        assert!(gaps.is_empty());
+    }
+
+    #[test]
+    fn test_only_gap_after_build_is_exempt() {
+        // A 6 minute build wait, steady turns, then a 6 minute stall
+        let mut session = paced_session(&[30, 360, 30, 30, 30, 360, 30]);
+        session.messages[1].tool_calls.push(ToolCall {
+            id: "b1".to_string(),
+            name: "Bash".to_string(),
+            input: serde_json::json!({"command": "cargo build --release"}),
+            parallel_group: 0,
+        });
+
+        let gaps = detect_long_gaps(&session, GAP_FLOOR_MINUTES, GAP_MEDIAN_MULTIPLIER);
+        assert_eq!(gaps.len(), 1);
+        let Bottleneck::LongGap(g) = &gaps[0] else {
+            panic!("expected a long gap");
+        };
+        assert_eq!(g.before_timestamp, session.messages[5].timestamp);
+    }
+
+    #[test]
+    fn test_error_loop_estimates_refeed_cost() {
+        // 36 KB stack trace returned three times in a row (~9K tokens each)
+        let trace = "at frame\n".repeat(4_000);
+        assert_eq!(trace.len(), 36_000);
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0).unwrap();
+        let messages = (0..3)
+            .map(|i| Message {
+                msg_type: MessageType::User,
+                timestamp: Some(start + chrono::Duration::minutes(i)),
+                tool_results: vec![ToolResult {
+                    tool_use_id: format!("t{}", i),
+                    content: trace.clone(),
+                    is_error: true,
+                }],
+                model: Some("claude-sonnet-4".to_string()),
+                ..Default::default()
+            })
+            .collect();
+        let session = Session {
+            session_id: "errors".to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: PathBuf::from("/test/session.jsonl"),
+            git_branch: None,
+            start_time: Some(start),
+            end_time: Some(start),
+            messages,
+            models_used: vec![],
+            excluded_windows: vec![],
+            was_compacted: false,
+            line_count: 0,
+            skipped_lines: 0,
+            git_branches: vec![],
+        };
+
+        let loops = detect_error_loops(&session, &BottleneckConfig::default());
+        let Some(Bottleneck::ErrorLoop(e)) = loops.first() else {
+            panic!("expected an error loop");
+        };
+
+        // Errors stay in context for each later retry: 3 + 2 + 1 re-feeds
+        assert_eq!(e.error_bytes, 108_000);
+        assert_eq!(e.refeed_count, 6);
+        // 6 x 9,000 tokens at Sonnet's $3/M input
+        assert!((e.refeed_cost - 0.162).abs() < 1e-9);
+    }
+
+    /// One Bash call per minute, failing where `outcomes` is false
+    fn bash_session(outcomes: &[bool]) -> Session {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0).unwrap();
+        let mut messages = vec![];
+        for (i, passed) in outcomes.iter().enumerate() {
+            let ts = start + chrono::Duration::minutes(i as i64);
+            let id = format!("b{}", i);
+            messages.push(Message {
+                msg_type: MessageType::Assistant,
+                timestamp: Some(ts),
+                tool_calls: vec![ToolCall {
+                    id: id.clone(),
+                    name: "Bash".to_string(),
+                    input: serde_json::json!({"command": "cargo test"}),
+                    parallel_group: i,
+                }],
+                ..Default::default()
+            });
+            messages.push(Message {
+                msg_type: MessageType::User,
+                timestamp: Some(ts + chrono::Duration::seconds(30)),
+                tool_results: vec![ToolResult {
+                    tool_use_id: id,
+                    content: if *passed { "ok" } else { "1 test failed" }.to_string(),
+                    is_error: !passed,
+                }],
+                ..Default::default()
+            });
+        }
+        Session {
+            session_id: "flaky".to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: PathBuf::from("/test/session.jsonl"),
+            git_branch: None,
+            start_time: Some(start),
+            end_time: messages.last().and_then(|m| m.timestamp),
+            messages,
+            models_used: vec![],
+            excluded_windows: vec![],
+            was_compacted: false,
+            line_count: 0,
+            skipped_lines: 0,
+            git_branches: vec![],
+        }
+    }
+
+    #[test]
+    fn test_error_loop_collects_samples() {
+        let mut session = bash_session(&[false, false, false, false]);
+        for msg in &mut session.messages {
+            for call in &mut msg.tool_calls {
+                call.input = serde_json::json!({"command": "cargo build"});
+            }
+            for result in &mut msg.tool_results {
+                result.content = "\nzsh: command not found: cargo\n".to_string();
+            }
+        }
+
+        let loops = detect_error_loops(&session, &BottleneckConfig::default());
+        let Some(Bottleneck::ErrorLoop(e)) = loops.first() else {
+            panic!("expected an error loop");
+        };
+
+        assert_eq!(e.failure_count, 4);
+        assert_eq!(
+            e.error_samples,
+            vec!["cargo build: zsh: command not found: cargo".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_edit_threshold_is_configurable() {
+        let mut session = bash_session(&[]);
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0).unwrap();
+        session.messages = (0..6)
+            .map(|i| Message {
+                msg_type: MessageType::Assistant,
+                timestamp: Some(start + chrono::Duration::minutes(i)),
+                tool_calls: vec![ToolCall {
+                    id: format!("e{}", i),
+                    name: "Edit".to_string(),
+                    input: serde_json::json!({"file_path": "/test/project/src/lib.rs"}),
+                    parallel_group: 0,
+                }],
+                ..Default::default()
+            })
+            .collect();
+
+        let default = BottleneckConfig::default();
+        assert_eq!(detect_edit_thrashing(&session, &default).len(), 1);
+
+        let lenient = BottleneckConfig {
+            edit_thrash_min_edits: 10,
+            ..default
+        };
+        assert!(detect_edit_thrashing(&session, &lenient).is_empty());
+        assert!(!detect_all_with_config(&[session], &lenient)
+            .iter()
+            .any(|b| matches!(b, Bottleneck::EditThrashing(_))));
+    }
+
+    #[test]
+    fn test_repeated_test_failures_flag_test_loop() {
+        // Three failing `cargo test` runs, then a pass
+        let session = bash_session(&[false, false, false, true]);
+
+        let loops = detect_test_failure_loops(&session);
+        let Some(Bottleneck::TestFailureLoop(t)) = loops.first() else {
+            panic!("expected a test failure loop");
+        };
+        assert_eq!(loops.len(), 1);
+        assert_eq!(t.command, "cargo test");
+        assert_eq!(t.failure_count, 3);
+        // From the first failing run until the passing run starts
+        assert_eq!(t.duration_minutes, 3.0);
+
+        // The same failures aren't also counted as a Bash error loop
+        let all = detect_all(&[session]);
+        assert!(!all.iter().any(|b| matches!(b, Bottleneck::ErrorLoop(_))));
+        assert_eq!(
+            all.iter()
+                .filter(|b| matches!(b, Bottleneck::TestFailureLoop(_)))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_test_loop_needs_consecutive_test_failures() {
+        assert!(detect_test_failure_loops(&bash_session(&[false, false, true, false])).is_empty());
+
+        // Failing commands that aren't test runners are error loops, not test loops
+        let mut session = bash_session(&[false, false, false]);
+        for call in session.messages.iter_mut().flat_map(|m| &mut m.tool_calls) {
+            call.input = serde_json::json!({"command": "ls missing-dir"});
+        }
+        assert!(detect_test_failure_loops(&session).is_empty());
+        assert_eq!(
+            detect_error_loops(&session, &BottleneckConfig::default()).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_rejected_tool_is_approval_stall_not_long_gap() {
+        let mut session = bash_session(&[true; 6]);
+        for call in session.messages.iter_mut().flat_map(|m| &mut m.tool_calls) {
+            call.input = serde_json::json!({"command": "rm -rf target"});
+        }
+        // The fourth call's result arrives 12.5 minutes later, rejected
+        for msg in &mut session.messages[7..] {
+            msg.timestamp = msg.timestamp.map(|t| t + chrono::Duration::minutes(12));
+        }
+        session.messages[7].tool_results[0].content =
+            "The user doesn't want to proceed with this tool use. The tool use was rejected"
+                .to_string();
+
+        let gaps = detect_long_gaps(&session, GAP_FLOOR_MINUTES, GAP_MEDIAN_MULTIPLIER);
+        assert_eq!(gaps.len(), 1);
+
+        let all = detect_all(&[session]);
+        assert!(!all.iter().any(|b| matches!(b, Bottleneck::LongGap(_))));
+        let Some(Bottleneck::ApprovalStall(a)) = all.first() else {
+            panic!("expected an approval stall");
+        };
+        assert_eq!(a.tool_name, "Bash");
+        assert_eq!(a.wait_minutes, 12.5);
+    }
+
+    #[test]
+    fn test_long_gaps_match_flamegraph_gap_spans() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0).unwrap();
+        // Half-minute turns with a 3 and a 6 minute pause
+        let mut offsets: Vec<i64> = (0..10).map(|i| i * 30).collect();
+        offsets.extend((0..10).map(|i| 450 + i * 30));
+        offsets.extend((0..10).map(|i| 1080 + i * 30));
+        let messages: Vec<Message> = offsets
+            .iter()
+            .map(|secs| Message {
+                msg_type: MessageType::User,
+                timestamp: Some(start + chrono::Duration::seconds(*secs)),
+                text_content: Some("next".to_string()),
+                ..Default::default()
+            })
+            .collect();
+        let session = Session {
+            session_id: "pauses".to_string(),
+            project: "/test/project".to_string(),
+            start_time: Some(start),
+            end_time: messages.last().and_then(|m| m.timestamp),
+            messages,
+            ..Default::default()
+        };
+
+        let gaps: Vec<_> = detect_long_gaps(&session, GAP_FLOOR_MINUTES, GAP_MEDIAN_MULTIPLIER)
+            .iter()
+            .filter_map(|b| match b {
+                Bottleneck::LongGap(g) => Some((g.before_timestamp, g.after_timestamp)),
+                _ => None,
+            })
+            .collect();
+        let spans: Vec<_> = crate::flamegraph::extract_spans(&session, GAP_FLOOR_SECONDS)
+            .iter()
+            .filter(|s| s.activity == crate::flamegraph::ActivityType::Gap)
+            .map(|s| (Some(s.start), Some(s.end)))
+            .collect();
+
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps, spans);
+    }
+
+    #[test]
+    fn test_permission_denied_is_not_approval_stall() {
+        let mut session = bash_session(&[true; 6]);
+        for call in session.messages.iter_mut().flat_map(|m| &mut m.tool_calls) {
+            call.input = serde_json::json!({"command": "git push"});
+        }
+        for msg in &mut session.messages[7..] {
+            msg.timestamp = msg.timestamp.map(|t| t + chrono::Duration::minutes(12));
+        }
+        session.messages[7].tool_results[0].content =
+            "git@github.com: Permission denied (publickey).".to_string();
+
+        let all = detect_all(&[session]);
+        assert!(!all
+            .iter()
+            .any(|b| matches!(b, Bottleneck::ApprovalStall(_))));
+        assert!(all.iter().any(|b| matches!(b, Bottleneck::LongGap(_))));
+    }
+
+    #[test]
+    fn test_stall_overlap_trims_long_gap() {
+        let at = |minute| Some(Utc.with_ymd_and_hms(2026, 1, 1, 10, minute, 0).unwrap());
+        let gap = Bottleneck::LongGap(LongGap {
+            session_id: "s".to_string(),
+            project: "p".to_string(),
+            gap_minutes: 20.0,
+            before_timestamp: at(0),
+            after_timestamp: at(20),
+        });
+        let stall = |from, to| {
+            Bottleneck::ApprovalStall(ApprovalStall {
+                session_id: "s".to_string(),
+                project: "p".to_string(),
+                tool_name: "Bash".to_string(),
+                wait_minutes: 8.0,
+                requested_at: at(from),
+                resolved_at: at(to),
+            })
+        };
+
+        let trimmed = without_stalls(gap.clone(), &[stall(12, 25)], GAP_FLOOR_MINUTES);
+        assert_eq!(trimmed.map(|b| b.wasted_minutes()), Some(12.0));
+        assert!(without_stalls(gap, &[stall(0, 20)], GAP_FLOOR_MINUTES).is_none());
+    }
+
+    #[test]
+    fn test_bottlenecks_json_is_flat_and_tagged() {
+        let mut detected = detect_flaky_tools(&bash_session(&[true, false, true, false, true]));
+        detected.extend(detect_test_failure_loops(&bash_session(&[
+            false, false, false,
+        ])));
+
+        let json = render_bottlenecks_json(&detected).unwrap();
+        let parsed: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0]["type"], "flaky_tool");
+        assert_eq!(parsed[0]["tool_name"], "Bash");
+        assert_eq!(parsed[0]["flips"], 4);
+        assert_eq!(parsed[1]["type"], "test_failure_loop");
+        assert_eq!(parsed[1]["command"], "cargo test");
+        for (value, b) in parsed.iter().zip(&detected) {
+            assert_eq!(value["session_id"], "flaky");
+            assert_eq!(value["project"], "project");
+            assert_eq!(value["wasted_minutes"], b.wasted_minutes());
+        }
+    }
+
+    #[test]
+    fn test_preceding_prompt_for_bottleneck() {
+        let mut session = bash_session(&[false, false, false]);
+        let prompt = |text: &str, minutes, is_hook| Message {
+            msg_type: MessageType::User,
+            timestamp: session
+                .start_time
+                .map(|t| t - chrono::Duration::minutes(minutes)),
+            text_content: Some(text.to_string()),
+            tool_calls: vec![],
+            tool_results: vec![],
+            model: None,
+            usage: None,
+            is_hook,
+        };
+        let typed = prompt(
+            "make the parser tests pass\nthey broke after the refactor",
+            2,
+            false,
+        );
+        let hook = prompt("Stop hook feedback: clippy failed", 1, true);
+        session.messages.splice(0..0, [typed, hook]);
+
+        let detected = detect_test_failure_loops(&session);
+        let prompts = preceding_prompts(std::slice::from_ref(&session), &detected);
+
+        assert_eq!(prompts.len(), 1);
+        let text = prompts[0].as_deref().unwrap();
+        assert!(text.starts_with("make the parser tests pass"));
+        assert_eq!(prompt_line(text), "\"make the parser tests pass\"");
+        assert!(prompt_line(&"x".repeat(100)).ends_with("...\""));
+
+        // Nothing typed before the bottleneck
+        let start = session.start_time.unwrap() - chrono::Duration::minutes(5);
+        assert_eq!(preceding_prompt(&session, start), None);
+    }
+
+    #[test]
+    fn test_prompt_line_printed_only_with_prompts() {
+        let mut session = bash_session(&[false, false, false]);
+        session.messages.insert(
+            0,
+            Message {
+                msg_type: MessageType::User,
+                timestamp: session.start_time,
+                text_content: Some("fix the flaky parser test".to_string()),
+                ..Default::default()
+            },
+        );
+        let detected = detect_test_failure_loops(&session);
+        let prompts = preceding_prompts(std::slice::from_ref(&session), &detected);
+
+        let shown = bottleneck_rows(&detected, 10, &prompts);
+        assert!(shown.contains(&"   Prompt: \"fix the flaky parser test\"".to_string()));
+        let hidden = bottleneck_rows(&detected, 10, &[]);
+        assert!(!hidden.iter().any(|r| r.contains("Prompt:")));
+    }
+
+    #[test]
+    fn test_interleaved_projects_flag_context_switch() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap();
+        // (project, start minute, length in minutes)
+        let session = |i: usize, project: &str, at: i64, minutes: i64| {
+            let mut s = bash_session(&[]);
+            s.session_id = format!("s{}", i);
+            s.project = format!("/w/{}", project);
+            s.start_time = Some(start + chrono::Duration::minutes(at));
+            s.end_time = Some(start + chrono::Duration::minutes(at + minutes));
+            s
+        };
+        let sessions = vec![
+            session(0, "app", 0, 20),
+            session(1, "api", 25, 15),
+            session(2, "app", 45, 10),
+            session(3, "web", 60, 20),
+            // Two hours later: a new stretch, and only two projects
+            session(4, "api", 200, 30),
+            session(5, "app", 235, 30),
+        ];
+
+        let switches = detect_cross_session(&sessions);
+
+        assert_eq!(switches.len(), 1);
+        let Bottleneck::ContextSwitch(c) = &switches[0] else {
+            panic!("expected a context switch");
+        };
+        assert_eq!(c.session_id, "s0");
+        assert_eq!(c.projects, ["app", "api", "web"]);
+        assert_eq!(c.switch_count, 3);
+        assert_eq!(c.session_count, 4);
+        assert_eq!(c.break_minutes, 15.0);
+
+        // Cross-session detection is part of detect_all
+        assert!(detect_all(&sessions)
+            .iter()
+            .any(|b| matches!(b, Bottleneck::ContextSwitch(_))));
+    }
+
+    #[test]
+    fn test_mentions_test_failure() {
+        assert!(mentions_test_failure(
+            "test result: FAILED. 3 passed; 2 failed"
+        ));
+        assert!(mentions_test_failure("=== 1 failed, 12 passed in 0.4s ==="));
+        assert!(mentions_test_failure("--- FAIL: TestParse (0.00s)"));
+        assert!(!mentions_test_failure(
+            "test result: ok. 10 passed; 0 failed"
+        ));
+    }
+
+    #[test]
+    fn test_alternating_results_flag_flaky_tool() {
+        let flaky = detect_flaky_tools(&bash_session(&[true, false, true, false, true]));
+        let Some(Bottleneck::FlakyTool(f)) = flaky.first() else {
+            panic!("expected a flaky tool");
+        };
+        assert_eq!(f.tool_name, "Bash");
+        assert_eq!(f.flips, 4);
+        assert_eq!(f.failure_count, 2);
+        assert_eq!(f.duration_minutes, 2.0);
+
+        assert!(detect_flaky_tools(&bash_session(&[true, true, true])).is_empty());
+        // Consecutive failures are an error loop, not flakiness
+        assert!(detect_flaky_tools(&bash_session(&[false, false, false, true])).is_empty());
+    }
+
+    #[test]
+    fn test_rows_shorten_non_ascii_session_ids() {
+        let mut session = bash_session(&[true, false, true, false, true]);
+        session.session_id = "añadir-exportación".to_string();
+        let detected = detect_flaky_tools(&session);
+
+        let rows = bottleneck_rows(&detected, 10, &[]);
+        assert!(rows.iter().any(|r| r.contains("Session: añadir-exp (")));
+    }
+
+    #[test]
+    fn test_error_loop_counts_failures_per_tool() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0).unwrap();
+        // (tool, failed): Bash fails three times in a row with Read failing in between
+        let calls = [
+            ("Bash", true),
+            ("Read", true),
+            ("Bash", true),
+            ("Read", false),
+            ("Bash", true),
+            ("Read", true),
+        ];
+        let mut messages = vec![];
+        for (i, (tool, failed)) in calls.iter().enumerate() {
+            let ts = start + chrono::Duration::minutes(i as i64);
+            let id = format!("t{}", i);
+            messages.push(Message {
+                msg_type: MessageType::Assistant,
+                timestamp: Some(ts),
+                tool_calls: vec![ToolCall {
+                    id: id.clone(),
+                    name: tool.to_string(),
+                    input: serde_json::Value::Null,
+                    parallel_group: i,
+                }],
+                ..Default::default()
+            });
+            messages.push(Message {
+                msg_type: MessageType::User,
+                timestamp: Some(ts),
+                tool_results: vec![ToolResult {
+                    tool_use_id: id,
+                    content: if *failed { "Error: boom" } else { "ok" }.to_string(),
+                    is_error: *failed,
+                }],
+                ..Default::default()
+            });
+        }
+        let session = Session {
+            session_id: "mixed".to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: PathBuf::from("/test/session.jsonl"),
+            git_branch: None,
+            start_time: Some(start),
+            end_time: messages.last().and_then(|m| m.timestamp),
+            messages,
+            models_used: vec![],
+            excluded_windows: vec![],
+            was_compacted: false,
+            line_count: 0,
+            skipped_lines: 0,
+            git_branches: vec![],
+        };
+
+        let loops = detect_error_loops(&session, &BottleneckConfig::default());
+
+        assert_eq!(loops.len(), 1);
+        let Bottleneck::ErrorLoop(e) = &loops[0] else {
+            panic!("expected an error loop");
+        };
+        assert_eq!(e.tool_name, "Bash");
+        assert_eq!(e.failure_count, 3);
+        assert_eq!(e.duration_minutes, 4.0);
+    }
 }
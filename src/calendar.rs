@@ -0,0 +1,232 @@
+use crate::cost;
+use crate::parser::Session;
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use colored::Colorize;
+use std::collections::HashMap;
+
+/// Width of one day column in the month grid, including the column of
+/// padding that keeps a full cell off the next one
+const CELL_WIDTH: usize = 12;
+
+/// Sessions and spend for a single local calendar day
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DayStats {
+    pub sessions: usize,
+    pub cost: f64,
+}
+
+/// Resolve a calendar period into a (year, month). Accepts `month` for the
+/// current month or an explicit `YYYY-MM`.
+pub fn parse_period(period: &str) -> Option<(i32, u32)> {
+    if period.eq_ignore_ascii_case("month") {
+        let today = Local::now().date_naive();
+        return Some((today.year(), today.month()));
+    }
+
+    let (year, month) = period.split_once('-')?;
+    let year: i32 = year.parse().ok()?;
+    let month: u32 = month.parse().ok()?;
+    NaiveDate::from_ymd_opt(year, month, 1).map(|_| (year, month))
+}
+
+/// Bucket sessions by the local date they started on
+pub fn bucket_by_day(sessions: &[Session]) -> HashMap<NaiveDate, DayStats> {
+    let mut days: HashMap<NaiveDate, DayStats> = HashMap::new();
+
+    for session in sessions {
+        let Some(start) = session.start_time else {
+            continue;
+        };
+        let local: DateTime<Local> = start.with_timezone(&Local);
+        let day = days.entry(local.date_naive()).or_default();
+        day.sessions += 1;
+        day.cost += cost::calculate_cost(session);
+    }
+
+    days
+}
+
+/// Plain text for a day cell: session count and cost, or a dot when idle
+fn format_cell(stats: Option<&DayStats>) -> String {
+    match stats {
+        Some(s) if s.sessions > 0 => format!("{}s {}", s.sessions, cost::format_cost(s.cost)),
+        _ => "\u{00B7}".to_string(),
+    }
+}
+
+/// Left-align `text` in a day column, always ending in a space
+fn pad_cell(text: &str) -> String {
+    format!("{:<width$} ", text, width = CELL_WIDTH - 1)
+}
+
+/// Color a padded cell by how busy the day was relative to the busiest day
+fn color_cell(cell: String, stats: Option<&DayStats>, max_sessions: usize) -> String {
+    let sessions = stats.map(|s| s.sessions).unwrap_or(0);
+    if sessions == 0 || max_sessions == 0 {
+        return cell.dimmed().to_string();
+    }
+
+    let intensity = sessions as f64 / max_sessions as f64;
+    if intensity > 0.66 {
+        cell.red().bold().to_string()
+    } else if intensity > 0.33 {
+        cell.yellow().to_string()
+    } else {
+        cell.green().to_string()
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|d| d.pred_opt())
+        .map(|d| d.day())
+        .unwrap_or(30)
+}
+
+/// Render a Monday-first month grid with one row per week. Each week is
+/// two lines: day numbers, then that day's session count and cost.
+pub fn render_calendar(year: i32, month: u32, days: &HashMap<NaiveDate, DayStats>) -> String {
+    let Some(first) = NaiveDate::from_ymd_opt(year, month, 1) else {
+        return String::new();
+    };
+    let last_day = days_in_month(year, month);
+
+    let month_days: Vec<(NaiveDate, Option<&DayStats>)> = (1..=last_day)
+        .filter_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+        .map(|date| (date, days.get(&date)))
+        .collect();
+    let max_sessions = month_days
+        .iter()
+        .filter_map(|(_, s)| s.map(|s| s.sessions))
+        .max()
+        .unwrap_or(0);
+
+    let mut out = String::new();
+    out.push_str(&format!("{}\n", first.format("%B %Y").to_string().bold()));
+    out.push_str(&format!("{}\n\n", "\u{2550}".repeat(CELL_WIDTH * 7)));
+
+    for name in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"] {
+        out.push_str(
+            &format!("{:<width$}", name, width = CELL_WIDTH)
+                .dimmed()
+                .to_string(),
+        );
+    }
+    out.push('\n');
+
+    // Blank leading cells for months that start mid-week
+    let mut cells: Vec<Option<(NaiveDate, Option<&DayStats>)>> =
+        vec![None; first.weekday().num_days_from_monday() as usize];
+    cells.extend(month_days.iter().copied().map(Some));
+
+    for week in cells.chunks(7) {
+        let mut numbers = String::new();
+        let mut values = String::new();
+        for cell in week {
+            match cell {
+                Some((date, stats)) => {
+                    numbers.push_str(&pad_cell(&date.day().to_string()));
+                    let text = pad_cell(&format_cell(*stats));
+                    values.push_str(&color_cell(text, *stats, max_sessions));
+                }
+                None => {
+                    numbers.push_str(&" ".repeat(CELL_WIDTH));
+                    values.push_str(&" ".repeat(CELL_WIDTH));
+                }
+            }
+        }
+        out.push_str(&format!(
+            "\n{}\n{}\n",
+            numbers.trim_end(),
+            values.trim_end()
+        ));
+    }
+
+    let (total_sessions, total_cost) = month_days
+        .iter()
+        .filter_map(|(_, s)| *s)
+        .fold((0, 0.0), |(n, c), s| (n + s.sessions, c + s.cost));
+    out.push_str(&format!(
+        "\n{} sessions, {} this month\n",
+        total_sessions.to_string().bold(),
+        cost::format_cost(total_cost).bold()
+    ));
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use std::path::PathBuf;
+
+    fn session_at(id: &str, year: i32, month: u32, day: u32) -> Session {
+        let start = Utc.with_ymd_and_hms(year, month, day, 12, 0, 0).unwrap();
+        Session {
+            session_id: id.to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: PathBuf::from("/test/session.jsonl"),
+            start_time: Some(start),
+            end_time: Some(start),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_day_with_two_sessions_shows_count() {
+        let sessions = vec![
+            session_at("a", 2026, 3, 10),
+            session_at("b", 2026, 3, 10),
+            session_at("c", 2026, 3, 11),
+        ];
+
+        let days = bucket_by_day(&sessions);
+        let local_day = sessions[0]
+            .start_time
+            .unwrap()
+            .with_timezone(&Local)
+            .date_naive();
+
+        assert_eq!(days[&local_day].sessions, 2);
+        assert!(format_cell(days.get(&local_day)).starts_with("2s "));
+        assert_eq!(format_cell(None), "\u{00B7}");
+    }
+
+    #[test]
+    fn test_full_cells_stay_apart() {
+        let busy = DayStats {
+            sessions: 12,
+            cost: 104.5,
+        };
+        let days: HashMap<NaiveDate, DayStats> = [10, 11]
+            .into_iter()
+            .map(|d| (NaiveDate::from_ymd_opt(2026, 3, d).unwrap(), busy))
+            .collect();
+
+        let grid = render_calendar(2026, 3, &days);
+
+        assert_eq!(format_cell(Some(&busy)).len(), CELL_WIDTH - 1);
+        assert!(grid.contains("12s $104.50 12s $104.50"));
+    }
+
+    #[test]
+    fn test_parse_period() {
+        assert_eq!(parse_period("2026-02"), Some((2026, 2)));
+        assert_eq!(parse_period("2026-13"), None);
+        assert_eq!(parse_period("week"), None);
+        assert!(parse_period("month").is_some());
+    }
+
+    #[test]
+    fn test_days_in_month() {
+        assert_eq!(days_in_month(2026, 2), 28);
+        assert_eq!(days_in_month(2028, 2), 29);
+        assert_eq!(days_in_month(2026, 12), 31);
+    }
+}
@@ -0,0 +1,31 @@
+/// Copy text to the system clipboard
+#[cfg(feature = "clipboard")]
+pub fn copy(text: &str) -> Result<(), String> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|e| format!("Clipboard unavailable: {}", e))?;
+    clipboard
+        .set_text(text.to_string())
+        .map_err(|e| format!("Failed to copy to clipboard: {}", e))
+}
+
+/// Copy text to the system clipboard (unavailable without the `clipboard` feature)
+#[cfg(not(feature = "clipboard"))]
+pub fn copy(_text: &str) -> Result<(), String> {
+    Err("aist was built without clipboard support (rebuild with --features clipboard)".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "clipboard")]
+    #[test]
+    fn test_clipboard_init_does_not_panic() {
+        // Headless CI has no display server, so an Err is fine - a panic is not
+        let _ = arboard::Clipboard::new();
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    #[test]
+    fn test_copy_without_feature_errors() {
+        assert!(super::copy("hello").is_err());
+    }
+}
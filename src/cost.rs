@@ -0,0 +1,729 @@
+use crate::error::AistError;
+use crate::metrics::ProjectMetrics;
+use crate::parser::{Session, TokenUsage};
+use chrono::{Datelike, Local, Months, NaiveDate, TimeZone};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// USD prices per million tokens for a model family
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    pub cache_write: f64,
+    pub cache_read: f64,
+}
+
+const OPUS: ModelPricing = ModelPricing {
+    input: 15.0,
+    output: 75.0,
+    cache_write: 18.75,
+    cache_read: 1.5,
+};
+
+const SONNET: ModelPricing = ModelPricing {
+    input: 3.0,
+    output: 15.0,
+    cache_write: 3.75,
+    cache_read: 0.3,
+};
+
+const HAIKU: ModelPricing = ModelPricing {
+    input: 0.8,
+    output: 4.0,
+    cache_write: 1.0,
+    cache_read: 0.08,
+};
+
+/// Rate overrides in the config dir, as a JSON object of model name to
+/// `{"input", "output", "cache_write", "cache_read"}` USD per million tokens
+const PRICING_FILE: &str = "pricing.json";
+
+/// Per-model rates, keyed by a lowercase name matched against model ids
+#[derive(Debug, Clone)]
+pub struct Pricing {
+    rates: Vec<(String, ModelPricing)>,
+}
+
+impl Default for Pricing {
+    fn default() -> Self {
+        Pricing {
+            rates: vec![
+                ("opus".to_string(), OPUS),
+                ("sonnet".to_string(), SONNET),
+                ("haiku".to_string(), HAIKU),
+            ],
+        }
+    }
+}
+
+impl Pricing {
+    /// The built-in table with `overrides` added or replacing entries. A key
+    /// can be a family (`sonnet`) or a full model id (`claude-sonnet-4-5`).
+    pub fn with_overrides(overrides: HashMap<String, ModelPricing>) -> Pricing {
+        let mut pricing = Pricing::default();
+        for (name, rates) in overrides {
+            let name = name.to_lowercase();
+            pricing.rates.retain(|(key, _)| *key != name);
+            pricing.rates.push((name, rates));
+        }
+        pricing
+    }
+
+    /// Rates for a model id: the longest key it contains wins, so a full id
+    /// overrides its family. Unknown models get Opus rates.
+    pub fn rates(&self, model: Option<&str>) -> ModelPricing {
+        let model = model.unwrap_or("").to_lowercase();
+        self.rates
+            .iter()
+            .filter(|(key, _)| model.contains(key.as_str()))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(_, rates)| *rates)
+            .unwrap_or(OPUS)
+    }
+}
+
+/// Built-in pricing merged with any user overrides from the config dir
+pub fn load_pricing() -> Result<Pricing, AistError> {
+    let path = crate::state::config_dir().join(PRICING_FILE);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Pricing::default()),
+        Err(e) => {
+            return Err(AistError::Io(
+                format!("Failed to read {}", path.display()),
+                e,
+            ))
+        }
+    };
+    let overrides: HashMap<String, ModelPricing> = serde_json::from_str(&content)
+        .map_err(|e| AistError::Parse(format!("Invalid {}: {}", path.display(), e)))?;
+    Ok(Pricing::with_overrides(overrides))
+}
+
+/// Pricing used by every cost calculation; built-in rates until set
+static PRICING: OnceLock<Pricing> = OnceLock::new();
+
+/// Price every cost calculated from now on with `pricing`
+pub fn set_pricing(pricing: Pricing) {
+    let _ = PRICING.set(pricing);
+}
+
+/// Pricing for a model id, falling back to Opus rates for unknown models
+pub fn pricing_for(model: Option<&str>) -> ModelPricing {
+    PRICING.get_or_init(Pricing::default).rates(model)
+}
+
+/// Cost in USD of one message's token usage
+pub fn usage_cost(usage: &TokenUsage, pricing: &ModelPricing) -> f64 {
+    (usage.input_tokens as f64 * pricing.input
+        + usage.output_tokens as f64 * pricing.output
+        + usage.cache_write_tokens as f64 * pricing.cache_write
+        + usage.cache_read_tokens as f64 * pricing.cache_read)
+        / 1_000_000.0
+}
+
+/// Estimated cost in USD of a session, from per-message token usage
+pub fn calculate_cost(session: &Session) -> f64 {
+    session
+        .messages
+        .iter()
+        .filter_map(|m| {
+            m.usage
+                .as_ref()
+                .map(|u| usage_cost(u, &pricing_for(m.model.as_deref())))
+        })
+        .sum()
+}
+
+/// Token counts and cost contributions by billing category
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CostBreakdown {
+    pub input_tokens: u64,
+    pub cache_write_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub output_tokens: u64,
+    pub input_cost: f64,
+    pub cache_write_cost: f64,
+    pub cache_read_cost: f64,
+    pub output_cost: f64,
+    /// What the same usage would cost if every cached token were fresh input
+    pub uncached_cost: f64,
+}
+
+impl CostBreakdown {
+    pub fn total(&self) -> f64 {
+        self.input_cost + self.cache_write_cost + self.cache_read_cost + self.output_cost
+    }
+
+    /// Money prompt caching saved compared with sending everything fresh
+    pub fn cache_savings(&self) -> f64 {
+        self.uncached_cost - self.total()
+    }
+}
+
+/// Split the cost of all sessions into fresh input, cache writes, cache
+/// reads and output, priced per message by its model
+pub fn cost_breakdown(sessions: &[Session]) -> CostBreakdown {
+    let mut b = CostBreakdown::default();
+
+    for message in sessions.iter().flat_map(|s| &s.messages) {
+        let Some(usage) = &message.usage else {
+            continue;
+        };
+        let pricing = pricing_for(message.model.as_deref());
+
+        b.input_tokens += usage.input_tokens;
+        b.cache_write_tokens += usage.cache_write_tokens;
+        b.cache_read_tokens += usage.cache_read_tokens;
+        b.output_tokens += usage.output_tokens;
+
+        b.input_cost += usage.input_tokens as f64 * pricing.input / 1_000_000.0;
+        b.cache_write_cost += usage.cache_write_tokens as f64 * pricing.cache_write / 1_000_000.0;
+        b.cache_read_cost += usage.cache_read_tokens as f64 * pricing.cache_read / 1_000_000.0;
+        b.output_cost += usage.output_tokens as f64 * pricing.output / 1_000_000.0;
+
+        let uncached = TokenUsage {
+            input_tokens: usage.input_tokens + usage.cache_write_tokens + usage.cache_read_tokens,
+            output_tokens: usage.output_tokens,
+            cache_write_tokens: 0,
+            cache_read_tokens: 0,
+        };
+        b.uncached_cost += usage_cost(&uncached, &pricing);
+    }
+
+    b
+}
+
+/// All tokens a session sent or received, cached or not
+pub fn session_tokens(session: &Session) -> u64 {
+    session
+        .messages
+        .iter()
+        .map(|m| m.token_input() + m.token_output())
+        .sum()
+}
+
+/// Sessions whose token total falls in `[low, high)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenBucket {
+    pub low: u64,
+    pub high: u64,
+    pub count: usize,
+}
+
+/// Bucket per-session token totals by order of magnitude (`<10`, `10-100`,
+/// ... `1M-10M`), covering only the decades between the smallest and
+/// largest total so the histogram has no empty tails
+pub fn token_histogram(totals: &[u64]) -> Vec<TokenBucket> {
+    let decade = |n: u64| if n == 0 { 0 } else { n.ilog10() };
+    let (Some(min), Some(max)) = (totals.iter().min(), totals.iter().max()) else {
+        return vec![];
+    };
+
+    (decade(*min)..=decade(*max))
+        .map(|k| {
+            let low = if k == 0 { 0 } else { 10u64.pow(k) };
+            let high = 10u64.saturating_pow(k + 1);
+            TokenBucket {
+                low,
+                high,
+                count: totals.iter().filter(|&&t| t >= low && t < high).count(),
+            }
+        })
+        .collect()
+}
+
+/// Bucket size for `cost --by`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostInterval {
+    Day,
+    Week,
+    Month,
+}
+
+impl CostInterval {
+    /// First day of the bucket holding `date`; weeks start on Monday
+    fn bucket_start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            CostInterval::Day => date,
+            CostInterval::Week => {
+                date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+            }
+            CostInterval::Month => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    fn next_bucket(self, start: NaiveDate) -> NaiveDate {
+        match self {
+            CostInterval::Day => start + chrono::Duration::days(1),
+            CostInterval::Week => start + chrono::Duration::days(7),
+            CostInterval::Month => start + Months::new(1),
+        }
+    }
+
+    /// Label for the bucket starting at `start`
+    pub fn label(self, start: NaiveDate) -> String {
+        match self {
+            CostInterval::Day | CostInterval::Week => start.format("%Y-%m-%d").to_string(),
+            CostInterval::Month => start.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// Parse a `--by` value: day, week or month
+pub fn parse_interval(value: &str) -> Result<CostInterval, String> {
+    match value {
+        "day" => Ok(CostInterval::Day),
+        "week" => Ok(CostInterval::Week),
+        "month" => Ok(CostInterval::Month),
+        _ => Err(format!(
+            "unknown interval '{}' (expected day, week or month)",
+            value
+        )),
+    }
+}
+
+/// Cost of the sessions started in one day, week or month
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostBucket {
+    pub start: NaiveDate,
+    pub sessions: usize,
+    pub cost: f64,
+}
+
+/// Cost per bucket of session start time (local date), from the first
+/// session's bucket to the last, including empty buckets in between so
+/// gaps show. Sessions without a start time are left out.
+pub fn cost_over_time(sessions: &[Session], interval: CostInterval) -> Vec<CostBucket> {
+    cost_over_time_in(sessions, interval, &Local)
+}
+
+fn cost_over_time_in<Tz: TimeZone>(
+    sessions: &[Session],
+    interval: CostInterval,
+    tz: &Tz,
+) -> Vec<CostBucket> {
+    let mut by_start: HashMap<NaiveDate, (usize, f64)> = HashMap::new();
+    for session in sessions {
+        let Some(start) = session.start_time else {
+            continue;
+        };
+        let bucket = interval.bucket_start(start.with_timezone(tz).date_naive());
+        let entry = by_start.entry(bucket).or_insert((0, 0.0));
+        entry.0 += 1;
+        entry.1 += calculate_cost(session);
+    }
+
+    let (Some(&first), Some(&last)) = (by_start.keys().min(), by_start.keys().max()) else {
+        return vec![];
+    };
+    let mut buckets = Vec::new();
+    let mut start = first;
+    while start <= last {
+        let (sessions, cost) = by_start.get(&start).copied().unwrap_or((0, 0.0));
+        buckets.push(CostBucket {
+            start,
+            sessions,
+            cost,
+        });
+        start = interval.next_bucket(start);
+    }
+    buckets
+}
+
+/// Compact token count for labels: 950, 12K, 3M
+pub fn format_tokens(tokens: u64) -> String {
+    if tokens >= 1_000_000 {
+        format!("{}M", tokens / 1_000_000)
+    } else if tokens >= 1_000 {
+        format!("{}K", tokens / 1_000)
+    } else {
+        tokens.to_string()
+    }
+}
+
+/// Format a USD cost for display
+pub fn format_cost(cost: f64) -> String {
+    if cost > 0.0 && cost < 0.01 {
+        "<$0.01".to_string()
+    } else {
+        format!("${:.2}", cost)
+    }
+}
+
+/// Parse a `--budget-per-project` value, `<project>=<usd>`
+pub fn parse_budget(value: &str) -> Result<(String, f64), String> {
+    let (project, usd) = value
+        .rsplit_once('=')
+        .ok_or_else(|| format!("Invalid budget '{}': use <project>=<usd>", value))?;
+    let usd: f64 = usd
+        .trim()
+        .trim_start_matches('$')
+        .parse()
+        .map_err(|_| format!("Invalid budget amount '{}'", usd))?;
+    let project = project.trim().trim_end_matches('/');
+    if project.is_empty() || usd < 0.0 {
+        return Err(format!("Invalid budget '{}': use <project>=<usd>", value));
+    }
+    // Budgets are keyed by short project name, like the BY PROJECT views
+    let name = project.rsplit('/').next().unwrap_or(project);
+    Ok((name.to_string(), usd))
+}
+
+/// One project's spend against its cap
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectBudget {
+    pub project: String,
+    pub spend: f64,
+    pub tokens: u64,
+    pub budget: f64,
+}
+
+impl ProjectBudget {
+    pub fn is_over(&self) -> bool {
+        self.spend > self.budget
+    }
+}
+
+/// Compare each budgeted project's spend with its cap, in the order the
+/// budgets were given. Projects without sessions have zero spend.
+pub fn check_budgets(
+    by_project: &HashMap<String, ProjectMetrics>,
+    budgets: &[(String, f64)],
+) -> Vec<ProjectBudget> {
+    budgets
+        .iter()
+        .map(|(project, budget)| {
+            let metrics = by_project.get(project);
+            ProjectBudget {
+                project: project.clone(),
+                spend: metrics.map(|m| m.total_cost).unwrap_or(0.0),
+                tokens: metrics.map(|m| m.total_tokens).unwrap_or(0),
+                budget: *budget,
+            }
+        })
+        .collect()
+}
+
+/// How far `total` is over `budget`, or None if it's within it
+pub fn budget_overage(total: f64, budget: f64) -> Option<f64> {
+    (total > budget).then_some(total - budget)
+}
+
+/// Token total and cost of one session, for `cost --detailed`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionCost {
+    pub session_id: String,
+    pub project: String,
+    pub tokens: u64,
+    pub cost: f64,
+}
+
+/// Per-session cost, most expensive first
+pub fn session_costs(sessions: &[Session]) -> Vec<SessionCost> {
+    let mut costs: Vec<SessionCost> = sessions
+        .iter()
+        .map(|s| SessionCost {
+            session_id: s.session_id.clone(),
+            project: s
+                .project
+                .rsplit('/')
+                .next()
+                .unwrap_or(&s.project)
+                .to_string(),
+            tokens: session_tokens(s),
+            cost: calculate_cost(s),
+        })
+        .collect();
+    costs.sort_by(|a, b| {
+        b.cost
+            .partial_cmp(&a.cost)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.session_id.cmp(&b.session_id))
+    });
+    costs
+}
+
+/// A session whose cost is an outlier among the analyzed sessions
+#[derive(Debug, Clone)]
+pub struct CostAnomaly {
+    pub session_id: String,
+    pub cost: f64,
+}
+
+/// Fewer sessions than this give too noisy a baseline to flag outliers
+const MIN_SESSIONS_FOR_ANOMALIES: usize = 5;
+
+/// Flag sessions costing more than mean + 2 standard deviations of the set,
+/// most expensive first
+pub fn detect_cost_anomalies(sessions: &[Session]) -> Vec<CostAnomaly> {
+    if sessions.len() < MIN_SESSIONS_FOR_ANOMALIES {
+        return vec![];
+    }
+
+    let costs: Vec<f64> = sessions.iter().map(calculate_cost).collect();
+    let n = costs.len() as f64;
+    let mean = costs.iter().sum::<f64>() / n;
+    let stddev = (costs.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / n).sqrt();
+    let threshold = mean + 2.0 * stddev;
+
+    let mut anomalies: Vec<CostAnomaly> = sessions
+        .iter()
+        .zip(&costs)
+        .filter(|(_, &cost)| stddev > 0.0 && cost > threshold)
+        .map(|(session, &cost)| CostAnomaly {
+            session_id: session.session_id.clone(),
+            cost,
+        })
+        .collect();
+
+    anomalies.sort_by(|a, b| {
+        b.cost
+            .partial_cmp(&a.cost)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.session_id.cmp(&b.session_id))
+    });
+    anomalies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Message, MessageType};
+    use std::path::PathBuf;
+
+    fn session_with_output_tokens(id: &str, output_tokens: u64) -> Session {
+        Session {
+            session_id: id.to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: PathBuf::from("/test/session.jsonl"),
+            messages: vec![Message {
+                msg_type: MessageType::Assistant,
+                model: Some("claude-opus-4".to_string()),
+                usage: Some(TokenUsage {
+                    output_tokens,
+                    ..TokenUsage::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_session_costs_most_expensive_first() {
+        let sessions = vec![
+            session_with_output_tokens("cheap", 1_000),
+            session_with_output_tokens("pricey", 100_000),
+            session_with_output_tokens("none", 0),
+        ];
+
+        let costs = session_costs(&sessions);
+
+        let ids: Vec<&str> = costs.iter().map(|c| c.session_id.as_str()).collect();
+        assert_eq!(ids, ["pricey", "cheap", "none"]);
+        assert_eq!(costs[0].tokens, 100_000);
+        assert_eq!(costs[0].project, "project");
+        assert!((costs[0].cost - 7.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_calculate_cost_uses_model_pricing() {
+        let session = session_with_output_tokens("s1", 1_000_000);
+        assert_eq!(calculate_cost(&session), 75.0);
+        assert_eq!(pricing_for(Some("claude-sonnet-4-5")), SONNET);
+        assert_eq!(pricing_for(None), OPUS);
+        assert_eq!(format_cost(1.234), "$1.23");
+        assert_eq!(format_cost(0.001), "<$0.01");
+    }
+
+    #[test]
+    fn test_haiku_session_costs_less_than_opus() {
+        let opus = session_with_output_tokens("opus", 200_000);
+        let mut haiku = opus.clone();
+        haiku.messages[0].model = Some("claude-haiku-4-5".to_string());
+
+        assert!(calculate_cost(&haiku) < calculate_cost(&opus));
+        assert!((calculate_cost(&haiku) - 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pricing_overrides_family_and_full_id() {
+        let cheap = ModelPricing {
+            input: 1.0,
+            output: 2.0,
+            cache_write: 0.0,
+            cache_read: 0.0,
+        };
+        let pricing = Pricing::with_overrides(HashMap::from([
+            ("Sonnet".to_string(), cheap),
+            ("claude-opus-4-5".to_string(), SONNET),
+        ]));
+
+        assert_eq!(pricing.rates(Some("claude-sonnet-4-5")), cheap);
+        assert_eq!(pricing.rates(Some("claude-opus-4-5-20251101")), SONNET);
+        assert_eq!(pricing.rates(Some("claude-opus-4-1")), OPUS);
+        assert_eq!(pricing.rates(Some("claude-haiku-4-5")), HAIKU);
+    }
+
+    #[test]
+    fn test_cost_over_time_weekly_keeps_empty_weeks() {
+        use chrono::{TimeZone, Utc};
+
+        let at = |day: u32, output_tokens: u64| {
+            let mut session = session_with_output_tokens("s", output_tokens);
+            session.start_time = Some(Utc.with_ymd_and_hms(2026, 3, day, 12, 0, 0).unwrap());
+            session
+        };
+        // Mon 2 Mar and Sun 8 Mar share a week, 9-15 Mar is empty, Wed 18 Mar
+        let sessions = vec![at(2, 100_000), at(8, 100_000), at(18, 200_000)];
+
+        let buckets = cost_over_time_in(&sessions, CostInterval::Week, &Utc);
+
+        let starts: Vec<String> = buckets
+            .iter()
+            .map(|b| CostInterval::Week.label(b.start))
+            .collect();
+        assert_eq!(starts, ["2026-03-02", "2026-03-09", "2026-03-16"]);
+        assert_eq!(
+            buckets.iter().map(|b| b.sessions).collect::<Vec<_>>(),
+            [2, 0, 1]
+        );
+        assert!((buckets[0].cost - 15.0).abs() < 1e-9);
+        assert_eq!(buckets[1].cost, 0.0);
+        assert!((buckets[2].cost - 15.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cache_read_only_session_is_billed() {
+        let mut session = session_with_output_tokens("s1", 0);
+        session.messages[0].usage = Some(TokenUsage {
+            cache_read_tokens: 1_000_000,
+            ..TokenUsage::default()
+        });
+
+        // Cached reads bill at a tenth of the input rate, not zero
+        assert!((calculate_cost(&session) - 1.5).abs() < 1e-9);
+        for pricing in [OPUS, SONNET, HAIKU] {
+            assert!((pricing.cache_read - pricing.input * 0.1).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_project_budgets_flag_overages() {
+        let project = |total_cost: f64| ProjectMetrics {
+            session_count: 1,
+            total_tokens: 1_000,
+            total_cost,
+            ..Default::default()
+        };
+        let by_project: HashMap<String, ProjectMetrics> = [
+            ("api".to_string(), project(12.5)),
+            ("web".to_string(), project(3.0)),
+        ]
+        .into_iter()
+        .collect();
+        let budgets = vec![
+            parse_budget("api=10").unwrap(),
+            parse_budget("/work/web=$5").unwrap(),
+            parse_budget("docs=1").unwrap(),
+        ];
+
+        let checked = check_budgets(&by_project, &budgets);
+
+        assert_eq!(checked[0].project, "api");
+        assert!(checked[0].is_over());
+        assert_eq!(checked[1].project, "web");
+        assert!(!checked[1].is_over());
+        assert_eq!(checked[1].budget, 5.0);
+        assert_eq!(checked[2].spend, 0.0);
+        assert!(parse_budget("api").is_err());
+        assert!(parse_budget("api=lots").is_err());
+    }
+
+    #[test]
+    fn test_budget_overage() {
+        let overage = budget_overage(212.5, 200.0).unwrap();
+        assert!((overage - 12.5).abs() < 1e-9);
+        assert_eq!(budget_overage(150.0, 200.0), None);
+        assert_eq!(budget_overage(200.0, 200.0), None);
+    }
+
+    #[test]
+    fn test_cost_breakdown_cache_savings() {
+        let mut session = session_with_output_tokens("s1", 100_000);
+        session.messages[0].usage = Some(TokenUsage {
+            input_tokens: 10_000,
+            output_tokens: 100_000,
+            cache_write_tokens: 200_000,
+            cache_read_tokens: 2_000_000,
+        });
+
+        let b = cost_breakdown(&[session.clone()]);
+
+        assert_eq!(b.cache_read_tokens, 2_000_000);
+        assert!((b.total() - calculate_cost(&session)).abs() < 1e-9);
+        // Uncached: 2.21M input at $15 + 0.1M output at $75
+        assert!((b.uncached_cost - 40.65).abs() < 1e-9);
+        assert!((b.cache_savings() - (b.uncached_cost - b.total())).abs() < 1e-9);
+        // Cached: $0.15 input + $3.75 writes + $3.00 reads + $7.50 output
+        assert!((b.cache_savings() - (40.65 - 14.40)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_token_histogram_buckets_by_decade() {
+        let totals = [5_000, 8_000, 12_000, 40_000, 99_999, 150_000, 2_500_000];
+
+        let buckets = token_histogram(&totals);
+
+        let shape: Vec<(u64, u64, usize)> =
+            buckets.iter().map(|b| (b.low, b.high, b.count)).collect();
+        assert_eq!(
+            shape,
+            vec![
+                (1_000, 10_000, 2),
+                (10_000, 100_000, 3),
+                (100_000, 1_000_000, 1),
+                (1_000_000, 10_000_000, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_token_histogram_empty_and_single() {
+        assert!(token_histogram(&[]).is_empty());
+
+        let single = token_histogram(&[42_000]);
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].count, 1);
+        assert_eq!(format_tokens(single[0].low), "10K");
+    }
+
+    #[test]
+    fn test_detect_cost_anomalies_flags_outlier() {
+        let mut sessions: Vec<Session> = [95, 100, 105, 98, 102, 100, 97, 103, 100]
+            .iter()
+            .enumerate()
+            .map(|(i, tokens)| session_with_output_tokens(&format!("normal-{}", i), tokens * 1000))
+            .collect();
+        sessions.push(session_with_output_tokens("runaway", 1_000_000));
+
+        let anomalies = detect_cost_anomalies(&sessions);
+
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].session_id, "runaway");
+        assert_eq!(anomalies[0].cost, 75.0);
+    }
+
+    #[test]
+    fn test_detect_cost_anomalies_skips_small_sets() {
+        let sessions = vec![
+            session_with_output_tokens("a", 1_000),
+            session_with_output_tokens("b", 1_000),
+            session_with_output_tokens("c", 100_000),
+        ];
+        assert!(detect_cost_anomalies(&sessions).is_empty());
+    }
+}
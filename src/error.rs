@@ -0,0 +1,204 @@
+use std::fmt;
+
+/// Errors from GitHub/GitLab sync, report export and git lookups, kept distinct so
+/// callers can suggest the right fix
+#[derive(Debug)]
+pub enum AistError {
+    /// The `gh` CLI isn't on PATH
+    GhNotInstalled,
+    /// `gh` isn't logged in or the token lacks access
+    GhAuth(String),
+    /// GitHub API rate limit hit
+    GhRateLimited(String),
+    /// The `glab` CLI isn't on PATH
+    GlabNotInstalled,
+    /// Couldn't reach GitHub or GitLab
+    Network(String),
+    /// Unexpected data from `gh` or a cache file
+    Parse(String),
+    /// Local filesystem failure, with what was being attempted
+    Io(String, std::io::Error),
+    /// No synced cache for the repo
+    NoCache(String),
+    /// No owner/repo given and none detectable from the git remote
+    RepoNotDetected,
+    /// A repo path the host's CLI can't address, such as a nested GitHub owner
+    UnsupportedRemote(String),
+    /// The directory used to resolve a git ref isn't inside a git repo
+    NotAGitRepo(String),
+    /// A git tag/branch/commit that doesn't resolve
+    GitRefNotFound(String),
+}
+
+impl AistError {
+    /// Classify a failed `gh` invocation from its stderr output
+    pub fn from_gh_stderr(stderr: &str) -> AistError {
+        let lower = stderr.to_lowercase();
+        let message = stderr.trim().to_string();
+
+        if lower.contains("rate limit") {
+            AistError::GhRateLimited(message)
+        } else if lower.contains("gh auth login")
+            || lower.contains("http 401")
+            || lower.contains("bad credentials")
+            || lower.contains("http 403")
+            || lower.contains("not logged")
+        {
+            AistError::GhAuth(message)
+        } else if is_network_error(&lower) {
+            AistError::Network(message)
+        } else {
+            AistError::Parse(format!("gh command failed: {}", message))
+        }
+    }
+
+    /// Classify a failed `glab` invocation from its stderr output. glab's
+    /// own messages already say how to log in, so only network errors get
+    /// a variant of their own.
+    pub fn from_glab_stderr(stderr: &str) -> AistError {
+        let message = stderr.trim().to_string();
+        if is_network_error(&stderr.to_lowercase()) {
+            AistError::Network(message)
+        } else {
+            AistError::Parse(format!("glab command failed: {}", message))
+        }
+    }
+
+    /// Classify a failure to launch `gh` at all
+    pub fn from_gh_spawn(e: std::io::Error) -> AistError {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AistError::GhNotInstalled
+        } else {
+            AistError::Io("Failed to run gh command".to_string(), e)
+        }
+    }
+
+    /// Classify a failure to launch `glab` at all
+    pub fn from_glab_spawn(e: std::io::Error) -> AistError {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AistError::GlabNotInstalled
+        } else {
+            AistError::Io("Failed to run glab command".to_string(), e)
+        }
+    }
+
+    /// A suggested fix to print after the error, if there is one
+    pub fn remediation(&self) -> Option<&'static str> {
+        match self {
+            AistError::GhNotInstalled => Some("Install the GitHub CLI: https://cli.github.com"),
+            AistError::GhAuth(_) => {
+                Some("Run `gh auth login` and make sure you can read the repo.")
+            }
+            AistError::GhRateLimited(_) => Some("Wait a few minutes and run `aist sync` again."),
+            AistError::GlabNotInstalled => {
+                Some("Install the GitLab CLI: https://gitlab.com/gitlab-org/cli")
+            }
+            AistError::Network(_) => Some("Check your network connection and retry."),
+            AistError::NoCache(_) => Some("Run `aist sync` first."),
+            AistError::RepoNotDetected => Some("Use --owner and --repo flags."),
+            AistError::UnsupportedRemote(_) => {
+                Some("Use --owner and --repo, or --gitlab for a GitLab project.")
+            }
+            AistError::NotAGitRepo(_) => {
+                Some("Run from inside the project or pass --project <path>.")
+            }
+            AistError::GitRefNotFound(_) => {
+                Some("Check the ref with `git tag` or `git log`, then retry.")
+            }
+            AistError::Parse(_) | AistError::Io(_, _) => None,
+        }
+    }
+}
+
+fn is_network_error(lower: &str) -> bool {
+    lower.contains("could not resolve")
+        || lower.contains("no such host")
+        || lower.contains("connection refused")
+        || lower.contains("timeout")
+        || lower.contains("timed out")
+        || lower.contains("network")
+}
+
+impl fmt::Display for AistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AistError::GhNotInstalled => write!(f, "gh CLI not found"),
+            AistError::GhAuth(msg) => write!(f, "GitHub authentication failed: {}", msg),
+            AistError::GhRateLimited(msg) => write!(f, "GitHub rate limit exceeded: {}", msg),
+            AistError::GlabNotInstalled => write!(f, "glab CLI not found"),
+            AistError::Network(msg) => write!(f, "Network error: {}", msg),
+            AistError::Parse(msg) => write!(f, "{}", msg),
+            AistError::Io(context, e) => write!(f, "{}: {}", context, e),
+            AistError::NoCache(repo) => write!(f, "No GitHub cache found for {}", repo),
+            AistError::RepoNotDetected => write!(f, "Could not detect repo from git remote"),
+            AistError::UnsupportedRemote(msg) => write!(f, "Unsupported remote: {}", msg),
+            AistError::NotAGitRepo(dir) => write!(f, "Not a git repository: {}", dir),
+            AistError::GitRefNotFound(git_ref) => {
+                write!(f, "Could not resolve git ref '{}'", git_ref)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AistError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AistError::Io(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_gh_stderr() {
+        assert!(matches!(
+            AistError::from_gh_stderr("To get started with GitHub CLI, please run:  gh auth login"),
+            AistError::GhAuth(_)
+        ));
+        assert!(matches!(
+            AistError::from_gh_stderr("HTTP 401: Bad credentials (https://api.github.com/graphql)"),
+            AistError::GhAuth(_)
+        ));
+        assert!(matches!(
+            AistError::from_gh_stderr("GraphQL: API rate limit exceeded for user ID 1"),
+            AistError::GhRateLimited(_)
+        ));
+        assert!(matches!(
+            AistError::from_gh_stderr(
+                "error connecting to api.github.com: dial tcp: lookup api.github.com: no such host"
+            ),
+            AistError::Network(_)
+        ));
+        assert!(matches!(
+            AistError::from_gh_stderr("something unexpected"),
+            AistError::Parse(_)
+        ));
+    }
+
+    #[test]
+    fn test_from_gh_spawn() {
+        let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+        assert!(matches!(
+            AistError::from_gh_spawn(missing),
+            AistError::GhNotInstalled
+        ));
+
+        let denied = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        let err = AistError::from_gh_spawn(denied);
+        assert!(matches!(err, AistError::Io(_, _)));
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn test_remediation() {
+        assert_eq!(
+            AistError::NoCache("o/r".to_string()).remediation(),
+            Some("Run `aist sync` first.")
+        );
+        assert!(AistError::Parse("bad".to_string()).remediation().is_none());
+    }
+}
@@ -0,0 +1,176 @@
+use crate::error::AistError;
+use crate::parser::Session;
+use crate::state;
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Sidecar file (in the config dir) holding excluded windows per session
+const EXCLUSIONS_FILE: &str = "exclusions.json";
+
+/// A stretch of a session marked as meeting/away time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ExcludedWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+fn exclusions_path() -> PathBuf {
+    state::config_dir().join(EXCLUSIONS_FILE)
+}
+
+/// Load every stored exclusion, keyed by session id. A missing or
+/// unreadable file means no exclusions.
+pub fn load_exclusions() -> HashMap<String, Vec<ExcludedWindow>> {
+    fs::read_to_string(exclusions_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Record an excluded window for a session, returning the sidecar path
+pub fn add_exclusion(session_id: &str, window: ExcludedWindow) -> Result<PathBuf, AistError> {
+    let mut all = load_exclusions();
+    all.entry(session_id.to_string()).or_default().push(window);
+
+    let path = exclusions_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| AistError::Io("Failed to create config directory".to_string(), e))?;
+    }
+    let json = serde_json::to_string_pretty(&all)
+        .map_err(|e| AistError::Parse(format!("Failed to serialize exclusions: {}", e)))?;
+    fs::write(&path, json)
+        .map_err(|e| AistError::Io("Failed to write exclusions file".to_string(), e))?;
+    Ok(path)
+}
+
+/// Attach stored windows to the sessions they belong to
+pub fn apply_exclusions(
+    sessions: &mut [Session],
+    exclusions: &HashMap<String, Vec<ExcludedWindow>>,
+) {
+    for session in sessions.iter_mut() {
+        if let Some(windows) = exclusions.get(&session.session_id) {
+            session.excluded_windows = windows.clone();
+        }
+    }
+}
+
+/// Turn local `HH:MM` start/end times into a window on the session's day.
+/// The window is placed on whichever day (the session's start date or the
+/// one after) makes it overlap the session, and may cross midnight.
+pub fn resolve_window(session: &Session, start: &str, end: &str) -> Result<ExcludedWindow, String> {
+    let parse = |s: &str| {
+        NaiveTime::parse_from_str(s, "%H:%M")
+            .map_err(|_| format!("Invalid time '{}': use HH:MM", s))
+    };
+    let (start_time, end_time) = (parse(start)?, parse(end)?);
+
+    let (Some(session_start), Some(session_end)) = (session.start_time, session.end_time) else {
+        return Err("Session has no timestamps".to_string());
+    };
+
+    let first_day = session_start.with_timezone(&Local).date_naive();
+    for offset in 0..2 {
+        let day = first_day + Duration::days(offset);
+        let Some(start) = Local
+            .from_local_datetime(&day.and_time(start_time))
+            .earliest()
+        else {
+            continue;
+        };
+        let mut end_day = day;
+        if end_time <= start_time {
+            end_day += Duration::days(1);
+        }
+        let Some(end) = Local
+            .from_local_datetime(&end_day.and_time(end_time))
+            .earliest()
+        else {
+            continue;
+        };
+
+        let window = ExcludedWindow {
+            start: start.with_timezone(&Utc),
+            end: end.with_timezone(&Utc),
+        };
+        if window.start < session_end && window.end > session_start {
+            return Ok(window);
+        }
+    }
+
+    Err(format!(
+        "{}-{} doesn't overlap the session ({} - {})",
+        start,
+        end,
+        session_start.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+        session_end.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+    ))
+}
+
+/// Minutes of `[start, end)` covered by the excluded windows
+pub fn excluded_minutes(
+    windows: &[ExcludedWindow],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> f64 {
+    windows
+        .iter()
+        .map(|w| {
+            let overlap_start = w.start.max(start);
+            let overlap_end = w.end.min(end);
+            if overlap_end > overlap_start {
+                (overlap_end - overlap_start).num_seconds() as f64 / 60.0
+            } else {
+                0.0
+            }
+        })
+        .sum()
+}
+
+/// Session wall-clock minutes minus any excluded windows
+pub fn active_minutes(session: &Session) -> f64 {
+    match (session.start_time, session.end_time) {
+        (Some(start), Some(end)) => {
+            let total = (end - start).num_seconds() as f64 / 60.0;
+            (total - excluded_minutes(&session.excluded_windows, start, end)).max(0.0)
+        }
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_excluded_minutes_clips_to_range() {
+        let windows = [ExcludedWindow {
+            start: at(10, 30),
+            end: at(11, 30),
+        }];
+        assert_eq!(excluded_minutes(&windows, at(10, 0), at(11, 0)), 30.0);
+        assert_eq!(excluded_minutes(&windows, at(12, 0), at(13, 0)), 0.0);
+    }
+
+    #[test]
+    fn test_resolve_window_rejects_bad_input() {
+        let session = Session {
+            session_id: "s1".to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: PathBuf::from("/test/session.jsonl"),
+            start_time: Some(at(10, 0)),
+            end_time: Some(at(11, 0)),
+            ..Default::default()
+        };
+        assert!(resolve_window(&session, "25:00", "10:30").is_err());
+        assert!(resolve_window(&session, "10", "10:30").is_err());
+    }
+}
@@ -1,15 +1,22 @@
 use crate::bottlenecks::{detect_all, Bottleneck};
-use crate::flamegraph::{extract_spans, generate_svg_by_pr, ActivityType};
+use crate::cost;
+use crate::error::AistError;
+use crate::exclusions;
+use crate::flamegraph::{
+    extract_spans, render_svg, render_svg_by_pr, ActivityType, ColorMap, Theme, DEFAULT_GAP_SECONDS,
+};
 use crate::github::{load_cache, RepoCache};
+use crate::metrics::{self, round_percentages};
 use crate::parser::Session;
 use crate::prs::calculate_pr_metrics;
-use chrono::{DateTime, Duration, Local, Utc};
+use crate::report::Report;
+use chrono::{DateTime, Local, Utc};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
 /// Filter sessions that belong to a specific GitHub repo
-/// by matching session git_branch to PR branches from the cache
+/// by matching session branches to PR branches from the cache
 pub fn filter_sessions_by_repo(
     sessions: &[Session],
     owner: &str,
@@ -24,60 +31,286 @@ pub fn filter_sessions_by_repo(
     let pr_branches: std::collections::HashSet<&str> =
         cache.prs.iter().map(|pr| pr.branch.as_str()).collect();
 
-    // Filter sessions where git_branch matches a PR branch
+    // Filter sessions where any branch matches a PR branch
     let filtered: Vec<Session> = sessions
         .iter()
-        .filter(|s| {
-            s.git_branch
-                .as_ref()
-                .map(|b| pr_branches.contains(b.as_str()))
-                .unwrap_or(false)
-        })
+        .filter(|s| s.branches().iter().any(|b| pr_branches.contains(b)))
         .cloned()
         .collect();
 
     (filtered, Some(cache))
 }
 
-/// Filter sessions by time period
-pub fn filter_sessions_by_period(sessions: &[Session], period: &str) -> Vec<Session> {
-    let now = Utc::now();
-    let cutoff = match period {
-        "day" => now - Duration::days(1),
-        "week" => now - Duration::weeks(1),
-        "month" => now - Duration::days(30),
-        _ => return sessions.to_vec(), // "all"
-    };
-
-    sessions
-        .iter()
-        .filter(|s| s.start_time.map(|t| t > cutoff).unwrap_or(false))
-        .cloned()
-        .collect()
-}
-
 /// Generate an HTML report for the given sessions
 pub fn generate_html_report(
     sessions: &[Session],
     cache: &RepoCache,
     output_path: &Path,
-) -> Result<(), String> {
+    theme: Theme,
+    colors: &ColorMap,
+) -> Result<(), AistError> {
     let (start_date, end_date) = get_date_range(sessions);
 
     // Generate flamegraph SVG
-    let flamegraph_svg = generate_flamegraph_svg(sessions);
+    let flamegraph_svg = generate_flamegraph_svg(sessions, cache, theme, colors);
+
+    let sections = [
+        generate_summary_section(sessions),
+        generate_time_breakdown_section(sessions),
+        generate_bottlenecks_section(sessions),
+        generate_pr_breakdown_section(sessions, cache),
+        generate_recommendations_section(sessions),
+        generate_flamegraph_section(&flamegraph_svg, "no PR data available"),
+    ];
+    let heading = format!("{}/{}", cache.owner, cache.repo);
+    let html = html_page(
+        &format!("AI Session Report - {}", heading),
+        &heading,
+        &format!("{} — {}", start_date, end_date),
+        &sections,
+        theme,
+    );
 
-    // Build HTML content
-    let html = format!(
-        r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>AI Session Report - {owner}/{repo}</title>
-    <style>
-        * {{ margin: 0; padding: 0; box-sizing: border-box; }}
-        body {{
+    fs::write(output_path, html)
+        .map_err(|e| AistError::Io("Failed to write HTML".to_string(), e))?;
+
+    Ok(())
+}
+
+/// Write `report` as a single shareable HTML file. Unlike
+/// `generate_html_report` this needs no GitHub cache: everything comes from
+/// the report and its sessions, and the report JSON is embedded for reuse.
+pub fn generate_standalone_html_report(
+    report: &Report,
+    sessions: &[Session],
+    output_path: &Path,
+    colors: &ColorMap,
+) -> Result<(), AistError> {
+    let html = render_standalone_html(report, sessions, colors)?;
+    fs::write(output_path, html)
+        .map_err(|e| AistError::Io("Failed to write HTML".to_string(), e))?;
+    Ok(())
+}
+
+/// Render `report` and the sessions it covers as a standalone HTML page
+pub fn render_standalone_html(
+    report: &Report,
+    sessions: &[Session],
+    colors: &ColorMap,
+) -> Result<String, AistError> {
+    let (start_date, end_date) = get_date_range(sessions);
+    let report_json = serde_json::to_string(report)
+        .map_err(|e| AistError::Parse(format!("Failed to serialize report: {}", e)))?;
+
+    let sections = [
+        report_summary_section(report),
+        report_time_breakdown_section(report),
+        report_bottlenecks_section(report),
+        session_table_section(sessions),
+        report_recommendations_section(report),
+        generate_flamegraph_section(
+            &render_svg(sessions, None, Theme::Light, colors).ok(),
+            "no timed sessions",
+        ),
+        // "</" would end the script element early
+        format!(
+            r#"<script type="application/json" id="aist-report">{}</script>"#,
+            report_json.replace("</", r"<\/")
+        ),
+    ];
+
+    Ok(html_page(
+        &format!("AI Session Report - {}", report.period),
+        &format!("Report: {}", report.period),
+        &format!("{} — {}", start_date, end_date),
+        &sections,
+        Theme::Light,
+    ))
+}
+
+fn report_summary_section(report: &Report) -> String {
+    format!(
+        r#"<h2>Summary</h2>
+    <div class="card">
+        <div class="stats-grid">
+            <div class="stat">
+                <div class="stat-value">{sessions}</div>
+                <div class="stat-label">Sessions</div>
+            </div>
+            <div class="stat">
+                <div class="stat-value">{time}</div>
+                <div class="stat-label">Total Time</div>
+            </div>
+            <div class="stat">
+                <div class="stat-value">{efficiency:.0}%</div>
+                <div class="stat-label">Efficiency</div>
+            </div>
+        </div>
+    </div>"#,
+        sessions = report.session_count,
+        time = format_duration(report.total_hours * 60.0),
+        efficiency = report.efficiency_percent,
+    )
+}
+
+fn report_time_breakdown_section(report: &Report) -> String {
+    let tb = &report.time_breakdown;
+    let rows = [
+        ("Productive", tb.productive_minutes, "bar-productive"),
+        ("Error loops", tb.error_loop_minutes, "bar-error"),
+        ("Exploration", tb.exploration_minutes, "bar-reading"),
+        ("Edit thrashing", tb.edit_thrashing_minutes, "bar-thinking"),
+        ("Long gaps", tb.long_gap_minutes, "bar-gap"),
+    ];
+    let minutes: Vec<f64> = rows.iter().map(|(_, m, _)| *m).collect();
+
+    if minutes.iter().sum::<f64>() == 0.0 {
+        return r#"<h2>Time Breakdown</h2><div class="card"><p>No activity data available.</p></div>"#.to_string();
+    }
+
+    let rows: String = rows
+        .iter()
+        .zip(round_percentages(&minutes))
+        .map(|((name, minutes, bar_class), percentage)| {
+            format!(
+                r#"<tr>
+                <td>{name}</td>
+                <td>{time}</td>
+                <td>{percentage}%</td>
+                <td><div class="bar-container"><div class="bar {bar_class}" style="width: {percentage}%"></div></div></td>
+            </tr>"#,
+                name = name,
+                time = format_duration(*minutes),
+                percentage = percentage,
+                bar_class = bar_class,
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<h2>Time Breakdown</h2>
+    <div class="card">
+        <table>
+            <thead>
+                <tr><th>Activity</th><th>Time</th><th>%</th><th>Distribution</th></tr>
+            </thead>
+            <tbody>
+                {rows}
+            </tbody>
+        </table>
+    </div>"#,
+        rows = rows
+    )
+}
+
+fn report_bottlenecks_section(report: &Report) -> String {
+    if report.top_bottlenecks.is_empty() {
+        return r#"<h2>Bottlenecks</h2><div class="card"><p>No significant bottlenecks detected.</p></div>"#.to_string();
+    }
+
+    let rows: String = report
+        .top_bottlenecks
+        .iter()
+        .map(|b| {
+            format!(
+                r#"<tr><td>{type_name}</td><td>{count}</td><td>{duration}</td><td>{description}</td></tr>"#,
+                type_name = html_escape(&b.bottleneck_type),
+                count = b.count,
+                duration = format_duration(b.total_minutes),
+                description = html_escape(&b.description),
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<h2>Bottlenecks</h2>
+    <div class="card">
+        <table>
+            <thead>
+                <tr><th>Type</th><th>Count</th><th>Time Lost</th><th>Detail</th></tr>
+            </thead>
+            <tbody>
+                {rows}
+            </tbody>
+        </table>
+    </div>"#,
+        rows = rows
+    )
+}
+
+fn session_table_section(sessions: &[Session]) -> String {
+    if sessions.is_empty() {
+        return r#"<h2>Sessions</h2><div class="card"><p>No sessions in this period.</p></div>"#
+            .to_string();
+    }
+
+    let mut sorted: Vec<&Session> = sessions.iter().collect();
+    sorted.sort_by(|a, b| {
+        a.start_time
+            .cmp(&b.start_time)
+            .then_with(|| a.session_id.cmp(&b.session_id))
+    });
+
+    let rows: String = sorted
+        .iter()
+        .map(|s| {
+            let started = s
+                .start_time
+                .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            let project = s.project.rsplit('/').next().unwrap_or(&s.project);
+            format!(
+                r#"<tr><td>{id}</td><td>{project}</td><td>{started}</td><td>{duration}</td><td>${cost:.2}</td></tr>"#,
+                id = html_escape(metrics::short_id(&s.session_id, 8)),
+                project = html_escape(project),
+                started = started,
+                duration = format_duration(exclusions::active_minutes(s)),
+                cost = cost::calculate_cost(s),
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<h2>Sessions</h2>
+    <div class="card">
+        <table>
+            <thead>
+                <tr><th>Session</th><th>Project</th><th>Started</th><th>Duration</th><th>Cost</th></tr>
+            </thead>
+            <tbody>
+                {rows}
+            </tbody>
+        </table>
+    </div>"#,
+        rows = rows
+    )
+}
+
+fn report_recommendations_section(report: &Report) -> String {
+    let items: String = if report.recommendations.is_empty() {
+        r#"<div class="recommendation">No major issues detected. Keep up the good work!</div>"#
+            .to_string()
+    } else {
+        report
+            .recommendations
+            .iter()
+            .map(|r| format!(r#"<div class="recommendation">{}</div>"#, html_escape(r)))
+            .collect()
+    };
+
+    format!(
+        r#"<h2>Recommendations</h2>
+    <div class="card">
+        {items}
+    </div>"#,
+        items = items
+    )
+}
+
+/// Stylesheet shared by every HTML report
+const REPORT_CSS: &str = r#"
+        * { margin: 0; padding: 0; box-sizing: border-box; }
+        body {
             font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Oxygen, Ubuntu, sans-serif;
             line-height: 1.6;
             color: #333;
@@ -85,72 +318,90 @@ pub fn generate_html_report(
             margin: 0 auto;
             padding: 2rem;
             background: #f9fafb;
-        }}
-        h1 {{ font-size: 2rem; color: #111; margin-bottom: 0.25rem; }}
-        h2 {{ font-size: 1.25rem; color: #374151; margin: 2rem 0 1rem; border-bottom: 2px solid #e5e7eb; padding-bottom: 0.5rem; }}
-        .subtitle {{ color: #6b7280; font-size: 1rem; margin-bottom: 0.5rem; }}
-        .date-range {{ color: #9ca3af; font-size: 0.875rem; margin-bottom: 2rem; }}
-        .card {{ background: white; border-radius: 8px; padding: 1.5rem; margin-bottom: 1.5rem; box-shadow: 0 1px 3px rgba(0,0,0,0.1); }}
-        .stats-grid {{ display: grid; grid-template-columns: repeat(auto-fit, minmax(150px, 1fr)); gap: 1rem; }}
-        .stat {{ text-align: center; padding: 1rem; }}
-        .stat-value {{ font-size: 2rem; font-weight: bold; color: #111; }}
-        .stat-label {{ font-size: 0.875rem; color: #6b7280; }}
-        table {{ width: 100%; border-collapse: collapse; }}
-        th, td {{ padding: 0.75rem 1rem; text-align: left; border-bottom: 1px solid #e5e7eb; }}
-        th {{ background: #f9fafb; font-weight: 600; color: #374151; }}
-        tr:hover {{ background: #f9fafb; }}
-        .bar-container {{ width: 100%; background: #e5e7eb; border-radius: 4px; height: 8px; }}
-        .bar {{ height: 100%; border-radius: 4px; }}
-        .bar-productive {{ background: #4ade80; }}
-        .bar-reading {{ background: #facc15; }}
-        .bar-executing {{ background: #60a5fa; }}
-        .bar-error {{ background: #f87171; }}
-        .bar-gap {{ background: #9ca3af; }}
-        .bar-thinking {{ background: #c4b5fd; }}
-        .recommendation {{ padding: 0.75rem 1rem; margin: 0.5rem 0; background: #f0f9ff; border-left: 4px solid #3b82f6; border-radius: 0 4px 4px 0; }}
-        .flamegraph-container {{ margin-top: 1rem; overflow-x: auto; }}
-        .flamegraph-container svg {{ max-width: 100%; height: auto; }}
-        .footer {{ margin-top: 3rem; padding-top: 1rem; border-top: 1px solid #e5e7eb; color: #9ca3af; font-size: 0.875rem; text-align: center; }}
-    </style>
+        }
+        h1 { font-size: 2rem; color: #111; margin-bottom: 0.25rem; }
+        h2 { font-size: 1.25rem; color: #374151; margin: 2rem 0 1rem; border-bottom: 2px solid #e5e7eb; padding-bottom: 0.5rem; }
+        .subtitle { color: #6b7280; font-size: 1rem; margin-bottom: 0.5rem; }
+        .date-range { color: #9ca3af; font-size: 0.875rem; margin-bottom: 2rem; }
+        .card { background: white; border-radius: 8px; padding: 1.5rem; margin-bottom: 1.5rem; box-shadow: 0 1px 3px rgba(0,0,0,0.1); }
+        .stats-grid { display: grid; grid-template-columns: repeat(auto-fit, minmax(150px, 1fr)); gap: 1rem; }
+        .stat { text-align: center; padding: 1rem; }
+        .stat-value { font-size: 2rem; font-weight: bold; color: #111; }
+        .stat-label { font-size: 0.875rem; color: #6b7280; }
+        table { width: 100%; border-collapse: collapse; }
+        th, td { padding: 0.75rem 1rem; text-align: left; border-bottom: 1px solid #e5e7eb; }
+        th { background: #f9fafb; font-weight: 600; color: #374151; }
+        tr:hover { background: #f9fafb; }
+        .bar-container { width: 100%; background: #e5e7eb; border-radius: 4px; height: 8px; }
+        .bar { height: 100%; border-radius: 4px; }
+        .bar-productive { background: #4ade80; }
+        .bar-reading { background: #facc15; }
+        .bar-executing { background: #60a5fa; }
+        .bar-error { background: #f87171; }
+        .bar-gap { background: #9ca3af; }
+        .bar-thinking { background: #c4b5fd; }
+        .recommendation { padding: 0.75rem 1rem; margin: 0.5rem 0; background: #f0f9ff; border-left: 4px solid #3b82f6; border-radius: 0 4px 4px 0; }
+        .flamegraph-container { margin-top: 1rem; overflow-x: auto; }
+        .flamegraph-container svg { max-width: 100%; height: auto; }
+        .footer { margin-top: 3rem; padding-top: 1rem; border-top: 1px solid #e5e7eb; color: #9ca3af; font-size: 0.875rem; text-align: center; }
+"#;
+
+/// Overrides layered on `REPORT_CSS` for the dark theme
+const REPORT_CSS_DARK: &str = r#"
+        body { color: #e5e7eb; background: #111827; }
+        h1, .stat-value { color: #f9fafb; }
+        h2 { color: #e5e7eb; border-bottom-color: #374151; }
+        .subtitle, .stat-label { color: #9ca3af; }
+        .date-range, .footer { color: #6b7280; }
+        .card { background: #1f2937; box-shadow: none; }
+        th, td { border-bottom-color: #374151; }
+        th { background: #111827; color: #e5e7eb; }
+        tr:hover { background: #111827; }
+        .bar-container { background: #374151; }
+        .recommendation { background: #172554; }
+        .footer { border-top-color: #374151; }
+"#;
+
+/// Wrap report sections in a self-contained HTML document
+fn html_page(
+    title: &str,
+    heading: &str,
+    date_range: &str,
+    sections: &[String],
+    theme: Theme,
+) -> String {
+    let css = match theme {
+        Theme::Light => REPORT_CSS.to_string(),
+        Theme::Dark => format!("{}{}", REPORT_CSS, REPORT_CSS_DARK),
+    };
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{title}</title>
+    <style>{css}    </style>
 </head>
 <body>
-    <h1>{owner}/{repo}</h1>
+    <h1>{heading}</h1>
     <p class="subtitle">AI Session Report</p>
-    <p class="date-range">{start_date} — {end_date}</p>
+    <p class="date-range">{date_range}</p>
 
-    {summary_section}
-
-    {time_breakdown_section}
-
-    {bottlenecks_section}
-
-    {pr_breakdown_section}
-
-    {recommendations_section}
-
-    {flamegraph_section}
+    {sections}
 
     <div class="footer">
         Generated by <strong>aist</strong> (AI Session Tracker) • {generated_at}
     </div>
 </body>
 </html>"#,
-        owner = cache.owner,
-        repo = cache.repo,
-        start_date = start_date,
-        end_date = end_date,
-        summary_section = generate_summary_section(sessions),
-        time_breakdown_section = generate_time_breakdown_section(sessions),
-        bottlenecks_section = generate_bottlenecks_section(sessions),
-        pr_breakdown_section = generate_pr_breakdown_section(sessions, cache),
-        recommendations_section = generate_recommendations_section(sessions),
-        flamegraph_section = generate_flamegraph_section(&flamegraph_svg),
+        title = html_escape(title),
+        css = css,
+        heading = html_escape(heading),
+        date_range = date_range,
+        sections = sections.join("\n\n    "),
         generated_at = Local::now().format("%Y-%m-%d %H:%M"),
-    );
-
-    fs::write(output_path, html).map_err(|e| format!("Failed to write HTML: {}", e))?;
-
-    Ok(())
+    )
 }
 
 fn get_date_range(sessions: &[Session]) -> (String, String) {
@@ -189,7 +440,7 @@ fn generate_summary_section(sessions: &[Session]) -> String {
     let mut gap_time = 0.0;
 
     for session in sessions {
-        for span in extract_spans(session) {
+        for span in extract_spans(session, DEFAULT_GAP_SECONDS) {
             let duration = (span.end - span.start).num_seconds() as f64 / 60.0;
             match span.activity {
                 ActivityType::Error => error_time += duration,
@@ -234,7 +485,7 @@ fn generate_time_breakdown_section(sessions: &[Session]) -> String {
     let mut time_by_activity: HashMap<ActivityType, f64> = HashMap::new();
 
     for session in sessions {
-        for span in extract_spans(session) {
+        for span in extract_spans(session, DEFAULT_GAP_SECONDS) {
             let duration = (span.end - span.start).num_seconds() as f64 / 60.0;
             *time_by_activity.entry(span.activity).or_insert(0.0) += duration;
         }
@@ -247,12 +498,19 @@ fn generate_time_breakdown_section(sessions: &[Session]) -> String {
     }
 
     let mut activities: Vec<_> = time_by_activity.into_iter().collect();
-    activities.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    activities.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+
+    let minutes: Vec<f64> = activities.iter().map(|(_, m)| *m).collect();
+    let percentages = round_percentages(&minutes);
 
     let rows: String = activities
         .iter()
-        .map(|(activity, minutes)| {
-            let percentage = (minutes / total_time * 100.0) as u32;
+        .zip(percentages)
+        .map(|((activity, minutes), percentage)| {
             let (name, bar_class) = match activity {
                 ActivityType::Productive => ("Productive", "bar-productive"),
                 ActivityType::Reading => ("Reading/Search", "bar-reading"),
@@ -307,6 +565,10 @@ fn generate_bottlenecks_section(sessions: &[Session]) -> String {
             Bottleneck::ExplorationSpiral(e) => ("Exploration Spiral", e.duration_minutes),
             Bottleneck::EditThrashing(e) => ("Edit Thrashing", e.duration_minutes),
             Bottleneck::LongGap(g) => ("Long Gap", g.gap_minutes),
+            Bottleneck::FlakyTool(f) => ("Flaky Tool", f.duration_minutes),
+            Bottleneck::TestFailureLoop(t) => ("Test Failure Loop", t.duration_minutes),
+            Bottleneck::ApprovalStall(a) => ("Approval Stall", a.wait_minutes),
+            Bottleneck::ContextSwitch(c) => ("Context Switching", c.break_minutes),
         };
         let entry = by_type.entry(type_name.to_string()).or_insert((0, 0.0));
         entry.0 += 1;
@@ -318,6 +580,7 @@ fn generate_bottlenecks_section(sessions: &[Session]) -> String {
         b.1 .1
             .partial_cmp(&a.1 .1)
             .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
     });
 
     let rows: String = types
@@ -360,11 +623,7 @@ fn generate_pr_breakdown_section(sessions: &[Session], cache: &RepoCache) -> Str
         .iter()
         .take(10)
         .map(|m| {
-            let title_display = if m.title.len() > 50 {
-                format!("{}...", &m.title[..47])
-            } else {
-                m.title.clone()
-            };
+            let title_display = metrics::truncate_chars(&m.title, 50);
             format!(
                 r#"<tr><td>#{number}</td><td>{title}</td><td>{time}</td><td>{sessions}</td></tr>"#,
                 number = m.pr_number,
@@ -398,6 +657,10 @@ fn generate_recommendations_section(sessions: &[Session]) -> String {
     let mut exploration_spirals = 0;
     let mut edit_thrashing = 0;
     let mut long_gaps = 0;
+    let mut flaky_tools = 0;
+    let mut test_loops = 0;
+    let mut approval_stalls = 0;
+    let mut context_switches = 0;
 
     for b in &bottlenecks {
         match b {
@@ -405,6 +668,10 @@ fn generate_recommendations_section(sessions: &[Session]) -> String {
             Bottleneck::ExplorationSpiral(_) => exploration_spirals += 1,
             Bottleneck::EditThrashing(_) => edit_thrashing += 1,
             Bottleneck::LongGap(_) => long_gaps += 1,
+            Bottleneck::FlakyTool(_) => flaky_tools += 1,
+            Bottleneck::TestFailureLoop(_) => test_loops += 1,
+            Bottleneck::ApprovalStall(_) => approval_stalls += 1,
+            Bottleneck::ContextSwitch(_) => context_switches += 1,
         }
     }
 
@@ -438,6 +705,34 @@ fn generate_recommendations_section(sessions: &[Session]) -> String {
         ));
     }
 
+    if flaky_tools > 0 {
+        recommendations.push(format!(
+            "<strong>{} flaky tools</strong> alternated between passing and failing. Stabilize the environment before blaming the prompt.",
+            flaky_tools
+        ));
+    }
+
+    if test_loops > 0 {
+        recommendations.push(format!(
+            "<strong>{} test failure loops</strong> kept rerunning a failing suite. Run just the failing test and read its output before editing again.",
+            test_loops
+        ));
+    }
+
+    if approval_stalls > 0 {
+        recommendations.push(format!(
+            "<strong>{} approval stalls</strong> held tool calls on a permission prompt. Allow-list the tools you always approve.",
+            approval_stalls
+        ));
+    }
+
+    if context_switches > 0 {
+        recommendations.push(format!(
+            "<strong>{} stretches of context switching</strong> hopped between 3+ projects. Batch work per project to avoid re-orienting.",
+            context_switches
+        ));
+    }
+
     if recommendations.is_empty() {
         recommendations.push("No major issues detected. Keep up the good work!".to_string());
     }
@@ -456,20 +751,16 @@ fn generate_recommendations_section(sessions: &[Session]) -> String {
     )
 }
 
-fn generate_flamegraph_svg(sessions: &[Session]) -> Option<String> {
-    let temp_svg = std::env::temp_dir().join("aist-flamegraph-temp.svg");
-
-    if generate_svg_by_pr(sessions, &temp_svg).is_err() {
-        return None;
-    }
-
-    let svg_content = fs::read_to_string(&temp_svg).ok();
-    let _ = fs::remove_file(&temp_svg);
-
-    svg_content
+fn generate_flamegraph_svg(
+    sessions: &[Session],
+    cache: &RepoCache,
+    theme: Theme,
+    colors: &ColorMap,
+) -> Option<String> {
+    render_svg_by_pr(sessions, cache, theme, colors).ok()
 }
 
-fn generate_flamegraph_section(svg: &Option<String>) -> String {
+fn generate_flamegraph_section(svg: &Option<String>, missing: &str) -> String {
     match svg {
         Some(svg_content) => {
             format!(
@@ -482,11 +773,13 @@ fn generate_flamegraph_section(svg: &Option<String>) -> String {
                 svg = svg_content
             )
         }
-        None => r#"<h2>Session Flamegraph</h2>
+        None => format!(
+            r#"<h2>Session Flamegraph</h2>
     <div class="card">
-        <p>Could not generate flamegraph (no PR data available).</p>
-    </div>"#
-            .to_string(),
+        <p>Could not generate flamegraph ({}).</p>
+    </div>"#,
+            missing
+        ),
     }
 }
 
@@ -524,17 +817,23 @@ mod tests {
             git_branch: branch.map(|s| s.to_string()),
             start_time: Some(start),
             end_time: Some(end),
-            messages: vec![],
+            ..Default::default()
         }
     }
 
-    #[allow(dead_code)]
     fn make_cache(prs: Vec<PrMapping>) -> RepoCache {
         RepoCache {
+            host: Default::default(),
+            domain: None,
             owner: "test".to_string(),
             repo: "repo".to_string(),
             prs,
             synced_at: "2026-01-01T00:00:00Z".to_string(),
+            truncated: false,
+            issue_titles: HashMap::new(),
+            state: None,
+            limit: None,
+            ttl_hours: None,
         }
     }
 
@@ -546,13 +845,58 @@ mod tests {
     }
 
     #[test]
-    fn test_filter_sessions_by_period_all() {
+    fn test_standalone_html_embeds_report() {
         let sessions = vec![
-            make_session("s1", Some("main"), 30),
+            make_session("s1", None, 30),
             make_session("s2", Some("feature"), 45),
         ];
-        let filtered = filter_sessions_by_period(&sessions, "all");
-        assert_eq!(filtered.len(), 2);
+        let report = crate::report::generate_report(&sessions, "all");
+
+        let html = render_standalone_html(&report, &sessions, &ColorMap::default()).unwrap();
+
+        assert!(html.contains("<h2>Summary</h2>"));
+        assert!(html.contains("<h2>Time Breakdown</h2>"));
+        assert!(html.contains("<svg"));
+        assert!(html.contains(r#"<script type="application/json" id="aist-report">"#));
+        assert!(html.contains(r#""session_count":2"#));
+    }
+
+    #[test]
+    fn test_standalone_html_uses_color_overrides() {
+        let sessions = vec![make_session("s1", None, 30)];
+        let report = crate::report::generate_report(&sessions, "all");
+        let overrides = HashMap::from([("gap".to_string(), "#123456".to_string())]);
+        let colors = ColorMap::from_overrides(overrides).unwrap();
+
+        let html = render_standalone_html(&report, &sessions, &colors).unwrap();
+
+        assert!(html.contains("#123456"));
+    }
+
+    #[test]
+    fn test_generate_html_report_writes_file() {
+        let sessions = vec![
+            make_session("s1", Some("feature/login"), 30),
+            make_session("s2", Some("feature/login"), 45),
+        ];
+        let cache = make_cache(vec![PrMapping {
+            pr_number: 7,
+            title: "Add login".to_string(),
+            branch: "feature/login".to_string(),
+            closed_issues: vec![],
+            merged_at: None,
+            state: None,
+        }]);
+        let dir = std::env::temp_dir().join("aist-export-tests");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("writes-file-{}.html", std::process::id()));
+
+        generate_html_report(&sessions, &cache, &path, Theme::Light, &ColorMap::default()).unwrap();
+        let html = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert!(html.contains("test/repo"));
+        assert!(html.contains("<svg"));
     }
 
     #[test]
@@ -1,13 +1,14 @@
-use crate::github::{load_current_repo_cache, RepoCache};
+use crate::error::AistError;
+use crate::exclusions::ExcludedWindow;
+use crate::github::{PrMapping, RepoCache};
+use crate::metrics;
 use crate::parser::{MessageType, Session};
-use chrono::{DateTime, Utc};
+use base64::Engine;
+use chrono::{DateTime, Local, Timelike, Utc};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
 
 /// Activity type for coloring
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum ActivityType {
     Productive, // Edit, Write - making changes
     Reading,    // Read, Grep, Glob - exploring
@@ -39,6 +40,95 @@ impl ActivityType {
             ActivityType::Thinking => "Thinking",
         }
     }
+
+    const ALL: [ActivityType; 6] = [
+        ActivityType::Productive,
+        ActivityType::Reading,
+        ActivityType::Executing,
+        ActivityType::Error,
+        ActivityType::Gap,
+        ActivityType::Thinking,
+    ];
+
+    /// Match a color file key: the full label ("Reading/Search") or its
+    /// first word ("reading"), case-insensitively
+    fn from_key(key: &str) -> Option<ActivityType> {
+        ActivityType::ALL.into_iter().find(|a| {
+            let label = a.label();
+            let short = label.split('/').next().unwrap_or(label);
+            key.eq_ignore_ascii_case(label) || key.eq_ignore_ascii_case(short)
+        })
+    }
+}
+
+/// Name of the file in the aist config dir that overrides activity colors,
+/// as a JSON object of `"Activity": "#rrggbb"`
+const COLORS_FILE: &str = "colors.json";
+
+/// Activity colors used when rendering, starting from the built-in palette
+#[derive(Debug, Clone, Default)]
+pub struct ColorMap {
+    overrides: HashMap<ActivityType, String>,
+}
+
+impl ColorMap {
+    /// Build a color map from activity -> hex overrides, rejecting unknown
+    /// activities and anything that isn't `#rgb` or `#rrggbb`
+    pub fn from_overrides(overrides: HashMap<String, String>) -> Result<ColorMap, String> {
+        let mut map = ColorMap::default();
+        for (key, hex) in overrides {
+            let activity = ActivityType::from_key(&key).ok_or_else(|| {
+                let known: Vec<&str> = ActivityType::ALL.iter().map(|a| a.label()).collect();
+                format!(
+                    "unknown activity '{}' (expected one of: {})",
+                    key,
+                    known.join(", ")
+                )
+            })?;
+            if !is_hex_color(&hex) {
+                return Err(format!(
+                    "invalid color '{}' for {}: expected #rgb or #rrggbb",
+                    hex,
+                    activity.label()
+                ));
+            }
+            map.overrides.insert(activity, hex);
+        }
+        Ok(map)
+    }
+
+    pub fn color(&self, activity: ActivityType) -> &str {
+        self.overrides
+            .get(&activity)
+            .map(String::as_str)
+            .unwrap_or_else(|| activity.color())
+    }
+}
+
+fn is_hex_color(value: &str) -> bool {
+    value.strip_prefix('#').is_some_and(|digits| {
+        matches!(digits.len(), 3 | 6) && digits.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+/// The built-in palette merged with any overrides from the config dir. A
+/// missing file means the defaults; a malformed one is an error.
+pub fn load_color_map() -> Result<ColorMap, AistError> {
+    let path = crate::state::config_dir().join(COLORS_FILE);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(ColorMap::default()),
+        Err(e) => {
+            return Err(AistError::Io(
+                format!("Failed to read {}", path.display()),
+                e,
+            ))
+        }
+    };
+    let overrides: HashMap<String, String> = serde_json::from_str(&content)
+        .map_err(|e| AistError::Parse(format!("Invalid {}: {}", path.display(), e)))?;
+    ColorMap::from_overrides(overrides)
+        .map_err(|e| AistError::Parse(format!("Invalid {}: {}", path.display(), e)))
 }
 
 /// A time span with an activity type
@@ -48,39 +138,84 @@ pub struct TimeSpan {
     pub end: DateTime<Utc>,
     pub activity: ActivityType,
     pub label: String,
+    /// The user prompt in effect when the span began, trimmed for display
+    pub prompt: Option<String>,
+}
+
+impl TimeSpan {
+    /// The label, followed by the prompt behind the work when there is one
+    fn description(&self) -> String {
+        match &self.prompt {
+            Some(prompt) => format!("{} — \"{}\"", self.label, prompt),
+            None => self.label.clone(),
+        }
+    }
 }
 
-/// Extract time spans from a session
-pub fn extract_spans(session: &Session) -> Vec<TimeSpan> {
+/// Pauses longer than this between messages become `Gap` spans
+pub const DEFAULT_GAP_SECONDS: i64 = 120;
+
+/// Extract time spans from a session. Pauses longer than `gap_secs`
+/// between messages become `Gap` spans; pass `bottlenecks::GAP_FLOOR_SECONDS`
+/// to agree with the long gaps the bottleneck report flags.
+pub fn extract_spans(session: &Session, gap_secs: i64) -> Vec<TimeSpan> {
     let mut spans = Vec::new();
     let mut prev_time: Option<DateTime<Utc>> = None;
-    let mut current_activity: Option<(DateTime<Utc>, ActivityType, String)> = None;
+    #[allow(clippy::type_complexity)]
+    let mut current_activity: Option<(DateTime<Utc>, ActivityType, String, Option<String>)> = None;
+    // Build/test command issued by the previous timestamped message
+    let mut pending_build: Option<&str> = None;
+    // Last prompt the user typed, attached to spans until the next one
+    let mut prompt: Option<String> = None;
 
     for msg in &session.messages {
         let Some(ts) = msg.timestamp else { continue };
 
-        // Check for gaps (>2 min between messages)
+        // Check for gaps between messages
         if let Some(prev) = prev_time {
-            let gap_secs = (ts - prev).num_seconds();
-            if gap_secs > 120 {
+            let pause_secs = (ts - prev).num_seconds();
+            if pause_secs > gap_secs {
                 // Close any current activity
-                if let Some((start, activity, label)) = current_activity.take() {
+                if let Some((start, activity, label, prompt)) = current_activity.take() {
                     spans.push(TimeSpan {
                         start,
                         end: prev,
                         activity,
                         label,
+                        prompt,
                     });
                 }
-                // Add gap span
+                // A pause right after a build/test call is the command running
+                let (activity, label) = match pending_build {
+                    Some(cmd) => (
+                        ActivityType::Executing,
+                        format!(
+                            "{:.0}m waiting on {}",
+                            pause_secs as f64 / 60.0,
+                            short_command(cmd)
+                        ),
+                    ),
+                    None => (
+                        ActivityType::Gap,
+                        format!("{:.0}m pause", pause_secs as f64 / 60.0),
+                    ),
+                };
                 spans.push(TimeSpan {
                     start: prev,
                     end: ts,
-                    activity: ActivityType::Gap,
-                    label: format!("{:.0}m pause", gap_secs as f64 / 60.0),
+                    activity,
+                    label,
+                    prompt: prompt.clone(),
                 });
             }
         }
+        pending_build = metrics::build_or_test_command(msg);
+
+        if msg.msg_type == MessageType::User && !msg.is_hook {
+            if let Some(text) = msg.text_content.as_deref().filter(|t| !t.trim().is_empty()) {
+                prompt = Some(short_prompt(text));
+            }
+        }
 
         // Determine activity type from message
         let (activity, label) = if msg.msg_type == MessageType::Assistant {
@@ -134,40 +269,485 @@ pub fn extract_spans(session: &Session) -> Vec<TimeSpan> {
         };
 
         // Close previous activity if type changed
-        if let Some((start, prev_activity, prev_label)) = &current_activity {
+        if let Some((start, prev_activity, prev_label, prev_prompt)) = &current_activity {
             if *prev_activity != activity {
                 spans.push(TimeSpan {
                     start: *start,
                     end: ts,
                     activity: *prev_activity,
                     label: prev_label.clone(),
+                    prompt: prev_prompt.clone(),
                 });
-                current_activity = Some((ts, activity, label));
+                current_activity = Some((ts, activity, label, prompt.clone()));
             }
         } else {
-            current_activity = Some((ts, activity, label));
+            current_activity = Some((ts, activity, label, prompt.clone()));
         }
 
         prev_time = Some(ts);
     }
 
     // Close final activity
-    if let Some((start, activity, label)) = current_activity {
+    if let Some((start, activity, label, prompt)) = current_activity {
         if let Some(end) = session.end_time {
             spans.push(TimeSpan {
                 start,
                 end,
                 activity,
                 label,
+                prompt,
             });
         }
     }
 
-    spans
+    if session.excluded_windows.is_empty() {
+        spans
+    } else {
+        subtract_windows(spans, &session.excluded_windows)
+    }
+}
+
+/// First line of a command, cut to a label-friendly length
+fn short_command(cmd: &str) -> String {
+    let line = cmd.lines().next().unwrap_or("").trim();
+    if line.chars().count() > 30 {
+        format!("{}...", line.chars().take(27).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+/// First non-empty line of a prompt, cut to a tooltip-friendly length
+fn short_prompt(text: &str) -> String {
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or("");
+    if line.chars().count() > 60 {
+        format!("{}...", line.chars().take(57).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+/// Cut excluded windows out of the spans, splitting any span that straddles one
+fn subtract_windows(spans: Vec<TimeSpan>, windows: &[ExcludedWindow]) -> Vec<TimeSpan> {
+    let mut pieces = spans;
+    for window in windows {
+        pieces = pieces
+            .into_iter()
+            .flat_map(|span| {
+                if span.end <= window.start || span.start >= window.end {
+                    return vec![span];
+                }
+                let mut kept = Vec::new();
+                if span.start < window.start {
+                    kept.push(TimeSpan {
+                        end: window.start,
+                        label: span.label.clone(),
+                        prompt: span.prompt.clone(),
+                        ..span
+                    });
+                }
+                if span.end > window.end {
+                    kept.push(TimeSpan {
+                        start: window.end,
+                        ..span
+                    });
+                }
+                kept
+            })
+            .collect();
+    }
+    pieces
+}
+
+/// What the rows of a flamegraph are grouped by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Grouping {
+    Session,
+    Project,
+    Pr,
+    Issue,
+}
+
+impl Grouping {
+    /// Parse a `--group-by` value; anything unrecognized is the session view
+    pub fn parse(group_by: &str) -> Self {
+        match group_by {
+            "project" => Grouping::Project,
+            "pr" => Grouping::Pr,
+            "issue" => Grouping::Issue,
+            _ => Grouping::Session,
+        }
+    }
+}
+
+/// Color scheme for rendered SVG and HTML. Only the chrome changes; the
+/// activity colors read fine on both backgrounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
 }
 
-/// Generate an SVG flamegraph for sessions
-pub fn generate_svg(sessions: &[Session], output_path: &Path) -> std::io::Result<()> {
+impl Theme {
+    pub fn background(&self) -> &'static str {
+        match self {
+            Theme::Light => "#f9fafb",
+            Theme::Dark => "#111827",
+        }
+    }
+
+    /// Titles and headings
+    pub fn heading(&self) -> &'static str {
+        match self {
+            Theme::Light => "#111827",
+            Theme::Dark => "#f9fafb",
+        }
+    }
+
+    /// Row and legend labels
+    pub fn text(&self) -> &'static str {
+        match self {
+            Theme::Light => "#374151",
+            Theme::Dark => "#e5e7eb",
+        }
+    }
+
+    /// Durations, stats and axis labels
+    pub fn muted(&self) -> &'static str {
+        match self {
+            Theme::Light => "#6b7280",
+            Theme::Dark => "#9ca3af",
+        }
+    }
+
+    /// Empty track behind each row's spans
+    fn track(&self) -> &'static str {
+        match self {
+            Theme::Light => "#e5e7eb",
+            Theme::Dark => "#374151",
+        }
+    }
+
+    fn gridline(&self) -> &'static str {
+        match self {
+            Theme::Light => "#d1d5db",
+            Theme::Dark => "#4b5563",
+        }
+    }
+
+    /// Outline separating adjacent spans
+    fn span_stroke(&self) -> &'static str {
+        match self {
+            Theme::Light => "#fff",
+            Theme::Dark => "#111827",
+        }
+    }
+
+    /// Outline of the span under the cursor
+    fn hover_stroke(&self) -> &'static str {
+        match self {
+            Theme::Light => "#000",
+            Theme::Dark => "#fff",
+        }
+    }
+}
+
+/// Parse a `--theme` value
+pub fn parse_theme(value: &str) -> Result<Theme, String> {
+    match value {
+        "light" => Ok(Theme::Light),
+        "dark" => Ok(Theme::Dark),
+        other => Err(format!("unknown theme '{}': use light or dark", other)),
+    }
+}
+
+/// Render the flamegraph for a grouping: session (default), project, pr, or
+/// issue. The pr and issue groupings need the repo's synced cache.
+pub fn render_grouped(
+    sessions: &[Session],
+    group_by: &str,
+    cache: Option<&RepoCache>,
+    theme: Theme,
+    colors: &ColorMap,
+) -> std::io::Result<String> {
+    let synced = || {
+        cache.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "No GitHub cache found. Run `aist sync` first.",
+            )
+        })
+    };
+    match Grouping::parse(group_by) {
+        Grouping::Project => render_svg_by_project(sessions, theme, colors),
+        Grouping::Pr => render_svg_by_pr(sessions, synced()?, theme, colors),
+        Grouping::Issue => render_svg_by_issue(sessions, synced()?, theme, colors),
+        Grouping::Session => render_svg(sessions, cache, theme, colors),
+    }
+}
+
+/// Encode an SVG as a base64 `data:image/svg+xml` URI for inline embedding
+pub fn svg_data_uri(svg: &str) -> String {
+    format!(
+        "data:image/svg+xml;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(svg)
+    )
+}
+
+/// Styles for the interactive HTML flamegraph page
+const FLAME_HTML_CSS: &str = r#"
+        body { font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; margin: 2rem; }
+        .hint { font-size: 0.875rem; margin-bottom: 1rem; }
+        .flamegraph svg { max-width: 100%; height: auto; }
+        rect.span { cursor: zoom-in; }
+        rect.span.zoomed { cursor: zoom-out; }
+        #tooltip { position: absolute; display: none; pointer-events: none; padding: 0.375rem 0.625rem; font: 12px monospace; border-radius: 4px; max-width: 480px; }
+"#;
+
+/// Hover tooltips and click-to-zoom for the interactive HTML flamegraph.
+/// Rows are identified by the `y` their spans and track share, so this
+/// works with any of the bar layouts.
+const FLAME_HTML_JS: &str = r#"
+(function () {
+    var svg = document.querySelector('.flamegraph svg');
+    var tip = document.getElementById('tooltip');
+    if (!svg) return;
+    var spans = Array.prototype.slice.call(svg.querySelectorAll('rect.span'));
+    var zoomedRow = null;
+
+    spans.forEach(function (rect) {
+        var title = rect.querySelector('title');
+        rect.dataset.tip = title ? title.textContent : '';
+        // The custom tooltip replaces the browser's own
+        if (title) title.remove();
+        rect.dataset.x = rect.getAttribute('x');
+        rect.dataset.width = rect.getAttribute('width');
+
+        rect.addEventListener('mousemove', function (e) {
+            tip.textContent = rect.dataset.tip;
+            tip.style.display = 'block';
+            tip.style.left = (e.pageX + 12) + 'px';
+            tip.style.top = (e.pageY + 12) + 'px';
+        });
+        rect.addEventListener('mouseleave', function () {
+            tip.style.display = 'none';
+        });
+        rect.addEventListener('click', function () {
+            toggleZoom(rect.getAttribute('y'));
+        });
+    });
+
+    function reset() {
+        spans.forEach(function (rect) {
+            rect.setAttribute('x', rect.dataset.x);
+            rect.setAttribute('width', rect.dataset.width);
+            rect.classList.remove('zoomed');
+        });
+        zoomedRow = null;
+    }
+
+    // Stretch one row's spans across its track; clicking it again undoes it
+    function toggleZoom(y) {
+        var wasZoomed = zoomedRow === y;
+        reset();
+        if (wasZoomed) return;
+
+        var row = spans.filter(function (rect) { return rect.getAttribute('y') === y; });
+        var track = Array.prototype.slice.call(svg.querySelectorAll('rect.track'))
+            .find(function (rect) { return rect.getAttribute('y') === y; });
+        if (!track || row.length === 0) return;
+
+        var left = Math.min.apply(null, row.map(function (r) { return +r.dataset.x; }));
+        var right = Math.max.apply(null, row.map(function (r) { return +r.dataset.x + +r.dataset.width; }));
+        var trackX = +track.getAttribute('x');
+        var scale = +track.getAttribute('width') / Math.max(right - left, 1);
+
+        row.forEach(function (rect) {
+            rect.setAttribute('x', trackX + (rect.dataset.x - left) * scale);
+            rect.setAttribute('width', rect.dataset.width * scale);
+            rect.classList.add('zoomed');
+        });
+        zoomedRow = y;
+    }
+
+    document.addEventListener('keydown', function (e) {
+        if (e.key === 'Escape') reset();
+    });
+})();
+"#;
+
+/// Wrap a rendered flamegraph SVG in a self-contained HTML page with hover
+/// tooltips and click-to-zoom on a row
+pub fn render_html(svg: &str, theme: Theme) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>AI Session Flamegraph</title>
+    <style>{css}
+        body {{ background: {background}; color: {heading}; }}
+        .hint {{ color: {muted}; }}
+        #tooltip {{ background: {text}; color: {background}; }}
+    </style>
+</head>
+<body>
+    <p class="hint">Hover a span for details. Click a row to zoom in; click again or press Esc to reset.</p>
+    <div class="flamegraph">
+{svg}
+    </div>
+    <div id="tooltip"></div>
+    <script>{js}</script>
+</body>
+</html>"#,
+        css = FLAME_HTML_CSS,
+        background = theme.background(),
+        heading = theme.heading(),
+        muted = theme.muted(),
+        text = theme.text(),
+        svg = svg,
+        js = FLAME_HTML_JS,
+    )
+}
+
+/// Opening `<svg>` tag, stylesheet and background shared by every layout.
+/// The stylesheet covers the classes of all of them, and is the only place
+/// the theme is applied: renderers mark elements with a class instead.
+fn svg_header(width: usize, height: usize, theme: Theme) -> String {
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {height}" width="{width}" height="{height}">
+<style>
+  .session-label {{ font: 11px monospace; fill: {text}; }}
+  .group-label {{ font: bold 12px monospace; fill: {text}; }}
+  .time-label {{ font: 10px monospace; fill: {muted}; }}
+  .stats-label {{ font: 10px monospace; fill: {muted}; }}
+  .legend-label {{ font: 12px sans-serif; fill: {text}; }}
+  .title {{ font: bold 16px sans-serif; fill: {heading}; }}
+  rect.track {{ fill: {track}; }}
+  line.grid {{ stroke: {grid}; stroke-width: 1; stroke-dasharray: 2 2; }}
+  rect.span {{ stroke: {span_stroke}; stroke-width: 1; }}
+  rect.span:hover {{ stroke: {hover}; stroke-width: 2; opacity: 0.8; }}
+  rect.mark:hover {{ stroke: {hover}; stroke-width: 1; }}
+</style>
+<rect width="100%" height="100%" fill="{background}"/>
+"#,
+        background = theme.background(),
+        text = theme.text(),
+        muted = theme.muted(),
+        heading = theme.heading(),
+        track = theme.track(),
+        grid = theme.gridline(),
+        span_stroke = theme.span_stroke(),
+        hover = theme.hover_stroke(),
+    )
+}
+
+/// Swatch and label for each activity type, in a row below the title
+fn render_legend(colors: &ColorMap, margin: usize) -> String {
+    let legend_y = 45;
+    let legend_items = [
+        (ActivityType::Productive, 0),
+        (ActivityType::Reading, 120),
+        (ActivityType::Executing, 260),
+        (ActivityType::Error, 380),
+        (ActivityType::Gap, 470),
+        (ActivityType::Thinking, 570),
+    ];
+
+    let mut legend = String::new();
+    for (activity, x_offset) in legend_items {
+        legend.push_str(&format!(
+            r#"<rect x="{}" y="{}" width="14" height="14" fill="{}" rx="2"/>
+<text x="{}" y="{}" class="legend-label">{}</text>"#,
+            margin + x_offset,
+            legend_y,
+            colors.color(activity),
+            margin + x_offset + 18,
+            legend_y + 11,
+            activity.label()
+        ));
+    }
+    legend
+}
+
+/// Escape text for an SVG element or attribute
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Tooltip for a span: its activity, description and length
+fn span_title(span: &TimeSpan) -> String {
+    let duration_mins = (span.end - span.start).num_seconds() as f64 / 60.0;
+    format!(
+        "{}: {} ({:.1}m)",
+        span.activity.label(),
+        span.description(),
+        duration_mins
+    )
+}
+
+/// A hoverable bar at `(x, y)` of `(width, height)`, with `title` as its
+/// tooltip
+fn span_rect(
+    (x, y): (usize, usize),
+    (width, height): (usize, usize),
+    rx: usize,
+    fill: &str,
+    title: &str,
+) -> String {
+    format!(
+        r#"<rect class="span" x="{}" y="{}" width="{}" height="{}" fill="{}" rx="{}">
+<title>{}</title>
+</rect>"#,
+        x,
+        y,
+        width,
+        height,
+        fill,
+        rx,
+        escape_xml(title)
+    )
+}
+
+/// Row label for a session: short ID and project, plus the issue (or else
+/// the PR) its branch is linked to in the GitHub cache
+fn session_row_label(session: &Session, branch_to_pr: &HashMap<&str, &PrMapping>) -> String {
+    let mut label = format!(
+        "{} ({})",
+        metrics::short_id(&session.session_id, 8),
+        extract_project_name(&session.project)
+    );
+    let pr = session
+        .branches()
+        .iter()
+        .find_map(|b| branch_to_pr.get(b).copied());
+    if let Some(pr) = pr {
+        match pr.closed_issues.first() {
+            Some(issue) => label.push_str(&format!(" #{}", issue)),
+            None => label.push_str(&format!(" PR #{}", pr.pr_number)),
+        }
+    }
+    label
+}
+
+/// Render an SVG flamegraph for sessions, labelling rows with their linked
+/// issue or PR when a repo cache is given
+pub fn render_svg(
+    sessions: &[Session],
+    cache: Option<&RepoCache>,
+    theme: Theme,
+    colors: &ColorMap,
+) -> std::io::Result<String> {
+    let branch_to_pr = cache.map(RepoCache::branch_to_pr).unwrap_or_default();
     let width = 1200;
     let row_height = 30;
     let margin = 40;
@@ -200,21 +780,7 @@ pub fn generate_svg(sessions: &[Session], output_path: &Path) -> std::io::Result
 
     let mut svg = String::new();
 
-    // SVG header with styles
-    svg.push_str(&format!(
-        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}" width="{}" height="{}">
-<style>
-  .session-label {{ font: 11px monospace; fill: #374151; }}
-  .time-label {{ font: 10px monospace; fill: #6b7280; }}
-  .legend-label {{ font: 12px sans-serif; fill: #374151; }}
-  .title {{ font: bold 16px sans-serif; fill: #111827; }}
-  rect.span {{ stroke: #fff; stroke-width: 1; }}
-  rect.span:hover {{ stroke: #000; stroke-width: 2; opacity: 0.8; }}
-</style>
-<rect width="100%" height="100%" fill="{}"/>
-"#,
-        width, height, width, height, "#f9fafb"
-    ));
+    svg.push_str(&svg_header(width, height, theme));
 
     // Title
     svg.push_str(&format!(
@@ -222,29 +788,7 @@ pub fn generate_svg(sessions: &[Session], output_path: &Path) -> std::io::Result
         margin
     ));
 
-    // Legend
-    let legend_y = 45;
-    let legend_items = [
-        (ActivityType::Productive, 0),
-        (ActivityType::Reading, 120),
-        (ActivityType::Executing, 260),
-        (ActivityType::Error, 380),
-        (ActivityType::Gap, 470),
-        (ActivityType::Thinking, 570),
-    ];
-
-    for (activity, x_offset) in legend_items {
-        svg.push_str(&format!(
-            r#"<rect x="{}" y="{}" width="14" height="14" fill="{}" rx="2"/>
-<text x="{}" y="{}" class="legend-label">{}</text>"#,
-            margin + x_offset,
-            legend_y,
-            activity.color(),
-            margin + x_offset + 18,
-            legend_y + 11,
-            activity.label()
-        ));
-    }
+    svg.push_str(&render_legend(colors, margin));
 
     let chart_y_start = margin + legend_height;
     let chart_width = width - margin * 2 - 150; // Leave room for labels
@@ -261,13 +805,8 @@ pub fn generate_svg(sessions: &[Session], output_path: &Path) -> std::io::Result
         }
 
         // Session label
-        let project_name = extract_project_name(&session.project);
         let duration_str = format_duration(session_duration / 60.0);
-        let label = format!(
-            "{} ({})",
-            &session.session_id[..8.min(session.session_id.len())],
-            project_name
-        );
+        let label = session_row_label(session, &branch_to_pr);
 
         svg.push_str(&format!(
             r#"<text x="{}" y="{}" class="session-label">{}</text>
@@ -283,15 +822,15 @@ pub fn generate_svg(sessions: &[Session], output_path: &Path) -> std::io::Result
         // Background for session row
         let bar_x = margin + 150;
         svg.push_str(&format!(
-            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#e5e7eb\" rx=\"2\"/>",
+            "<rect class=\"track\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"2\"/>",
             bar_x,
             y + 2,
             chart_width,
-            row_height - 4
+            row_height - 4,
         ));
 
         // Draw spans
-        let spans = extract_spans(session);
+        let spans = extract_spans(session, DEFAULT_GAP_SECONDS);
         for span in &spans {
             let span_start = (span.start - session_start).num_seconds() as f64;
             let span_end = (span.end - session_start).num_seconds() as f64;
@@ -303,39 +842,355 @@ pub fn generate_svg(sessions: &[Session], output_path: &Path) -> std::io::Result
                 continue;
             }
 
-            // Escape label for XML
-            let escaped_label = span
-                .label
-                .replace('&', "&amp;")
-                .replace('<', "&lt;")
-                .replace('>', "&gt;")
-                .replace('"', "&quot;");
+            svg.push_str(&span_rect(
+                (x, y + 2),
+                (w, row_height - 4),
+                1,
+                colors.color(span.activity),
+                &span_title(span),
+            ));
+        }
+    }
 
-            let duration_mins = (span.end - span.start).num_seconds() as f64 / 60.0;
+    svg.push_str("</svg>");
 
-            svg.push_str(&format!(
-                r#"<rect class="span" x="{}" y="{}" width="{}" height="{}" fill="{}" rx="1">
-<title>{}: {} ({:.1}m)</title>
-</rect>"#,
-                x,
-                y + 2,
-                w.max(1),
-                row_height - 4,
-                span.activity.color(),
-                span.activity.label(),
-                escaped_label,
-                duration_mins
+    // Write to file
+    Ok(svg)
+}
+
+/// Hour steps the absolute time axis may use between gridlines
+const AXIS_STEP_HOURS: &[i64] = &[1, 2, 3, 6, 12, 24, 48, 168];
+
+/// Most gridlines drawn on the absolute time axis
+const AXIS_MAX_GRIDLINES: i64 = 12;
+
+/// Start of the local hour containing `t`
+fn floor_to_hour(t: DateTime<Utc>) -> DateTime<Utc> {
+    t.with_timezone(&Local)
+        .with_minute(0)
+        .and_then(|d| d.with_second(0))
+        .and_then(|d| d.with_nanosecond(0))
+        .map(|d| d.with_timezone(&Utc))
+        .unwrap_or(t)
+}
+
+/// Render an SVG flamegraph where every session row shares one wall-clock
+/// axis, from the earliest start to the latest end, with hour gridlines
+pub fn render_svg_absolute(
+    sessions: &[Session],
+    cache: Option<&RepoCache>,
+    theme: Theme,
+    colors: &ColorMap,
+) -> std::io::Result<String> {
+    let width = 1200;
+    let row_height = 30;
+    let margin = 40;
+    let legend_height = 60;
+    let axis_height = 20;
+
+    let mut valid_sessions: Vec<_> = sessions
+        .iter()
+        .filter(|s| s.start_time.is_some() && s.end_time.is_some())
+        .collect();
+    valid_sessions.sort_by_key(|s| s.start_time);
+
+    let max_sessions = 20;
+    let sessions_to_show: Vec<_> = valid_sessions
+        .into_iter()
+        .rev()
+        .take(max_sessions)
+        .rev()
+        .collect();
+
+    if sessions_to_show.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "No sessions with valid timestamps",
+        ));
+    }
+
+    // Pad the axis out to whole hours, so even a single instantaneous
+    // session gets an hour-wide axis to sit on
+    let earliest = sessions_to_show.iter().map(|s| s.start_time.unwrap()).min();
+    let latest = sessions_to_show.iter().map(|s| s.end_time.unwrap()).max();
+    let (earliest, latest) = (earliest.unwrap(), latest.unwrap());
+    let axis_start = floor_to_hour(earliest);
+    let mut axis_end = floor_to_hour(latest);
+    if axis_end < latest || axis_end <= axis_start {
+        axis_end += chrono::Duration::hours(1);
+    }
+    let axis_seconds = (axis_end - axis_start).num_seconds().max(1) as f64;
+
+    let branch_to_pr = cache.map(RepoCache::branch_to_pr).unwrap_or_default();
+
+    let height = margin * 2 + legend_height + axis_height + (sessions_to_show.len() * row_height);
+
+    let mut svg = String::new();
+
+    svg.push_str(&svg_header(width, height, theme));
+
+    svg.push_str(&format!(
+        r#"<text x="{}" y="25" class="title">AI Session Flamegraph (wall clock)</text>"#,
+        margin
+    ));
+
+    svg.push_str(&render_legend(colors, margin));
+
+    let axis_y = margin + legend_height;
+    let chart_y_start = axis_y + axis_height;
+    let chart_y_end = chart_y_start + sessions_to_show.len() * row_height;
+    let bar_x = margin + 150;
+    let chart_width = width - margin * 2 - 150;
+    let x_at = |t: DateTime<Utc>| {
+        bar_x + ((t - axis_start).num_seconds() as f64 / axis_seconds * chart_width as f64) as usize
+    };
+
+    // Hour gridlines, thinned out so labels don't collide on long ranges
+    let hours = (axis_end - axis_start).num_hours().max(1);
+    let step = AXIS_STEP_HOURS
+        .iter()
+        .copied()
+        .find(|step| hours / step <= AXIS_MAX_GRIDLINES)
+        .unwrap_or_else(|| 168 * (hours / (168 * AXIS_MAX_GRIDLINES) + 1));
+    let multi_day = axis_start.with_timezone(&Local).date_naive()
+        != (axis_end - chrono::Duration::seconds(1))
+            .with_timezone(&Local)
+            .date_naive();
+    let mut tick = axis_start;
+    while tick <= axis_end {
+        let x = x_at(tick);
+        let local = tick.with_timezone(&Local);
+        let label = if multi_day {
+            local.format("%b %d %H:%M").to_string()
+        } else {
+            local.format("%H:%M").to_string()
+        };
+        svg.push_str(&format!(
+            r#"<line class="grid" x1="{}" y1="{}" x2="{}" y2="{}"/>
+<text x="{}" y="{}" class="time-label" text-anchor="middle">{}</text>"#,
+            x,
+            chart_y_start,
+            x,
+            chart_y_end,
+            x,
+            axis_y + 12,
+            label
+        ));
+        tick += chrono::Duration::hours(step);
+    }
+
+    for (i, session) in sessions_to_show.iter().enumerate() {
+        let y = chart_y_start + (i * row_height);
+        let session_start = session.start_time.unwrap();
+        let session_end = session.end_time.unwrap();
+        let session_minutes = (session_end - session_start).num_seconds() as f64 / 60.0;
+
+        let label = session_row_label(session, &branch_to_pr);
+
+        svg.push_str(&format!(
+            r#"<text x="{}" y="{}" class="session-label">{}</text>
+<text x="{}" y="{}" class="time-label">{}</text>"#,
+            margin,
+            y + row_height / 2 + 4,
+            label,
+            width - margin - 50,
+            y + row_height / 2 + 4,
+            format_duration(session_minutes.max(0.0))
+        ));
+
+        // The session's own extent on the shared axis
+        let start_x = x_at(session_start);
+        svg.push_str(&format!(
+            "<rect class=\"track\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"2\"/>",
+            start_x,
+            y + 2,
+            x_at(session_end).saturating_sub(start_x).max(1),
+            row_height - 4,
+        ));
+
+        for span in &extract_spans(session, DEFAULT_GAP_SECONDS) {
+            let x = x_at(span.start);
+            let w = x_at(span.end).saturating_sub(x);
+            if w < 1 {
+                continue;
+            }
+
+            svg.push_str(&span_rect(
+                (x, y + 2),
+                (w, row_height - 4),
+                1,
+                colors.color(span.activity),
+                &span_title(span),
             ));
         }
     }
 
     svg.push_str("</svg>");
 
-    // Write to file
-    let mut file = File::create(output_path)?;
-    file.write_all(svg.as_bytes())?;
+    Ok(svg)
+}
+
+/// Tool categories drawn as separate lanes in the lanes layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum ToolLane {
+    Read,
+    Edit,
+    Bash,
+    Web,
+    Other,
+}
+
+impl ToolLane {
+    fn for_tool(name: &str) -> ToolLane {
+        match name {
+            "Read" | "Grep" | "Glob" | "LS" => ToolLane::Read,
+            "Edit" | "Write" | "NotebookEdit" => ToolLane::Edit,
+            "Bash" => ToolLane::Bash,
+            "WebFetch" | "WebSearch" => ToolLane::Web,
+            _ => ToolLane::Other,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            ToolLane::Read => "Read",
+            ToolLane::Edit => "Edit",
+            ToolLane::Bash => "Bash",
+            ToolLane::Web => "Web",
+            ToolLane::Other => "Other",
+        }
+    }
 
-    Ok(())
+    fn color<'a>(&self, colors: &'a ColorMap) -> &'a str {
+        match self {
+            ToolLane::Read => colors.color(ActivityType::Reading),
+            ToolLane::Edit => colors.color(ActivityType::Productive),
+            ToolLane::Bash => colors.color(ActivityType::Executing),
+            ToolLane::Web => "#2dd4bf", // teal
+            ToolLane::Other => colors.color(ActivityType::Thinking),
+        }
+    }
+}
+
+/// Render the most recent session as one horizontal lane per tool category,
+/// with a mark at each invocation. Calls whose result was an error are
+/// marked red.
+pub fn render_svg_lanes(
+    sessions: &[Session],
+    theme: Theme,
+    colors: &ColorMap,
+) -> std::io::Result<String> {
+    let session = sessions
+        .iter()
+        .filter(|s| s.start_time.is_some() && s.end_time.is_some())
+        .max_by_key(|s| s.start_time)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "No sessions with valid timestamps",
+            )
+        })?;
+    let session_start = session.start_time.unwrap();
+    let session_duration = (session.end_time.unwrap() - session_start)
+        .num_seconds()
+        .max(1) as f64;
+
+    let failed: std::collections::HashSet<&str> = session
+        .messages
+        .iter()
+        .flat_map(|m| &m.tool_results)
+        .filter(|r| r.is_error)
+        .map(|r| r.tool_use_id.as_str())
+        .collect();
+
+    // (lane, offset seconds, tool name, failed)
+    let mut marks: Vec<(ToolLane, f64, &str, bool)> = Vec::new();
+    for msg in &session.messages {
+        let Some(ts) = msg.timestamp else {
+            continue;
+        };
+        let offset = (ts - session_start).num_seconds() as f64;
+        for tc in &msg.tool_calls {
+            marks.push((
+                ToolLane::for_tool(&tc.name),
+                offset,
+                &tc.name,
+                failed.contains(tc.id.as_str()),
+            ));
+        }
+    }
+
+    let mut lanes: Vec<ToolLane> = marks.iter().map(|m| m.0).collect();
+    lanes.sort();
+    lanes.dedup();
+
+    let width = 1200;
+    let row_height = 30;
+    let margin = 40;
+    let header_height = 40;
+    let label_width = 80;
+    let chart_width = width - margin * 2 - label_width;
+    let height = margin * 2 + header_height + lanes.len().max(1) * row_height;
+
+    let mut svg = String::new();
+    svg.push_str(&svg_header(width, height, theme));
+
+    let session_short = metrics::short_id(&session.session_id, 8);
+    svg.push_str(&format!(
+        r#"<text x="{}" y="25" class="title">Tool Lanes: {} ({}, {})</text>"#,
+        margin,
+        session_short,
+        extract_project_name(&session.project),
+        format_duration(session_duration / 60.0)
+    ));
+
+    let chart_y_start = margin + header_height;
+    let bar_x = margin + label_width;
+    for (i, lane) in lanes.iter().enumerate() {
+        let y = chart_y_start + i * row_height;
+        svg.push_str(&format!(
+            "<g class=\"lane\" data-lane=\"{}\">\n<text x=\"{}\" y=\"{}\" class=\"group-label\">{}</text>\n<rect class=\"track\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"2\"/>\n",
+            lane.label(),
+            margin,
+            y + row_height / 2 + 4,
+            lane.label(),
+            bar_x,
+            y + 2,
+            chart_width,
+            row_height - 4,
+        ));
+
+        for (_, offset, name, is_error) in marks.iter().filter(|m| m.0 == *lane) {
+            let x = bar_x + (offset / session_duration * chart_width as f64) as usize;
+            let color = if *is_error {
+                colors.color(ActivityType::Error)
+            } else {
+                lane.color(colors)
+            };
+            svg.push_str(&format!(
+                "<rect class=\"mark\" x=\"{}\" y=\"{}\" width=\"3\" height=\"{}\" fill=\"{}\">\n<title>{} at +{}{}</title>\n</rect>\n",
+                x.min(bar_x + chart_width - 3),
+                y + 4,
+                row_height - 8,
+                color,
+                escape_xml(name),
+                format_duration(offset / 60.0),
+                if *is_error { " (error)" } else { "" }
+            ));
+        }
+        svg.push_str("</g>\n");
+    }
+
+    if lanes.is_empty() {
+        svg.push_str(&format!(
+            r#"<text x="{}" y="{}" class="time-label">No tool calls in this session</text>"#,
+            margin,
+            chart_y_start + row_height / 2
+        ));
+    }
+
+    svg.push_str("</svg>");
+    Ok(svg)
 }
 
 fn extract_project_name(project_path: &str) -> String {
@@ -355,8 +1210,12 @@ fn format_duration(minutes: f64) -> String {
     }
 }
 
-/// Generate an SVG flamegraph grouped by project
-pub fn generate_svg_by_project(sessions: &[Session], output_path: &Path) -> std::io::Result<()> {
+/// Render an SVG flamegraph grouped by project
+pub fn render_svg_by_project(
+    sessions: &[Session],
+    theme: Theme,
+    colors: &ColorMap,
+) -> std::io::Result<String> {
     use std::collections::HashMap;
 
     let width = 1200;
@@ -395,7 +1254,11 @@ pub fn generate_svg_by_project(sessions: &[Session], output_path: &Path) -> std:
         })
         .collect();
 
-    projects.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+    projects.sort_by(|a, b| {
+        b.2.partial_cmp(&a.2)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
 
     let max_projects = 15;
     let projects: Vec<_> = projects.into_iter().take(max_projects).collect();
@@ -404,21 +1267,7 @@ pub fn generate_svg_by_project(sessions: &[Session], output_path: &Path) -> std:
 
     let mut svg = String::new();
 
-    // SVG header
-    svg.push_str(&format!(
-        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}" width="{}" height="{}">
-<style>
-  .project-label {{ font: bold 12px monospace; fill: #374151; }}
-  .stats-label {{ font: 10px monospace; fill: #6b7280; }}
-  .legend-label {{ font: 12px sans-serif; fill: #374151; }}
-  .title {{ font: bold 16px sans-serif; fill: #111827; }}
-  rect.span {{ stroke: #fff; stroke-width: 1; }}
-  rect.span:hover {{ stroke: #000; stroke-width: 2; opacity: 0.8; }}
-</style>
-<rect width="100%" height="100%" fill="{}"/>
-"#,
-        width, height, width, height, "#f9fafb"
-    ));
+    svg.push_str(&svg_header(width, height, theme));
 
     // Title
     svg.push_str(&format!(
@@ -426,29 +1275,7 @@ pub fn generate_svg_by_project(sessions: &[Session], output_path: &Path) -> std:
         margin
     ));
 
-    // Legend
-    let legend_y = 45;
-    let legend_items = [
-        (ActivityType::Productive, 0),
-        (ActivityType::Reading, 120),
-        (ActivityType::Executing, 260),
-        (ActivityType::Error, 380),
-        (ActivityType::Gap, 470),
-        (ActivityType::Thinking, 570),
-    ];
-
-    for (activity, x_offset) in legend_items {
-        svg.push_str(&format!(
-            r#"<rect x="{}" y="{}" width="14" height="14" fill="{}" rx="2"/>
-<text x="{}" y="{}" class="legend-label">{}</text>"#,
-            margin + x_offset,
-            legend_y,
-            activity.color(),
-            margin + x_offset + 18,
-            legend_y + 11,
-            activity.label()
-        ));
-    }
+    svg.push_str(&render_legend(colors, margin));
 
     let chart_y_start = margin + legend_height;
     let chart_width = width - margin * 2 - 180;
@@ -460,7 +1287,7 @@ pub fn generate_svg_by_project(sessions: &[Session], output_path: &Path) -> std:
         // Collect all spans from all sessions for this project
         let mut all_spans: Vec<TimeSpan> = Vec::new();
         for session in project_sessions {
-            all_spans.extend(extract_spans(session));
+            all_spans.extend(extract_spans(session, DEFAULT_GAP_SECONDS));
         }
 
         // Calculate time breakdown by activity type
@@ -478,7 +1305,7 @@ pub fn generate_svg_by_project(sessions: &[Session], output_path: &Path) -> std:
         };
 
         svg.push_str(&format!(
-            r#"<text x="{}" y="{}" class="project-label">{}</text>
+            r#"<text x="{}" y="{}" class="group-label">{}</text>
 <text x="{}" y="{}" class="stats-label">{} sessions, {}</text>"#,
             margin,
             y + row_height / 2,
@@ -492,11 +1319,11 @@ pub fn generate_svg_by_project(sessions: &[Session], output_path: &Path) -> std:
         // Background bar
         let bar_x = margin + 180;
         svg.push_str(&format!(
-            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#e5e7eb\" rx=\"3\"/>",
+            "<rect class=\"track\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"3\"/>",
             bar_x,
             y + 4,
             chart_width,
-            row_height - 8
+            row_height - 8,
         ));
 
         // Draw proportional blocks for each activity type
@@ -518,18 +1345,17 @@ pub fn generate_svg_by_project(sessions: &[Session], output_path: &Path) -> std:
 
                 if block_width >= 1 {
                     let percent = (width_ratio * 100.0) as usize;
-                    svg.push_str(&format!(
-                        r#"<rect class="span" x="{}" y="{}" width="{}" height="{}" fill="{}" rx="2">
-<title>{}: {} ({}%)</title>
-</rect>"#,
-                        bar_x + x_offset as usize,
-                        y + 4,
-                        block_width,
-                        row_height - 8,
-                        activity.color(),
-                        activity.label(),
-                        format_duration(activity_time),
-                        percent
+                    svg.push_str(&span_rect(
+                        (bar_x + x_offset as usize, y + 4),
+                        (block_width, row_height - 8),
+                        2,
+                        colors.color(activity),
+                        &format!(
+                            "{}: {} ({}%)",
+                            activity.label(),
+                            format_duration(activity_time),
+                            percent
+                        ),
                     ));
                     x_offset += block_width as f64;
                 }
@@ -539,10 +1365,7 @@ pub fn generate_svg_by_project(sessions: &[Session], output_path: &Path) -> std:
 
     svg.push_str("</svg>");
 
-    let mut file = File::create(output_path)?;
-    file.write_all(svg.as_bytes())?;
-
-    Ok(())
+    Ok(svg)
 }
 
 /// PR data for grouping sessions
@@ -562,17 +1385,14 @@ struct IssueGroup<'a> {
     total_mins: f64,
 }
 
-/// Generate an SVG flamegraph grouped by GitHub issue
-pub fn generate_svg_by_issue(sessions: &[Session], output_path: &Path) -> std::io::Result<()> {
-    // Load GitHub cache
-    let cache = load_current_repo_cache().ok_or_else(|| {
-        std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "No GitHub cache found. Run `aist sync` first.",
-        )
-    })?;
-
-    let issues = group_sessions_by_issue(sessions, &cache);
+/// Render an SVG flamegraph grouped by the issues in a repo cache
+pub fn render_svg_by_issue(
+    sessions: &[Session],
+    cache: &RepoCache,
+    theme: Theme,
+    colors: &ColorMap,
+) -> std::io::Result<String> {
+    let issues = group_sessions_by_issue(sessions, cache);
 
     if issues.is_empty() {
         return Err(std::io::Error::new(
@@ -593,21 +1413,7 @@ pub fn generate_svg_by_issue(sessions: &[Session], output_path: &Path) -> std::i
 
     let mut svg = String::new();
 
-    // SVG header
-    svg.push_str(&format!(
-        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}" width="{}" height="{}">
-<style>
-  .issue-label {{ font: bold 12px monospace; fill: #374151; }}
-  .stats-label {{ font: 10px monospace; fill: #6b7280; }}
-  .legend-label {{ font: 12px sans-serif; fill: #374151; }}
-  .title {{ font: bold 16px sans-serif; fill: #111827; }}
-  rect.span {{ stroke: #fff; stroke-width: 1; }}
-  rect.span:hover {{ stroke: #000; stroke-width: 2; opacity: 0.8; }}
-</style>
-<rect width="100%" height="100%" fill="{}"/>
-"#,
-        width, height, width, height, "#f9fafb"
-    ));
+    svg.push_str(&svg_header(width, height, theme));
 
     // Title
     svg.push_str(&format!(
@@ -615,29 +1421,7 @@ pub fn generate_svg_by_issue(sessions: &[Session], output_path: &Path) -> std::i
         margin
     ));
 
-    // Legend
-    let legend_y = 45;
-    let legend_items = [
-        (ActivityType::Productive, 0),
-        (ActivityType::Reading, 120),
-        (ActivityType::Executing, 260),
-        (ActivityType::Error, 380),
-        (ActivityType::Gap, 470),
-        (ActivityType::Thinking, 570),
-    ];
-
-    for (activity, x_offset) in legend_items {
-        svg.push_str(&format!(
-            r#"<rect x="{}" y="{}" width="14" height="14" fill="{}" rx="2"/>
-<text x="{}" y="{}" class="legend-label">{}</text>"#,
-            margin + x_offset,
-            legend_y,
-            activity.color(),
-            margin + x_offset + 18,
-            legend_y + 11,
-            activity.label()
-        ));
-    }
+    svg.push_str(&render_legend(colors, margin));
 
     let chart_y_start = margin + legend_height;
     let chart_width = width - margin * 2 - 180;
@@ -649,7 +1433,7 @@ pub fn generate_svg_by_issue(sessions: &[Session], output_path: &Path) -> std::i
         // Collect all spans from all sessions for this issue
         let mut all_spans: Vec<TimeSpan> = Vec::new();
         for session in &issue.sessions {
-            all_spans.extend(extract_spans(session));
+            all_spans.extend(extract_spans(session, DEFAULT_GAP_SECONDS));
         }
 
         // Calculate time breakdown by activity type
@@ -660,18 +1444,10 @@ pub fn generate_svg_by_issue(sessions: &[Session], output_path: &Path) -> std::i
         }
 
         // Issue label - escape for XML
-        let display_title = if issue.title.len() > 20 {
-            format!("{}...", &issue.title[..17])
-        } else {
-            issue.title.clone()
-        };
-        let display_title = display_title
-            .replace('&', "&amp;")
-            .replace('<', "&lt;")
-            .replace('>', "&gt;");
+        let display_title = escape_xml(&metrics::truncate_chars(&issue.title, 20));
 
         svg.push_str(&format!(
-            r#"<text x="{}" y="{}" class="issue-label">#{} {}</text>
+            r#"<text x="{}" y="{}" class="group-label">#{} {}</text>
 <text x="{}" y="{}" class="stats-label">{} sessions, {}</text>"#,
             margin,
             y + row_height / 2,
@@ -686,11 +1462,11 @@ pub fn generate_svg_by_issue(sessions: &[Session], output_path: &Path) -> std::i
         // Background bar
         let bar_x = margin + 180;
         svg.push_str(&format!(
-            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#e5e7eb\" rx=\"3\"/>",
+            "<rect class=\"track\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"3\"/>",
             bar_x,
             y + 4,
             chart_width,
-            row_height - 8
+            row_height - 8,
         ));
 
         // Draw proportional blocks for each activity type
@@ -712,18 +1488,17 @@ pub fn generate_svg_by_issue(sessions: &[Session], output_path: &Path) -> std::i
 
                 if block_width >= 1 {
                     let percent = (width_ratio * 100.0) as usize;
-                    svg.push_str(&format!(
-                        r#"<rect class="span" x="{}" y="{}" width="{}" height="{}" fill="{}" rx="2">
-<title>{}: {} ({}%)</title>
-</rect>"#,
-                        bar_x + x_offset as usize,
-                        y + 4,
-                        block_width,
-                        row_height - 8,
-                        activity.color(),
-                        activity.label(),
-                        format_duration(activity_time),
-                        percent
+                    svg.push_str(&span_rect(
+                        (bar_x + x_offset as usize, y + 4),
+                        (block_width, row_height - 8),
+                        2,
+                        colors.color(activity),
+                        &format!(
+                            "{}: {} ({}%)",
+                            activity.label(),
+                            format_duration(activity_time),
+                            percent
+                        ),
                     ));
                     x_offset += block_width as f64;
                 }
@@ -733,23 +1508,17 @@ pub fn generate_svg_by_issue(sessions: &[Session], output_path: &Path) -> std::i
 
     svg.push_str("</svg>");
 
-    let mut file = File::create(output_path)?;
-    file.write_all(svg.as_bytes())?;
-
-    Ok(())
+    Ok(svg)
 }
 
-/// Generate an SVG flamegraph grouped by GitHub PR
-pub fn generate_svg_by_pr(sessions: &[Session], output_path: &Path) -> std::io::Result<()> {
-    // Load GitHub cache
-    let cache = load_current_repo_cache().ok_or_else(|| {
-        std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "No GitHub cache found. Run `aist sync` first.",
-        )
-    })?;
-
-    let prs = group_sessions_by_pr(sessions, &cache);
+/// Render an SVG flamegraph grouped by the PRs in a repo cache
+pub fn render_svg_by_pr(
+    sessions: &[Session],
+    cache: &RepoCache,
+    theme: Theme,
+    colors: &ColorMap,
+) -> std::io::Result<String> {
+    let prs = group_sessions_by_pr(sessions, cache);
 
     if prs.is_empty() {
         return Err(std::io::Error::new(
@@ -770,21 +1539,7 @@ pub fn generate_svg_by_pr(sessions: &[Session], output_path: &Path) -> std::io::
 
     let mut svg = String::new();
 
-    // SVG header
-    svg.push_str(&format!(
-        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}" width="{}" height="{}">
-<style>
-  .pr-label {{ font: bold 12px monospace; fill: #374151; }}
-  .stats-label {{ font: 10px monospace; fill: #6b7280; }}
-  .legend-label {{ font: 12px sans-serif; fill: #374151; }}
-  .title {{ font: bold 16px sans-serif; fill: #111827; }}
-  rect.span {{ stroke: #fff; stroke-width: 1; }}
-  rect.span:hover {{ stroke: #000; stroke-width: 2; opacity: 0.8; }}
-</style>
-<rect width="100%" height="100%" fill="{}"/>
-"#,
-        width, height, width, height, "#f9fafb"
-    ));
+    svg.push_str(&svg_header(width, height, theme));
 
     // Title
     svg.push_str(&format!(
@@ -792,29 +1547,7 @@ pub fn generate_svg_by_pr(sessions: &[Session], output_path: &Path) -> std::io::
         margin
     ));
 
-    // Legend
-    let legend_y = 45;
-    let legend_items = [
-        (ActivityType::Productive, 0),
-        (ActivityType::Reading, 120),
-        (ActivityType::Executing, 260),
-        (ActivityType::Error, 380),
-        (ActivityType::Gap, 470),
-        (ActivityType::Thinking, 570),
-    ];
-
-    for (activity, x_offset) in legend_items {
-        svg.push_str(&format!(
-            r#"<rect x="{}" y="{}" width="14" height="14" fill="{}" rx="2"/>
-<text x="{}" y="{}" class="legend-label">{}</text>"#,
-            margin + x_offset,
-            legend_y,
-            activity.color(),
-            margin + x_offset + 18,
-            legend_y + 11,
-            activity.label()
-        ));
-    }
+    svg.push_str(&render_legend(colors, margin));
 
     let chart_y_start = margin + legend_height;
     let chart_width = width - margin * 2 - 180;
@@ -826,7 +1559,7 @@ pub fn generate_svg_by_pr(sessions: &[Session], output_path: &Path) -> std::io::
         // Collect all spans from all sessions for this PR
         let mut all_spans: Vec<TimeSpan> = Vec::new();
         for session in &pr.sessions {
-            all_spans.extend(extract_spans(session));
+            all_spans.extend(extract_spans(session, DEFAULT_GAP_SECONDS));
         }
 
         // Calculate time breakdown by activity type
@@ -837,15 +1570,7 @@ pub fn generate_svg_by_pr(sessions: &[Session], output_path: &Path) -> std::io::
         }
 
         // PR label - escape for XML
-        let display_title = if pr.title.len() > 20 {
-            format!("{}...", &pr.title[..17])
-        } else {
-            pr.title.clone()
-        };
-        let display_title = display_title
-            .replace('&', "&amp;")
-            .replace('<', "&lt;")
-            .replace('>', "&gt;");
+        let display_title = escape_xml(&metrics::truncate_chars(&pr.title, 20));
 
         // Issues closed by this PR
         let issues_str = if pr.closed_issues.is_empty() {
@@ -862,7 +1587,7 @@ pub fn generate_svg_by_pr(sessions: &[Session], output_path: &Path) -> std::io::
         };
 
         svg.push_str(&format!(
-            r#"<text x="{}" y="{}" class="pr-label">PR #{} {}</text>
+            r#"<text x="{}" y="{}" class="group-label">PR #{} {}</text>
 <text x="{}" y="{}" class="stats-label">{} sessions, {}{}</text>"#,
             margin,
             y + row_height / 2,
@@ -878,11 +1603,11 @@ pub fn generate_svg_by_pr(sessions: &[Session], output_path: &Path) -> std::io::
         // Background bar
         let bar_x = margin + 180;
         svg.push_str(&format!(
-            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#e5e7eb\" rx=\"3\"/>",
+            "<rect class=\"track\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"3\"/>",
             bar_x,
             y + 4,
             chart_width,
-            row_height - 8
+            row_height - 8,
         ));
 
         // Draw proportional blocks for each activity type
@@ -904,18 +1629,17 @@ pub fn generate_svg_by_pr(sessions: &[Session], output_path: &Path) -> std::io::
 
                 if block_width >= 1 {
                     let percent = (width_ratio * 100.0) as usize;
-                    svg.push_str(&format!(
-                        r#"<rect class="span" x="{}" y="{}" width="{}" height="{}" fill="{}" rx="2">
-<title>{}: {} ({}%)</title>
-</rect>"#,
-                        bar_x + x_offset as usize,
-                        y + 4,
-                        block_width,
-                        row_height - 8,
-                        activity.color(),
-                        activity.label(),
-                        format_duration(activity_time),
-                        percent
+                    svg.push_str(&span_rect(
+                        (bar_x + x_offset as usize, y + 4),
+                        (block_width, row_height - 8),
+                        2,
+                        colors.color(activity),
+                        &format!(
+                            "{}: {} ({}%)",
+                            activity.label(),
+                            format_duration(activity_time),
+                            percent
+                        ),
                     ));
                     x_offset += block_width as f64;
                 }
@@ -925,21 +1649,13 @@ pub fn generate_svg_by_pr(sessions: &[Session], output_path: &Path) -> std::io::
 
     svg.push_str("</svg>");
 
-    let mut file = File::create(output_path)?;
-    file.write_all(svg.as_bytes())?;
-
-    Ok(())
+    Ok(svg)
 }
 
 /// Group sessions by GitHub PR number
 #[allow(clippy::type_complexity)]
 fn group_sessions_by_pr<'a>(sessions: &'a [Session], cache: &RepoCache) -> Vec<PrGroup<'a>> {
-    // Build branch -> PR mapping
-    let branch_to_pr: HashMap<&str, &crate::github::PrMapping> = cache
-        .prs
-        .iter()
-        .map(|pr| (pr.branch.as_str(), pr))
-        .collect();
+    let branch_to_pr = cache.branch_to_pr();
 
     // Build PR -> (title, sessions, total_mins, closed_issues)
     let mut pr_data: HashMap<u32, (String, Vec<&'a Session>, f64, Vec<u32>)> = HashMap::new();
@@ -985,6 +1701,7 @@ fn group_sessions_by_pr<'a>(sessions: &'a [Session], cache: &RepoCache) -> Vec<P
         b.total_mins
             .partial_cmp(&a.total_mins)
             .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.pr_number.cmp(&b.pr_number))
     });
 
     prs
@@ -992,12 +1709,7 @@ fn group_sessions_by_pr<'a>(sessions: &'a [Session], cache: &RepoCache) -> Vec<P
 
 /// Group sessions by GitHub issue number
 fn group_sessions_by_issue<'a>(sessions: &'a [Session], cache: &RepoCache) -> Vec<IssueGroup<'a>> {
-    // Build branch -> PR mapping
-    let branch_to_pr: HashMap<&str, &crate::github::PrMapping> = cache
-        .prs
-        .iter()
-        .map(|pr| (pr.branch.as_str(), pr))
-        .collect();
+    let branch_to_pr = cache.branch_to_pr();
 
     // Build issue -> (title, sessions, total_mins)
     let mut issue_data: HashMap<u32, (String, Vec<&'a Session>, f64)> = HashMap::new();
@@ -1046,6 +1758,7 @@ fn group_sessions_by_issue<'a>(sessions: &'a [Session], cache: &RepoCache) -> Ve
         b.total_mins
             .partial_cmp(&a.total_mins)
             .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.issue_number.cmp(&b.issue_number))
     });
 
     issues
@@ -1054,6 +1767,16 @@ fn group_sessions_by_issue<'a>(sessions: &'a [Session], cache: &RepoCache) -> Ve
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bottlenecks::GAP_FLOOR_SECONDS;
+
+    #[test]
+    fn test_group_by_dispatch() {
+        assert_eq!(Grouping::parse("session"), Grouping::Session);
+        assert_eq!(Grouping::parse("project"), Grouping::Project);
+        assert_eq!(Grouping::parse("pr"), Grouping::Pr);
+        assert_eq!(Grouping::parse("issue"), Grouping::Issue);
+        assert_eq!(Grouping::parse("whatever"), Grouping::Session);
+    }
 
     #[test]
     fn test_activity_colors() {
@@ -1074,4 +1797,467 @@ mod tests {
             "my-app"
         );
     }
+
+    #[test]
+    fn test_svg_data_uri_round_trips() {
+        let start = chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 1, 1, 10, 0, 0).unwrap();
+        let session = Session {
+            session_id: "s1".to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: std::path::PathBuf::from("/test/session.jsonl"),
+            start_time: Some(start),
+            end_time: Some(start + chrono::Duration::minutes(30)),
+            ..Default::default()
+        };
+        let svg = render_svg(&[session], None, Theme::Light, &ColorMap::default()).unwrap();
+
+        let uri = svg_data_uri(&svg);
+        let prefix = "data:image/svg+xml;base64,";
+        assert!(uri.starts_with(prefix));
+
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(&uri[prefix.len()..])
+            .unwrap();
+        let decoded = String::from_utf8(decoded).unwrap();
+        assert!(decoded.starts_with("<svg"));
+        assert!(decoded.ends_with("</svg>"));
+        assert_eq!(decoded, svg);
+    }
+
+    #[test]
+    fn test_render_svg_absolute_shares_one_axis() {
+        let start = chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 1, 1, 10, 0, 0).unwrap();
+        let session = |id: &str, offset: i64, minutes: i64| Session {
+            session_id: id.to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: std::path::PathBuf::from("/test/session.jsonl"),
+            start_time: Some(start + chrono::Duration::minutes(offset)),
+            end_time: Some(start + chrono::Duration::minutes(offset + minutes)),
+            ..Default::default()
+        };
+
+        // Two overlapping sessions spanning about three hours
+        let svg = render_svg_absolute(
+            &[session("s1", 0, 90), session("s2", 60, 120)],
+            None,
+            Theme::Light,
+            &ColorMap::default(),
+        )
+        .unwrap();
+        assert!(svg.matches("<line").count() >= 3);
+
+        // A single instantaneous session still gets an hour-wide axis
+        let svg = render_svg_absolute(
+            &[session("s3", 15, 0)],
+            None,
+            Theme::Light,
+            &ColorMap::default(),
+        )
+        .unwrap();
+        assert_eq!(svg.matches("<line").count(), 2);
+        assert!(svg.ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_bar_layouts_share_legend_and_span_markup() {
+        let start = chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 1, 1, 10, 0, 0).unwrap();
+        let session = Session {
+            session_id: "s1".to_string(),
+            project: "/test/project".to_string(),
+            start_time: Some(start),
+            end_time: Some(start + chrono::Duration::minutes(30)),
+            ..Default::default()
+        };
+        let colors = ColorMap::default();
+        let sessions = std::slice::from_ref(&session);
+        let legend = render_legend(&colors, 40);
+
+        for svg in [
+            render_svg(sessions, None, Theme::Light, &colors).unwrap(),
+            render_svg_absolute(sessions, None, Theme::Light, &colors).unwrap(),
+            render_svg_by_project(sessions, Theme::Light, &colors).unwrap(),
+        ] {
+            assert!(svg.starts_with("<svg"));
+            assert!(svg.contains(&legend));
+        }
+
+        let rect = span_rect((10, 20), (30, 40), 1, "#000", r#"Bash: echo "<hi>" & bye"#);
+        assert!(rect.starts_with(r#"<rect class="span" x="10" y="20" width="30" height="40""#));
+        assert!(rect.contains("echo &quot;&lt;hi&gt;&quot; &amp; bye"));
+    }
+
+    #[test]
+    fn test_dark_theme_swaps_background() {
+        let start = chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 1, 1, 10, 0, 0).unwrap();
+        let session = Session {
+            session_id: "s1".to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: std::path::PathBuf::from("/test/session.jsonl"),
+            start_time: Some(start),
+            end_time: Some(start + chrono::Duration::minutes(30)),
+            ..Default::default()
+        };
+        let background = |theme: Theme| {
+            let svg = render_svg(
+                std::slice::from_ref(&session),
+                None,
+                theme,
+                &ColorMap::default(),
+            )
+            .unwrap();
+            svg.contains(&format!(
+                r#"<rect width="100%" height="100%" fill="{}"/>"#,
+                "#111827"
+            ))
+        };
+
+        assert!(background(Theme::Dark));
+        assert!(!background(Theme::Light));
+
+        // Tracks take their color from the header's stylesheet in every layout
+        let sessions = std::slice::from_ref(&session);
+        let colors = ColorMap::default();
+        for svg in [
+            render_svg_by_project(sessions, Theme::Dark, &colors).unwrap(),
+            render_svg_lanes(sessions, Theme::Dark, &colors).unwrap(),
+        ] {
+            assert!(svg.contains("rect.track { fill: #374151; }"));
+            assert!(!svg.contains(r##"fill="#374151""##));
+        }
+        assert_eq!(parse_theme("dark"), Ok(Theme::Dark));
+        assert!(parse_theme("solarized").is_err());
+    }
+
+    #[test]
+    fn test_color_map_overrides_productive() {
+        use crate::parser::{Message, ToolCall};
+        let start = chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 1, 1, 10, 0, 0).unwrap();
+        let edit = |minute: i64| Message {
+            msg_type: MessageType::Assistant,
+            timestamp: Some(start + chrono::Duration::minutes(minute)),
+            tool_calls: vec![ToolCall {
+                id: format!("t{}", minute),
+                name: "Edit".to_string(),
+                input: serde_json::json!({}),
+                parallel_group: 0,
+            }],
+            ..Default::default()
+        };
+        let session = Session {
+            session_id: "color-session".to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: std::path::PathBuf::from("/test/session.jsonl"),
+            start_time: Some(start),
+            end_time: Some(start + chrono::Duration::minutes(2)),
+            messages: vec![edit(0), edit(1), edit(2)],
+            ..Default::default()
+        };
+        let overrides = HashMap::from([("productive".to_string(), "#0055ff".to_string())]);
+        let colors = ColorMap::from_overrides(overrides).unwrap();
+
+        let svg = render_svg(&[session], None, Theme::Light, &colors).unwrap();
+
+        assert!(svg.contains(r##"fill="#0055ff""##));
+        assert!(!svg.contains(ActivityType::Productive.color()));
+        // Everything not overridden keeps the built-in palette
+        assert_eq!(
+            colors.color(ActivityType::Error),
+            ActivityType::Error.color()
+        );
+    }
+
+    #[test]
+    fn test_color_map_rejects_malformed_entries() {
+        let bad_hex = HashMap::from([("Gap/Pause".to_string(), "gray".to_string())]);
+        let err = ColorMap::from_overrides(bad_hex).unwrap_err();
+        assert!(err.contains("invalid color 'gray' for Gap/Pause"));
+
+        let unknown = HashMap::from([("Idle".to_string(), "#123".to_string())]);
+        assert!(ColorMap::from_overrides(unknown)
+            .unwrap_err()
+            .contains("unknown activity 'Idle'"));
+    }
+
+    #[test]
+    fn test_span_titles_carry_preceding_prompt() {
+        use crate::parser::{Message, ToolCall};
+        let start = chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 1, 1, 10, 0, 0).unwrap();
+        let message =
+            |minute: i64, msg_type: MessageType, text: Option<&str>, tool: Option<&str>| Message {
+                msg_type,
+                timestamp: Some(start + chrono::Duration::minutes(minute)),
+                text_content: text.map(str::to_string),
+                tool_calls: tool
+                    .map(|name| ToolCall {
+                        id: format!("t{}", minute),
+                        name: name.to_string(),
+                        input: serde_json::json!({}),
+                        parallel_group: 0,
+                    })
+                    .into_iter()
+                    .collect(),
+                tool_results: vec![],
+                model: None,
+                usage: None,
+                is_hook: false,
+            };
+        let session = Session {
+            session_id: "prompt-session".to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: std::path::PathBuf::from("/test/session.jsonl"),
+            start_time: Some(start),
+            end_time: Some(start + chrono::Duration::minutes(6)),
+            messages: vec![
+                message(0, MessageType::Assistant, None, Some("Read")),
+                message(
+                    1,
+                    MessageType::User,
+                    Some("refactor the auth module\nthanks"),
+                    None,
+                ),
+                message(2, MessageType::Assistant, None, Some("Edit")),
+                message(4, MessageType::Assistant, None, Some("Bash")),
+            ],
+            ..Default::default()
+        };
+
+        let spans = extract_spans(&session, DEFAULT_GAP_SECONDS);
+        assert_eq!(spans[0].activity, ActivityType::Reading);
+        assert!(spans[0].prompt.is_none());
+        assert!(spans
+            .iter()
+            .filter(|s| s.start > start)
+            .all(|s| s.prompt.as_deref() == Some("refactor the auth module")));
+
+        let svg = render_svg(&[session], None, Theme::Light, &ColorMap::default()).unwrap();
+        assert!(
+            svg.contains("<title>Productive: Edit — &quot;refactor the auth module&quot; (2.0m)")
+        );
+    }
+
+    #[test]
+    fn test_session_row_shows_linked_pr() {
+        use crate::github::PrMapping;
+        let start = chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 1, 1, 10, 0, 0).unwrap();
+        let session = |id: &str, branch: &str| Session {
+            session_id: id.to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: std::path::PathBuf::from("/test/session.jsonl"),
+            git_branch: Some(branch.to_string()),
+            start_time: Some(start),
+            end_time: Some(start + chrono::Duration::minutes(30)),
+            ..Default::default()
+        };
+        let pr = |pr_number: u32, branch: &str, closed_issues: Vec<u32>| PrMapping {
+            pr_number,
+            title: "Some change".to_string(),
+            branch: branch.to_string(),
+            closed_issues,
+            merged_at: None,
+            state: None,
+        };
+        let cache = RepoCache {
+            host: Default::default(),
+            domain: None,
+            owner: "o".to_string(),
+            repo: "r".to_string(),
+            prs: vec![pr(42, "feat/login", vec![]), pr(43, "fix/crash", vec![7])],
+            synced_at: String::new(),
+            truncated: false,
+            issue_titles: HashMap::new(),
+            state: None,
+            limit: None,
+            ttl_hours: None,
+        };
+        let sessions = [
+            session("login-session", "feat/login"),
+            session("crash-session", "fix/crash"),
+            session("other-session", "main"),
+        ];
+
+        let svg = render_svg(&sessions, Some(&cache), Theme::Light, &ColorMap::default()).unwrap();
+
+        assert!(svg.contains(">login-se (project) PR #42<"));
+        assert!(svg.contains(">crash-se (project) #7<"));
+        assert!(svg.contains(">other-se (project)<"));
+    }
+
+    #[test]
+    fn test_render_html_is_interactive() {
+        use crate::parser::{Message, ToolCall};
+        let start = chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 1, 1, 10, 0, 0).unwrap();
+        let edit = |minute: i64| Message {
+            msg_type: MessageType::Assistant,
+            timestamp: Some(start + chrono::Duration::minutes(minute)),
+            tool_calls: vec![ToolCall {
+                id: format!("t{}", minute),
+                name: "Edit".to_string(),
+                input: serde_json::json!({}),
+                parallel_group: 0,
+            }],
+            ..Default::default()
+        };
+        let session = Session {
+            session_id: "html-session".to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: std::path::PathBuf::from("/test/session.jsonl"),
+            start_time: Some(start),
+            end_time: Some(start + chrono::Duration::minutes(10)),
+            messages: vec![edit(0), edit(1), edit(10)],
+            ..Default::default()
+        };
+
+        let html = render_html(
+            &render_svg(&[session], None, Theme::Light, &ColorMap::default()).unwrap(),
+            Theme::Light,
+        );
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<script>"));
+        assert!(html.contains("<title>Productive: "));
+        assert!(html.contains("<title>Gap/Pause: 9m pause"));
+        assert!(!html.contains("src=\"http"));
+    }
+
+    #[test]
+    fn test_render_svg_lanes_one_lane_per_category() {
+        use crate::parser::{Message, ToolCall};
+        let start = chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 1, 1, 10, 0, 0).unwrap();
+        let call = |id: &str, name: &str| ToolCall {
+            id: id.to_string(),
+            name: name.to_string(),
+            input: serde_json::json!({}),
+            parallel_group: 0,
+        };
+        let message = |minute: i64, calls: Vec<ToolCall>| Message {
+            msg_type: MessageType::Assistant,
+            timestamp: Some(start + chrono::Duration::minutes(minute)),
+            tool_calls: calls,
+            ..Default::default()
+        };
+        let session = Session {
+            session_id: "lanes-session".to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: std::path::PathBuf::from("/test/session.jsonl"),
+            start_time: Some(start),
+            end_time: Some(start + chrono::Duration::minutes(30)),
+            messages: vec![
+                message(1, vec![call("t1", "Read"), call("t2", "Grep")]),
+                message(5, vec![call("t3", "Edit")]),
+                message(9, vec![call("t4", "Bash"), call("t5", "Read")]),
+            ],
+            ..Default::default()
+        };
+
+        let svg = render_svg_lanes(&[session], Theme::Light, &ColorMap::default()).unwrap();
+
+        assert_eq!(svg.matches("class=\"lane\"").count(), 3);
+        assert!(svg.contains("data-lane=\"Read\""));
+        assert!(svg.contains("data-lane=\"Edit\""));
+        assert!(svg.contains("data-lane=\"Bash\""));
+        assert!(!svg.contains("data-lane=\"Web\""));
+        assert_eq!(svg.matches("class=\"mark\"").count(), 5);
+    }
+
+    #[test]
+    fn test_non_ascii_generic_session_renders() {
+        let dir = std::env::temp_dir().join("aist-flamegraph-tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        // No `session` field, so the id is the multi-byte file stem
+        let path = dir.join("añadir-exportación-日本.jsonl");
+        std::fs::write(
+            &path,
+            [
+                r#"{"role":"user","timestamp":"2026-01-13T10:00:00Z","cwd":"/w/app","text":"add export"}"#,
+                r#"{"role":"assistant","timestamp":"2026-01-13T10:01:00Z","tool":"Read","input":{"file_path":"/w/app/a.rs"}}"#,
+                r#"{"role":"assistant","timestamp":"2026-01-13T10:02:00Z","tool":"Edit","input":{"file_path":"/w/app/a.rs"}}"#,
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        let session = crate::parser::parse_transcript(&path).unwrap();
+        assert_eq!(session.session_id, "añadir-exportación-日本");
+        let sessions = [session];
+
+        let svg = render_svg(&sessions, None, Theme::Light, &ColorMap::default()).unwrap();
+        assert!(svg.contains("añadir-e (app)"));
+        let lanes = render_svg_lanes(&sessions, Theme::Light, &ColorMap::default()).unwrap();
+        assert!(lanes.contains("Tool Lanes: añadir-e"));
+    }
+
+    #[test]
+    fn test_gap_after_build_is_executing() {
+        use crate::parser::{Message, ToolCall};
+        let start = chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 1, 1, 10, 0, 0).unwrap();
+        let bash = |minute: i64, command: &str| Message {
+            msg_type: MessageType::Assistant,
+            timestamp: Some(start + chrono::Duration::minutes(minute)),
+            tool_calls: vec![ToolCall {
+                id: format!("t{}", minute),
+                name: "Bash".to_string(),
+                input: serde_json::json!({ "command": command }),
+                parallel_group: 0,
+            }],
+            ..Default::default()
+        };
+        let session = Session {
+            session_id: "build-session".to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: std::path::PathBuf::from("/test/session.jsonl"),
+            start_time: Some(start),
+            end_time: Some(start + chrono::Duration::minutes(20)),
+            messages: vec![
+                bash(0, "cargo build --release"),
+                bash(6, "ls target"),
+                bash(12, "echo done"),
+            ],
+            ..Default::default()
+        };
+
+        let spans = extract_spans(&session, DEFAULT_GAP_SECONDS);
+        let waits: Vec<&TimeSpan> = spans
+            .iter()
+            .filter(|s| s.label.contains("waiting on"))
+            .collect();
+
+        assert_eq!(waits.len(), 1);
+        assert_eq!(waits[0].activity, ActivityType::Executing);
+        assert!(waits[0].label.contains("cargo build"));
+        // The pause after `ls` is still a plain gap
+        assert!(spans
+            .iter()
+            .any(|s| s.activity == ActivityType::Gap
+                && s.start == start + chrono::Duration::minutes(6)));
+    }
+
+    #[test]
+    fn test_gap_threshold_is_a_parameter() {
+        use crate::parser::Message;
+        let start = chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 1, 1, 10, 0, 0).unwrap();
+        let message = |minute: i64| Message {
+            msg_type: MessageType::User,
+            timestamp: Some(start + chrono::Duration::minutes(minute)),
+            text_content: Some("next step".to_string()),
+            ..Default::default()
+        };
+        let session = Session {
+            session_id: "pause-session".to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: std::path::PathBuf::from("/test/session.jsonl"),
+            start_time: Some(start),
+            end_time: Some(start + chrono::Duration::minutes(4)),
+            messages: vec![message(0), message(3), message(4)],
+            ..Default::default()
+        };
+        let has_gap = |gap_secs| {
+            extract_spans(&session, gap_secs)
+                .iter()
+                .any(|s| s.activity == ActivityType::Gap)
+        };
+
+        // A 3 minute pause is a gap at the default threshold...
+        assert!(has_gap(DEFAULT_GAP_SECONDS));
+        // ...but not at the 5 minute bottleneck floor
+        assert!(!has_gap(GAP_FLOOR_SECONDS));
+    }
 }
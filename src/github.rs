@@ -1,10 +1,16 @@
+use crate::error::AistError;
+use crate::gitlab;
+use crate::metrics::format_duration;
+use crate::state;
+use chrono::{DateTime, Duration, Utc};
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-/// A merged PR with its metadata
+/// A PR with its metadata, as listed by `gh pr list`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MergedPr {
     pub number: u32,
@@ -14,6 +20,17 @@ pub struct MergedPr {
     pub body: Option<String>,
     #[serde(rename = "mergedAt")]
     pub merged_at: Option<String>,
+    #[serde(default)]
+    pub state: Option<PrState>,
+}
+
+/// Where a PR stands on GitHub
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum PrState {
+    Open,
+    Merged,
+    Closed,
 }
 
 /// PR→Issue→Branch mapping stored in cache
@@ -24,32 +41,170 @@ pub struct PrMapping {
     pub branch: String,
     pub closed_issues: Vec<u32>,
     pub merged_at: Option<String>,
+    /// Missing from caches synced before open PRs could be fetched
+    #[serde(default)]
+    pub state: Option<PrState>,
+}
+
+impl PrMapping {
+    /// The PR's state, falling back to `merged_at` for older caches
+    pub fn status(&self) -> PrState {
+        match (self.state, &self.merged_at) {
+            (Some(state), _) => state,
+            (None, Some(_)) => PrState::Merged,
+            (None, None) => PrState::Open,
+        }
+    }
+}
+
+/// Where a repo is hosted, which decides the CLI `sync` shells out to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteHost {
+    #[default]
+    GitHub,
+    GitLab,
 }
 
-/// Cached repo data
+impl RemoteHost {
+    /// The public instance, whose repos the CLIs address without a host
+    fn default_domain(self) -> &'static str {
+        match self {
+            RemoteHost::GitHub => "github.com",
+            RemoteHost::GitLab => "gitlab.com",
+        }
+    }
+
+    /// Recognize a host name: gitlab.com or a self-managed `gitlab.*`, and
+    /// github.com or a GitHub Enterprise `github.*`
+    fn from_domain(domain: &str) -> Option<RemoteHost> {
+        let domain = domain.to_lowercase();
+        if domain.contains("gitlab") {
+            Some(RemoteHost::GitLab)
+        } else if domain.contains("github") {
+            Some(RemoteHost::GitHub)
+        } else {
+            None
+        }
+    }
+}
+
+/// Cached repo data. For GitLab, `owner` is the full group path and PRs
+/// are merge requests.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RepoCache {
+    #[serde(default)]
+    pub host: RemoteHost,
+    /// Host name for GitHub Enterprise or self-managed GitLab; None for
+    /// github.com and gitlab.com
+    #[serde(default)]
+    pub domain: Option<String>,
     pub owner: String,
     pub repo: String,
     pub prs: Vec<PrMapping>,
     pub synced_at: String,
+    /// Sync hit the PR limit, so older PRs are probably missing
+    #[serde(default)]
+    pub truncated: bool,
+    /// Issue number -> title, for issues closed by the cached PRs
+    #[serde(default)]
+    pub issue_titles: HashMap<u32, String>,
+    /// `sync --state` the PRs were fetched with; None for older caches
+    #[serde(default)]
+    pub state: Option<String>,
+    /// PR limit of that fetch; None for older caches
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// `sync --ttl` the cache was written with; None means the default
+    #[serde(default)]
+    pub ttl_hours: Option<i64>,
+}
+
+impl RepoCache {
+    /// Branch name -> the PR opened from it. A reused branch can have
+    /// several PRs; an open or merged one wins over closed ones, then the
+    /// newest (highest number).
+    pub fn branch_to_pr(&self) -> HashMap<&str, &PrMapping> {
+        let rank = |pr: &PrMapping| (pr.status() != PrState::Closed, pr.pr_number);
+        let mut by_branch: HashMap<&str, &PrMapping> = HashMap::new();
+        for pr in &self.prs {
+            by_branch
+                .entry(pr.branch.as_str())
+                .and_modify(|best| {
+                    if rank(pr) > rank(best) {
+                        *best = pr;
+                    }
+                })
+                .or_insert(pr);
+        }
+        by_branch
+    }
+
+    /// Time since the last sync, or None if `synced_at` doesn't parse
+    pub fn age(&self, now: DateTime<Utc>) -> Option<Duration> {
+        let synced_at = DateTime::parse_from_rfc3339(&self.synced_at).ok()?;
+        Some(now - synced_at.with_timezone(&Utc))
+    }
+
+    /// Whether the cache was synced less than `ttl_hours` ago
+    pub fn is_fresh(&self, ttl_hours: i64, now: DateTime<Utc>) -> bool {
+        self.age(now)
+            .is_some_and(|age| age < Duration::hours(ttl_hours))
+    }
+
+    /// Whether the cache already holds what a sync of `state` PRs up to
+    /// `limit` would fetch. An `all` cache covers a narrower state only when
+    /// it wasn't truncated, since its limit counts PRs of every state. Older
+    /// caches don't record state or limit, so they never do.
+    pub fn covers(&self, state: &str, limit: usize) -> bool {
+        let Some(fetched_limit) = self.limit else {
+            return false;
+        };
+        match self.state.as_deref() {
+            Some(s) if s == state => fetched_limit >= limit,
+            Some("all") => !self.truncated,
+            _ => false,
+        }
+    }
+
+    /// Title of an issue, falling back to the title of the PR that closed
+    /// it when the issue wasn't fetched
+    pub fn issue_title<'a>(&'a self, issue: u32, pr: &'a PrMapping) -> &'a str {
+        self.issue_titles
+            .get(&issue)
+            .map(String::as_str)
+            .unwrap_or(&pr.title)
+    }
 }
 
 /// Get the cache directory path
 fn get_cache_dir() -> PathBuf {
-    dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("aist")
-        .join("repos")
+    state::config_dir().join("repos")
 }
 
-/// Get the cache file path for a repo
+/// Get the cache file path for a repo. GitLab subgroups put slashes in
+/// `owner`, which can't go in a file name.
 fn get_cache_path(owner: &str, repo: &str) -> PathBuf {
-    get_cache_dir().join(format!("{}-{}.json", owner, repo))
+    get_cache_dir().join(format!("{}-{}.json", owner.replace('/', "-"), repo))
+}
+
+/// A repo as named by a git remote URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Remote {
+    host: RemoteHost,
+    /// None for the host's public instance
+    domain: Option<String>,
+    owner: String,
+    repo: String,
 }
 
-/// Auto-detect repo from git remote
+/// Auto-detect owner/repo from git remote
 pub fn detect_repo() -> Option<(String, String)> {
+    detect_remote().map(|r| (r.owner, r.repo))
+}
+
+/// Auto-detect host and owner/repo from git remote
+fn detect_remote() -> Option<Remote> {
     let output = Command::new("git")
         .args(["remote", "get-url", "origin"])
         .output()
@@ -60,36 +215,96 @@ pub fn detect_repo() -> Option<(String, String)> {
     }
 
     let url = String::from_utf8_lossy(&output.stdout);
-    parse_github_remote(&url)
+    parse_remote(&url)
 }
 
-/// Parse owner/repo from git remote URL
-fn parse_github_remote(url: &str) -> Option<(String, String)> {
-    let url = url.trim();
+/// Committer date of a git ref (tag, branch or sha) in the repo at `dir`
+pub fn commit_date(dir: &Path, git_ref: &str) -> Result<DateTime<Utc>, AistError> {
+    let not_a_repo = || AistError::NotAGitRepo(dir.display().to_string());
 
-    // SSH format: git@github.com:owner/repo.git
-    if let Some(rest) = url.strip_prefix("git@github.com:") {
-        let rest = rest.strip_suffix(".git").unwrap_or(rest);
-        let parts: Vec<&str> = rest.split('/').collect();
-        if parts.len() == 2 {
-            return Some((parts[0].to_string(), parts[1].to_string()));
-        }
+    let is_repo = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--git-dir"])
+        .output()
+        .map_err(|e| AistError::Io("Failed to run git".to_string(), e))?;
+    if !is_repo.status.success() {
+        return Err(not_a_repo());
     }
 
-    // HTTPS format: https://github.com/owner/repo.git
-    if url.contains("github.com") {
-        let parts: Vec<&str> = url.split('/').collect();
-        if parts.len() >= 2 {
-            let owner = parts[parts.len() - 2].to_string();
-            let repo = parts[parts.len() - 1]
-                .strip_suffix(".git")
-                .unwrap_or(parts[parts.len() - 1])
-                .to_string();
-            return Some((owner, repo));
-        }
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["show", "-s", "--format=%cI", git_ref, "--"])
+        .output()
+        .map_err(|e| AistError::Io("Failed to run git".to_string(), e))?;
+    if !output.status.success() {
+        return Err(AistError::GitRefNotFound(git_ref.to_string()));
+    }
+
+    parse_commit_date(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse the `%cI` (strict ISO 8601) output of `git show`
+fn parse_commit_date(output: &str) -> Result<DateTime<Utc>, AistError> {
+    let line = output.lines().next().unwrap_or("").trim();
+    DateTime::parse_from_rfc3339(line)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| AistError::Parse(format!("Unexpected git date '{}': {}", line, e)))
+}
+
+/// Parse host and owner/repo from a GitHub or GitLab remote URL. The repo
+/// is the last path segment and the owner everything before it, so GitLab
+/// subgroups come out as e.g. `group/subgroup`.
+fn parse_remote(url: &str) -> Option<Remote> {
+    let (domain, path) = split_remote(url.trim())?;
+    let host = RemoteHost::from_domain(domain)?;
+
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let (owner, repo) = path.rsplit_once('/')?;
+    if owner.is_empty() || repo.is_empty() {
+        return None;
     }
+    let domain = domain.to_lowercase();
+    Some(Remote {
+        host,
+        domain: (domain != host.default_domain()).then_some(domain),
+        owner: owner.to_string(),
+        repo: repo.to_string(),
+    })
+}
+
+/// The `--repo` value for gh or glab. gh takes `host/owner/repo` for
+/// GitHub Enterprise but can't address a nested owner, which GitHub doesn't
+/// have; glab takes a full URL for a self-managed instance.
+fn repo_arg(
+    host: RemoteHost,
+    domain: Option<&str>,
+    owner: &str,
+    repo: &str,
+) -> Result<String, AistError> {
+    match (host, domain) {
+        (RemoteHost::GitHub, _) if owner.contains('/') => Err(AistError::UnsupportedRemote(
+            format!("GitHub repos have a single owner, not {}/{}", owner, repo),
+        )),
+        (RemoteHost::GitHub, Some(domain)) => Ok(format!("{}/{}/{}", domain, owner, repo)),
+        (RemoteHost::GitLab, Some(domain)) => Ok(format!("https://{}/{}/{}", domain, owner, repo)),
+        (_, None) => Ok(format!("{}/{}", owner, repo)),
+    }
+}
 
-    None
+/// Split a remote URL into host name and repo path. Handles URLs with a
+/// scheme (`https://host/path`, `ssh://git@host:22/path`) and scp-style
+/// SSH (`git@host:path`).
+fn split_remote(url: &str) -> Option<(&str, &str)> {
+    let (authority, path) = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?,
+        None => url.split_once(':')?,
+    };
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?;
+    (!host.is_empty()).then_some((host, path))
 }
 
 /// Extract closed issue numbers from PR body
@@ -138,52 +353,102 @@ fn extract_closed_issues(body: &Option<String>) -> Vec<u32> {
     issues
 }
 
-/// Fetch merged PRs using gh CLI
-fn fetch_merged_prs(owner: &str, repo: &str) -> Result<Vec<MergedPr>, String> {
+/// Merged PRs fetched by a normal sync
+const DEFAULT_PR_LIMIT: usize = 100;
+
+/// Merged PRs fetched by `sync --full`; gh paginates up to this many
+const FULL_PR_LIMIT: usize = 10_000;
+
+/// Which PRs `aist sync` fetches: values for `gh pr list --state`
+pub const PR_STATES: [&str; 3] = ["merged", "open", "all"];
+
+/// Fetch up to `limit` PRs in the given state of `repo` (a `repo_arg`
+/// value) using gh CLI
+fn fetch_prs(repo: &str, limit: usize, state: &str) -> Result<Vec<MergedPr>, AistError> {
     let output = Command::new("gh")
         .args([
             "pr",
             "list",
             "--repo",
-            &format!("{}/{}", owner, repo),
+            repo,
             "--state",
-            "merged",
+            state,
             "--json",
-            "number,headRefName,body,mergedAt,title",
+            "number,headRefName,body,mergedAt,title,state",
             "--limit",
-            "100",
+            &limit.to_string(),
         ])
         .output()
-        .map_err(|e| format!("Failed to run gh command: {}", e))?;
+        .map_err(AistError::from_gh_spawn)?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("gh command failed: {}", stderr));
+        return Err(AistError::from_gh_stderr(&stderr));
     }
 
     let prs: Vec<MergedPr> = serde_json::from_slice(&output.stdout)
-        .map_err(|e| format!("Failed to parse gh output: {}", e))?;
+        .map_err(|e| AistError::Parse(format!("Failed to parse gh output: {}", e)))?;
 
     Ok(prs)
 }
 
-/// Sync GitHub PRs and cache the mappings
-pub fn sync(owner: Option<&str>, repo: Option<&str>) -> Result<(), String> {
-    // Auto-detect repo if not specified
-    let (owner, repo) = match (owner, repo) {
-        (Some(o), Some(r)) => (o.to_string(), r.to_string()),
-        _ => detect_repo().ok_or_else(|| {
-            "Could not detect repo from git remote. Use --owner and --repo flags.".to_string()
-        })?,
-    };
+/// An issue as returned by `gh issue view`
+#[derive(Debug, Deserialize)]
+struct GhIssue {
+    title: String,
+}
 
-    println!("{} Syncing {}/{}...", "→".blue(), owner.bold(), repo.bold());
+/// Fetch an issue's title using gh CLI
+fn fetch_issue_title(repo: &str, number: u32) -> Result<String, AistError> {
+    let output = Command::new("gh")
+        .args([
+            "issue",
+            "view",
+            &number.to_string(),
+            "--repo",
+            repo,
+            "--json",
+            "number,title,state",
+        ])
+        .output()
+        .map_err(AistError::from_gh_spawn)?;
 
-    // Fetch merged PRs
-    let prs = fetch_merged_prs(&owner, &repo)?;
-    println!("{} Fetched {} merged PRs", "✓".green(), prs.len());
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AistError::from_gh_stderr(&stderr));
+    }
+
+    let issue: GhIssue = serde_json::from_slice(&output.stdout)
+        .map_err(|e| AistError::Parse(format!("Failed to parse gh output: {}", e)))?;
+
+    Ok(issue.title)
+}
+
+/// Issues closed by the cached PRs whose titles aren't known yet
+fn missing_issue_titles(cache: &RepoCache) -> Vec<u32> {
+    let mut missing: Vec<u32> = cache
+        .prs
+        .iter()
+        .flat_map(|pr| pr.closed_issues.iter().copied())
+        .filter(|issue| !cache.issue_titles.contains_key(issue))
+        .collect();
+    missing.sort_unstable();
+    missing.dedup();
+    missing
+}
+
+/// Build the cache from fetched PRs. A response that fills the limit is
+/// flagged as truncated, since gh stops there without saying so.
+fn build_cache(
+    host: RemoteHost,
+    domain: Option<String>,
+    owner: &str,
+    repo: &str,
+    prs: Vec<MergedPr>,
+    limit: usize,
+) -> RepoCache {
+    let truncated = prs.len() >= limit;
 
-    // Convert to mappings
     let mappings: Vec<PrMapping> = prs
         .into_iter()
         .map(|pr| {
@@ -194,32 +459,222 @@ pub fn sync(owner: Option<&str>, repo: Option<&str>) -> Result<(), String> {
                 branch: pr.branch,
                 closed_issues,
                 merged_at: pr.merged_at,
+                state: pr.state,
             }
         })
         .collect();
 
+    RepoCache {
+        host,
+        domain,
+        owner: owner.to_string(),
+        repo: repo.to_string(),
+        prs: mappings,
+        synced_at: chrono::Utc::now().to_rfc3339(),
+        truncated,
+        issue_titles: HashMap::new(),
+        state: None,
+        limit: Some(limit),
+        ttl_hours: None,
+    }
+}
+
+/// Hours a synced cache counts as fresh, unless `sync --ttl` says otherwise
+pub const DEFAULT_CACHE_TTL_HOURS: i64 = 24;
+
+/// Print a note when a cache is known to be missing older PRs or is older
+/// than the TTL it was synced with
+pub fn print_cache_notes(cache: &RepoCache) {
+    if let Some(age) = cache.age(Utc::now()) {
+        if age >= Duration::hours(cache.ttl_hours.unwrap_or(DEFAULT_CACHE_TTL_HOURS)) {
+            println!(
+                "{}",
+                format!(
+                    "Note: {}/{} was last synced {} ago. Run `aist sync` to refresh.",
+                    cache.owner,
+                    cache.repo,
+                    format_duration(age.num_minutes() as f64)
+                )
+                .yellow()
+            );
+        }
+    }
+    if cache.truncated {
+        println!(
+            "{}",
+            format!(
+                "Note: only the latest {} merged PRs of {}/{} were synced, metrics may be incomplete. Run `aist sync --full`.",
+                cache.prs.len(),
+                cache.owner,
+                cache.repo
+            )
+            .yellow()
+        );
+    }
+}
+
+/// What `sync` did
+#[derive(Debug, PartialEq, Eq)]
+pub enum SyncOutcome {
+    Synced,
+    /// The cache was younger than the TTL and `--force` wasn't given
+    Fresh,
+}
+
+/// Whether to hit GitHub: always with `force`, otherwise only when there's
+/// no cache, it's at least `ttl_hours` old, or it was fetched with a
+/// narrower `state` or smaller `limit` than asked for now
+fn needs_sync(
+    cache: Option<&RepoCache>,
+    force: bool,
+    state: &str,
+    limit: usize,
+    ttl_hours: i64,
+    now: DateTime<Utc>,
+) -> bool {
+    force || !cache.is_some_and(|c| c.covers(state, limit) && c.is_fresh(ttl_hours, now))
+}
+
+/// Sync GitHub PRs (or GitLab merge requests) and cache the mappings.
+/// `full` lifts the default 100-PR limit. A cache younger than `ttl_hours`
+/// that already covers `state` and the limit is left alone unless `force`
+/// is set. `gitlab` marks an explicit
+/// owner/repo as a GitLab project; detected remotes carry their own host.
+pub fn sync(
+    owner: Option<&str>,
+    repo: Option<&str>,
+    gitlab: bool,
+    full: bool,
+    state: &str,
+    force: bool,
+    ttl_hours: i64,
+) -> Result<SyncOutcome, AistError> {
+    // Auto-detect repo if not specified
+    let Remote {
+        host,
+        domain,
+        owner,
+        repo,
+    } = match (owner, repo) {
+        (Some(o), Some(r)) => Remote {
+            host: if gitlab {
+                RemoteHost::GitLab
+            } else {
+                RemoteHost::GitHub
+            },
+            domain: None,
+            owner: o.to_string(),
+            repo: r.to_string(),
+        },
+        _ => detect_remote().ok_or(AistError::RepoNotDetected)?,
+    };
+    let qualified = repo_arg(host, domain.as_deref(), &owner, &repo)?;
+
+    let limit = if full {
+        FULL_PR_LIMIT
+    } else {
+        DEFAULT_PR_LIMIT
+    };
+    let previous = load_cache(&owner, &repo);
+    let now = Utc::now();
+    if !needs_sync(previous.as_ref(), force, state, limit, ttl_hours, now) {
+        let age = previous
+            .as_ref()
+            .and_then(|c| c.age(now))
+            .unwrap_or_default();
+        println!(
+            "{} Cache for {}/{} is fresh (synced {} ago). Use --force to re-sync.",
+            "✓".green(),
+            owner.bold(),
+            repo.bold(),
+            format_duration(age.num_minutes() as f64)
+        );
+        return Ok(SyncOutcome::Fresh);
+    }
+
+    println!("{} Syncing {}/{}...", "→".blue(), owner.bold(), repo.bold());
+
+    let prs = match host {
+        RemoteHost::GitHub => fetch_prs(&qualified, limit, state)?,
+        RemoteHost::GitLab => gitlab::fetch_mrs(&qualified, limit, state)?,
+    };
+    let kind = if state == "all" {
+        String::new()
+    } else {
+        format!("{} ", state)
+    };
+    let noun = match host {
+        RemoteHost::GitHub => "PRs",
+        RemoteHost::GitLab => "merge requests",
+    };
+    println!("{} Fetched {} {}{}", "✓".green(), prs.len(), kind, noun);
+
+    let mut cache = build_cache(host, domain, &owner, &repo, prs, limit);
+    cache.state = Some(state.to_string());
+    cache.ttl_hours = Some(ttl_hours);
+    if cache.truncated {
+        println!(
+            "{} Hit the {}-PR limit; older PRs were likely left out.{}",
+            "⚠".yellow(),
+            limit,
+            if full {
+                ""
+            } else {
+                " Run `aist sync --full` to fetch them all."
+            }
+        );
+    }
+
     // Count issues linked
-    let issues_count: usize = mappings.iter().map(|m| m.closed_issues.len()).sum();
+    let issues_count: usize = cache.prs.iter().map(|m| m.closed_issues.len()).sum();
     println!("{} Found {} linked issues", "✓".green(), issues_count);
 
+    // Issue titles rarely change once closed, so keep the ones already
+    // cached and only ask gh for new issues
+    if let Some(previous) = previous {
+        cache.issue_titles = previous.issue_titles;
+    }
+    // GitLab issues keep their merge request's title for now
+    let missing = match host {
+        RemoteHost::GitHub => missing_issue_titles(&cache),
+        RemoteHost::GitLab => vec![],
+    };
+    let mut fetched = 0;
+    for &issue in &missing {
+        match fetch_issue_title(&qualified, issue) {
+            Ok(title) => {
+                cache.issue_titles.insert(issue, title);
+                fetched += 1;
+            }
+            // Every further request would fail the same way
+            Err(AistError::GhRateLimited(_)) => break,
+            // Deleted or transferred issues; skip them
+            Err(_) => {}
+        }
+    }
+    if fetched > 0 {
+        println!("{} Fetched {} issue titles", "✓".green(), fetched);
+    }
+    let failed = missing.len() - fetched;
+    if failed > 0 {
+        println!(
+            "{} Couldn't fetch {} issue titles; their PR titles will be shown instead",
+            "⚠".yellow(),
+            failed
+        );
+    }
+
     // Create cache directory
     let cache_dir = get_cache_dir();
     fs::create_dir_all(&cache_dir)
-        .map_err(|e| format!("Failed to create cache directory: {}", e))?;
-
-    // Create cache data
-    let cache = RepoCache {
-        owner: owner.clone(),
-        repo: repo.clone(),
-        prs: mappings,
-        synced_at: chrono::Utc::now().to_rfc3339(),
-    };
+        .map_err(|e| AistError::Io("Failed to create cache directory".to_string(), e))?;
 
     // Write cache file
     let cache_path = get_cache_path(&owner, &repo);
     let json = serde_json::to_string_pretty(&cache)
-        .map_err(|e| format!("Failed to serialize cache: {}", e))?;
-    fs::write(&cache_path, json).map_err(|e| format!("Failed to write cache file: {}", e))?;
+        .map_err(|e| AistError::Parse(format!("Failed to serialize cache: {}", e)))?;
+    fs::write(&cache_path, json)
+        .map_err(|e| AistError::Io("Failed to write cache file".to_string(), e))?;
 
     println!(
         "{} Cached to {}",
@@ -227,7 +682,7 @@ pub fn sync(owner: Option<&str>, repo: Option<&str>) -> Result<(), String> {
         cache_path.display().to_string().dimmed()
     );
 
-    Ok(())
+    Ok(SyncOutcome::Synced)
 }
 
 /// Load cached repo data
@@ -245,16 +700,261 @@ pub fn load_current_repo_cache() -> Option<RepoCache> {
     load_cache(&owner, &repo)
 }
 
+/// Load every synced repo cache, sorted by owner/repo
+pub fn load_all_caches() -> Vec<RepoCache> {
+    load_caches_from(&get_cache_dir())
+}
+
+/// Load every repo cache file in a directory, skipping unreadable ones
+pub fn load_caches_from(dir: &Path) -> Vec<RepoCache> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut caches: Vec<RepoCache> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+        .collect();
+
+    caches.sort_by(|a, b| (&a.owner, &a.repo).cmp(&(&b.owner, &b.repo)));
+    caches
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
+
+    fn merged_pr(number: u32) -> MergedPr {
+        MergedPr {
+            number,
+            title: format!("PR {}", number),
+            branch: format!("branch-{}", number),
+            body: Some("Closes #1".to_string()),
+            merged_at: None,
+            state: Some(PrState::Merged),
+        }
+    }
+
+    #[test]
+    fn test_build_cache_flags_full_response_as_truncated() {
+        let full: Vec<MergedPr> = (1..=100).map(merged_pr).collect();
+        let cache = build_cache(
+            RemoteHost::GitHub,
+            None,
+            "owner",
+            "repo",
+            full,
+            DEFAULT_PR_LIMIT,
+        );
+        assert!(cache.truncated);
+        assert_eq!(cache.prs.len(), 100);
+
+        let partial: Vec<MergedPr> = (1..=42).map(merged_pr).collect();
+        let cache = build_cache(
+            RemoteHost::GitHub,
+            None,
+            "owner",
+            "repo",
+            partial,
+            DEFAULT_PR_LIMIT,
+        );
+        assert!(!cache.truncated);
+        assert_eq!(cache.prs[0].closed_issues, vec![1]);
+    }
+
+    #[test]
+    fn test_open_pr_maps_without_merged_at() {
+        let json = r#"[{"number":7,"title":"WIP login","headRefName":"feat/login","body":"Fixes #12","mergedAt":null,"state":"OPEN"}]"#;
+        let prs: Vec<MergedPr> = serde_json::from_str(json).unwrap();
+        assert_eq!(extract_closed_issues(&prs[0].body), vec![12]);
+
+        let cache = build_cache(
+            RemoteHost::GitHub,
+            None,
+            "owner",
+            "repo",
+            prs,
+            DEFAULT_PR_LIMIT,
+        );
+        let pr = &cache.prs[0];
+        assert_eq!(pr.branch, "feat/login");
+        assert_eq!(pr.closed_issues, vec![12]);
+        assert!(pr.merged_at.is_none());
+        assert_eq!(pr.status(), PrState::Open);
+    }
+
+    #[test]
+    fn test_old_cache_infers_state_from_merged_at() {
+        let json = r#"{"pr_number":3,"title":"t","branch":"b","closed_issues":[],"merged_at":"2026-01-01T00:00:00Z"}"#;
+        let pr: PrMapping = serde_json::from_str(json).unwrap();
+        assert_eq!(pr.state, None);
+        assert_eq!(pr.status(), PrState::Merged);
+    }
+
+    #[test]
+    fn test_old_cache_without_truncated_field_loads() {
+        let json = r#"{"owner":"o","repo":"r","prs":[],"synced_at":"2026-01-01T00:00:00Z"}"#;
+        let cache: RepoCache = serde_json::from_str(json).unwrap();
+        assert!(!cache.truncated);
+    }
+
+    fn cache_synced_at(synced_at: &str) -> RepoCache {
+        RepoCache {
+            host: RemoteHost::GitHub,
+            domain: None,
+            owner: "o".to_string(),
+            repo: "r".to_string(),
+            prs: vec![],
+            synced_at: synced_at.to_string(),
+            truncated: false,
+            issue_titles: HashMap::new(),
+            state: Some("merged".to_string()),
+            limit: Some(DEFAULT_PR_LIMIT),
+            ttl_hours: None,
+        }
+    }
+
+    #[test]
+    fn test_reused_branch_maps_to_live_pr() {
+        let pr = |number, state| MergedPr {
+            branch: "fix/login".to_string(),
+            state: Some(state),
+            ..merged_pr(number)
+        };
+        // gh lists newest first: a closed retry, the merged PR, an old attempt
+        let prs = vec![
+            pr(30, PrState::Closed),
+            pr(20, PrState::Merged),
+            pr(10, PrState::Closed),
+        ];
+        let cache = build_cache(RemoteHost::GitHub, None, "o", "r", prs, DEFAULT_PR_LIMIT);
+        assert_eq!(cache.branch_to_pr()["fix/login"].pr_number, 20);
+
+        let closed = vec![pr(30, PrState::Closed), pr(10, PrState::Closed)];
+        let cache = build_cache(RemoteHost::GitHub, None, "o", "r", closed, DEFAULT_PR_LIMIT);
+        assert_eq!(cache.branch_to_pr()["fix/login"].pr_number, 30);
+    }
+
+    #[test]
+    fn test_cache_age_from_synced_at() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap();
+        let cache = cache_synced_at("2026-03-02T09:30:00+00:00");
+        assert_eq!(cache.age(now), Some(Duration::minutes(150)));
+        assert!(cache.is_fresh(DEFAULT_CACHE_TTL_HOURS, now));
+        assert!(!cache.is_fresh(2, now));
+
+        let garbled = cache_synced_at("yesterday");
+        assert_eq!(garbled.age(now), None);
+        assert!(!garbled.is_fresh(DEFAULT_CACHE_TTL_HOURS, now));
+    }
+
+    #[test]
+    fn test_fresh_cache_skips_sync_unless_forced() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap();
+        let fresh = cache_synced_at("2026-03-02T11:00:00Z");
+        let stale = cache_synced_at("2026-02-28T11:00:00Z");
+        let needs = |cache: Option<&RepoCache>, force: bool| {
+            needs_sync(
+                cache,
+                force,
+                "merged",
+                DEFAULT_PR_LIMIT,
+                DEFAULT_CACHE_TTL_HOURS,
+                now,
+            )
+        };
+
+        assert!(!needs(Some(&fresh), false));
+        assert!(needs(Some(&fresh), true));
+        assert!(needs(Some(&stale), false));
+        assert!(needs(None, false));
+    }
+
+    #[test]
+    fn test_broader_request_bypasses_fresh_cache() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap();
+        let fresh = cache_synced_at("2026-03-02T11:00:00Z");
+        let ttl = DEFAULT_CACHE_TTL_HOURS;
+
+        assert!(needs_sync(
+            Some(&fresh),
+            false,
+            "all",
+            DEFAULT_PR_LIMIT,
+            ttl,
+            now
+        ));
+        assert!(needs_sync(
+            Some(&fresh),
+            false,
+            "merged",
+            FULL_PR_LIMIT,
+            ttl,
+            now
+        ));
+
+        let all = RepoCache {
+            state: Some("all".to_string()),
+            ..fresh.clone()
+        };
+        assert!(!needs_sync(
+            Some(&all),
+            false,
+            "open",
+            DEFAULT_PR_LIMIT,
+            ttl,
+            now
+        ));
+        // A full page of all states may hold far fewer merged PRs
+        let truncated_all = RepoCache {
+            truncated: true,
+            ..all.clone()
+        };
+        assert!(needs_sync(
+            Some(&truncated_all),
+            false,
+            "merged",
+            DEFAULT_PR_LIMIT,
+            ttl,
+            now
+        ));
+
+        let old_format = RepoCache {
+            state: None,
+            limit: None,
+            ..fresh
+        };
+        assert!(needs_sync(
+            Some(&old_format),
+            false,
+            "merged",
+            DEFAULT_PR_LIMIT,
+            ttl,
+            now
+        ));
+    }
+
+    #[test]
+    fn test_parse_commit_date() {
+        let date = parse_commit_date("2026-03-01T12:30:00+02:00\n").unwrap();
+        assert_eq!(date.to_rfc3339(), "2026-03-01T10:30:00+00:00");
+        assert!(matches!(
+            parse_commit_date("fatal: bad revision"),
+            Err(AistError::Parse(_))
+        ));
+    }
 
     #[test]
     fn test_parse_github_remote_ssh() {
         let url = "git@github.com:owner/repo.git";
         assert_eq!(
-            parse_github_remote(url),
-            Some(("owner".to_string(), "repo".to_string()))
+            parse_remote(url).map(|r| (r.host, r.owner, r.repo)),
+            Some((RemoteHost::GitHub, "owner".to_string(), "repo".to_string()))
         );
     }
 
@@ -262,8 +962,8 @@ mod tests {
     fn test_parse_github_remote_https() {
         let url = "https://github.com/owner/repo.git";
         assert_eq!(
-            parse_github_remote(url),
-            Some(("owner".to_string(), "repo".to_string()))
+            parse_remote(url).map(|r| (r.host, r.owner, r.repo)),
+            Some((RemoteHost::GitHub, "owner".to_string(), "repo".to_string()))
         );
     }
 
@@ -271,9 +971,116 @@ mod tests {
     fn test_parse_github_remote_https_no_git() {
         let url = "https://github.com/owner/repo";
         assert_eq!(
-            parse_github_remote(url),
-            Some(("owner".to_string(), "repo".to_string()))
+            parse_remote(url).map(|r| (r.host, r.owner, r.repo)),
+            Some((RemoteHost::GitHub, "owner".to_string(), "repo".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_gitlab_remote_ssh() {
+        let url = "git@gitlab.com:group/proj.git";
+        assert_eq!(
+            parse_remote(url).map(|r| (r.host, r.owner, r.repo)),
+            Some((RemoteHost::GitLab, "group".to_string(), "proj".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_gitlab_remote_https_with_subgroup() {
+        let url = "https://gitlab.com/group/sub/proj.git\n";
+        assert_eq!(
+            parse_remote(url).map(|r| (r.host, r.owner, r.repo)),
+            Some((
+                RemoteHost::GitLab,
+                "group/sub".to_string(),
+                "proj".to_string()
+            ))
+        );
+        assert_eq!(
+            get_cache_path("group/sub", "proj").file_name().unwrap(),
+            "group-sub-proj.json"
+        );
+    }
+
+    #[test]
+    fn test_parse_remote_nested_namespaces() {
+        let cases = [
+            ("git@gitlab.com:group/proj.git", "group"),
+            ("git@gitlab.com:group/sub/proj.git", "group/sub"),
+            ("https://gitlab.com/group/proj", "group"),
+            ("https://gitlab.com/group/sub/proj.git", "group/sub"),
+            (
+                "ssh://git@gitlab.example.com:2222/group/sub/proj.git",
+                "group/sub",
+            ),
+            ("git@github.example.com:org/team/proj.git", "org/team"),
+            ("https://github.example.com/org/team/proj.git", "org/team"),
+        ];
+        for (url, owner) in cases {
+            let Remote {
+                owner: parsed_owner,
+                repo,
+                ..
+            } = parse_remote(url).unwrap();
+            assert_eq!(parsed_owner, owner, "{}", url);
+            assert_eq!(repo, "proj", "{}", url);
+        }
+        assert_eq!(
+            parse_remote("git@github.example.com:org/team/proj.git").map(|r| r.host),
+            Some(RemoteHost::GitHub)
+        );
+        assert_eq!(parse_remote("git@gitlab.com:proj.git"), None);
+    }
+
+    #[test]
+    fn test_remote_keeps_self_hosted_domain() {
+        let public = parse_remote("git@github.com:owner/repo.git").unwrap();
+        assert_eq!(public.domain, None);
+        assert_eq!(
+            repo_arg(public.host, None, &public.owner, &public.repo).unwrap(),
+            "owner/repo"
         );
+
+        let enterprise = parse_remote("https://GitHub.Example.com/owner/repo.git").unwrap();
+        assert_eq!(enterprise.domain.as_deref(), Some("github.example.com"));
+        assert_eq!(
+            repo_arg(
+                enterprise.host,
+                enterprise.domain.as_deref(),
+                &enterprise.owner,
+                &enterprise.repo
+            )
+            .unwrap(),
+            "github.example.com/owner/repo"
+        );
+
+        let gitlab = parse_remote("git@gitlab.example.com:group/sub/proj.git").unwrap();
+        assert_eq!(
+            repo_arg(
+                gitlab.host,
+                gitlab.domain.as_deref(),
+                &gitlab.owner,
+                &gitlab.repo
+            )
+            .unwrap(),
+            "https://gitlab.example.com/group/sub/proj"
+        );
+
+        let nested = parse_remote("git@github.example.com:org/team/proj.git").unwrap();
+        assert!(matches!(
+            repo_arg(
+                nested.host,
+                nested.domain.as_deref(),
+                &nested.owner,
+                &nested.repo
+            ),
+            Err(AistError::UnsupportedRemote(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_remote_unknown_host() {
+        assert_eq!(parse_remote("git@bitbucket.org:team/repo.git"), None);
     }
 
     #[test]
@@ -0,0 +1,108 @@
+use crate::error::AistError;
+use crate::github::{MergedPr, PrState};
+use serde::Deserialize;
+use std::process::Command;
+
+/// Merge requests per `glab mr list` page; GitLab caps pages at 100
+const GLAB_PAGE_SIZE: usize = 100;
+
+/// A merge request as listed by `glab mr list --output json`
+#[derive(Debug, Deserialize)]
+struct GlabMr {
+    iid: u32,
+    title: String,
+    source_branch: String,
+    description: Option<String>,
+    merged_at: Option<String>,
+    state: String,
+}
+
+impl From<GlabMr> for MergedPr {
+    fn from(mr: GlabMr) -> Self {
+        let state = match mr.state.as_str() {
+            "merged" => Some(PrState::Merged),
+            "opened" => Some(PrState::Open),
+            "closed" => Some(PrState::Closed),
+            _ => None,
+        };
+        MergedPr {
+            number: mr.iid,
+            title: mr.title,
+            branch: mr.source_branch,
+            body: mr.description,
+            merged_at: mr.merged_at,
+            state,
+        }
+    }
+}
+
+/// `glab mr list` flags selecting MRs for a `sync --state` value
+fn state_args(state: &str) -> &'static [&'static str] {
+    match state {
+        "open" => &[],
+        "all" => &["--all"],
+        _ => &["--merged"],
+    }
+}
+
+/// Fetch up to `limit` merge requests of a GitLab project (`group/project`,
+/// subgroups included) using glab CLI, in the same shape as GitHub PRs
+pub fn fetch_mrs(project: &str, limit: usize, state: &str) -> Result<Vec<MergedPr>, AistError> {
+    let per_page = limit.min(GLAB_PAGE_SIZE);
+    let mut mrs: Vec<MergedPr> = Vec::new();
+
+    for page in 1.. {
+        let output = Command::new("glab")
+            .args(["mr", "list", "--repo", project])
+            .args(state_args(state))
+            .args([
+                "--output",
+                "json",
+                "--per-page",
+                &per_page.to_string(),
+                "--page",
+                &page.to_string(),
+            ])
+            .output()
+            .map_err(AistError::from_glab_spawn)?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(AistError::from_glab_stderr(&stderr));
+        }
+
+        let batch: Vec<GlabMr> = serde_json::from_slice(&output.stdout)
+            .map_err(|e| AistError::Parse(format!("Failed to parse glab output: {}", e)))?;
+        let last_page = batch.len() < per_page;
+        mrs.extend(batch.into_iter().map(MergedPr::from));
+
+        if last_page || mrs.len() >= limit {
+            break;
+        }
+    }
+
+    mrs.truncate(limit);
+    Ok(mrs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glab_mr_maps_to_pr_shape() {
+        let json = r#"[
+            {"iid":14,"title":"Add export","source_branch":"feat/export","description":"Closes #9","merged_at":"2026-02-01T10:00:00.000Z","state":"merged","web_url":"https://gitlab.com/g/p/-/merge_requests/14"},
+            {"iid":15,"title":"WIP","source_branch":"wip","description":null,"merged_at":null,"state":"opened"}
+        ]"#;
+        let mrs: Vec<GlabMr> = serde_json::from_str(json).unwrap();
+        let prs: Vec<MergedPr> = mrs.into_iter().map(MergedPr::from).collect();
+
+        assert_eq!(prs[0].number, 14);
+        assert_eq!(prs[0].branch, "feat/export");
+        assert_eq!(prs[0].body.as_deref(), Some("Closes #9"));
+        assert_eq!(prs[0].state, Some(PrState::Merged));
+        assert_eq!(prs[1].state, Some(PrState::Open));
+        assert!(prs[1].merged_at.is_none());
+    }
+}
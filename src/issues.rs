@@ -1,9 +1,10 @@
-use crate::flamegraph::{extract_spans, ActivityType};
-use crate::github::{load_current_repo_cache, PrMapping, RepoCache};
+use crate::flamegraph::{extract_spans, ActivityType, DEFAULT_GAP_SECONDS};
+use crate::github::{load_current_repo_cache, print_cache_notes, PrState, RepoCache};
+use crate::metrics::{round_percentages, truncate_chars};
 use crate::parser::Session;
 use chrono::{DateTime, Local, Utc};
 use colored::Colorize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Time metrics for a single GitHub issue
 #[derive(Debug, Clone)]
@@ -18,46 +19,36 @@ pub struct IssueMetrics {
 
 /// Calculate time spent per issue by matching sessions to PR branches
 pub fn calculate_issue_metrics(sessions: &[Session], cache: &RepoCache) -> Vec<IssueMetrics> {
-    // Build branch -> PR mapping (a branch can only have one PR)
-    let branch_to_pr: HashMap<&str, &PrMapping> = cache
-        .prs
-        .iter()
-        .map(|pr| (pr.branch.as_str(), pr))
-        .collect();
+    let branch_to_pr = cache.branch_to_pr();
 
     // Build issue -> (title, branch, minutes, session_count)
     let mut issue_metrics: HashMap<u32, (String, String, f64, usize)> = HashMap::new();
 
     for session in sessions {
-        let branch = match &session.git_branch {
-            Some(b) => b.as_str(),
-            None => continue,
-        };
-
-        // Find the PR for this branch
-        let pr = match branch_to_pr.get(branch) {
-            Some(pr) => pr,
-            None => continue,
-        };
-
-        // Skip PRs with no linked issues
-        if pr.closed_issues.is_empty() {
-            continue;
-        }
-
         // Calculate session duration
         let duration_minutes = match (session.start_time, session.end_time) {
             (Some(start), Some(end)) => (end - start).num_minutes() as f64,
             _ => 0.0,
         };
 
-        // Add time to each linked issue
-        for &issue_num in &pr.closed_issues {
-            let entry = issue_metrics
-                .entry(issue_num)
-                .or_insert_with(|| (pr.title.clone(), pr.branch.clone(), 0.0, 0));
-            entry.2 += duration_minutes;
-            entry.3 += 1;
+        // Add time to each issue linked from any of the session's PRs, once
+        // per issue even if two of its branches close the same one
+        let mut counted: HashSet<u32> = HashSet::new();
+        for branch in session.branches() {
+            let Some(pr) = branch_to_pr.get(branch) else {
+                continue;
+            };
+            for &issue_num in &pr.closed_issues {
+                if !counted.insert(issue_num) {
+                    continue;
+                }
+                let entry = issue_metrics.entry(issue_num).or_insert_with(|| {
+                    let title = cache.issue_title(issue_num, pr).to_string();
+                    (title, pr.branch.clone(), 0.0, 0)
+                });
+                entry.2 += duration_minutes;
+                entry.3 += 1;
+            }
         }
     }
 
@@ -79,6 +70,7 @@ pub fn calculate_issue_metrics(sessions: &[Session], cache: &RepoCache) -> Vec<I
         b.total_minutes
             .partial_cmp(&a.total_minutes)
             .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.issue_number.cmp(&b.issue_number))
     });
 
     metrics
@@ -108,6 +100,7 @@ pub fn list_issues(sessions: &[Session]) {
             return;
         }
     };
+    print_cache_notes(&cache);
 
     let metrics = calculate_issue_metrics(sessions, &cache);
 
@@ -146,11 +139,7 @@ pub fn list_issues(sessions: &[Session]) {
 
     // List issues
     for m in &metrics {
-        let title_display = if m.title.len() > 38 {
-            format!("{}...", &m.title[..35])
-        } else {
-            m.title.clone()
-        };
+        let title_display = truncate_chars(&m.title, 38);
 
         println!(
             "#{:<7} {:<40} {:>10} {:>10}",
@@ -191,6 +180,7 @@ pub fn show_issue_detail(issue_number: u32, sessions: &[Session]) {
             return;
         }
     };
+    print_cache_notes(&cache);
 
     // Find the PR that closes this issue
     let pr = cache
@@ -214,7 +204,7 @@ pub fn show_issue_detail(issue_number: u32, sessions: &[Session]) {
     // Find sessions matching this PR's branch
     let mut issue_sessions: Vec<IssueSession> = sessions
         .iter()
-        .filter(|s| s.git_branch.as_deref() == Some(&pr.branch))
+        .filter(|s| s.on_branch(&pr.branch))
         .map(|s| {
             let duration = match (s.start_time, s.end_time) {
                 (Some(start), Some(end)) => (end - start).num_minutes() as f64,
@@ -228,17 +218,17 @@ pub fn show_issue_detail(issue_number: u32, sessions: &[Session]) {
         .collect();
 
     // Sort by start time
-    issue_sessions.sort_by(|a, b| a.session.start_time.cmp(&b.session.start_time));
+    issue_sessions.sort_by_key(|s| s.session.start_time);
 
     // Calculate totals
     let total_time: f64 = issue_sessions.iter().map(|s| s.duration_minutes).sum();
     let session_count = issue_sessions.len();
 
     // Determine status
-    let status = if pr.merged_at.is_some() {
-        "Merged".green()
-    } else {
-        "Open".yellow()
+    let status = match pr.status() {
+        PrState::Merged => "Merged".green(),
+        PrState::Open => "Open".yellow(),
+        PrState::Closed => "Closed".red(),
     };
 
     // Print header
@@ -247,7 +237,12 @@ pub fn show_issue_detail(issue_number: u32, sessions: &[Session]) {
     println!();
 
     // Issue metadata
-    println!("{}: {}", "Title".dimmed(), pr.title);
+    println!(
+        "{}: {}",
+        "Title".dimmed(),
+        cache.issue_title(issue_number, pr)
+    );
+    println!("{}: #{} {}", "PR".dimmed(), pr.pr_number, pr.title);
     println!("{}: {}", "Status".dimmed(), status);
     println!("{}: {}", "Branch".dimmed(), pr.branch);
     println!(
@@ -316,7 +311,7 @@ fn print_activity_breakdown(issue_sessions: &[IssueSession]) {
     let mut total_span_time = 0.0;
 
     for issue_session in issue_sessions {
-        let spans = extract_spans(issue_session.session);
+        let spans = extract_spans(issue_session.session, DEFAULT_GAP_SECONDS);
         for span in spans {
             let duration_mins = (span.end - span.start).num_seconds() as f64 / 60.0;
             *time_by_activity.entry(span.activity).or_insert(0.0) += duration_mins;
@@ -331,11 +326,17 @@ fn print_activity_breakdown(issue_sessions: &[IssueSession]) {
 
     // Sort by time descending
     let mut activities: Vec<_> = time_by_activity.into_iter().collect();
-    activities.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    activities.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+
+    let minutes: Vec<f64> = activities.iter().map(|(_, m)| *m).collect();
+    let percentages = round_percentages(&minutes);
 
     // Print each activity with a simple bar
-    for (activity, minutes) in &activities {
-        let percentage = (*minutes / total_span_time * 100.0) as usize;
+    for ((activity, minutes), percentage) in activities.iter().zip(percentages) {
         let bar_width = (percentage / 2).clamp(1, 30); // scale to ~30 chars max
         let bar: String = "█".repeat(bar_width);
 
@@ -384,19 +385,64 @@ mod tests {
             git_branch: branch.map(|s| s.to_string()),
             start_time: Some(start),
             end_time: Some(end),
-            messages: vec![],
+            ..Default::default()
         }
     }
 
     fn make_cache(prs: Vec<PrMapping>) -> RepoCache {
         RepoCache {
+            host: Default::default(),
+            domain: None,
             owner: "test".to_string(),
             repo: "repo".to_string(),
             prs,
             synced_at: "2026-01-01T00:00:00Z".to_string(),
+            truncated: false,
+            issue_titles: HashMap::new(),
+            state: None,
+            limit: None,
+            ttl_hours: None,
         }
     }
 
+    #[test]
+    fn test_issue_title_falls_back_to_pr_title() {
+        let sessions = vec![
+            make_session("s1", Some("feature/issue-1"), 30),
+            make_session("s2", Some("fix/issue-2"), 20),
+        ];
+        let mut cache = make_cache(vec![
+            PrMapping {
+                pr_number: 10,
+                title: "Feature PR".to_string(),
+                branch: "feature/issue-1".to_string(),
+                closed_issues: vec![1],
+                merged_at: None,
+                state: None,
+            },
+            PrMapping {
+                pr_number: 11,
+                title: "Fix PR".to_string(),
+                branch: "fix/issue-2".to_string(),
+                closed_issues: vec![2],
+                merged_at: None,
+                state: None,
+            },
+        ]);
+        cache.issue_titles.insert(1, "Add dark mode".to_string());
+
+        let metrics = calculate_issue_metrics(&sessions, &cache);
+        let title = |n: u32| {
+            metrics
+                .iter()
+                .find(|m| m.issue_number == n)
+                .map(|m| m.title.as_str())
+        };
+
+        assert_eq!(title(1), Some("Add dark mode"));
+        assert_eq!(title(2), Some("Fix PR"));
+    }
+
     #[test]
     fn test_calculate_issue_metrics_basic() {
         let sessions = vec![
@@ -412,6 +458,7 @@ mod tests {
                 branch: "feature/issue-1".to_string(),
                 closed_issues: vec![1],
                 merged_at: None,
+                state: None,
             },
             PrMapping {
                 pr_number: 11,
@@ -419,6 +466,7 @@ mod tests {
                 branch: "fix/issue-2".to_string(),
                 closed_issues: vec![2],
                 merged_at: None,
+                state: None,
             },
         ]);
 
@@ -445,6 +493,7 @@ mod tests {
             branch: "feature/x".to_string(),
             closed_issues: vec![1],
             merged_at: None,
+            state: None,
         }]);
 
         let metrics = calculate_issue_metrics(&sessions, &cache);
@@ -461,6 +510,7 @@ mod tests {
             branch: "feature/x".to_string(),
             closed_issues: vec![1],
             merged_at: None,
+            state: None,
         }]);
 
         let metrics = calculate_issue_metrics(&sessions, &cache);
@@ -477,6 +527,7 @@ mod tests {
             branch: "feature/x".to_string(),
             closed_issues: vec![], // No linked issues
             merged_at: None,
+            state: None,
         }]);
 
         let metrics = calculate_issue_metrics(&sessions, &cache);
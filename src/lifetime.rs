@@ -0,0 +1,208 @@
+use crate::cost;
+use crate::metrics::{self, format_duration};
+use crate::parser::Session;
+use chrono::{DateTime, Local, Timelike, Utc};
+use colored::Colorize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// All-time totals and favorites across every session
+#[derive(Debug)]
+pub struct LifetimeStats {
+    pub session_count: usize,
+    pub total_hours: f64,
+    pub total_cost: f64,
+    pub first_session: Option<DateTime<Utc>>,
+    /// (tool, calls)
+    pub most_used_tool: Option<(String, usize)>,
+    /// (project, hours)
+    pub top_project: Option<(String, f64)>,
+    /// (session ID, minutes)
+    pub longest_session: Option<(String, f64)>,
+    /// (local hour of day, sessions started in it)
+    pub favorite_hour: Option<(u32, usize)>,
+}
+
+/// Compute lifetime stats. Ties go to the alphabetically first name (or the
+/// earliest hour) so the result doesn't depend on load order.
+pub fn compute_lifetime_stats(sessions: &[Session]) -> LifetimeStats {
+    let aggregated = metrics::aggregate_metrics(sessions);
+
+    let most_used_tool = aggregated
+        .tool_counts
+        .iter()
+        .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(tool, count)| (tool.clone(), *count));
+
+    let top_project = aggregated
+        .by_project
+        .iter()
+        .max_by(|a, b| {
+            a.1.total_duration_minutes
+                .partial_cmp(&b.1.total_duration_minutes)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| b.0.cmp(a.0))
+        })
+        .map(|(name, p)| (name.clone(), p.total_duration_minutes / 60.0));
+
+    let longest_session = sessions
+        .iter()
+        .filter_map(|s| {
+            let minutes = (s.end_time? - s.start_time?).num_seconds() as f64 / 60.0;
+            Some((s.session_id.as_str(), minutes))
+        })
+        .max_by(|a, b| {
+            a.1.partial_cmp(&b.1)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| b.0.cmp(a.0))
+        })
+        .map(|(id, minutes)| (id.to_string(), minutes));
+
+    let mut by_hour: HashMap<u32, usize> = HashMap::new();
+    for start in sessions.iter().filter_map(|s| s.start_time) {
+        *by_hour
+            .entry(start.with_timezone(&Local).hour())
+            .or_insert(0) += 1;
+    }
+    let favorite_hour = by_hour
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)));
+
+    LifetimeStats {
+        session_count: sessions.len(),
+        total_hours: aggregated.total_duration_minutes / 60.0,
+        total_cost: sessions.iter().map(cost::calculate_cost).sum(),
+        first_session: sessions.iter().filter_map(|s| s.start_time).min(),
+        most_used_tool,
+        top_project,
+        longest_session,
+        favorite_hour,
+    }
+}
+
+/// Render lifetime stats as a short dashboard
+pub fn render_lifetime(stats: &LifetimeStats) -> String {
+    let mut out = String::new();
+
+    out.push_str(&format!("{}\n", "LIFETIME STATS".bold()));
+    out.push_str(&format!("{}\n", "━".repeat(50)));
+
+    if let Some(first) = stats.first_session {
+        out.push_str(&format!(
+            "{}\n\n",
+            format!("Since {}", first.with_timezone(&Local).format("%B %d, %Y")).dimmed()
+        ));
+    }
+
+    let row = |label: &str, value: String| format!("  {:<18} {}\n", label, value);
+
+    out.push_str(&row(
+        "Sessions",
+        stats.session_count.to_string().bold().to_string(),
+    ));
+    out.push_str(&row(
+        "Time with AI",
+        format!("{:.1}h", stats.total_hours).bold().to_string(),
+    ));
+    out.push_str(&row(
+        "Total cost",
+        cost::format_cost(stats.total_cost).bold().to_string(),
+    ));
+
+    if let Some((tool, calls)) = &stats.most_used_tool {
+        out.push_str(&row(
+            "Most-used tool",
+            format!("{} ({} calls)", tool.cyan(), calls),
+        ));
+    }
+    if let Some((project, hours)) = &stats.top_project {
+        out.push_str(&row(
+            "Top project",
+            format!("{} ({:.1}h)", project.cyan(), hours),
+        ));
+    }
+    if let Some((id, minutes)) = &stats.longest_session {
+        out.push_str(&row(
+            "Longest session",
+            format!(
+                "{} ({})",
+                format_duration(*minutes),
+                metrics::short_id(id, 8)
+            ),
+        ));
+    }
+    if let Some((hour, count)) = stats.favorite_hour {
+        out.push_str(&row(
+            "Favorite hour",
+            format!("{:02}:00 ({} sessions started)", hour, count),
+        ));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Message, MessageType, ToolCall};
+    use chrono::TimeZone;
+    use std::path::PathBuf;
+
+    fn session_with_tools(id: &str, project: &str, minutes: i64, tools: &[&str]) -> Session {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0).unwrap();
+        let tool_calls = tools
+            .iter()
+            .enumerate()
+            .map(|(i, name)| ToolCall {
+                id: format!("{}-{}", id, i),
+                name: name.to_string(),
+                input: serde_json::Value::Null,
+                parallel_group: i,
+            })
+            .collect();
+        Session {
+            session_id: id.to_string(),
+            project: project.to_string(),
+            jsonl_path: PathBuf::from("/test/session.jsonl"),
+            start_time: Some(start),
+            end_time: Some(start + chrono::Duration::minutes(minutes)),
+            messages: vec![Message {
+                msg_type: MessageType::Assistant,
+                timestamp: Some(start),
+                text_content: None,
+                tool_calls,
+                tool_results: vec![],
+                model: None,
+                usage: None,
+                is_hook: false,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_lifetime_totals_and_favorites() {
+        let sessions = vec![
+            session_with_tools("s1", "/w/app", 60, &["Read", "Edit", "Read"]),
+            session_with_tools("s2", "/w/api", 90, &["Bash", "Read"]),
+            session_with_tools("s3", "/w/app", 45, &["Bash"]),
+        ];
+
+        let stats = compute_lifetime_stats(&sessions);
+
+        assert_eq!(stats.session_count, 3);
+        assert!((stats.total_hours - 3.25).abs() < 1e-9);
+        assert_eq!(stats.most_used_tool, Some(("Read".to_string(), 3)));
+        assert_eq!(stats.top_project, Some(("app".to_string(), 1.75)));
+        assert_eq!(stats.longest_session, Some(("s2".to_string(), 90.0)));
+        assert_eq!(stats.favorite_hour.map(|(_, count)| count), Some(3));
+    }
+
+    #[test]
+    fn test_lifetime_empty_history() {
+        let stats = compute_lifetime_stats(&[]);
+        assert_eq!(stats.session_count, 0);
+        assert!(stats.most_used_tool.is_none());
+        assert!(render_lifetime(&stats).contains("Sessions"));
+    }
+}
@@ -1,27 +1,137 @@
 mod bottlenecks;
+mod calendar;
+mod clipboard;
+mod cost;
+mod error;
+mod exclusions;
 mod export;
 mod flamegraph;
 mod github;
+mod gitlab;
 mod issues;
+mod lifetime;
 mod metrics;
 mod parser;
+mod png;
 mod prs;
+mod redact;
 mod report;
+mod state;
+mod team;
 mod timeline;
 
-use clap::{Parser, Subcommand};
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand};
 use colored::Colorize;
-use std::path::PathBuf;
+use parser::Session;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
 #[derive(Parser)]
 #[command(name = "aist")]
 #[command(about = "AI Session Tracker - Find bottlenecks in AI-assisted development")]
 #[command(version)]
 struct Cli {
+    /// Read transcripts from files matching this glob instead of ~/.claude/projects
+    #[arg(long, global = true, value_name = "GLOB")]
+    transcripts: Option<String>,
+
+    /// Replace project names with stable pseudonyms (project-a, project-b, ...),
+    /// including the project dir in tool-input file paths
+    #[arg(long, global = true)]
+    anonymize_projects: bool,
+
+    /// Warn about transcripts where more than 5% of lines are malformed
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Show API keys and tokens found in transcripts instead of masking them
+    #[arg(long, global = true)]
+    no_mask: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Tool-name handling shared by the commands that count or classify tools
+#[derive(Args)]
+struct ToolFilterArgs {
+    /// Ignore calls to this tool (repeatable)
+    #[arg(long, value_name = "NAME")]
+    exclude_tool: Vec<String>,
+
+    /// Merge tool name variants (e.g. BashOutput and mcp__x__bash into Bash)
+    #[arg(long)]
+    merge_tool_variants: bool,
+}
+
+/// Thresholds for counting a session as a focus session
+#[derive(Args)]
+struct FocusArgs {
+    /// Minimum minutes for a focus session
+    #[arg(long, default_value = "30")]
+    focus_min_minutes: f64,
+
+    /// Minimum efficiency percent (time not lost to bottlenecks) for a focus session
+    #[arg(long, default_value = "80")]
+    focus_min_efficiency: f64,
+}
+
+impl FocusArgs {
+    fn criteria(&self) -> bottlenecks::FocusCriteria {
+        bottlenecks::FocusCriteria {
+            min_minutes: self.focus_min_minutes,
+            min_efficiency: self.focus_min_efficiency,
+        }
+    }
+}
+
+/// Thresholds at which a pattern counts as a bottleneck; unset ones keep
+/// `BottleneckConfig::default()`
+#[derive(Args)]
+struct ThresholdArgs {
+    /// Consecutive failures of one tool that make an error loop
+    #[arg(long)]
+    error_threshold: Option<usize>,
+
+    /// Read/Grep/Glob calls without an edit that make an exploration spiral
+    #[arg(long)]
+    exploration_calls: Option<usize>,
+
+    /// Minutes those calls must span to count as an exploration spiral
+    #[arg(long)]
+    exploration_minutes: Option<f64>,
+
+    /// Edits to one file that count as edit thrashing
+    #[arg(long)]
+    edit_threshold: Option<usize>,
+
+    /// Shortest pause, in minutes, that can count as a long gap
+    #[arg(long)]
+    gap_minutes: Option<f64>,
+}
+
+impl ThresholdArgs {
+    fn config(&self) -> bottlenecks::BottleneckConfig {
+        let defaults = bottlenecks::BottleneckConfig::default();
+        bottlenecks::BottleneckConfig {
+            error_loop_min_failures: self
+                .error_threshold
+                .unwrap_or(defaults.error_loop_min_failures),
+            exploration_min_calls: self
+                .exploration_calls
+                .unwrap_or(defaults.exploration_min_calls),
+            exploration_min_minutes: self
+                .exploration_minutes
+                .unwrap_or(defaults.exploration_min_minutes),
+            edit_thrash_min_edits: self
+                .edit_threshold
+                .unwrap_or(defaults.edit_thrash_min_edits),
+            gap_floor_minutes: self.gap_minutes.unwrap_or(defaults.gap_floor_minutes),
+        }
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Analyze sessions and show metrics
@@ -30,9 +140,43 @@ enum Commands {
         #[arg(short, long)]
         project: Option<PathBuf>,
 
-        /// Show detailed output
+        /// Show detailed output (all tools and projects unless --limit is set)
         #[arg(short, long)]
         verbose: bool,
+
+        /// Number of tools and projects to show (default: 10)
+        #[arg(short, long)]
+        limit: Option<usize>,
+
+        /// Flag Write/Edit calls with more than this many lines as bulk overwrites
+        #[arg(long, default_value = "300")]
+        bulk_lines: usize,
+
+        /// Flag Write/Edit calls with more than this many bytes as bulk overwrites
+        #[arg(long, default_value = "20000")]
+        bulk_bytes: usize,
+
+        #[command(flatten)]
+        tools: ToolFilterArgs,
+
+        /// Skip sessions with no user prompt (started by hooks or scripts)
+        #[arg(long)]
+        exclude_scripted: bool,
+
+        #[command(flatten)]
+        focus: FocusArgs,
+
+        /// Only include sessions started after this git ref's commit date
+        #[arg(long, value_name = "REF")]
+        since_commit: Option<String>,
+
+        /// Also count subagent transcripts, attributed to their parent's project
+        #[arg(long)]
+        include_subagents: bool,
+
+        /// Also print an hour-of-day by day-of-week grid of session time
+        #[arg(long)]
+        heatmap: bool,
     },
 
     /// Detect and display bottlenecks
@@ -44,17 +188,131 @@ enum Commands {
         /// Number of bottlenecks to show
         #[arg(short, long, default_value = "10")]
         limit: usize,
+
+        /// Output format: text, json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Show the prompt that preceded each bottleneck
+        #[arg(long)]
+        show_prompts: bool,
+
+        #[command(flatten)]
+        tools: ToolFilterArgs,
+
+        #[command(flatten)]
+        thresholds: ThresholdArgs,
+
+        /// Only include sessions started after this git ref's commit date
+        #[arg(long, value_name = "REF")]
+        since_commit: Option<String>,
+
+        /// Also count subagent transcripts, attributed to their parent's project
+        #[arg(long)]
+        include_subagents: bool,
     },
 
     /// Generate a summary report
     Report {
-        /// Report period: day, week, month, all
+        /// Report period: day, week, month, quarter, year, all, or a span like 6h, 3d, 2w
         #[arg(short, long, default_value = "week")]
         period: String,
 
-        /// Output format: text, json
+        /// Output format: text, json, markdown (md), html
         #[arg(short, long, default_value = "text")]
         format: String,
+
+        /// Output path for --format html (default: report-{period}.html)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Copy the report to the system clipboard instead of printing it
+        #[arg(long)]
+        clipboard: bool,
+
+        /// Compare with the previous period (sessions, time, efficiency, tool mix)
+        #[arg(long)]
+        compare: bool,
+
+        /// Benchmark efficiency and cost against an anonymized team bundle (JSON)
+        #[arg(long, value_name = "FILE")]
+        team_bundle: Option<PathBuf>,
+
+        /// Also count subagent transcripts, attributed to their parent's project
+        #[arg(long)]
+        include_subagents: bool,
+
+        /// Only sessions started on or after this date (YYYY-MM-DD); overrides --period
+        #[arg(long, value_name = "DATE", value_parser = metrics::parse_date)]
+        since: Option<NaiveDate>,
+
+        /// Only sessions started on or before this date (YYYY-MM-DD); overrides --period
+        #[arg(long, value_name = "DATE", value_parser = metrics::parse_date)]
+        until: Option<NaiveDate>,
+
+        /// Filter by project path
+        #[arg(long)]
+        project: Option<PathBuf>,
+
+        #[command(flatten)]
+        tools: ToolFilterArgs,
+    },
+
+    /// Show token usage and cost by billing category
+    Cost {
+        /// Filter by project path
+        #[arg(short, long)]
+        project: Option<PathBuf>,
+
+        /// Period: day, week, month, quarter, year, all, or a span like 6h, 3d, 2w
+        #[arg(long, default_value = "all")]
+        period: String,
+
+        /// Also print tokens and cost for each session
+        #[arg(long)]
+        detailed: bool,
+
+        /// Also print a histogram of tokens per session
+        #[arg(long)]
+        histogram: bool,
+
+        /// Also chart cost per day, week or month of session start
+        #[arg(long, value_name = "INTERVAL", value_parser = cost::parse_interval)]
+        by: Option<cost::CostInterval>,
+
+        /// Warn and exit with status 1 if total cost exceeds this many USD
+        #[arg(long, value_name = "USD")]
+        budget: Option<f64>,
+
+        /// Cap a project's spend, e.g. my-app=50 (repeatable)
+        #[arg(long = "budget-per-project", value_name = "PROJECT=USD", value_parser = cost::parse_budget)]
+        budgets: Vec<(String, f64)>,
+    },
+
+    /// Show a month grid of daily session counts and cost
+    Calendar {
+        /// Month to show: month (current) or YYYY-MM
+        #[arg(long, default_value = "month")]
+        period: String,
+
+        /// Filter by project path
+        #[arg(short, long)]
+        project: Option<PathBuf>,
+    },
+
+    /// All-time stats: totals, favorite tool, project and hour
+    Lifetime,
+
+    /// Mark part of a session as meeting/away time so it isn't counted
+    Exclude {
+        /// Session ID (or a unique prefix)
+        session: String,
+
+        /// Start of the window, local time HH:MM
+        start: String,
+
+        /// End of the window, local time HH:MM
+        end: String,
     },
 
     /// Show timeline for a specific session
@@ -66,6 +324,21 @@ enum Commands {
         /// Project path to filter sessions
         #[arg(short, long)]
         project: Option<PathBuf>,
+
+        #[command(flatten)]
+        tools: ToolFilterArgs,
+
+        /// Show full dates (YYYY-MM-DD HH:MM:SS) instead of time of day
+        #[arg(long, conflicts_with = "elapsed")]
+        full_timestamps: bool,
+
+        /// Show time since session start (+mm:ss) instead of time of day
+        #[arg(long)]
+        elapsed: bool,
+
+        /// Print only the summary statistics as JSON, without events
+        #[arg(long)]
+        summary_json: bool,
     },
 
     /// List all sessions
@@ -77,13 +350,21 @@ enum Commands {
         /// Filter by project path
         #[arg(short, long)]
         project: Option<PathBuf>,
+
+        /// Output format: text, json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Comma-separated JSON fields to keep (id, project, branch, start, end, duration, messages, cost)
+        #[arg(long, value_delimiter = ',')]
+        fields: Vec<String>,
     },
 
     /// Generate a flamegraph-style SVG visualization
     Flame {
-        /// Output file path
-        #[arg(short, long, default_value = "session-flamegraph.svg")]
-        output: PathBuf,
+        /// Output file path (default: session-flamegraph.svg, or stdout for data-uri)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
 
         /// Filter by project path
         #[arg(short, long)]
@@ -92,9 +373,32 @@ enum Commands {
         /// Group by: session (default), project, pr, or issue
         #[arg(short, long, default_value = "session")]
         group_by: String,
+
+        /// Output format: svg (default), data-uri for inline embedding, or png (needs the png feature)
+        #[arg(short, long, default_value = "svg")]
+        format: String,
+
+        /// Also copy the generated SVG to the system clipboard
+        #[arg(long)]
+        clipboard: bool,
+
+        /// Layout: bars (default) or lanes (one row per tool type, latest session only)
+        #[arg(long, default_value = "bars")]
+        layout: String,
+
+        /// Place every session on one shared wall-clock axis with hour gridlines
+        #[arg(long)]
+        absolute_time: bool,
+
+        /// Color theme: light (default) or dark
+        #[arg(long, default_value = "light", value_parser = flamegraph::parse_theme)]
+        theme: flamegraph::Theme,
+
+        #[command(flatten)]
+        tools: ToolFilterArgs,
     },
 
-    /// Sync GitHub PRs and cache PR→Issue→Branch mappings
+    /// Sync GitHub PRs (or GitLab merge requests) and cache PR→Issue→Branch mappings
     Sync {
         /// GitHub repository owner (auto-detected from git remote if not specified)
         #[arg(long)]
@@ -103,6 +407,27 @@ enum Commands {
         /// GitHub repository name (auto-detected from git remote if not specified)
         #[arg(long)]
         repo: Option<String>,
+
+        /// Treat --owner/--repo as a GitLab group/project and fetch merge
+        /// requests with glab
+        #[arg(long)]
+        gitlab: bool,
+
+        /// Fetch all matching PRs instead of the latest 100
+        #[arg(long)]
+        full: bool,
+
+        /// Which PRs to fetch: merged (default), open, or all
+        #[arg(long, default_value = "merged", value_parser = github::PR_STATES)]
+        state: String,
+
+        /// Re-sync even if the cache is younger than the TTL
+        #[arg(long)]
+        force: bool,
+
+        /// Hours a synced cache counts as fresh
+        #[arg(long, default_value_t = github::DEFAULT_CACHE_TTL_HOURS)]
+        ttl: i64,
     },
 
     /// List GitHub issues with time metrics
@@ -127,6 +452,21 @@ enum Commands {
         /// Filter by project path
         #[arg(short, long)]
         project: Option<PathBuf>,
+
+        /// Combine every synced repo cache into one listing
+        #[arg(long)]
+        all_repos: bool,
+    },
+
+    /// List sessions whose time isn't attributed to any GitHub PR
+    Orphans {
+        /// Filter by project path
+        #[arg(short, long)]
+        project: Option<PathBuf>,
+
+        /// Number of sessions to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
     },
 
     /// Show detailed metrics for a specific GitHub PR
@@ -149,44 +489,212 @@ enum Commands {
         #[arg(long)]
         repo: Option<String>,
 
-        /// Report period: day, week, month, all
+        /// Report period: day, week, month, quarter, year, all, or a span like 6h, 3d, 2w
         #[arg(short, long, default_value = "all")]
         period: String,
 
-        /// Output HTML path (default: report-{owner}-{repo}.html)
+        /// Output HTML path (default: ai-session-report.html)
         #[arg(short, long)]
         output: Option<PathBuf>,
+
+        /// Color theme: light (default) or dark
+        #[arg(long, default_value = "light", value_parser = flamegraph::parse_theme)]
+        theme: flamegraph::Theme,
+
+        #[command(flatten)]
+        tools: ToolFilterArgs,
+    },
+
+    /// Remove all state aist has created (Claude transcripts are untouched)
+    Clean {
+        /// Actually delete instead of listing what would be removed
+        #[arg(long)]
+        yes: bool,
     },
 }
 
-fn main() {
+fn main() -> ExitCode {
     let cli = Cli::parse();
 
+    if let Some(pattern) = &cli.transcripts {
+        if let Err(e) = parser::set_transcripts_glob(pattern) {
+            println!("{}: {}", "Error".red(), e);
+            return ExitCode::FAILURE;
+        }
+    }
+    parser::set_anonymize_projects(cli.anonymize_projects);
+    parser::set_mask_secrets(!cli.no_mask);
+    parser::set_strict(cli.strict);
+    match cost::load_pricing() {
+        Ok(pricing) => cost::set_pricing(pricing),
+        Err(e) => print_aist_error(&e),
+    }
+
     match cli.command {
-        Commands::Analyze { project, verbose } => {
-            analyze_command(project, verbose);
+        Commands::Analyze {
+            project,
+            verbose,
+            limit,
+            bulk_lines,
+            bulk_bytes,
+            tools,
+            exclude_scripted,
+            focus,
+            since_commit,
+            include_subagents,
+            heatmap,
+        } => {
+            analyze_command(
+                project,
+                verbose,
+                limit,
+                bulk_lines,
+                bulk_bytes,
+                &tools,
+                exclude_scripted,
+                &focus,
+                since_commit.as_deref(),
+                include_subagents,
+                heatmap,
+            );
         }
-        Commands::Bottlenecks { project, limit } => {
-            bottlenecks_command(project, limit);
+        Commands::Bottlenecks {
+            project,
+            limit,
+            format,
+            show_prompts,
+            tools,
+            thresholds,
+            since_commit,
+            include_subagents,
+        } => {
+            bottlenecks_command(
+                project,
+                limit,
+                &format,
+                show_prompts,
+                &tools,
+                &thresholds.config(),
+                since_commit.as_deref(),
+                include_subagents,
+            );
         }
-        Commands::Report { period, format } => {
-            report_command(&period, &format);
+        Commands::Report {
+            period,
+            format,
+            output,
+            clipboard,
+            compare,
+            team_bundle,
+            include_subagents,
+            since,
+            until,
+            project,
+            tools,
+        } => {
+            report_command(
+                project.as_deref(),
+                &tools,
+                &period,
+                (since, until),
+                &format,
+                output,
+                clipboard,
+                compare,
+                team_bundle.as_deref(),
+                include_subagents,
+            );
+        }
+        Commands::Cost {
+            project,
+            period,
+            detailed,
+            histogram,
+            by,
+            budget,
+            budgets,
+        } => {
+            return cost_command(project, &period, detailed, histogram, by, budget, &budgets);
         }
-        Commands::Timeline { session, project } => {
-            timeline_command(&session, project);
+        Commands::Calendar { period, project } => {
+            calendar_command(&period, project);
         }
-        Commands::List { limit, project } => {
-            list_command(limit, project);
+        Commands::Lifetime => {
+            lifetime_command();
+        }
+        Commands::Exclude {
+            session,
+            start,
+            end,
+        } => {
+            exclude_command(&session, &start, &end);
+        }
+        Commands::Timeline {
+            session,
+            project,
+            tools,
+            full_timestamps,
+            elapsed,
+            summary_json,
+        } => {
+            let ts_format = if full_timestamps {
+                timeline::TimestampFormat::Full
+            } else if elapsed {
+                timeline::TimestampFormat::Elapsed
+            } else {
+                timeline::TimestampFormat::Clock
+            };
+            timeline_command(&session, project, &tools, ts_format, summary_json);
+        }
+        Commands::List {
+            limit,
+            project,
+            format,
+            fields,
+        } => {
+            list_command(limit, project, &format, &fields);
         }
         Commands::Flame {
             output,
             project,
             group_by,
+            format,
+            clipboard,
+            layout,
+            absolute_time,
+            theme,
+            tools,
         } => {
-            flame_command(output, project, &group_by);
+            flame_command(
+                output,
+                project,
+                &tools,
+                &group_by,
+                &format,
+                clipboard,
+                &layout,
+                absolute_time,
+                theme,
+            );
         }
-        Commands::Sync { owner, repo } => {
-            sync_command(owner.as_deref(), repo.as_deref());
+        Commands::Sync {
+            owner,
+            repo,
+            gitlab,
+            full,
+            state,
+            force,
+            ttl,
+        } => {
+            sync_command(
+                owner.as_deref(),
+                repo.as_deref(),
+                gitlab,
+                full,
+                &state,
+                force,
+                ttl,
+            );
         }
         Commands::Issues { project } => {
             issues_command(project);
@@ -194,8 +702,11 @@ fn main() {
         Commands::Issue { number, project } => {
             issue_detail_command(number, project);
         }
-        Commands::Prs { project } => {
-            prs_command(project);
+        Commands::Prs { project, all_repos } => {
+            prs_command(project, all_repos);
+        }
+        Commands::Orphans { project, limit } => {
+            orphans_command(project, limit);
         }
         Commands::Pr { number, project } => {
             pr_detail_command(number, project);
@@ -205,179 +716,1085 @@ fn main() {
             repo,
             period,
             output,
+            theme,
+            tools,
         } => {
-            export_command(owner.as_deref(), repo.as_deref(), &period, output);
+            export_command(
+                owner.as_deref(),
+                repo.as_deref(),
+                &period,
+                output,
+                theme,
+                &tools,
+            );
+        }
+        Commands::Clean { yes } => {
+            clean_command(yes);
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Load sessions with excluded tools dropped by their raw names, then tool
+/// variants merged when asked
+fn load_tool_filtered_sessions(
+    project: Option<&Path>,
+    tools: &ToolFilterArgs,
+    include_subagents: bool,
+) -> Vec<Session> {
+    let mut sessions = parser::load_sessions(project, include_subagents);
+    parser::exclude_tools(&mut sessions, &tools.exclude_tool);
+    if tools.merge_tool_variants {
+        parser::normalize_tool_names(&mut sessions, &parser::load_tool_aliases());
+    }
+    sessions
+}
+
+/// Keep sessions started after `git_ref`'s commit date, resolved in the
+/// `--project` directory or the current one
+fn sessions_since_commit(
+    sessions: Vec<Session>,
+    project: Option<&Path>,
+    git_ref: &str,
+) -> Result<Vec<Session>, error::AistError> {
+    let dir = match project {
+        Some(p) => p.to_path_buf(),
+        None => std::env::current_dir()
+            .map_err(|e| error::AistError::Io("Failed to read current directory".to_string(), e))?,
+    };
+    let cutoff = github::commit_date(&dir, git_ref)?;
+    Ok(metrics::filter_since(sessions, cutoff))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn analyze_command(
+    project: Option<PathBuf>,
+    verbose: bool,
+    limit: Option<usize>,
+    bulk_lines: usize,
+    bulk_bytes: usize,
+    tools: &ToolFilterArgs,
+    exclude_scripted: bool,
+    focus: &FocusArgs,
+    since_commit: Option<&str>,
+    include_subagents: bool,
+    heatmap: bool,
+) {
+    let mut sessions = load_tool_filtered_sessions(project.as_deref(), tools, include_subagents);
+    if let Some(git_ref) = since_commit {
+        match sessions_since_commit(sessions, project.as_deref(), git_ref) {
+            Ok(s) => sessions = s,
+            Err(e) => {
+                print_aist_error(&e);
+                return;
+            }
+        }
+    }
+
+    let scripted_count = sessions.iter().filter(|s| parser::is_scripted(s)).count();
+    if exclude_scripted {
+        sessions.retain(|s| !parser::is_scripted(s));
+    }
+
+    if sessions.is_empty() {
+        println!("{}", "No sessions found.".yellow());
+        return;
+    }
+
+    let aggregated = metrics::aggregate_metrics(&sessions);
+
+    // Header
+    println!("{}", "SESSION ANALYSIS".bold());
+    println!("{}", "\u{2550}".repeat(16));
+    println!();
+
+    // Summary line
+    println!(
+        "Sessions: {} | Total time: {} | Active: {} | median {}, p90 {}",
+        aggregated.session_count.to_string().bold(),
+        metrics::format_duration(aggregated.total_duration_minutes).bold(),
+        metrics::format_duration(aggregated.total_active_minutes).bold(),
+        metrics::format_duration(aggregated.median_duration_minutes),
+        metrics::format_duration(aggregated.p90_duration_minutes)
+    );
+    if aggregated.tool_turns > 0 {
+        println!(
+            "Parallelism: avg {:.1} tools per assistant turn",
+            metrics::tools_per_turn(aggregated.total_tool_calls, aggregated.tool_turns)
+        );
+    }
+    let models = metrics::model_session_counts(&sessions);
+    if !models.is_empty() {
+        let list: Vec<String> = models
+            .iter()
+            .map(|(model, count)| format!("{} ({})", model, count))
+            .collect();
+        println!("Models: {}", list.join(", "));
+    }
+    if scripted_count > 0 {
+        let note = if exclude_scripted {
+            format!(
+                "Excluded {} scripted/automated sessions (no user prompt)",
+                scripted_count
+            )
+        } else {
+            format!(
+                "Scripted/automated: {} sessions with no user prompt (--exclude-scripted to skip)",
+                scripted_count
+            )
+        };
+        println!("{}", note.dimmed());
+    }
+    if aggregated.total_hook_injections > 0 {
+        println!(
+            "{}",
+            format!(
+                "{} hook injections (not counted as user prompts)",
+                aggregated.total_hook_injections
+            )
+            .dimmed()
+        );
+    }
+    let criteria = focus.criteria();
+    let focus_count = sessions
+        .iter()
+        .filter(|s| bottlenecks::is_focus_session(s, &criteria))
+        .count();
+    println!(
+        "Focus: {} of {} sessions were focus sessions ({:.0}%)",
+        focus_count.to_string().bold(),
+        sessions.len(),
+        focus_count as f64 / sessions.len() as f64 * 100.0
+    );
+    println!(
+        "Consistency: {} active days, longest streak {} days",
+        aggregated.active_days.to_string().bold(),
+        aggregated.longest_streak_days.to_string().bold()
+    );
+    println!();
+
+    let limit = limit.unwrap_or(if verbose { usize::MAX } else { 10 });
+
+    // Tool usage section
+    println!("{}", "TOOL USAGE".bold());
+    println!("{}", "\u{2500}".repeat(10));
+    for row in tool_usage_rows(&aggregated, limit, verbose) {
+        println!("{}", row);
+    }
+    print_more_note(aggregated.tool_counts.len(), limit);
+    if verbose {
+        let unreliable = metrics::unreliable_tools(&aggregated);
+        if !unreliable.is_empty() {
+            println!();
+            println!("{}", "UNRELIABLE TOOLS".yellow().bold());
+            println!("{}", "\u{2500}".repeat(16));
+            for t in &unreliable {
+                println!(
+                    "{:<12} {:>3.0}% of {} calls failed",
+                    t.tool,
+                    t.failure_rate() * 100.0,
+                    metrics::format_number(t.calls)
+                );
+            }
+        }
+    }
+    println!();
+
+    // By project section
+    println!("{}", "BY PROJECT".bold());
+    println!("{}", "\u{2500}".repeat(10));
+    for row in project_rows(&aggregated, limit) {
+        println!("{}", row);
+    }
+    print_more_note(aggregated.by_project.len(), limit);
+    println!();
+
+    // Tokens and cost section
+    println!("{}", "TOKENS & COST".bold());
+    println!("{}", "\u{2500}".repeat(13));
+    println!(
+        "Input: {} tokens | Output: {} tokens | Cost: {}",
+        metrics::format_number(aggregated.total_input_tokens as usize).bold(),
+        metrics::format_number(aggregated.total_output_tokens as usize).bold(),
+        cost::format_cost(aggregated.total_cost).bold()
+    );
+    println!();
+
+    // Errors section
+    println!("{}", "ERRORS".bold());
+    println!("{}", "\u{2500}".repeat(6));
+    println!(
+        "Total: {} errors detected ({} distinct after collapsing retries)",
+        metrics::format_number(aggregated.total_errors),
+        metrics::format_number(aggregated.total_distinct_errors)
+    );
+
+    // Session length vs efficiency
+    if verbose {
+        let bins = bottlenecks::efficiency_by_duration(&sessions);
+        if !bins.is_empty() {
+            println!();
+            println!("{}", "EFFICIENCY BY SESSION LENGTH".bold());
+            println!("{}", "\u{2500}".repeat(28));
+            for b in &bins {
+                println!(
+                    "{:<8} {:>4} sessions  {:>4.0}% efficient",
+                    b.label, b.session_count, b.avg_efficiency
+                );
+            }
+            if let Some(summary) = bottlenecks::duration_efficiency_summary(&bins) {
+                println!("{}", summary.yellow());
+            }
+        }
+    }
+
+    // Cost section
+    let breakdown = cost::cost_breakdown(&sessions);
+    if breakdown.total() > 0.0 {
+        println!();
+        print_cost_breakdown(&breakdown);
+    }
+
+    // Cost anomalies section
+    let anomalies = cost::detect_cost_anomalies(&sessions);
+    if !anomalies.is_empty() {
+        println!();
+        println!(
+            "{}",
+            "\u{26A0} UNUSUALLY EXPENSIVE SESSIONS".yellow().bold()
+        );
+        println!("{}", "\u{2500}".repeat(32));
+        for a in &anomalies {
+            let session_short: String = a.session_id.chars().take(10).collect();
+            println!("{:<12} {:>10}", session_short, cost::format_cost(a.cost));
+        }
+    }
+
+    // Bulk overwrites section
+    let overwrites = metrics::detect_bulk_overwrites(&sessions, bulk_lines, bulk_bytes);
+    if !overwrites.is_empty() {
+        println!();
+        println!("{}", "BULK OVERWRITES".bold());
+        println!("{}", "\u{2500}".repeat(15));
+        println!(
+            "{} Write/Edit calls replaced over {} lines or {} at once",
+            overwrites.len().to_string().yellow(),
+            bulk_lines,
+            format_bytes(bulk_bytes)
+        );
+
+        let home = dirs::home_dir()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        for o in overwrites
+            .iter()
+            .take(if verbose { overwrites.len() } else { 5 })
+        {
+            let path = match o.file_path.strip_prefix(&home) {
+                Some(rest) if !home.is_empty() && rest.starts_with('/') => format!("~{}", rest),
+                _ => o.file_path.clone(),
+            };
+            let path_short = metrics::tail_chars(&path, 40);
+            let session_short: String = o.session_id.chars().take(10).collect();
+            println!(
+                "{:<12} {:<40} {:>5} {:>6} lines, {:>8}",
+                session_short,
+                path_short,
+                o.tool_name,
+                metrics::format_number(o.lines),
+                format_bytes(o.bytes)
+            );
+        }
+    }
+
+    // Uncommitted work section
+    let uncommitted =
+        metrics::detect_uncommitted_sessions(&sessions, metrics::UNCOMMITTED_MIN_EDITS);
+    if !uncommitted.is_empty() {
+        println!();
+        println!("{}", "UNCOMMITTED WORK".bold());
+        println!("{}", "\u{2500}".repeat(16));
+        println!(
+            "{} sessions made {}+ edits without running git commit",
+            uncommitted.len().to_string().yellow(),
+            metrics::UNCOMMITTED_MIN_EDITS
+        );
+
+        for u in uncommitted
+            .iter()
+            .take(if verbose { uncommitted.len() } else { 5 })
+        {
+            let session_short: String = u.session_id.chars().take(10).collect();
+            println!(
+                "{:<12} {:<30} {:>4} edits",
+                session_short, u.project, u.edit_count
+            );
+        }
+    }
+
+    if heatmap {
+        println!();
+        print_heatmap(&metrics::build_hour_dow_histogram(&sessions));
+    }
+}
+
+/// 7x24 grid of session minutes, shaded relative to the busiest hour
+fn print_heatmap(grid: &[[f64; 24]; 7]) {
+    const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    const SHADES: [&str; 5] = [" ", "\u{2591}", "\u{2592}", "\u{2593}", "\u{2588}"];
+
+    println!("{}", "ACTIVITY HEATMAP".bold());
+    println!("{}", "\u{2500}".repeat(16));
+
+    let max = grid.iter().flatten().copied().fold(0.0, f64::max);
+    if max == 0.0 {
+        println!("{}", "No timed sessions".dimmed());
+        return;
+    }
+
+    let header: String = (0..24).step_by(3).map(|h| format!("{:<6}", h)).collect();
+    println!("    {}", header.trim_end().dimmed());
+    for (day, hours) in DAYS.iter().zip(grid) {
+        let cells: String = hours
+            .iter()
+            .map(|&minutes| {
+                let level = if minutes > 0.0 {
+                    ((minutes / max * 4.0).ceil() as usize).clamp(1, 4)
+                } else {
+                    0
+                };
+                SHADES[level].repeat(2)
+            })
+            .collect();
+        println!("{} {}", day, cells.cyan());
+    }
+    println!(
+        "{}",
+        format!(
+            "Shaded by minutes per hour; {} = {} at the busiest",
+            SHADES[4],
+            metrics::format_duration(max)
+        )
+        .dimmed()
+    );
+}
+
+/// Per-category token counts and cost, with what caching saved
+fn print_cost_breakdown(breakdown: &cost::CostBreakdown) {
+    println!("{}", "COST".bold());
+    println!("{}", "\u{2500}".repeat(4));
+    let rows = [
+        ("Fresh input", breakdown.input_tokens, breakdown.input_cost),
+        (
+            "Cache writes",
+            breakdown.cache_write_tokens,
+            breakdown.cache_write_cost,
+        ),
+        (
+            "Cache reads",
+            breakdown.cache_read_tokens,
+            breakdown.cache_read_cost,
+        ),
+        ("Output", breakdown.output_tokens, breakdown.output_cost),
+    ];
+    for (label, tokens, dollars) in rows {
+        println!(
+            "{:<14} {:>14} tokens {:>10}",
+            label,
+            metrics::format_number(tokens as usize),
+            cost::format_cost(dollars)
+        );
+    }
+    println!(
+        "{:<14} {:>32}",
+        "Total".bold(),
+        cost::format_cost(breakdown.total()).bold()
+    );
+    if breakdown.cache_savings() > 0.0 {
+        println!(
+            "{}",
+            format!(
+                "Cache saved you {} (would have been {} uncached)",
+                cost::format_cost(breakdown.cache_savings()),
+                cost::format_cost(breakdown.uncached_cost)
+            )
+            .green()
+        );
+    }
+}
+
+/// Top `limit` tools by call count, one formatted row each; `verbose` adds
+/// the share of each tool's calls that failed
+fn tool_usage_rows(
+    aggregated: &metrics::AggregatedMetrics,
+    limit: usize,
+    verbose: bool,
+) -> Vec<String> {
+    // Sort tools by count (descending)
+    let mut tool_list: Vec<_> = aggregated.tool_counts.iter().collect();
+    tool_list.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+    tool_list
+        .iter()
+        .take(limit)
+        .map(|(tool, count)| {
+            let percentage = if aggregated.total_tool_calls > 0 {
+                (**count as f64 / aggregated.total_tool_calls as f64 * 100.0) as usize
+            } else {
+                0
+            };
+            let row = format!(
+                "{:<12} {:>6} ({:>2}%)",
+                tool,
+                metrics::format_number(**count),
+                percentage
+            );
+            if !verbose {
+                return row;
+            }
+            let failure_rate = metrics::tool_reliability(aggregated, tool).failure_rate();
+            format!("{}  {:>3.0}% failed", row, failure_rate * 100.0)
+        })
+        .collect()
+}
+
+/// Top `limit` projects by total duration, one formatted row each
+fn project_rows(aggregated: &metrics::AggregatedMetrics, limit: usize) -> Vec<String> {
+    // Sort projects by duration (descending)
+    let mut project_list: Vec<_> = aggregated.by_project.iter().collect();
+    project_list.sort_by(|a, b| {
+        b.1.total_duration_minutes
+            .partial_cmp(&a.1.total_duration_minutes)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(b.0))
+    });
+
+    project_list
+        .iter()
+        .take(limit)
+        .map(|(project_name, proj_metrics)| {
+            format!(
+                "{:<20} {:>2} sessions, {:>6}  {:>11} in / {:>9} out  {:>8}",
+                if project_name.len() > 18 {
+                    format!("{}...", &project_name[..15])
+                } else {
+                    (*project_name).clone()
+                },
+                proj_metrics.session_count,
+                metrics::format_duration(proj_metrics.total_duration_minutes),
+                metrics::format_number(proj_metrics.input_tokens as usize),
+                metrics::format_number(proj_metrics.output_tokens as usize),
+                cost::format_cost(proj_metrics.total_cost)
+            )
+        })
+        .collect()
+}
+
+fn print_more_note(total: usize, limit: usize) {
+    if total > limit {
+        println!(
+            "{}",
+            format!(
+                "... and {} more (use --limit or --verbose to see more)",
+                total - limit
+            )
+            .dimmed()
+        );
+    }
+}
+
+/// Format a byte count as B/KB/MB
+fn format_bytes(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1}MB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1}KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{}B", bytes)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn bottlenecks_command(
+    project: Option<PathBuf>,
+    limit: usize,
+    format: &str,
+    show_prompts: bool,
+    tools: &ToolFilterArgs,
+    config: &bottlenecks::BottleneckConfig,
+    since_commit: Option<&str>,
+    include_subagents: bool,
+) {
+    let mut sessions = load_tool_filtered_sessions(project.as_deref(), tools, include_subagents);
+    if let Some(git_ref) = since_commit {
+        match sessions_since_commit(sessions, project.as_deref(), git_ref) {
+            Ok(s) => sessions = s,
+            Err(e) => {
+                print_aist_error(&e);
+                return;
+            }
         }
     }
+
+    if sessions.is_empty() && format != "json" {
+        println!("{}", "No sessions found.".yellow());
+        return;
+    }
+
+    let detected = bottlenecks::detect_all_with_config(&sessions, config);
+    match format {
+        "json" => bottlenecks::print_bottlenecks_json(&detected[..limit.min(detected.len())]),
+        _ => {
+            let prompts = if show_prompts {
+                bottlenecks::preceding_prompts(&sessions, &detected)
+            } else {
+                vec![]
+            };
+            bottlenecks::print_bottlenecks(&detected, limit, &prompts);
+        }
+    }
+}
+
+/// Unrecognized periods fall back to all time; say so instead of silently
+/// reporting everything
+fn warn_unknown_period(period: &str) {
+    if !metrics::is_valid_period(period) {
+        eprintln!(
+            "Note: unrecognized period '{}', showing all time (use day, week, month, quarter, year, all, or e.g. 3d, 2w)",
+            period
+        );
+    }
+}
+
+/// Why `--compare` produced no comparison, if it didn't
+fn compare_note(date_range: bool, compared: bool) -> Option<&'static str> {
+    if date_range {
+        Some("--compare is ignored with an explicit --since/--until date range")
+    } else if !compared {
+        Some("--compare needs a day, week or month period")
+    } else {
+        None
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn report_command(
+    project: Option<&Path>,
+    tools: &ToolFilterArgs,
+    period: &str,
+    (since, until): (Option<NaiveDate>, Option<NaiveDate>),
+    format: &str,
+    output: Option<PathBuf>,
+    clipboard: bool,
+    compare: bool,
+    team_bundle: Option<&Path>,
+    include_subagents: bool,
+) {
+    let date_range = since.is_some() || until.is_some();
+    if !date_range {
+        warn_unknown_period(period);
+    }
+
+    let sessions = load_tool_filtered_sessions(project, tools, include_subagents);
+
+    if sessions.is_empty() {
+        println!("{}", "No sessions found.".yellow());
+        return;
+    }
+
+    let (mut report_data, filtered) = if date_range {
+        (
+            report::generate_report_for_range(&sessions, since, until),
+            metrics::filter_by_date_range(&sessions, since, until),
+        )
+    } else {
+        (
+            report::generate_report(&sessions, period),
+            metrics::filter_by_period(&sessions, period),
+        )
+    };
+    if compare {
+        if !date_range {
+            report_data.comparison = report::compare_with_previous(&report_data, &sessions, period);
+        }
+        if let Some(note) = compare_note(date_range, report_data.comparison.is_some()) {
+            eprintln!("Note: {}", note);
+        }
+    }
+    if let Some(path) = team_bundle {
+        match team::load_team_bundle(path) {
+            Ok(bundle) => {
+                report_data.team = Some(team::compare_to_team(
+                    report_data.efficiency_percent,
+                    &filtered,
+                    &bundle,
+                ));
+            }
+            Err(e) => {
+                print_aist_error(&e);
+                return;
+            }
+        }
+    }
+
+    if format == "html" {
+        let output_path = output.unwrap_or_else(|| {
+            PathBuf::from(format!(
+                "report-{}.html",
+                report_data.period.replace(' ', "-")
+            ))
+        });
+        let colors = match flamegraph::load_color_map() {
+            Ok(colors) => colors,
+            Err(e) => {
+                print_aist_error(&e);
+                return;
+            }
+        };
+        match export::generate_standalone_html_report(
+            &report_data,
+            &filtered,
+            &output_path,
+            &colors,
+        ) {
+            Ok(()) => println!(
+                "{} Generated report: {}",
+                "✓".green(),
+                output_path.display()
+            ),
+            Err(e) => print_aist_error(&e),
+        }
+        return;
+    }
+
+    if clipboard {
+        // Clipboard contents should be plain text, not ANSI escape codes
+        colored::control::set_override(false);
+        let output = match format {
+            "json" => report::render_json_report(&report_data),
+            "markdown" | "md" => Ok(report::render_markdown_report(&report_data)),
+            _ => Ok(report::render_text_report(&report_data)),
+        };
+        match output {
+            Ok(text) => match clipboard::copy(&text) {
+                Ok(()) => println!("{} Copied report to clipboard", "✓".green()),
+                Err(e) => {
+                    println!("{}", text.trim_end());
+                    eprintln!("Note: {} - printed to stdout instead", e);
+                }
+            },
+            Err(e) => eprintln!("{}", e),
+        }
+        return;
+    }
+
+    match format {
+        "json" => report::print_json_report(&report_data),
+        "markdown" | "md" => report::print_markdown_report(&report_data),
+        _ => report::print_text_report(&report_data),
+    }
 }
 
-fn analyze_command(project: Option<PathBuf>, verbose: bool) {
-    let sessions = parser::load_sessions(project.as_deref());
+fn timeline_command(
+    session_id: &str,
+    project: Option<PathBuf>,
+    tools: &ToolFilterArgs,
+    ts_format: timeline::TimestampFormat,
+    summary_json: bool,
+) {
+    let sessions = load_tool_filtered_sessions(project.as_deref(), tools, false);
 
     if sessions.is_empty() {
         println!("{}", "No sessions found.".yellow());
         return;
     }
 
-    let aggregated = metrics::aggregate_metrics(&sessions);
-
-    // Header
-    println!("{}", "SESSION ANALYSIS".bold());
-    println!("{}", "\u{2550}".repeat(16));
-    println!();
+    let session = if session_id == "latest" {
+        timeline::get_latest_session(&sessions)
+    } else {
+        timeline::find_session_by_id(&sessions, session_id)
+    };
+
+    match session {
+        Some(s) if summary_json => {
+            match serde_json::to_string_pretty(&timeline::compute_timeline_summary(s)) {
+                Ok(json) => println!("{}", json),
+                Err(e) => eprintln!("{}", format!("Failed to serialize summary: {}", e).red()),
+            }
+        }
+        Some(s) => timeline::print_timeline(s, ts_format),
+        None => {
+            println!(
+                "{}: No session found matching '{}'",
+                "Error".red(),
+                session_id
+            );
+            println!("{}", "Use 'aist list' to see available sessions.".dimmed());
+        }
+    }
+}
+
+/// Fields available to `list --format json`
+const LIST_FIELDS: &[&str] = &[
+    "id", "project", "branch", "start", "end", "duration", "messages", "cost",
+];
+
+/// One session as serialized by `list --format json`
+fn session_json(session: &Session) -> serde_json::Value {
+    let duration = match (session.start_time, session.end_time) {
+        (Some(start), Some(end)) => Some((end - start).num_minutes()),
+        _ => None,
+    };
+
+    serde_json::json!({
+        "id": session.session_id,
+        "project": session.project,
+        "branch": session.git_branch,
+        "start": session.start_time.map(|t| t.to_rfc3339()),
+        "end": session.end_time.map(|t| t.to_rfc3339()),
+        "duration": duration,
+        "messages": session.messages.len(),
+        "cost": cost::calculate_cost(session),
+    })
+}
+
+/// Keep only the requested keys of a JSON object.
+/// An empty selection keeps every field.
+fn select_fields(value: serde_json::Value, fields: &[String]) -> Result<serde_json::Value, String> {
+    if fields.is_empty() {
+        return Ok(value);
+    }
+
+    let serde_json::Value::Object(mut object) = value else {
+        return Ok(value);
+    };
+
+    let mut selected = serde_json::Map::new();
+    for field in fields {
+        match object.remove(field.as_str()) {
+            Some(v) => {
+                selected.insert(field.clone(), v);
+            }
+            None => {
+                return Err(format!(
+                    "Unknown field '{}' (available: {})",
+                    field,
+                    LIST_FIELDS.join(", ")
+                ))
+            }
+        }
+    }
+
+    Ok(serde_json::Value::Object(selected))
+}
+
+fn cost_command(
+    project: Option<PathBuf>,
+    period: &str,
+    detailed: bool,
+    histogram: bool,
+    by: Option<cost::CostInterval>,
+    budget: Option<f64>,
+    budgets: &[(String, f64)],
+) -> ExitCode {
+    warn_unknown_period(period);
+    let sessions =
+        metrics::filter_by_period(&parser::load_sessions(project.as_deref(), false), period);
+
+    if sessions.is_empty() {
+        println!("{}", "No sessions found.".yellow());
+        return ExitCode::SUCCESS;
+    }
+
+    let breakdown = cost::cost_breakdown(&sessions);
+    if breakdown.total() == 0.0 {
+        println!("{}", "No token usage recorded in these sessions.".yellow());
+        return ExitCode::SUCCESS;
+    }
+    print_cost_breakdown(&breakdown);
+
+    if detailed {
+        println!();
+        print_session_costs(&cost::session_costs(&sessions));
+    }
+
+    if histogram {
+        println!();
+        print_token_histogram(&sessions);
+    }
+
+    if let Some(interval) = by {
+        println!();
+        print_cost_trend(&cost::cost_over_time(&sessions, interval), interval);
+    }
+
+    if !budgets.is_empty() {
+        println!();
+        let aggregated = metrics::aggregate_metrics(&sessions);
+        print_project_budgets(&cost::check_budgets(&aggregated.by_project, budgets));
+    }
+
+    if let Some(budget) = budget {
+        if let Some(overage) = cost::budget_overage(breakdown.total(), budget) {
+            println!();
+            println!(
+                "{}",
+                format!(
+                    "Over budget: {} spent of {} ({} over)",
+                    cost::format_cost(breakdown.total()),
+                    cost::format_cost(budget),
+                    cost::format_cost(overage)
+                )
+                .red()
+                .bold()
+            );
+            return ExitCode::FAILURE;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
 
-    // Summary line
+/// Table of budgeted projects' spend against their caps
+fn print_project_budgets(budgets: &[cost::ProjectBudget]) {
+    println!("{}", "BUDGET BY PROJECT".bold());
+    println!("{}", "\u{2500}".repeat(17));
     println!(
-        "Sessions: {} | Total time: {}",
-        aggregated.session_count.to_string().bold(),
-        metrics::format_duration(aggregated.total_duration_minutes).bold()
+        "{:<24} {:>8} {:>10} {:>10}  STATUS",
+        "PROJECT", "TOKENS", "SPEND", "BUDGET"
     );
-    println!();
-
-    // Tool usage section
-    println!("{}", "TOOL USAGE".bold());
-    println!("{}", "\u{2500}".repeat(10));
-
-    // Sort tools by count (descending)
-    let mut tool_list: Vec<_> = aggregated.tool_counts.iter().collect();
-    tool_list.sort_by(|a, b| b.1.cmp(a.1));
 
-    for (tool, count) in tool_list
-        .iter()
-        .take(if verbose { tool_list.len() } else { 10 })
-    {
-        let percentage = if aggregated.total_tool_calls > 0 {
-            (**count as f64 / aggregated.total_tool_calls as f64 * 100.0) as usize
+    for b in budgets {
+        let status = if b.is_over() {
+            format!("OVER by {}", cost::format_cost(b.spend - b.budget))
+                .red()
+                .bold()
+                .to_string()
         } else {
-            0
+            format!("{} left", cost::format_cost(b.budget - b.spend))
+                .green()
+                .to_string()
+        };
+        let name = if b.project.chars().count() > 24 {
+            format!("{}...", b.project.chars().take(21).collect::<String>())
+        } else {
+            b.project.clone()
         };
         println!(
-            "{:<12} {:>6} ({:>2}%)",
-            tool,
-            metrics::format_number(**count),
-            percentage
+            "{:<24} {:>8} {:>10} {:>10}  {}",
+            name,
+            cost::format_tokens(b.tokens),
+            cost::format_cost(b.spend),
+            cost::format_cost(b.budget),
+            status
         );
     }
 
-    if !verbose && tool_list.len() > 10 {
+    let over = budgets.iter().filter(|b| b.is_over()).count();
+    if over > 0 {
+        println!();
         println!(
             "{}",
-            format!(
-                "... and {} more (use --verbose to see all)",
-                tool_list.len() - 10
-            )
-            .dimmed()
+            format!("{} of {} projects over budget", over, budgets.len()).yellow()
         );
     }
-    println!();
+}
 
-    // By project section
-    println!("{}", "BY PROJECT".bold());
+/// Table of each session's tokens and cost
+fn print_session_costs(costs: &[cost::SessionCost]) {
+    println!("{}", "BY SESSION".bold());
     println!("{}", "\u{2500}".repeat(10));
+    println!(
+        "{:<10} {:<24} {:>8} {:>10}",
+        "SESSION", "PROJECT", "TOKENS", "COST"
+    );
+    for c in costs {
+        println!(
+            "{:<10} {:<24} {:>8} {:>10}",
+            metrics::short_id(&c.session_id, 8),
+            c.project,
+            cost::format_tokens(c.tokens),
+            cost::format_cost(c.cost)
+        );
+    }
+}
 
-    // Sort projects by duration (descending)
-    let mut project_list: Vec<_> = aggregated.by_project.iter().collect();
-    project_list.sort_by(|a, b| {
-        b.1.total_duration_minutes
-            .partial_cmp(&a.1.total_duration_minutes)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+/// Bar per day/week/month of cost, empty bars included so gaps show
+fn print_cost_trend(buckets: &[cost::CostBucket], interval: cost::CostInterval) {
+    println!("{}", "COST OVER TIME".bold());
+    println!("{}", "\u{2500}".repeat(14));
 
-    for (project_name, proj_metrics) in
-        project_list
-            .iter()
-            .take(if verbose { project_list.len() } else { 10 })
-    {
+    let max_cost = buckets.iter().map(|b| b.cost).fold(0.0, f64::max);
+    let bar_width = 30;
+    for b in buckets {
+        let filled = if max_cost > 0.0 {
+            (b.cost / max_cost * bar_width as f64).ceil() as usize
+        } else {
+            0
+        };
         println!(
-            "{:<20} {:>2} sessions, {:>6}",
-            if project_name.len() > 18 {
-                format!("{}...", &project_name[..15])
-            } else {
-                (*project_name).clone()
-            },
-            proj_metrics.session_count,
-            metrics::format_duration(proj_metrics.total_duration_minutes)
+            "{:>10} {}{} {} {}",
+            interval.label(b.start),
+            "\u{2588}".repeat(filled).cyan(),
+            "\u{2591}".repeat(bar_width - filled).dimmed(),
+            cost::format_cost(b.cost),
+            format!("({} sessions)", b.sessions).dimmed()
         );
     }
+}
 
-    if !verbose && project_list.len() > 10 {
-        println!(
-            "{}",
+/// ASCII histogram of total tokens per session, one row per decade
+fn print_token_histogram(sessions: &[Session]) {
+    println!("{}", "TOKENS PER SESSION".bold());
+    println!("{}", "\u{2500}".repeat(18));
+    for row in token_histogram_rows(sessions) {
+        println!("{}", row);
+    }
+}
+
+/// The histogram's rows: one bar per decade of tokens, then a note on
+/// sessions without usage data
+fn token_histogram_rows(sessions: &[Session]) -> Vec<String> {
+    let totals: Vec<u64> = sessions
+        .iter()
+        .map(cost::session_tokens)
+        .filter(|&t| t > 0)
+        .collect();
+
+    let buckets = cost::token_histogram(&totals);
+    let max_count = buckets.iter().map(|b| b.count).max().unwrap_or(0);
+    if max_count == 0 {
+        return vec!["No sessions with token usage".dimmed().to_string()];
+    }
+
+    let bar_width = 30;
+    let mut rows: Vec<String> = buckets
+        .iter()
+        .map(|b| {
+            let label = format!(
+                "{}-{}",
+                cost::format_tokens(b.low),
+                cost::format_tokens(b.high)
+            );
+            let filled = (b.count * bar_width).div_ceil(max_count);
             format!(
-                "... and {} more (use --verbose to see all)",
-                project_list.len() - 10
+                "{:>11} {} {}",
+                label,
+                "\u{2588}".repeat(filled).cyan(),
+                b.count
             )
-            .dimmed()
+        })
+        .collect();
+
+    let skipped = sessions.len() - totals.len();
+    if skipped > 0 {
+        rows.push(
+            format!("{} sessions had no token usage data", skipped)
+                .dimmed()
+                .to_string(),
         );
     }
-    println!();
-
-    // Errors section
-    println!("{}", "ERRORS".bold());
-    println!("{}", "\u{2500}".repeat(6));
-    println!(
-        "Total: {} errors detected",
-        metrics::format_number(aggregated.total_errors)
-    );
+    rows
 }
 
-fn bottlenecks_command(project: Option<PathBuf>, limit: usize) {
-    let sessions = parser::load_sessions(project.as_deref());
+fn calendar_command(period: &str, project: Option<PathBuf>) {
+    let Some((year, month)) = calendar::parse_period(period) else {
+        eprintln!(
+            "{}",
+            format!("Invalid period '{}': use month or YYYY-MM", period).red()
+        );
+        return;
+    };
 
+    let sessions = parser::load_sessions(project.as_deref(), false);
     if sessions.is_empty() {
         println!("{}", "No sessions found.".yellow());
         return;
     }
 
-    let detected = bottlenecks::detect_all(&sessions);
-    bottlenecks::print_bottlenecks(&detected, limit);
+    let days = calendar::bucket_by_day(&sessions);
+    print!("{}", calendar::render_calendar(year, month, &days));
 }
 
-fn report_command(period: &str, format: &str) {
-    let sessions = parser::load_sessions(None);
-
+fn lifetime_command() {
+    let sessions = parser::load_sessions(None, false);
     if sessions.is_empty() {
         println!("{}", "No sessions found.".yellow());
         return;
     }
 
-    let report_data = report::generate_report(&sessions, period);
-
-    match format {
-        "json" => report::print_json_report(&report_data),
-        _ => report::print_text_report(&report_data),
-    }
+    print!(
+        "{}",
+        lifetime::render_lifetime(&lifetime::compute_lifetime_stats(&sessions))
+    );
 }
 
-fn timeline_command(session_id: &str, project: Option<PathBuf>) {
-    let sessions = parser::load_sessions(project.as_deref());
-
-    if sessions.is_empty() {
-        println!("{}", "No sessions found.".yellow());
+fn exclude_command(session_id: &str, start: &str, end: &str) {
+    let sessions = parser::load_sessions(None, false);
+    let Some(session) = timeline::find_session_by_id(&sessions, session_id) else {
+        println!(
+            "{}: No session found matching '{}'",
+            "Error".red(),
+            session_id
+        );
+        println!("{}", "Use 'aist list' to see available sessions.".dimmed());
         return;
-    }
+    };
 
-    let session = if session_id == "latest" {
-        timeline::get_latest_session(&sessions)
-    } else {
-        timeline::find_session_by_id(&sessions, session_id)
+    let window = match exclusions::resolve_window(session, start, end) {
+        Ok(w) => w,
+        Err(e) => {
+            eprintln!("{}", e.red());
+            return;
+        }
     };
 
-    match session {
-        Some(s) => timeline::print_timeline(s),
-        None => {
+    match exclusions::add_exclusion(&session.session_id, window) {
+        Ok(path) => {
+            let minutes = exclusions::excluded_minutes(
+                &[window],
+                session.start_time.unwrap_or(window.start),
+                session.end_time.unwrap_or(window.end),
+            );
             println!(
-                "{}: No session found matching '{}'",
-                "Error".red(),
-                session_id
+                "Excluded {:.0}m from session {} ({})",
+                minutes,
+                metrics::short_id(&session.session_id, 8),
+                path.display().to_string().dimmed()
             );
-            println!("{}", "Use 'aist list' to see available sessions.".dimmed());
         }
+        Err(e) => print_aist_error(&e),
     }
 }
 
-fn list_command(limit: usize, project: Option<PathBuf>) {
-    let sessions = parser::load_sessions(project.as_deref());
+fn list_command(limit: usize, project: Option<PathBuf>, format: &str, fields: &[String]) {
+    let sessions = parser::load_sessions(project.as_deref(), false);
+
+    if format == "json" {
+        let mut sessions = sessions;
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.end_time));
+
+        let rows: Result<Vec<_>, _> = sessions
+            .iter()
+            .take(limit)
+            .map(|s| select_fields(session_json(s), fields))
+            .collect();
+
+        match rows.and_then(|rows| {
+            serde_json::to_string_pretty(&rows)
+                .map_err(|e| format!("Error serializing sessions: {}", e))
+        }) {
+            Ok(json) => println!("{}", json),
+            Err(e) => eprintln!("{}", e),
+        }
+        return;
+    }
+
+    if !fields.is_empty() {
+        eprintln!("Note: --fields only applies to --format json");
+    }
 
     if sessions.is_empty() {
         println!("{}", "No sessions found.".yellow());
@@ -386,7 +1803,7 @@ fn list_command(limit: usize, project: Option<PathBuf>) {
 
     // Sort by end time, most recent first
     let mut sessions = sessions;
-    sessions.sort_by(|a, b| b.end_time.cmp(&a.end_time));
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.end_time));
 
     println!(
         "{}\n",
@@ -451,48 +1868,176 @@ fn list_command(limit: usize, project: Option<PathBuf>) {
     );
 }
 
-fn flame_command(output: PathBuf, project: Option<PathBuf>, group_by: &str) {
-    let sessions = parser::load_sessions(project.as_deref());
+#[allow(clippy::too_many_arguments)]
+fn flame_command(
+    output: Option<PathBuf>,
+    project: Option<PathBuf>,
+    tools: &ToolFilterArgs,
+    group_by: &str,
+    format: &str,
+    clipboard: bool,
+    layout: &str,
+    absolute_time: bool,
+    theme: flamegraph::Theme,
+) {
+    let sessions = load_tool_filtered_sessions(project.as_deref(), tools, false);
 
     if sessions.is_empty() {
         println!("{}", "No sessions found.".yellow());
         return;
     }
 
-    let result = match group_by {
-        "project" => flamegraph::generate_svg_by_project(&sessions, &output),
-        "pr" => flamegraph::generate_svg_by_pr(&sessions, &output),
-        "issue" => flamegraph::generate_svg_by_issue(&sessions, &output),
-        _ => flamegraph::generate_svg(&sessions, &output),
+    let colors = match flamegraph::load_color_map() {
+        Ok(colors) => colors,
+        Err(e) => {
+            print_aist_error(&e);
+            return;
+        }
     };
 
-    match result {
-        Ok(()) => {
-            println!("{} Generated flamegraph: {}", "✓".green(), output.display());
+    if absolute_time && (layout != "bars" || group_by != "session") {
+        println!(
+            "{}: --absolute-time only works with the default bars layout and session grouping",
+            "Error".red()
+        );
+        return;
+    }
+
+    let cache = github::load_current_repo_cache();
+    let rendered = match layout {
+        "bars" if absolute_time => {
+            flamegraph::render_svg_absolute(&sessions, cache.as_ref(), theme, &colors)
+        }
+        "bars" => flamegraph::render_grouped(&sessions, group_by, cache.as_ref(), theme, &colors),
+        "lanes" => flamegraph::render_svg_lanes(&sessions, theme, &colors),
+        other => {
             println!(
-                "{}",
-                "Open in browser to view interactive visualization".dimmed()
+                "{}: Unknown layout '{}'. Use bars or lanes.",
+                "Error".red(),
+                other
             );
+            return;
         }
+    };
+    let svg = match rendered {
+        Ok(svg) => svg,
         Err(e) => {
             println!("{}: Failed to generate flamegraph: {}", "Error".red(), e);
+            return;
+        }
+    };
+
+    if format == "png" || has_extension(output.as_deref(), "png") {
+        write_flame_png(&svg, output, clipboard);
+        return;
+    }
+    let wants_html = has_extension(output.as_deref(), "html");
+
+    let content = match format {
+        "svg" if wants_html => flamegraph::render_html(&svg, theme),
+        "svg" => svg,
+        "data-uri" => flamegraph::svg_data_uri(&svg),
+        other => {
+            println!(
+                "{}: Unknown format '{}'. Use svg, data-uri or png.",
+                "Error".red(),
+                other
+            );
+            return;
+        }
+    };
+
+    // A data URI goes to stdout unless a file was asked for
+    let output = match (output, format) {
+        (Some(path), _) => path,
+        (None, "data-uri") => {
+            println!("{}", content);
+            if clipboard {
+                copy_flame_to_clipboard(&content, None);
+            }
+            return;
         }
+        (None, _) => PathBuf::from("session-flamegraph.svg"),
+    };
+
+    if let Err(e) = std::fs::write(&output, &content) {
+        println!("{}: Failed to write flamegraph: {}", "Error".red(), e);
+        return;
+    }
+
+    println!("{} Generated flamegraph: {}", "✓".green(), output.display());
+    if format == "svg" {
+        println!(
+            "{}",
+            "Open in browser to view interactive visualization".dimmed()
+        );
+    }
+
+    if clipboard {
+        copy_flame_to_clipboard(&content, Some(&output));
     }
 }
 
-fn sync_command(owner: Option<&str>, repo: Option<&str>) {
-    match github::sync(owner, repo) {
-        Ok(()) => {
-            println!("{}", "Sync complete!".green().bold());
-        }
+fn has_extension(path: Option<&Path>, extension: &str) -> bool {
+    path.and_then(|p| p.extension())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// Rasterize the flamegraph and write it as a PNG
+fn write_flame_png(svg: &str, output: Option<PathBuf>, clipboard: bool) {
+    let output = output.unwrap_or_else(|| PathBuf::from("session-flamegraph.png"));
+    let png = match png::svg_to_png(svg) {
+        Ok(png) => png,
         Err(e) => {
             println!("{}: {}", "Error".red(), e);
+            return;
+        }
+    };
+    if let Err(e) = std::fs::write(&output, png) {
+        println!("{}: Failed to write flamegraph: {}", "Error".red(), e);
+        return;
+    }
+
+    println!("{} Generated flamegraph: {}", "✓".green(), output.display());
+    if clipboard {
+        eprintln!("Note: --clipboard only copies SVG or data-uri output");
+    }
+}
+
+fn copy_flame_to_clipboard(content: &str, output: Option<&Path>) {
+    match clipboard::copy(content) {
+        Ok(()) => eprintln!("{} Copied flamegraph to clipboard", "✓".green()),
+        Err(e) => match output {
+            Some(path) => eprintln!(
+                "Note: {} - flamegraph is at {}",
+                e,
+                path.display().to_string().dimmed()
+            ),
+            None => eprintln!("Note: {}", e),
+        },
+    }
+}
+
+fn sync_command(
+    owner: Option<&str>,
+    repo: Option<&str>,
+    gitlab: bool,
+    full: bool,
+    state: &str,
+    force: bool,
+    ttl: i64,
+) {
+    match github::sync(owner, repo, gitlab, full, state, force, ttl) {
+        Ok(github::SyncOutcome::Synced) => {
+            println!("{}", "Sync complete!".green().bold());
         }
+        Ok(github::SyncOutcome::Fresh) => {}
+        Err(e) => print_aist_error(&e),
     }
 }
 
 fn issues_command(project: Option<PathBuf>) {
-    let sessions = parser::load_sessions(project.as_deref());
+    let sessions = parser::load_sessions(project.as_deref(), false);
 
     if sessions.is_empty() {
         println!("{}", "No sessions found.".yellow());
@@ -503,7 +2048,7 @@ fn issues_command(project: Option<PathBuf>) {
 }
 
 fn issue_detail_command(issue_number: u32, project: Option<PathBuf>) {
-    let sessions = parser::load_sessions(project.as_deref());
+    let sessions = parser::load_sessions(project.as_deref(), false);
 
     if sessions.is_empty() {
         println!("{}", "No sessions found.".yellow());
@@ -513,19 +2058,34 @@ fn issue_detail_command(issue_number: u32, project: Option<PathBuf>) {
     issues::show_issue_detail(issue_number, &sessions);
 }
 
-fn prs_command(project: Option<PathBuf>) {
-    let sessions = parser::load_sessions(project.as_deref());
+fn prs_command(project: Option<PathBuf>, all_repos: bool) {
+    let sessions = parser::load_sessions(project.as_deref(), false);
+
+    if sessions.is_empty() {
+        println!("{}", "No sessions found.".yellow());
+        return;
+    }
+
+    if all_repos {
+        prs::list_all_repo_prs(&sessions);
+    } else {
+        prs::list_prs(&sessions);
+    }
+}
+
+fn orphans_command(project: Option<PathBuf>, limit: usize) {
+    let sessions = parser::load_sessions(project.as_deref(), false);
 
     if sessions.is_empty() {
         println!("{}", "No sessions found.".yellow());
         return;
     }
 
-    prs::list_prs(&sessions);
+    prs::list_orphans(&sessions, limit);
 }
 
 fn pr_detail_command(pr_number: u32, project: Option<PathBuf>) {
-    let sessions = parser::load_sessions(project.as_deref());
+    let sessions = parser::load_sessions(project.as_deref(), false);
 
     if sessions.is_empty() {
         println!("{}", "No sessions found.".yellow());
@@ -535,17 +2095,23 @@ fn pr_detail_command(pr_number: u32, project: Option<PathBuf>) {
     prs::show_pr_detail(pr_number, &sessions);
 }
 
-fn export_command(owner: Option<&str>, repo: Option<&str>, period: &str, output: Option<PathBuf>) {
+fn export_command(
+    owner: Option<&str>,
+    repo: Option<&str>,
+    period: &str,
+    output: Option<PathBuf>,
+    theme: flamegraph::Theme,
+    tools: &ToolFilterArgs,
+) {
+    warn_unknown_period(period);
+
     // Auto-detect repo if not specified
     let (owner, repo) = match (owner, repo) {
         (Some(o), Some(r)) => (o.to_string(), r.to_string()),
         _ => match github::detect_repo() {
             Some((o, r)) => (o, r),
             None => {
-                println!(
-                    "{}: Could not detect repo from git remote. Use --owner and --repo flags.",
-                    "Error".red()
-                );
+                print_aist_error(&error::AistError::RepoNotDetected);
                 return;
             }
         },
@@ -559,7 +2125,7 @@ fn export_command(owner: Option<&str>, repo: Option<&str>, period: &str, output:
     );
 
     // Load all sessions
-    let sessions = parser::load_sessions(None);
+    let sessions = load_tool_filtered_sessions(None, tools, false);
 
     if sessions.is_empty() {
         println!("{}", "No sessions found.".yellow());
@@ -572,12 +2138,7 @@ fn export_command(owner: Option<&str>, repo: Option<&str>, period: &str, output:
     let cache = match cache {
         Some(c) => c,
         None => {
-            println!(
-                "{}: No GitHub cache found for {}/{}. Run `aist sync` first.",
-                "Error".red(),
-                owner,
-                repo
-            );
+            print_aist_error(&error::AistError::NoCache(format!("{}/{}", owner, repo)));
             return;
         }
     };
@@ -592,7 +2153,7 @@ fn export_command(owner: Option<&str>, repo: Option<&str>, period: &str, output:
     }
 
     // Filter by period
-    let filtered = export::filter_sessions_by_period(&filtered, period);
+    let filtered = metrics::filter_by_period(&filtered, period);
 
     if filtered.is_empty() {
         println!(
@@ -609,12 +2170,19 @@ fn export_command(owner: Option<&str>, repo: Option<&str>, period: &str, output:
         cache.prs.len()
     );
 
+    let colors = match flamegraph::load_color_map() {
+        Ok(colors) => colors,
+        Err(e) => {
+            print_aist_error(&e);
+            return;
+        }
+    };
+
     // Determine output path
-    let output_path =
-        output.unwrap_or_else(|| PathBuf::from(format!("report-{}-{}.html", owner, repo)));
+    let output_path = output.unwrap_or_else(|| PathBuf::from("ai-session-report.html"));
 
     // Generate HTML report
-    match export::generate_html_report(&filtered, &cache, &output_path) {
+    match export::generate_html_report(&filtered, &cache, &output_path, theme, &colors) {
         Ok(()) => {
             println!(
                 "{} Generated report: {}",
@@ -622,8 +2190,241 @@ fn export_command(owner: Option<&str>, repo: Option<&str>, period: &str, output:
                 output_path.display()
             );
         }
-        Err(e) => {
-            println!("{}: {}", "Error".red(), e);
-        }
+        Err(e) => print_aist_error(&e),
+    }
+}
+
+/// Print an error followed by its suggested fix, if any
+fn print_aist_error(e: &error::AistError) {
+    println!("{}: {}", "Error".red(), e);
+    if let Some(tip) = e.remediation() {
+        println!("{}", format!("Tip: {}", tip).dimmed());
+    }
+}
+
+fn clean_command(yes: bool) {
+    let entries = state::find_state(&state::state_dirs());
+
+    if entries.is_empty() {
+        println!("{}", "Nothing to clean.".green());
+        return;
+    }
+
+    println!("{}", "AIST STATE".bold());
+    println!("{}", "\u{2500}".repeat(10));
+    for entry in &entries {
+        println!(
+            "{:<60} {:>10}",
+            entry.path.display(),
+            format_bytes(entry.bytes as usize)
+        );
+    }
+    println!();
+
+    if !yes {
+        println!(
+            "{}",
+            "Run `aist clean --yes` to delete. Transcripts in ~/.claude are never touched."
+                .dimmed()
+        );
+        return;
+    }
+
+    match state::remove_state(&entries) {
+        Ok(reclaimed) => println!(
+            "{} Removed {} {}, reclaimed {}",
+            "✓".green(),
+            entries.len(),
+            if entries.len() == 1 {
+                "directory"
+            } else {
+                "directories"
+            },
+            format_bytes(reclaimed as usize)
+        ),
+        Err(e) => println!("{}: {}", "Error".red(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_select_fields_keeps_only_requested() {
+        let value = serde_json::json!({"id": "abc", "project": "/p", "cost": 1.5});
+        let fields = vec!["id".to_string(), "cost".to_string()];
+
+        let selected = select_fields(value, &fields).unwrap();
+        let object = selected.as_object().unwrap();
+
+        assert_eq!(object.len(), 2);
+        assert_eq!(object["id"], "abc");
+        assert_eq!(object["cost"], 1.5);
+        assert!(!object.contains_key("project"));
+    }
+
+    #[test]
+    fn test_select_fields_rejects_unknown() {
+        let value = serde_json::json!({"id": "abc"});
+        let fields = vec!["nope".to_string()];
+
+        let err = select_fields(value, &fields).unwrap_err();
+        assert!(err.contains("nope"));
+    }
+
+    #[test]
+    fn test_tool_usage_rows_respects_limit() {
+        let tool_counts: HashMap<String, usize> = ["Read", "Edit", "Bash", "Grep", "Glob"]
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.to_string(), 10 - i))
+            .collect();
+        let aggregated = metrics::AggregatedMetrics {
+            session_count: 1,
+            total_duration_minutes: 30.0,
+            total_active_minutes: 30.0,
+            median_duration_minutes: 30.0,
+            p90_duration_minutes: 30.0,
+            active_days: 1,
+            longest_streak_days: 1,
+            total_tool_calls: tool_counts.values().sum(),
+            tool_turns: 5,
+            total_errors: 0,
+            total_distinct_errors: 0,
+            total_hook_injections: 0,
+            total_input_tokens: 0,
+            total_output_tokens: 0,
+            total_cost: 0.0,
+            tool_counts,
+            tool_error_counts: HashMap::from([("Bash".to_string(), 2)]),
+            by_project: HashMap::new(),
+        };
+
+        let rows = tool_usage_rows(&aggregated, 3, false);
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].starts_with("Read"));
+        assert!(rows[2].starts_with("Bash"));
+        assert!(!rows[2].contains("failed"));
+
+        let rows = tool_usage_rows(&aggregated, usize::MAX, true);
+        assert_eq!(rows.len(), 5);
+        assert!(rows[2].ends_with("25% failed"));
+    }
+
+    #[test]
+    fn test_token_histogram_without_usage() {
+        let rows = token_histogram_rows(&[Session::default()]);
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].contains("No sessions with token usage"));
+    }
+
+    #[test]
+    fn test_parse_prs_and_pr_commands() {
+        let cli = Cli::try_parse_from(["aist", "prs", "--project", "/w/app"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Prs { project: Some(p), all_repos: false } if p == Path::new("/w/app")
+        ));
+
+        let cli = Cli::try_parse_from(["aist", "pr", "12"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Pr {
+                number: 12,
+                project: None
+            }
+        ));
+
+        assert!(Cli::try_parse_from(["aist", "pr", "twelve"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_report_project() {
+        let cli =
+            Cli::try_parse_from(["aist", "report", "--project", "/w/app", "-p", "week"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Report { project: Some(p), period, .. }
+                if p == Path::new("/w/app") && period == "week"
+        ));
+
+        let cli = Cli::try_parse_from(["aist", "report"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Report { project: None, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_bottlenecks_format() {
+        let cli = Cli::try_parse_from(["aist", "bottlenecks", "-f", "json", "-l", "50"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Bottlenecks { format, limit: 50, show_prompts: false, .. } if format == "json"
+        ));
+
+        let cli = Cli::try_parse_from(["aist", "bottlenecks", "--show-prompts"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Bottlenecks {
+                show_prompts: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_unset_thresholds_use_config_defaults() {
+        let thresholds = |args: &[&str]| {
+            let cli = Cli::try_parse_from([&["aist", "bottlenecks"], args].concat()).unwrap();
+            let Commands::Bottlenecks { thresholds, .. } = cli.command else {
+                panic!("expected bottlenecks");
+            };
+            thresholds.config()
+        };
+        let defaults = bottlenecks::BottleneckConfig::default();
+
+        let config = thresholds(&[]);
+        assert_eq!(
+            config.error_loop_min_failures,
+            defaults.error_loop_min_failures
+        );
+        assert_eq!(config.exploration_min_calls, defaults.exploration_min_calls);
+        assert_eq!(config.edit_thrash_min_edits, defaults.edit_thrash_min_edits);
+        assert_eq!(config.gap_floor_minutes, defaults.gap_floor_minutes);
+
+        let config = thresholds(&["--edit-threshold", "8", "--gap-minutes", "2"]);
+        assert_eq!(config.edit_thrash_min_edits, 8);
+        assert_eq!(config.gap_floor_minutes, 2.0);
+        assert_eq!(
+            config.error_loop_min_failures,
+            defaults.error_loop_min_failures
+        );
+    }
+
+    #[test]
+    fn test_compare_note_names_date_range() {
+        assert_eq!(
+            compare_note(true, false),
+            Some("--compare is ignored with an explicit --since/--until date range")
+        );
+        assert_eq!(
+            compare_note(false, false),
+            Some("--compare needs a day, week or month period")
+        );
+        assert_eq!(compare_note(false, true), None);
+    }
+
+    #[test]
+    fn test_parse_issue_command() {
+        let cli = Cli::try_parse_from(["aist", "issue", "4", "-p", "/w/app"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Issue { number: 4, project: Some(p) } if p == Path::new("/w/app")
+        ));
+
+        assert!(Cli::try_parse_from(["aist", "issue"]).is_err());
     }
 }
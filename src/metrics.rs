@@ -1,16 +1,32 @@
-use crate::parser::{MessageType, Session};
-use chrono::{Duration, Utc};
-use std::collections::{HashMap, HashSet};
+use crate::bottlenecks;
+use crate::cost;
+use crate::exclusions;
+use crate::flamegraph::{self, ActivityType};
+use crate::parser::{Message, MessageType, Session};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Timelike, Utc};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 /// Metrics for a single session
 #[derive(Debug, Clone)]
 #[allow(dead_code)] // Fields will be used in later issues
 pub struct SessionMetrics {
     pub duration_minutes: f64,
+    /// Calls per tool that came back with an error result
+    pub tool_error_counts: HashMap<String, usize>,
+    /// Duration minus idle time
+    pub active_minutes: f64,
+    /// Pauses between messages longer than the flamegraph gap threshold
+    pub idle_minutes: f64,
     pub tool_counts: HashMap<String, usize>,
     pub total_tool_calls: usize,
+    /// Assistant turns that issued at least one tool call
+    pub tool_turns: usize,
     pub error_count: usize,
+    /// Errors with consecutive identical retries collapsed into one
+    pub distinct_error_count: usize,
+    /// User messages typed by the user, excluding hook injections
     pub user_messages: usize,
+    pub hook_injections: usize,
     pub assistant_messages: usize,
     pub files_read: HashSet<String>,
     pub files_edited: HashSet<String>,
@@ -23,6 +39,12 @@ pub struct ProjectMetrics {
     pub total_duration_minutes: f64,
     pub total_tool_calls: usize,
     pub total_errors: usize,
+    /// Input, output and cache tokens across the project's sessions
+    pub total_tokens: u64,
+    /// Tokens sent to the model, cached or not
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub total_cost: f64,
 }
 
 /// Aggregated metrics across multiple sessions
@@ -30,25 +52,49 @@ pub struct ProjectMetrics {
 pub struct AggregatedMetrics {
     pub session_count: usize,
     pub total_duration_minutes: f64,
+    pub total_active_minutes: f64,
+    /// Distribution of per-session durations; 0 without timed sessions
+    pub median_duration_minutes: f64,
+    pub p90_duration_minutes: f64,
+    /// Distinct local dates with a session start
+    pub active_days: usize,
+    /// Most consecutive active days
+    pub longest_streak_days: usize,
     pub total_tool_calls: usize,
+    pub tool_turns: usize,
     pub total_errors: usize,
+    pub total_distinct_errors: usize,
+    pub total_hook_injections: usize,
+    /// Tokens sent to the model, cached or not
+    pub total_input_tokens: u64,
+    pub total_output_tokens: u64,
+    pub total_cost: f64,
     pub tool_counts: HashMap<String, usize>,
+    pub tool_error_counts: HashMap<String, usize>,
     pub by_project: HashMap<String, ProjectMetrics>,
 }
 
 /// Calculate metrics for a single session
 pub fn calculate_session_metrics(session: &Session) -> SessionMetrics {
     let mut tool_counts: HashMap<String, usize> = HashMap::new();
+    let mut tool_error_counts: HashMap<String, usize> = HashMap::new();
     let mut total_tool_calls = 0;
     let mut error_count = 0;
     let mut user_messages = 0;
+    let mut hook_injections = 0;
     let mut assistant_messages = 0;
     let mut files_read: HashSet<String> = HashSet::new();
     let mut files_edited: HashSet<String> = HashSet::new();
+    let mut parallel_groups: HashSet<usize> = HashSet::new();
+    let mut distinct_error_count = 0;
+    let mut tool_names_by_id: HashMap<&str, &str> = HashMap::new();
+    // (tool, normalized content) of the previous result if it was an error
+    let mut last_error: Option<(&str, String)> = None;
 
     for message in &session.messages {
         // Count message types
         match message.msg_type {
+            _ if message.is_hook => hook_injections += 1,
             MessageType::User => user_messages += 1,
             MessageType::Assistant => assistant_messages += 1,
             _ => {}
@@ -58,6 +104,8 @@ pub fn calculate_session_metrics(session: &Session) -> SessionMetrics {
         for tool_call in &message.tool_calls {
             *tool_counts.entry(tool_call.name.clone()).or_insert(0) += 1;
             total_tool_calls += 1;
+            parallel_groups.insert(tool_call.parallel_group);
+            tool_names_by_id.insert(&tool_call.id, &tool_call.name);
 
             // Track files read
             if tool_call.name == "Read" {
@@ -74,51 +122,132 @@ pub fn calculate_session_metrics(session: &Session) -> SessionMetrics {
             }
         }
 
-        // Count errors
+        // Count errors, collapsing consecutive retries of the same failure
         for tool_result in &message.tool_results {
             if tool_result.is_error {
                 error_count += 1;
+
+                let tool = tool_names_by_id
+                    .get(tool_result.tool_use_id.as_str())
+                    .copied();
+                if let Some(tool) = tool {
+                    *tool_error_counts.entry(tool.to_string()).or_insert(0) += 1;
+                }
+                let tool = tool.unwrap_or("unknown");
+                let key = (tool, normalize_error(&tool_result.content));
+                if last_error.as_ref() != Some(&key) {
+                    distinct_error_count += 1;
+                }
+                last_error = Some(key);
+            } else {
+                last_error = None;
             }
         }
     }
 
     // Calculate duration
-    let duration_minutes = match (session.start_time, session.end_time) {
-        (Some(start), Some(end)) => (end - start).num_seconds() as f64 / 60.0,
-        _ => 0.0,
-    };
+    let duration_minutes = exclusions::active_minutes(session);
+    let idle_minutes = idle_minutes(session).min(duration_minutes);
 
     SessionMetrics {
         duration_minutes,
+        tool_error_counts,
+        active_minutes: duration_minutes - idle_minutes,
+        idle_minutes,
         tool_counts,
         total_tool_calls,
+        tool_turns: parallel_groups.len(),
         error_count,
+        distinct_error_count,
         user_messages,
+        hook_injections,
         assistant_messages,
         files_read,
         files_edited,
     }
 }
 
+/// Minutes spent in `Gap` spans (pauses of at least the long-gap floor),
+/// not counting time already excluded with `aist exclude`. Pauses spent
+/// waiting on a build or test run aren't gaps.
+fn idle_minutes(session: &Session) -> f64 {
+    flamegraph::extract_spans(session, bottlenecks::GAP_FLOOR_SECONDS)
+        .iter()
+        .filter(|span| span.activity == ActivityType::Gap)
+        .map(|span| {
+            let minutes = (span.end - span.start).num_seconds() as f64 / 60.0;
+            let excluded =
+                exclusions::excluded_minutes(&session.excluded_windows, span.start, span.end);
+            (minutes - excluded).max(0.0)
+        })
+        .sum()
+}
+
+/// Reduce an error message to a comparable form: first line, lowercased,
+/// with digits dropped so line numbers and PIDs don't make retries differ
+fn normalize_error(content: &str) -> String {
+    content
+        .lines()
+        .next()
+        .unwrap_or("")
+        .to_lowercase()
+        .chars()
+        .filter(|c| !c.is_ascii_digit())
+        .take(120)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
 /// Aggregate metrics across multiple sessions
 pub fn aggregate_metrics(sessions: &[Session]) -> AggregatedMetrics {
     let mut total_duration_minutes = 0.0;
+    let mut total_active_minutes = 0.0;
+    let mut durations: Vec<f64> = Vec::new();
+    let mut dates: BTreeSet<NaiveDate> = BTreeSet::new();
     let mut total_tool_calls = 0;
+    let mut tool_turns = 0;
     let mut total_errors = 0;
+    let mut total_distinct_errors = 0;
+    let mut total_hook_injections = 0;
+    let mut total_input_tokens = 0;
+    let mut total_output_tokens = 0;
+    let mut total_cost = 0.0;
     let mut tool_counts: HashMap<String, usize> = HashMap::new();
+    let mut tool_error_counts: HashMap<String, usize> = HashMap::new();
     let mut by_project: HashMap<String, ProjectMetrics> = HashMap::new();
 
     for session in sessions {
         let metrics = calculate_session_metrics(session);
 
         total_duration_minutes += metrics.duration_minutes;
+        total_active_minutes += metrics.active_minutes;
+        if session.start_time.is_some() && session.end_time.is_some() {
+            durations.push(metrics.duration_minutes);
+        }
+        if let Some(start) = session.start_time {
+            dates.insert(start.with_timezone(&Local).date_naive());
+        }
         total_tool_calls += metrics.total_tool_calls;
+        tool_turns += metrics.tool_turns;
         total_errors += metrics.error_count;
+        total_distinct_errors += metrics.distinct_error_count;
+        total_hook_injections += metrics.hook_injections;
+
+        let input_tokens: u64 = session.messages.iter().map(|m| m.token_input()).sum();
+        let output_tokens: u64 = session.messages.iter().map(|m| m.token_output()).sum();
+        let session_cost = cost::calculate_cost(session);
+        total_input_tokens += input_tokens;
+        total_output_tokens += output_tokens;
+        total_cost += session_cost;
 
         // Aggregate tool counts
         for (tool, count) in &metrics.tool_counts {
             *tool_counts.entry(tool.clone()).or_insert(0) += count;
         }
+        for (tool, count) in &metrics.tool_error_counts {
+            *tool_error_counts.entry(tool.clone()).or_insert(0) += count;
+        }
 
         // Aggregate by project
         let project_name = extract_project_name(&session.project);
@@ -127,27 +256,491 @@ pub fn aggregate_metrics(sessions: &[Session]) -> AggregatedMetrics {
         project_metrics.total_duration_minutes += metrics.duration_minutes;
         project_metrics.total_tool_calls += metrics.total_tool_calls;
         project_metrics.total_errors += metrics.error_count;
+        project_metrics.total_tokens += input_tokens + output_tokens;
+        project_metrics.input_tokens += input_tokens;
+        project_metrics.output_tokens += output_tokens;
+        project_metrics.total_cost += session_cost;
     }
 
     AggregatedMetrics {
         session_count: sessions.len(),
         total_duration_minutes,
+        total_active_minutes,
+        median_duration_minutes: percentile(&durations, 50.0),
+        p90_duration_minutes: percentile(&durations, 90.0),
+        active_days: dates.len(),
+        longest_streak_days: longest_streak(&dates),
         total_tool_calls,
+        tool_turns,
         total_errors,
+        total_distinct_errors,
+        total_hook_injections,
+        total_input_tokens,
+        total_output_tokens,
+        total_cost,
         tool_counts,
+        tool_error_counts,
         by_project,
     }
 }
 
-/// Filter sessions by time period
-#[allow(dead_code)] // Will be used in report command
+/// Longest run of consecutive calendar dates. Dates carry no time of day,
+/// so DST changes can't split or merge days.
+fn longest_streak(dates: &BTreeSet<NaiveDate>) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut prev: Option<NaiveDate> = None;
+    for &date in dates {
+        current = match prev {
+            Some(p) if p.succ_opt() == Some(date) => current + 1,
+            _ => 1,
+        };
+        longest = longest.max(current);
+        prev = Some(date);
+    }
+    longest
+}
+
+/// The `p`th percentile (0-100) of `values`, interpolating linearly between
+/// the closest ranks. 0.0 for no values.
+pub fn percentile(values: &[f64], p: f64) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let Some(&last) = sorted.last() else {
+        return 0.0;
+    };
+    let rank = (p / 100.0).clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    if upper >= sorted.len() {
+        return last;
+    }
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Active session minutes per local day of week (0 = Monday) and hour of
+/// day. A session is split across every hour it overlaps, so one running past
+/// midnight counts toward both days. Excluded windows and idle `Gap` spans
+/// don't count.
+pub fn build_hour_dow_histogram(sessions: &[Session]) -> [[f64; 24]; 7] {
+    hour_dow_histogram_in(sessions, &Local)
+}
+
+fn hour_dow_histogram_in<Tz: TimeZone>(sessions: &[Session], tz: &Tz) -> [[f64; 24]; 7] {
+    let mut grid = [[0.0; 24]; 7];
+    for session in sessions {
+        let (Some(start), Some(end)) = (session.start_time, session.end_time) else {
+            continue;
+        };
+        let unexcluded = |from: DateTime<Utc>, to: DateTime<Utc>| {
+            (to - from).num_seconds() as f64 / 60.0
+                - exclusions::excluded_minutes(&session.excluded_windows, from, to)
+        };
+        let gaps: Vec<(DateTime<Utc>, DateTime<Utc>)> =
+            flamegraph::extract_spans(session, bottlenecks::GAP_FLOOR_SECONDS)
+                .iter()
+                .filter(|span| span.activity == ActivityType::Gap)
+                .map(|span| (span.start, span.end))
+                .collect();
+        let mut t = start;
+        while t < end {
+            let local = t.with_timezone(tz);
+            let hour_start = local
+                .with_minute(0)
+                .and_then(|l| l.with_second(0))
+                .and_then(|l| l.with_nanosecond(0))
+                .unwrap_or_else(|| local.clone());
+            let next = (hour_start + Duration::hours(1))
+                .with_timezone(&Utc)
+                .min(end);
+            let idle: f64 = gaps
+                .iter()
+                .map(|&(from, to)| (from.max(t), to.min(next)))
+                .filter(|(from, to)| from < to)
+                .map(|(from, to)| unexcluded(from, to).max(0.0))
+                .sum();
+            let minutes = unexcluded(t, next) - idle;
+            let day = local.weekday().num_days_from_monday() as usize;
+            grid[day][local.hour() as usize] += minutes.max(0.0);
+            t = next;
+        }
+    }
+    grid
+}
+
+/// Tools used at least this often can be flagged as unreliable
+pub const UNRELIABLE_MIN_CALLS: usize = 5;
+
+/// Share of failed calls at which a tool is flagged as unreliable
+pub const UNRELIABLE_MIN_FAILURE_RATE: f64 = 0.2;
+
+/// How often one tool's calls failed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolReliability {
+    pub tool: String,
+    pub calls: usize,
+    pub failures: usize,
+}
+
+impl ToolReliability {
+    /// Share of calls that returned an error, 0.0-1.0
+    pub fn failure_rate(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.calls as f64
+        }
+    }
+}
+
+/// Call and failure counts of one tool
+pub fn tool_reliability(aggregated: &AggregatedMetrics, tool: &str) -> ToolReliability {
+    ToolReliability {
+        tool: tool.to_string(),
+        calls: aggregated.tool_counts.get(tool).copied().unwrap_or(0),
+        failures: aggregated.tool_error_counts.get(tool).copied().unwrap_or(0),
+    }
+}
+
+/// Tools with at least `UNRELIABLE_MIN_CALLS` calls failing at
+/// `UNRELIABLE_MIN_FAILURE_RATE` or more, worst first
+pub fn unreliable_tools(aggregated: &AggregatedMetrics) -> Vec<ToolReliability> {
+    let mut tools: Vec<ToolReliability> = aggregated
+        .tool_counts
+        .keys()
+        .map(|tool| tool_reliability(aggregated, tool))
+        .filter(|t| {
+            t.calls >= UNRELIABLE_MIN_CALLS && t.failure_rate() >= UNRELIABLE_MIN_FAILURE_RATE
+        })
+        .collect();
+    tools.sort_by(|a, b| {
+        b.failure_rate()
+            .partial_cmp(&a.failure_rate())
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| b.calls.cmp(&a.calls))
+            .then_with(|| a.tool.cmp(&b.tool))
+    });
+    tools
+}
+
+/// Average number of tool calls issued together per assistant turn
+pub fn tools_per_turn(tool_calls: usize, tool_turns: usize) -> f64 {
+    if tool_turns == 0 {
+        0.0
+    } else {
+        tool_calls as f64 / tool_turns as f64
+    }
+}
+
+/// Models and how many sessions used each, most used first
+pub fn model_session_counts(sessions: &[Session]) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for model in sessions.iter().flat_map(|s| &s.models_used) {
+        *counts.entry(model).or_insert(0) += 1;
+    }
+
+    let mut counts: Vec<(String, usize)> = counts
+        .into_iter()
+        .map(|(model, count)| (model.to_string(), count))
+        .collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// A single Write/Edit call whose content exceeds the bulk threshold
+#[derive(Debug, Clone)]
+pub struct BulkOverwrite {
+    pub session_id: String,
+    pub tool_name: String,
+    pub file_path: String,
+    pub lines: usize,
+    pub bytes: usize,
+}
+
+/// Detect Write/Edit calls whose new content exceeds `max_lines` lines or
+/// `max_bytes` bytes - usually the model regenerating a whole file instead
+/// of making a targeted edit
+pub fn detect_bulk_overwrites(
+    sessions: &[Session],
+    max_lines: usize,
+    max_bytes: usize,
+) -> Vec<BulkOverwrite> {
+    let mut overwrites = Vec::new();
+
+    for session in sessions {
+        for message in &session.messages {
+            for tool_call in &message.tool_calls {
+                let content_key = match tool_call.name.as_str() {
+                    "Write" => "content",
+                    "Edit" => "new_string",
+                    _ => continue,
+                };
+                let Some(content) = tool_call.input.get(content_key).and_then(|v| v.as_str())
+                else {
+                    continue;
+                };
+
+                let lines = content.lines().count();
+                let bytes = content.len();
+                if lines > max_lines || bytes > max_bytes {
+                    let file_path = tool_call
+                        .input
+                        .get("file_path")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("unknown")
+                        .to_string();
+                    overwrites.push(BulkOverwrite {
+                        session_id: session.session_id.clone(),
+                        tool_name: tool_call.name.clone(),
+                        file_path,
+                        lines,
+                        bytes,
+                    });
+                }
+            }
+        }
+    }
+
+    // Largest first
+    overwrites.sort_by_key(|o| std::cmp::Reverse(o.bytes));
+    overwrites
+}
+
+/// Minimum Write/Edit calls before a commit-less session counts as
+/// uncommitted work
+pub const UNCOMMITTED_MIN_EDITS: usize = 5;
+
+/// An edit-heavy session whose Bash history never ran `git commit`
+#[derive(Debug, Clone)]
+pub struct UncommittedSession {
+    pub session_id: String,
+    pub project: String,
+    pub edit_count: usize,
+}
+
+/// Whether a shell command line runs `git commit` (including `--amend`,
+/// `-am`, and `git -C <dir> commit`) in any of its chained segments
+pub fn is_git_commit(command: &str) -> bool {
+    command.split(['\n', ';', '|', '&']).any(|segment| {
+        let mut tokens = segment.split_whitespace();
+        while let Some(token) = tokens.next() {
+            if token != "git" {
+                continue;
+            }
+            // Skip global options between `git` and the subcommand
+            while let Some(arg) = tokens.next() {
+                match arg {
+                    "-C" | "-c" => {
+                        tokens.next();
+                    }
+                    a if a.starts_with('-') => {}
+                    a => return a == "commit",
+                }
+            }
+        }
+        false
+    })
+}
+
+/// (program, subcommand) pairs that compile or run tests; `None` matches
+/// the program alone
+const BUILD_TEST_COMMANDS: &[(&str, Option<&str>)] = &[
+    ("cargo", Some("build")),
+    ("cargo", Some("test")),
+    ("cargo", Some("check")),
+    ("cargo", Some("clippy")),
+    ("cargo", Some("bench")),
+    ("cargo", Some("nextest")),
+    ("npm", Some("test")),
+    ("npm", Some("run")),
+    ("npm", Some("ci")),
+    ("npm", Some("install")),
+    ("pnpm", Some("test")),
+    ("pnpm", Some("build")),
+    ("yarn", Some("test")),
+    ("yarn", Some("build")),
+    ("go", Some("build")),
+    ("go", Some("test")),
+    ("docker", Some("build")),
+    ("make", None),
+    ("mvn", None),
+    ("gradle", None),
+    ("./gradlew", None),
+    ("pytest", None),
+    ("tsc", None),
+    ("jest", None),
+    ("vitest", None),
+];
+
+/// (program, subcommand) pairs that run a test suite
+const TEST_COMMANDS: &[(&str, Option<&str>)] = &[
+    ("cargo", Some("test")),
+    ("cargo", Some("nextest")),
+    ("npm", Some("test")),
+    ("pnpm", Some("test")),
+    ("yarn", Some("test")),
+    ("go", Some("test")),
+    ("pytest", None),
+    ("jest", None),
+    ("vitest", None),
+];
+
+/// Whether a shell command line runs a build or test suite - the kind of
+/// command that can keep a session waiting for minutes
+pub fn is_build_or_test(command: &str) -> bool {
+    runs_any(command, BUILD_TEST_COMMANDS)
+}
+
+/// Whether a shell command line runs a test suite
+pub fn is_test_command(command: &str) -> bool {
+    runs_any(command, TEST_COMMANDS)
+}
+
+fn runs_any(command: &str, programs: &[(&str, Option<&str>)]) -> bool {
+    command.split(['\n', ';', '|', '&']).any(|segment| {
+        // Skip leading `VAR=value` assignments
+        let mut tokens = segment.split_whitespace().skip_while(|t| t.contains('='));
+        let Some(program) = tokens.next() else {
+            return false;
+        };
+        // Skip flags and toolchain overrides like `cargo +nightly`
+        let subcommand = tokens.find(|t| !t.starts_with(['-', '+']));
+        programs
+            .iter()
+            .any(|(p, sub)| *p == program && sub.is_none_or(|s| subcommand == Some(s)))
+    })
+}
+
+/// The first build/test command among a message's Bash calls, if any
+pub fn build_or_test_command(message: &Message) -> Option<&str> {
+    message
+        .tool_calls
+        .iter()
+        .filter(|tc| tc.name == "Bash")
+        .filter_map(|tc| tc.input.get("command").and_then(|v| v.as_str()))
+        .find(|cmd| is_build_or_test(cmd))
+}
+
+/// Find sessions with at least `min_edits` Write/Edit calls and no
+/// `git commit` in their Bash calls - likely abandoned or unfinished work
+pub fn detect_uncommitted_sessions(
+    sessions: &[Session],
+    min_edits: usize,
+) -> Vec<UncommittedSession> {
+    let mut uncommitted: Vec<UncommittedSession> = sessions
+        .iter()
+        .filter_map(|session| {
+            let calls = session.messages.iter().flat_map(|m| &m.tool_calls);
+
+            let committed = calls.clone().any(|tc| {
+                tc.name == "Bash"
+                    && tc
+                        .input
+                        .get("command")
+                        .and_then(|v| v.as_str())
+                        .is_some_and(is_git_commit)
+            });
+            if committed {
+                return None;
+            }
+
+            let edit_count = calls
+                .filter(|tc| matches!(tc.name.as_str(), "Edit" | "Write" | "NotebookEdit"))
+                .count();
+            (edit_count >= min_edits).then(|| UncommittedSession {
+                session_id: session.session_id.clone(),
+                project: extract_project_name(&session.project),
+                edit_count,
+            })
+        })
+        .collect();
+
+    // Most edits first
+    uncommitted.sort_by_key(|u| std::cmp::Reverse(u.edit_count));
+    uncommitted
+}
+
+/// Keep sessions that started at or after `cutoff`
+pub fn filter_since(sessions: Vec<Session>, cutoff: DateTime<Utc>) -> Vec<Session> {
+    sessions
+        .into_iter()
+        .filter(|s| s.start_time.is_some_and(|t| t >= cutoff))
+        .collect()
+}
+
+/// Parse a `--since`/`--until` date, `YYYY-MM-DD`
+pub fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{}': use YYYY-MM-DD", value))
+}
+
+/// Keep sessions whose local start date falls within `since..=until`.
+/// Either bound may be omitted.
+pub fn filter_by_date_range(
+    sessions: &[Session],
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+) -> Vec<Session> {
+    sessions
+        .iter()
+        .filter(|s| {
+            s.start_time.is_some_and(|t| {
+                let day = t.with_timezone(&Local).date_naive();
+                since.is_none_or(|d| day >= d) && until.is_none_or(|d| day <= d)
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// Label for an explicit date range, e.g. `2026-01-05 to 2026-01-18`
+pub fn date_range_label(since: Option<NaiveDate>, until: Option<NaiveDate>) -> String {
+    match (since, until) {
+        (Some(since), Some(until)) => format!("{} to {}", since, until),
+        (Some(since), None) => format!("since {}", since),
+        (None, Some(until)) => format!("until {}", until),
+        (None, None) => "all".to_string(),
+    }
+}
+
+/// Length of a report period: `day`, `week`, `month` (30 days), `quarter`
+/// (90 days), `year` (365 days), or a count with a
+/// unit suffix like `90m`, `6h`, `3d`, `2w`. None for `all` or anything
+/// unparseable, which callers treat as all time.
+pub fn period_length(period: &str) -> Option<Duration> {
+    let period = period.trim().to_lowercase();
+    match period.as_str() {
+        "day" => return Some(Duration::days(1)),
+        "week" => return Some(Duration::weeks(1)),
+        "month" => return Some(Duration::days(30)),
+        "quarter" => return Some(Duration::days(90)),
+        "year" => return Some(Duration::days(365)),
+        _ => {}
+    }
+
+    let unit = period.chars().last()?;
+    let count: i64 = period[..period.len() - unit.len_utf8()].parse().ok()?;
+    if count <= 0 {
+        return None;
+    }
+    match unit {
+        'm' => Some(Duration::minutes(count)),
+        'h' => Some(Duration::hours(count)),
+        'd' => Some(Duration::days(count)),
+        'w' => Some(Duration::weeks(count)),
+        _ => None,
+    }
+}
+
+/// Whether a period is `all` or something `period_length` understands
+pub fn is_valid_period(period: &str) -> bool {
+    period.eq_ignore_ascii_case("all") || period_length(period).is_some()
+}
+
+/// Filter sessions by time period, keeping those still running at the cutoff
 pub fn filter_by_period(sessions: &[Session], period: &str) -> Vec<Session> {
-    let now = Utc::now();
-    let cutoff = match period.to_lowercase().as_str() {
-        "day" => now - Duration::days(1),
-        "week" => now - Duration::weeks(1),
-        "month" => now - Duration::days(30),
-        _ => return sessions.to_vec(),
+    let cutoff = match period_length(period) {
+        Some(length) => Utc::now() - length,
+        None => return sessions.to_vec(),
     };
 
     sessions
@@ -157,6 +750,22 @@ pub fn filter_by_period(sessions: &[Session], period: &str) -> Vec<Session> {
         .collect()
 }
 
+/// Sessions from the period just before the current one (e.g. last week
+/// for `week`). Returns None for periods with no fixed length.
+pub fn filter_previous_period(sessions: &[Session], period: &str) -> Option<Vec<Session>> {
+    let length = period_length(period)?;
+    let end = Utc::now() - length;
+    let start = end - length;
+
+    Some(
+        sessions
+            .iter()
+            .filter(|s| s.end_time.is_some_and(|t| t >= start && t < end))
+            .cloned()
+            .collect(),
+    )
+}
+
 /// Extract a short project name from the full path
 fn extract_project_name(project_path: &str) -> String {
     project_path
@@ -177,6 +786,35 @@ pub fn format_duration(minutes: f64) -> String {
     }
 }
 
+/// Round shares of a whole to integer percentages that sum to exactly 100,
+/// using the largest-remainder method. Returns all zeros if the total is 0.
+pub fn round_percentages(values: &[f64]) -> Vec<usize> {
+    let total: f64 = values.iter().sum();
+    if total <= 0.0 {
+        return vec![0; values.len()];
+    }
+
+    let quotas: Vec<f64> = values.iter().map(|v| v / total * 100.0).collect();
+    let mut percents: Vec<usize> = quotas.iter().map(|q| q.floor() as usize).collect();
+
+    // Hand out the leftover points to the largest fractional parts
+    let assigned: usize = percents.iter().sum();
+    let mut by_remainder: Vec<usize> = (0..values.len()).collect();
+    by_remainder.sort_by(|&a, &b| {
+        let rem_a = quotas[a] - quotas[a].floor();
+        let rem_b = quotas[b] - quotas[b].floor();
+        rem_b
+            .partial_cmp(&rem_a)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.cmp(&b))
+    });
+    for &i in by_remainder.iter().take(100usize.saturating_sub(assigned)) {
+        percents[i] += 1;
+    }
+
+    percents
+}
+
 /// Format a number with thousands separators
 pub fn format_number(n: usize) -> String {
     let s = n.to_string();
@@ -190,10 +828,36 @@ pub fn format_number(n: usize) -> String {
     result.chars().rev().collect()
 }
 
+/// `text` cut to at most `max` characters, ending in "..." when shortened.
+/// Counts characters, not bytes, so it never splits a multi-byte one.
+pub fn truncate_chars(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max.saturating_sub(3)).collect();
+    format!("{}...", kept)
+}
+
+/// The first `len` characters of a session id, for compact display
+pub fn short_id(id: &str, len: usize) -> &str {
+    id.char_indices().nth(len).map_or(id, |(i, _)| &id[..i])
+}
+
+/// The end of `text`, at most `max` characters, starting with "..." when
+/// shortened
+pub fn tail_chars(text: &str, max: usize) -> String {
+    let count = text.chars().count();
+    if count <= max {
+        return text.to_string();
+    }
+    let kept: String = text.chars().skip(count - max.saturating_sub(3)).collect();
+    format!("...{}", kept)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parser::{Message, ToolCall, ToolResult};
+    use crate::parser::{Message, TokenUsage, ToolCall, ToolResult};
     use chrono::TimeZone;
     use std::path::PathBuf;
 
@@ -212,28 +876,30 @@ mod tests {
                 Message {
                     msg_type: MessageType::User,
                     timestamp: Some(start),
-                    tool_calls: vec![],
-                    tool_results: vec![],
+                    ..Default::default()
                 },
                 Message {
                     msg_type: MessageType::Assistant,
                     timestamp: Some(start),
                     tool_calls: vec![
                         ToolCall {
+                            id: "t1".to_string(),
                             name: "Read".to_string(),
                             input: serde_json::json!({"file_path": "/test/file.rs"}),
+                            parallel_group: 0,
                         },
                         ToolCall {
+                            id: "t2".to_string(),
                             name: "Edit".to_string(),
                             input: serde_json::json!({"file_path": "/test/file.rs"}),
+                            parallel_group: 0,
                         },
                     ],
-                    tool_results: vec![],
+                    ..Default::default()
                 },
                 Message {
                     msg_type: MessageType::User,
                     timestamp: Some(end),
-                    tool_calls: vec![],
                     tool_results: vec![
                         ToolResult {
                             tool_use_id: "1".to_string(),
@@ -246,8 +912,10 @@ mod tests {
                             is_error: true,
                         },
                     ],
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         }
     }
 
@@ -282,6 +950,193 @@ mod tests {
         assert_eq!(metrics.by_project.len(), 2);
     }
 
+    #[test]
+    fn test_aggregate_metrics_tokens_per_project() {
+        let mut session1 = create_test_session();
+        session1.messages[1].model = Some("claude-sonnet-4-5".to_string());
+        session1.messages[1].usage = Some(TokenUsage {
+            input_tokens: 1_000,
+            output_tokens: 200,
+            cache_write_tokens: 300,
+            cache_read_tokens: 500,
+        });
+        let mut session2 = session1.clone();
+        session2.messages[1].usage = Some(TokenUsage {
+            input_tokens: 4_000,
+            output_tokens: 1_000,
+            ..TokenUsage::default()
+        });
+        let mut other = create_test_session();
+        other.project = "/Users/test/projects/other-project".to_string();
+
+        let metrics = aggregate_metrics(&[session1, session2, other]);
+
+        let project = &metrics.by_project["my-project"];
+        assert_eq!(project.input_tokens, 5_800);
+        assert_eq!(project.output_tokens, 1_200);
+        assert_eq!(project.total_tokens, 7_000);
+        assert!(project.total_cost > 0.0);
+
+        let other = &metrics.by_project["other-project"];
+        assert_eq!((other.input_tokens, other.output_tokens), (0, 0));
+        assert_eq!(other.total_cost, 0.0);
+
+        assert_eq!(metrics.total_input_tokens, 5_800);
+        assert_eq!(metrics.total_output_tokens, 1_200);
+        assert!((metrics.total_cost - project.total_cost).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_active_and_idle_minutes_sum_to_duration() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 13, 10, 0, 0).unwrap();
+        let message = |minutes: i64| Message {
+            msg_type: MessageType::Assistant,
+            timestamp: Some(start + Duration::minutes(minutes)),
+            text_content: Some("working".to_string()),
+            ..Default::default()
+        };
+        let mut session = create_test_session();
+        session.start_time = Some(start);
+        session.end_time = Some(start + Duration::minutes(60));
+        // One 40-minute pause between 10:15 and 10:55; the rest is steady
+        session.messages = (0..=15).chain(55..=60).map(message).collect();
+
+        let metrics = calculate_session_metrics(&session);
+
+        assert_eq!(metrics.duration_minutes, 60.0);
+        assert_eq!(metrics.idle_minutes, 40.0);
+        assert_eq!(metrics.active_minutes, 20.0);
+        assert_eq!(
+            metrics.active_minutes + metrics.idle_minutes,
+            metrics.duration_minutes
+        );
+    }
+
+    #[test]
+    fn test_tool_failure_rate() {
+        let call = |id: &str, name: &str| ToolCall {
+            id: id.to_string(),
+            name: name.to_string(),
+            input: serde_json::Value::Null,
+            parallel_group: 0,
+        };
+        let result = |id: &str, is_error: bool| ToolResult {
+            tool_use_id: id.to_string(),
+            content: format!("exit {}", id),
+            is_error,
+        };
+        let mut session = create_test_session();
+        session.messages[1].tool_calls = (0..10)
+            .map(|i| call(&format!("b{}", i), "Bash"))
+            .chain((0..5).map(|i| call(&format!("r{}", i), "Read")))
+            .collect();
+        session.messages[1].tool_results = (0..10)
+            .map(|i| result(&format!("b{}", i), i < 3))
+            .chain((0..5).map(|i| result(&format!("r{}", i), false)))
+            .collect();
+
+        let aggregated = aggregate_metrics(&[session]);
+
+        let bash = tool_reliability(&aggregated, "Bash");
+        assert_eq!((bash.calls, bash.failures), (10, 3));
+        assert!((bash.failure_rate() - 0.3).abs() < 1e-9);
+        assert_eq!(tool_reliability(&aggregated, "Read").failure_rate(), 0.0);
+
+        let unreliable = unreliable_tools(&aggregated);
+        assert_eq!(unreliable.len(), 1);
+        assert_eq!(unreliable[0].tool, "Bash");
+    }
+
+    #[test]
+    fn test_median_and_p90_duration() {
+        let durations = [5.0, 10.0, 15.0, 20.0, 25.0, 30.0, 35.0, 40.0, 45.0, 100.0];
+        let sessions: Vec<Session> = durations
+            .iter()
+            .map(|&minutes| {
+                let mut session = create_test_session();
+                let start = session.start_time.unwrap();
+                session.end_time = Some(start + Duration::minutes(minutes as i64));
+                session
+            })
+            .collect();
+
+        let metrics = aggregate_metrics(&sessions);
+        assert_eq!(metrics.median_duration_minutes, 27.5);
+        assert!((metrics.p90_duration_minutes - 50.5).abs() < 1e-9);
+
+        let single = aggregate_metrics(&sessions[9..]);
+        assert_eq!(single.median_duration_minutes, 100.0);
+        assert_eq!(single.p90_duration_minutes, 100.0);
+
+        let empty = aggregate_metrics(&[]);
+        assert_eq!(empty.median_duration_minutes, 0.0);
+        assert_eq!(empty.p90_duration_minutes, 0.0);
+    }
+
+    #[test]
+    fn test_hour_dow_histogram_splits_across_midnight() {
+        let mut session = create_test_session();
+        // Sunday 2026-01-11 23:20 to Monday 00:50 UTC
+        session.start_time = Some(Utc.with_ymd_and_hms(2026, 1, 11, 23, 20, 0).unwrap());
+        session.end_time = Some(Utc.with_ymd_and_hms(2026, 1, 12, 0, 50, 0).unwrap());
+
+        let grid = hour_dow_histogram_in(&[session], &Utc);
+
+        assert_eq!(grid[6][23], 40.0);
+        assert_eq!(grid[0][0], 50.0);
+        let total: f64 = grid.iter().flatten().sum();
+        assert_eq!(total, 90.0);
+    }
+
+    #[test]
+    fn test_hour_dow_histogram_skips_idle_gaps() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 13, 10, 0, 0).unwrap();
+        let at = |minutes| Message {
+            msg_type: MessageType::User,
+            timestamp: Some(start + Duration::minutes(minutes)),
+            ..Default::default()
+        };
+        // Busy 10:00-10:20, away until 11:40, busy again until 12:00
+        let mut messages: Vec<Message> = (0..=20).map(at).collect();
+        messages.extend((100..=120).map(at));
+        let session = Session {
+            start_time: Some(start),
+            end_time: Some(start + Duration::minutes(120)),
+            messages,
+            ..Default::default()
+        };
+
+        let grid = hour_dow_histogram_in(&[session], &Utc);
+
+        assert_eq!(grid[1][10], 20.0);
+        assert_eq!(grid[1][11], 20.0);
+        let total: f64 = grid.iter().flatten().sum();
+        assert_eq!(total, 40.0);
+    }
+
+    #[test]
+    fn test_longest_streak_broken_by_gap() {
+        let dates = |days: &[u32]| -> BTreeSet<NaiveDate> {
+            days.iter()
+                .map(|&d| NaiveDate::from_ymd_opt(2026, 3, d).unwrap())
+                .collect()
+        };
+        // 1-3, then the 4th off, then 5-8; the DST switch on the 8th changes nothing
+        assert_eq!(longest_streak(&dates(&[1, 2, 3, 5, 6, 7, 8])), 4);
+        assert_eq!(longest_streak(&dates(&[10])), 1);
+        assert_eq!(longest_streak(&BTreeSet::new()), 0);
+
+        let session_on = |day: u32| {
+            let mut session = create_test_session();
+            session.start_time = Some(Utc.with_ymd_and_hms(2026, 3, day, 12, 0, 0).unwrap());
+            session
+        };
+        let sessions: Vec<Session> = [1, 1, 2, 4].into_iter().map(session_on).collect();
+        let metrics = aggregate_metrics(&sessions);
+        assert_eq!(metrics.active_days, 3);
+        assert_eq!(metrics.longest_streak_days, 2);
+    }
+
     #[test]
     fn test_extract_project_name() {
         assert_eq!(
@@ -309,6 +1164,320 @@ mod tests {
         assert_eq!(format_number(1234567), "1,234,567");
     }
 
+    #[test]
+    fn test_truncate_chars_non_ascii() {
+        assert_eq!(truncate_chars("short", 20), "short");
+        assert_eq!(
+            truncate_chars("Añadir exportación → CSV y más", 20),
+            "Añadir exportació..."
+        );
+        assert_eq!(truncate_chars("日本語のタイトル", 5), "日本...");
+    }
+
+    #[test]
+    fn test_short_id_non_ascii() {
+        assert_eq!(short_id("abc", 8), "abc");
+        assert_eq!(short_id("0123456789abcdef", 8), "01234567");
+        assert_eq!(short_id("añadir-exportación", 8), "añadir-e");
+        assert_eq!(short_id("日本語のセッション", 3), "日本語");
+    }
+
+    #[test]
+    fn test_tail_chars_non_ascii() {
+        assert_eq!(tail_chars("src/main.rs", 20), "src/main.rs");
+        assert_eq!(
+            tail_chars("/w/app/src/módulos/configuración_ñ.rs", 20),
+            "...onfiguración_ñ.rs"
+        );
+    }
+
+    #[test]
+    fn test_round_percentages_sum_to_100() {
+        let percents = round_percentages(&[1.0, 1.0, 1.0]);
+        assert_eq!(percents, vec![34, 33, 33]);
+        assert_eq!(percents.iter().sum::<usize>(), 100);
+
+        let percents = round_percentages(&[55.5, 30.3, 14.2]);
+        assert_eq!(percents.iter().sum::<usize>(), 100);
+        assert_eq!(percents, vec![56, 30, 14]);
+
+        assert_eq!(round_percentages(&[0.0, 0.0]), vec![0, 0]);
+    }
+
+    #[test]
+    fn test_distinct_errors_collapse_retries() {
+        let mut session = create_test_session();
+        let start = session.start_time.unwrap();
+        for i in 0..3 {
+            let id = format!("b{}", i);
+            session.messages.push(Message {
+                msg_type: MessageType::Assistant,
+                timestamp: Some(start),
+                tool_calls: vec![ToolCall {
+                    id: id.clone(),
+                    name: "Bash".to_string(),
+                    input: serde_json::json!({"command": "cargo build"}),
+                    parallel_group: 10 + i,
+                }],
+                ..Default::default()
+            });
+            session.messages.push(Message {
+                msg_type: MessageType::User,
+                timestamp: Some(start),
+                tool_results: vec![ToolResult {
+                    tool_use_id: id,
+                    content: format!("error: linker failed at line {}", 40 + i),
+                    is_error: true,
+                }],
+                ..Default::default()
+            });
+        }
+
+        let metrics = calculate_session_metrics(&session);
+
+        // The fixture's own failing result plus three identical retries
+        assert_eq!(metrics.error_count, 4);
+        assert_eq!(metrics.distinct_error_count, 2);
+    }
+
+    #[test]
+    fn test_parallel_tool_calls_per_turn() {
+        let mut session = create_test_session();
+        let read = |id: &str| ToolCall {
+            id: id.to_string(),
+            name: "Read".to_string(),
+            input: serde_json::json!({"file_path": format!("/test/{}.rs", id)}),
+            parallel_group: 3,
+        };
+        session.messages.push(Message {
+            msg_type: MessageType::Assistant,
+            timestamp: session.end_time,
+            tool_calls: vec![read("p1"), read("p2"), read("p3")],
+            ..Default::default()
+        });
+
+        let metrics = calculate_session_metrics(&session);
+
+        // One turn with two calls plus one turn with three parallel calls
+        assert_eq!(metrics.total_tool_calls, 5);
+        assert_eq!(metrics.tool_turns, 2);
+        assert_eq!(
+            tools_per_turn(metrics.total_tool_calls, metrics.tool_turns),
+            2.5
+        );
+        assert_eq!(tools_per_turn(0, 0), 0.0);
+    }
+
+    #[test]
+    fn test_detect_bulk_overwrites() {
+        let mut session = create_test_session();
+        let big_content = "fn line() {}\n".repeat(400);
+        session.messages.push(Message {
+msg_type: MessageType::Assistant,
+timestamp: session.end_time,
+tool_calls: vec![
+                ToolCall {
+                    id: "t3".to_string(),
+                    name: "Write".to_string(),
+                    input: serde_json::json!({"file_path": "/test/big.rs", "content": big_content}),
+                    parallel_group: 0,
+                },
+                ToolCall {
+                    id: "t4".to_string(),
+                    name: "Write".to_string(),
+                    input: serde_json::json!({"file_path": "/test/small.rs", "content": "fn main() {}"}),
+                    parallel_group: 0,
+                },
+            ],
+..Default::default()
+});
+
+        let overwrites = detect_bulk_overwrites(&[session], 300, 20_000);
+
+        assert_eq!(overwrites.len(), 1);
+        assert_eq!(overwrites[0].file_path, "/test/big.rs");
+        assert_eq!(overwrites[0].lines, 400);
+        assert_eq!(overwrites[0].tool_name, "Write");
+    }
+
+    fn bash_call(id: &str, command: &str) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            name: "Bash".to_string(),
+            input: serde_json::json!({ "command": command }),
+            parallel_group: 0,
+        }
+    }
+
+    fn edit_heavy_session(id: &str, bash_commands: &[&str]) -> Session {
+        let mut session = create_test_session();
+        session.session_id = id.to_string();
+        let mut tool_calls: Vec<ToolCall> = (0..6)
+            .map(|i| ToolCall {
+                id: format!("e{}", i),
+                name: "Edit".to_string(),
+                input: serde_json::json!({"file_path": "/test/file.rs"}),
+                parallel_group: 0,
+            })
+            .collect();
+        tool_calls.extend(
+            bash_commands
+                .iter()
+                .enumerate()
+                .map(|(i, c)| bash_call(&format!("b{}", i), c)),
+        );
+        session.messages.push(Message {
+            msg_type: MessageType::Assistant,
+            timestamp: session.end_time,
+            text_content: None,
+            tool_calls,
+            tool_results: vec![],
+            model: None,
+            usage: None,
+            is_hook: false,
+        });
+        session
+    }
+
+    #[test]
+    fn test_is_git_commit_variants() {
+        assert!(is_git_commit("git commit -m 'fix'"));
+        assert!(is_git_commit("git add -A && git commit --amend --no-edit"));
+        assert!(is_git_commit("git -C /repo commit -am wip"));
+        assert!(!is_git_commit("git status"));
+        assert!(!is_git_commit("git log --grep commit"));
+        assert!(!is_git_commit("echo commit"));
+    }
+
+    #[test]
+    fn test_is_build_or_test() {
+        assert!(is_build_or_test("cargo build --release"));
+        assert!(is_build_or_test("cd web && npm test"));
+        assert!(is_build_or_test("RUST_LOG=debug cargo +nightly test"));
+        assert!(is_build_or_test("make -j8"));
+        assert!(!is_build_or_test("cargo fmt"));
+        assert!(!is_build_or_test("ls -la"));
+
+        assert!(is_test_command("cd api && python -m venv .v; pytest -x"));
+        assert!(is_test_command("go test ./..."));
+        assert!(!is_test_command("cargo build"));
+    }
+
+    #[test]
+    fn test_detect_uncommitted_sessions() {
+        let abandoned = edit_heavy_session("abandoned", &["cargo test", "git status"]);
+        let committed = edit_heavy_session("committed", &["git add -A && git commit -m done"]);
+        let light = create_test_session();
+
+        let flagged =
+            detect_uncommitted_sessions(&[abandoned, committed, light], UNCOMMITTED_MIN_EDITS);
+
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].session_id, "abandoned");
+        assert_eq!(flagged[0].edit_count, 7);
+    }
+
+    #[test]
+    fn test_filter_by_date_range_is_inclusive() {
+        let session_on = |id: &str, day: u32| {
+            let mut s = create_test_session();
+            s.session_id = id.to_string();
+            // Local noon, so the local date is `day` in every timezone
+            let noon = Local.with_ymd_and_hms(2026, 1, day, 12, 0, 0).unwrap();
+            s.start_time = Some(noon.with_timezone(&Utc));
+            s
+        };
+        let sessions = vec![
+            session_on("before", 4),
+            session_on("first", 5),
+            session_on("middle", 10),
+            session_on("last", 18),
+            session_on("after", 19),
+        ];
+        let date = |day| NaiveDate::from_ymd_opt(2026, 1, day);
+
+        let sprint = filter_by_date_range(&sessions, date(5), date(18));
+        let ids: Vec<_> = sprint.iter().map(|s| s.session_id.as_str()).collect();
+        assert_eq!(ids, ["first", "middle", "last"]);
+
+        assert_eq!(filter_by_date_range(&sessions, date(18), None).len(), 2);
+        assert_eq!(filter_by_date_range(&sessions, None, date(4)).len(), 1);
+    }
+
+    #[test]
+    fn test_filter_by_date_range_empty() {
+        let sessions = vec![create_test_session()];
+        let date = |day| NaiveDate::from_ymd_opt(2025, 6, day);
+
+        assert!(filter_by_date_range(&sessions, date(1), date(30)).is_empty());
+        // A reversed range matches nothing rather than everything
+        assert!(filter_by_date_range(&sessions, date(30), date(1)).is_empty());
+        assert!(parse_date("2026-13-01").is_err());
+    }
+
+    #[test]
+    fn test_filter_since_commit_date() {
+        // Stands in for the date `git show -s --format=%cI v1.0` resolves to
+        let release = Utc.with_ymd_and_hms(2026, 1, 13, 10, 30, 0).unwrap();
+
+        let before = create_test_session();
+        let mut after = create_test_session();
+        after.session_id = "after".to_string();
+        after.start_time = Some(release + Duration::hours(2));
+
+        let filtered = filter_since(vec![before, after], release);
+
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].session_id, "after");
+    }
+
+    #[test]
+    fn test_period_length_suffixed_spans() {
+        assert_eq!(period_length("3d"), Some(Duration::days(3)));
+        assert_eq!(period_length("2w"), Some(Duration::weeks(2)));
+        assert_eq!(period_length("6h"), Some(Duration::hours(6)));
+        assert_eq!(period_length("90m"), Some(Duration::minutes(90)));
+        assert_eq!(period_length("week"), Some(Duration::weeks(1)));
+    }
+
+    #[test]
+    fn test_period_length_invalid_falls_back_to_all() {
+        for period in ["all", "", "d", "0d", "-3d", "3x", "three days"] {
+            assert_eq!(period_length(period), None, "{:?}", period);
+        }
+        assert!(is_valid_period("all"));
+        assert!(!is_valid_period("3x"));
+
+        // Unparseable periods keep every session, like `all`
+        let sessions = vec![create_test_session()];
+        assert_eq!(filter_by_period(&sessions, "3x").len(), 1);
+    }
+
+    #[test]
+    fn test_quarter_and_year_cutoffs() {
+        assert_eq!(period_length("quarter"), Some(Duration::days(90)));
+        assert_eq!(period_length("year"), Some(Duration::days(365)));
+
+        let ended_days_ago = |days: i64| {
+            let mut s = create_test_session();
+            s.session_id = format!("{}d", days);
+            s.end_time = Some(Utc::now() - Duration::days(days));
+            s.start_time = s.end_time.map(|t| t - Duration::hours(1));
+            s
+        };
+        let sessions: Vec<_> = [20, 60, 200, 400].map(ended_days_ago).into();
+        let ids = |period| {
+            filter_by_period(&sessions, period)
+                .into_iter()
+                .map(|s| s.session_id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids("month"), ["20d"]);
+        assert_eq!(ids("quarter"), ["20d", "60d"]);
+        assert_eq!(ids("year"), ["20d", "60d", "200d"]);
+    }
+
     #[test]
     fn test_filter_by_period_all() {
         let sessions = vec![create_test_session()];
@@ -1,48 +1,127 @@
 use chrono::{DateTime, Utc};
+use rayon::prelude::*;
 use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
 use walkdir::WalkDir;
 
 /// A parsed Claude Code session
 #[derive(Debug, Clone)]
+#[cfg_attr(test, derive(Default))]
 #[allow(dead_code)] // Fields will be used in later issues
 pub struct Session {
     pub session_id: String,
     pub project: String,
     pub jsonl_path: PathBuf,
+    /// Branch the session was last on
     pub git_branch: Option<String>,
     pub start_time: Option<DateTime<Utc>>,
     pub end_time: Option<DateTime<Utc>>,
     pub messages: Vec<Message>,
+    /// Distinct models that produced assistant messages, in first-seen order
+    pub models_used: Vec<String>,
+    /// Meeting/away windows marked with `aist exclude`, not counted as work
+    pub excluded_windows: Vec<crate::exclusions::ExcludedWindow>,
+    /// Claude Code auto-compacted the session, leaving a summary message
+    pub was_compacted: bool,
+    /// Non-empty lines in the transcript file
+    pub line_count: usize,
+    /// Lines that weren't valid JSON for the transcript format
+    pub skipped_lines: usize,
+    /// Every branch the session was on, in the order first seen
+    pub git_branches: Vec<String>,
+}
+
+impl Session {
+    /// Every branch the session was on, including `git_branch`
+    pub fn branches(&self) -> Vec<&str> {
+        let mut branches: Vec<&str> = self.git_branches.iter().map(String::as_str).collect();
+        if let Some(last) = self.git_branch.as_deref() {
+            if !branches.contains(&last) {
+                branches.push(last);
+            }
+        }
+        branches
+    }
+
+    /// Whether the session was on `branch` at any point
+    pub fn on_branch(&self, branch: &str) -> bool {
+        self.branches().contains(&branch)
+    }
 }
 
 /// A message in a session
 #[derive(Debug, Clone)]
+#[cfg_attr(test, derive(Default))]
 #[allow(dead_code)] // Fields will be used in later issues
 pub struct Message {
     pub msg_type: MessageType,
     pub timestamp: Option<DateTime<Utc>>,
+    /// Plain text typed by the user or written by the assistant, if any
+    pub text_content: Option<String>,
     pub tool_calls: Vec<ToolCall>,
     pub tool_results: Vec<ToolResult>,
+    /// Model that produced an assistant message
+    pub model: Option<String>,
+    /// Token usage reported for an assistant message
+    pub usage: Option<TokenUsage>,
+    /// Injected by a Claude Code hook (lint/test output, stop feedback)
+    /// rather than typed by the user
+    pub is_hook: bool,
+}
+
+impl Message {
+    /// Tokens this turn sent to the model, cached or not (0 without usage)
+    pub fn token_input(&self) -> u64 {
+        self.usage
+            .map(|u| u.input_tokens + u.cache_write_tokens + u.cache_read_tokens)
+            .unwrap_or(0)
+    }
+
+    /// Tokens this turn generated (0 without usage)
+    pub fn token_output(&self) -> u64 {
+        self.usage.map(|u| u.output_tokens).unwrap_or(0)
+    }
+}
+
+/// Token counts billed for one assistant message
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct TokenUsage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default, rename = "cache_creation_input_tokens")]
+    pub cache_write_tokens: u64,
+    #[serde(default, rename = "cache_read_input_tokens")]
+    pub cache_read_tokens: u64,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Default))]
 pub enum MessageType {
     User,
     Assistant,
     System,
     Summary,
     FileHistorySnapshot,
+    #[cfg_attr(test, default)]
     Unknown,
 }
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)] // Fields will be used in later issues
 pub struct ToolCall {
+    pub id: String,
     pub name: String,
     pub input: serde_json::Value,
+    /// Index of the assistant turn that issued this call. Calls sharing a
+    /// group were emitted together and ran concurrently.
+    pub parallel_group: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -64,12 +143,79 @@ struct RawMessage {
     #[serde(rename = "gitBranch")]
     git_branch: Option<String>,
     cwd: Option<String>,
+    /// Set on system entries, e.g. `stop_hook_summary`
+    subtype: Option<String>,
     message: Option<RawMessageContent>,
 }
 
 #[derive(Debug, Deserialize)]
 struct RawMessageContent {
+    id: Option<String>,
     content: Option<serde_json::Value>,
+    model: Option<String>,
+    usage: Option<TokenUsage>,
+}
+
+/// Glob from `--transcripts`; when set it replaces the walk of the Claude
+/// projects directory
+static TRANSCRIPTS_GLOB: OnceLock<String> = OnceLock::new();
+
+/// Read transcripts from files matching `pattern` instead of
+/// `~/.claude/projects`. Fails on an invalid pattern.
+pub fn set_transcripts_glob(pattern: &str) -> Result<(), String> {
+    glob::Pattern::new(pattern).map_err(|e| format!("Invalid --transcripts glob: {}", e))?;
+    TRANSCRIPTS_GLOB
+        .set(pattern.to_string())
+        .map_err(|_| "Transcripts glob already set".to_string())
+}
+
+/// Set by `--anonymize-projects`; loaded sessions get pseudonymous projects
+static ANONYMIZE_PROJECTS: AtomicBool = AtomicBool::new(false);
+
+/// Replace project names with pseudonyms in every session loaded from now on
+pub fn set_anonymize_projects(enabled: bool) {
+    ANONYMIZE_PROJECTS.store(enabled, Ordering::Relaxed);
+}
+
+/// Cleared by `--no-mask`; loaded sessions have secrets replaced with `[secret]`
+static MASK_SECRETS: AtomicBool = AtomicBool::new(true);
+
+/// Mask secrets in every session loaded from now on
+pub fn set_mask_secrets(enabled: bool) {
+    MASK_SECRETS.store(enabled, Ordering::Relaxed);
+}
+
+/// Set by `--strict`; loading warns about transcripts with many bad lines
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// Warn on stderr about corrupt transcripts in every load from now on
+pub fn set_strict(enabled: bool) {
+    STRICT.store(enabled, Ordering::Relaxed);
+}
+
+/// Share of a transcript's lines that may be malformed before `--strict`
+/// warns about it
+const MAX_SKIPPED_SHARE: f64 = 0.05;
+
+/// Whether too many of a session's lines failed to parse
+fn has_many_skipped_lines(session: &Session) -> bool {
+    session.line_count > 0
+        && session.skipped_lines as f64 / session.line_count as f64 > MAX_SKIPPED_SHARE
+}
+
+/// Find JSONL transcript files matching a glob pattern
+fn find_files_by_glob(pattern: &str) -> Vec<PathBuf> {
+    let paths = match glob::glob(pattern) {
+        Ok(paths) => paths,
+        Err(_) => return vec![],
+    };
+
+    let mut files: Vec<PathBuf> = paths
+        .filter_map(|p| p.ok())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "jsonl"))
+        .collect();
+    files.sort();
+    files
 }
 
 /// Get the Claude projects directory
@@ -77,16 +223,31 @@ fn claude_projects_dir() -> Option<PathBuf> {
     dirs::home_dir().map(|h| h.join(".claude").join("projects"))
 }
 
-/// Find all session JSONL files
-fn find_session_files(filter_project: Option<&Path>) -> Vec<PathBuf> {
-    let projects_dir = match claude_projects_dir() {
-        Some(dir) if dir.exists() => dir,
-        _ => return vec![],
-    };
+/// Find all session JSONL files. Subagent transcripts are skipped unless
+/// `include_subagents` is set.
+fn find_session_files(filter_project: Option<&Path>, include_subagents: bool) -> Vec<PathBuf> {
+    if let Some(pattern) = TRANSCRIPTS_GLOB.get() {
+        return find_files_by_glob(pattern)
+            .into_iter()
+            .filter(|path| matches_project_filter(path, filter_project))
+            .collect();
+    }
 
+    match claude_projects_dir() {
+        Some(dir) if dir.exists() => walk_projects_dir(&dir, filter_project, include_subagents),
+        _ => vec![],
+    }
+}
+
+/// Collect transcripts under a Claude projects directory
+fn walk_projects_dir(
+    projects_dir: &Path,
+    filter_project: Option<&Path>,
+    include_subagents: bool,
+) -> Vec<PathBuf> {
     let mut files = vec![];
 
-    for entry in WalkDir::new(&projects_dir)
+    for entry in WalkDir::new(projects_dir)
         .into_iter()
         .filter_map(|e| e.ok())
     {
@@ -97,22 +258,14 @@ fn find_session_files(filter_project: Option<&Path>) -> Vec<PathBuf> {
             continue;
         }
 
-        // Skip subagent files
-        if path.to_string_lossy().contains("/subagents/") {
+        // Skip subagent files unless asked for
+        if !include_subagents && is_subagent_transcript(path) {
             continue;
         }
 
         // Apply project filter if specified
-        if let Some(filter) = filter_project {
-            let filter_str = filter.to_string_lossy();
-            let path_str = path.to_string_lossy();
-
-            // The project path is encoded in the directory name
-            // e.g., ~/.claude/projects/-Users-rj-personal-projects-ai-editor/
-            let encoded_filter = filter_str.replace('/', "-");
-            if !path_str.contains(&encoded_filter) && !path_str.contains(&*filter_str) {
-                continue;
-            }
+        if !matches_project_filter(path, filter_project) {
+            continue;
         }
 
         files.push(path.to_path_buf());
@@ -121,6 +274,244 @@ fn find_session_files(filter_project: Option<&Path>) -> Vec<PathBuf> {
     files
 }
 
+/// Whether a transcript was written by a subagent, i.e. lives under
+/// `<project>/<parent session>/subagents/`
+fn is_subagent_transcript(path: &Path) -> bool {
+    path.to_string_lossy().contains("/subagents/")
+}
+
+/// Session ID of the parent that spawned a subagent transcript
+fn subagent_parent_id(path: &Path) -> Option<String> {
+    let subagents_dir = path.parent()?;
+    if subagents_dir.file_name()? != "subagents" {
+        return None;
+    }
+    let parent = subagents_dir.parent()?.file_name()?;
+    Some(parent.to_string_lossy().to_string())
+}
+
+/// Give subagent sessions the project and branch of the session that spawned
+/// them, so their time is counted in the same place
+fn inherit_from_parents(sessions: &mut [Session]) {
+    let parents: HashMap<String, (String, Option<String>)> = sessions
+        .iter()
+        .filter(|s| !is_subagent_transcript(&s.jsonl_path))
+        .filter_map(|s| {
+            let id = s.jsonl_path.file_stem()?.to_string_lossy().to_string();
+            Some((id, (s.project.clone(), s.git_branch.clone())))
+        })
+        .collect();
+
+    for session in sessions.iter_mut() {
+        let Some(parent_id) = subagent_parent_id(&session.jsonl_path) else {
+            continue;
+        };
+        if let Some((project, branch)) = parents.get(&parent_id) {
+            session.project = project.clone();
+            if let Some(branch) = branch {
+                if !session.git_branches.contains(branch) {
+                    session.git_branches.push(branch.clone());
+                }
+                session.git_branch = Some(branch.clone());
+            }
+        }
+    }
+}
+
+/// Whether a transcript path belongs to the filtered project, if any
+fn matches_project_filter(path: &Path, filter_project: Option<&Path>) -> bool {
+    let Some(filter) = filter_project else {
+        return true;
+    };
+    let filter_str = filter.to_string_lossy();
+    let path_str = path.to_string_lossy();
+
+    // The project path is encoded in the directory name
+    // e.g., ~/.claude/projects/-Users-rj-personal-projects-ai-editor/
+    let encoded_filter = filter_str.replace('/', "-");
+    path_str.contains(&encoded_filter) || path_str.contains(&*filter_str)
+}
+
+/// A transcript format aist can read sessions from
+pub trait SessionSource {
+    /// Whether a transcript's first JSON line looks like this format
+    fn detect(&self, first_line: &serde_json::Value) -> bool;
+    /// Parse a whole transcript file into a session
+    fn parse(&self, path: &Path) -> Option<Session>;
+}
+
+/// Claude Code's `~/.claude/projects` JSONL transcripts
+pub struct ClaudeCodeSource;
+
+impl SessionSource for ClaudeCodeSource {
+    fn detect(&self, first_line: &serde_json::Value) -> bool {
+        first_line.get("type").is_some()
+            && (first_line.get("sessionId").is_some() || first_line.get("message").is_some())
+    }
+
+    fn parse(&self, path: &Path) -> Option<Session> {
+        parse_session_file(path)
+    }
+}
+
+/// Tool-agnostic JSONL for other agents (Cursor, Windsurf, ...), one event
+/// per line:
+///
+/// - `role`: `user`, `assistant`, or `system` (required)
+/// - `timestamp`: RFC 3339
+/// - `session`: session id (defaults to the file name)
+/// - `cwd`: project directory
+/// - `text`: prompt or reply text
+/// - `tool` / `input`: a tool call and its JSON arguments
+/// - `error`: true when the event reports a failed tool call: its own
+///   `tool`, or else the latest one before it
+pub struct GenericJsonlSource;
+
+#[derive(Debug, Deserialize)]
+struct GenericEvent {
+    role: String,
+    timestamp: Option<String>,
+    session: Option<String>,
+    cwd: Option<String>,
+    text: Option<String>,
+    tool: Option<String>,
+    #[serde(default)]
+    input: serde_json::Value,
+    #[serde(default)]
+    error: bool,
+}
+
+impl SessionSource for GenericJsonlSource {
+    fn detect(&self, first_line: &serde_json::Value) -> bool {
+        first_line.get("role").is_some_and(|r| r.is_string()) && first_line.get("type").is_none()
+    }
+
+    fn parse(&self, path: &Path) -> Option<Session> {
+        let reader = BufReader::new(File::open(path).ok()?);
+        let mut session_id = None;
+        let mut project = None;
+        let mut messages: Vec<Message> = Vec::new();
+        let mut line_count = 0;
+        let mut skipped_lines = 0;
+        let mut last_call_id: Option<String> = None;
+
+        for line in reader.lines().map_while(Result::ok) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            line_count += 1;
+            let Ok(event) = serde_json::from_str::<GenericEvent>(&line) else {
+                skipped_lines += 1;
+                continue;
+            };
+            session_id = session_id.or(event.session);
+            project = project.or(event.cwd);
+
+            let timestamp = event.timestamp.as_deref().and_then(|ts| {
+                DateTime::parse_from_rfc3339(ts)
+                    .ok()
+                    .map(|dt| dt.with_timezone(&Utc))
+            });
+            let id = format!("g{}", messages.len());
+            if event.tool.is_some() {
+                last_call_id = Some(id.clone());
+            }
+            let tool_calls = event
+                .tool
+                .map(|name| ToolCall {
+                    id: id.clone(),
+                    name,
+                    input: event.input,
+                    parallel_group: messages.len(),
+                })
+                .into_iter()
+                .collect();
+            let tool_results = if event.error {
+                vec![ToolResult {
+                    tool_use_id: last_call_id.clone().unwrap_or(id),
+                    content: event.text.clone().unwrap_or_default(),
+                    is_error: true,
+                }]
+            } else {
+                vec![]
+            };
+
+            messages.push(Message {
+                msg_type: match event.role.as_str() {
+                    "user" => MessageType::User,
+                    "assistant" => MessageType::Assistant,
+                    "system" => MessageType::System,
+                    _ => MessageType::Unknown,
+                },
+                timestamp,
+                text_content: event.text.filter(|t| !t.trim().is_empty()),
+                tool_calls,
+                tool_results,
+                model: None,
+                usage: None,
+                is_hook: false,
+            });
+        }
+
+        if messages.is_empty() {
+            return None;
+        }
+
+        let timestamps = messages.iter().filter_map(|m| m.timestamp);
+        Some(Session {
+            session_id: session_id.unwrap_or_else(|| {
+                path.file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "unknown".to_string())
+            }),
+            project: project.unwrap_or_else(|| {
+                path.parent()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            }),
+            jsonl_path: path.to_path_buf(),
+            git_branch: None,
+            start_time: timestamps.clone().min(),
+            end_time: timestamps.max(),
+            messages,
+            models_used: vec![],
+            excluded_windows: vec![],
+            was_compacted: false,
+            line_count,
+            skipped_lines,
+            git_branches: vec![],
+        })
+    }
+}
+
+/// Every supported format, in detection order. Claude Code is the fallback
+/// when nothing matches.
+const SOURCES: &[&dyn SessionSource] = &[&ClaudeCodeSource, &GenericJsonlSource];
+
+/// Pick the source for a transcript from its first non-empty line
+pub fn detect_source(path: &Path) -> &'static dyn SessionSource {
+    let first_line = File::open(path).ok().and_then(|file| {
+        BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .find(|l| !l.trim().is_empty())
+    });
+    let Some(value) = first_line.and_then(|l| serde_json::from_str(&l).ok()) else {
+        return &ClaudeCodeSource;
+    };
+
+    SOURCES
+        .iter()
+        .copied()
+        .find(|s| s.detect(&value))
+        .unwrap_or(&ClaudeCodeSource)
+}
+
+/// Parse a transcript with whichever source recognizes its format
+pub fn parse_transcript(path: &Path) -> Option<Session> {
+    detect_source(path).parse(path)
+}
+
 /// Parse a single JSONL file into a Session
 fn parse_session_file(path: &Path) -> Option<Session> {
     let file = File::open(path).ok()?;
@@ -129,8 +520,13 @@ fn parse_session_file(path: &Path) -> Option<Session> {
     let mut session_id = String::new();
     let mut project = String::new();
     let mut git_branch = None;
-    let mut messages = vec![];
+    let mut git_branches: Vec<String> = vec![];
+    let mut messages: Vec<Message> = vec![];
     let mut timestamps: Vec<DateTime<Utc>> = vec![];
+    // message id -> index in `messages`, for coalescing streamed turns
+    let mut message_index: HashMap<String, usize> = HashMap::new();
+    let mut line_count = 0;
+    let mut skipped_lines = 0;
 
     for line in reader.lines() {
         let line = match line {
@@ -141,10 +537,15 @@ fn parse_session_file(path: &Path) -> Option<Session> {
         if line.trim().is_empty() {
             continue;
         }
+        line_count += 1;
 
         let raw: RawMessage = match serde_json::from_str(&line) {
             Ok(r) => r,
-            Err(_) => continue, // Skip malformed lines
+            Err(_) => {
+                // Skip malformed lines, but count them for --strict
+                skipped_lines += 1;
+                continue;
+            }
         };
 
         // Extract session metadata from first valid message
@@ -158,8 +559,11 @@ fn parse_session_file(path: &Path) -> Option<Session> {
                 project = cwd.clone();
             }
         }
-        if git_branch.is_none() {
-            git_branch = raw.git_branch.clone();
+        if let Some(branch) = &raw.git_branch {
+            if !git_branches.contains(branch) {
+                git_branches.push(branch.clone());
+            }
+            git_branch = Some(branch.clone());
         }
 
         // Parse timestamp
@@ -169,10 +573,6 @@ fn parse_session_file(path: &Path) -> Option<Session> {
                 .map(|dt| dt.with_timezone(&Utc))
         });
 
-        if let Some(ts) = timestamp {
-            timestamps.push(ts);
-        }
-
         // Parse message type
         let msg_type = match raw.msg_type.as_deref() {
             Some("user") => MessageType::User,
@@ -183,14 +583,60 @@ fn parse_session_file(path: &Path) -> Option<Session> {
             _ => MessageType::Unknown,
         };
 
+        // Compaction summaries are stamped when written, which can be long
+        // after the work they summarize; drop the stamp so they neither bound
+        // the session nor open a gap before the next message
+        let timestamp = timestamp.filter(|_| msg_type != MessageType::Summary);
+        if let Some(ts) = timestamp {
+            timestamps.push(ts);
+        }
+
         // Parse tool calls and results from message content
-        let (tool_calls, tool_results) = parse_message_content(&raw.message);
+        let (mut tool_calls, tool_results) = parse_message_content(&raw.message);
+        let text_content = parse_text_content(&raw.message);
+        // Only assistant turns are produced by a model
+        let model = if msg_type == MessageType::Assistant {
+            raw.message.as_ref().and_then(|m| m.model.clone())
+        } else {
+            None
+        };
+        let usage = raw.message.as_ref().and_then(|m| m.usage);
+        let is_hook = is_hook_injection(raw.subtype.as_deref(), text_content.as_deref());
+
+        // Streamed turns are split across several lines sharing a message id;
+        // merge them into the first line's message so one turn is one Message
+        if let Some(id) = raw.message.as_ref().and_then(|m| m.id.as_ref()) {
+            if let Some(&idx) = message_index.get(id) {
+                for tool_call in &mut tool_calls {
+                    tool_call.parallel_group = idx;
+                }
+                let merged = &mut messages[idx];
+                merged.text_content = match (merged.text_content.take(), text_content) {
+                    (Some(a), Some(b)) => Some(format!("{}\n{}", a, b)),
+                    (a, b) => a.or(b),
+                };
+                merged.tool_calls.extend(tool_calls);
+                merged.tool_results.extend(tool_results);
+                // Each streamed line repeats the turn's usage; keep the latest
+                merged.usage = usage.or(merged.usage);
+                merged.model = model.or(merged.model.take());
+                continue;
+            }
+            message_index.insert(id.clone(), messages.len());
+        }
 
+        for tool_call in &mut tool_calls {
+            tool_call.parallel_group = messages.len();
+        }
         messages.push(Message {
             msg_type,
             timestamp,
+            text_content,
             tool_calls,
             tool_results,
+            model,
+            usage,
+            is_hook,
         });
     }
 
@@ -204,8 +650,14 @@ fn parse_session_file(path: &Path) -> Option<Session> {
 
     // Extract project from path if not found in messages
     if project.is_empty() {
-        // Path like: ~/.claude/projects/-Users-rj-personal-projects-ai-editor/abc.jsonl
-        if let Some(parent) = path.parent() {
+        // Path like: ~/.claude/projects/-Users-rj-personal-projects-ai-editor/abc.jsonl,
+        // or .../abc/subagents/agent-1.jsonl for a subagent
+        let project_dir = if subagent_parent_id(path).is_some() {
+            path.parent().and_then(Path::parent).and_then(Path::parent)
+        } else {
+            path.parent()
+        };
+        if let Some(parent) = project_dir {
             let dir_name = parent.file_name().unwrap_or_default().to_string_lossy();
             // Decode the path: -Users-rj-... -> /Users/rj/...
             project = dir_name.replace('-', "/");
@@ -219,6 +671,14 @@ fn parse_session_file(path: &Path) -> Option<Session> {
 
     let start_time = timestamps.iter().min().cloned();
     let end_time = timestamps.iter().max().cloned();
+    let was_compacted = messages.iter().any(|m| m.msg_type == MessageType::Summary);
+
+    let mut models_used: Vec<String> = Vec::new();
+    for model in messages.iter().filter_map(|m| m.model.as_ref()) {
+        if !models_used.contains(model) {
+            models_used.push(model.clone());
+        }
+    }
 
     Some(Session {
         session_id,
@@ -228,6 +688,56 @@ fn parse_session_file(path: &Path) -> Option<Session> {
         start_time,
         end_time,
         messages,
+        models_used,
+        excluded_windows: vec![],
+        was_compacted,
+        line_count,
+        skipped_lines,
+        git_branches,
+    })
+}
+
+/// Extract the plain text of a message: either a bare string or the `text`
+/// items of a content array. Tool calls and results are not text.
+fn parse_text_content(content: &Option<RawMessageContent>) -> Option<String> {
+    let text = match content.as_ref()?.content.as_ref()? {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Array(items) => items
+            .iter()
+            .filter(|item| item.get("type").and_then(|v| v.as_str()) == Some("text"))
+            .filter_map(|item| item.get("text").and_then(|v| v.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => return None,
+    };
+
+    if text.trim().is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// Text that Claude Code puts at the start of messages it injects on a
+/// hook's behalf
+const HOOK_TEXT_PREFIXES: &[&str] = &[
+    "<user-prompt-submit-hook>",
+    "Stop hook feedback:",
+    "SubagentStop hook feedback:",
+    "PreToolUse:",
+    "PostToolUse:",
+    "UserPromptSubmit hook",
+    "SessionStart hook",
+];
+
+/// Whether a transcript entry was injected by a hook rather than typed
+fn is_hook_injection(subtype: Option<&str>, text: Option<&str>) -> bool {
+    if subtype.is_some_and(|s| s.contains("hook")) {
+        return true;
+    }
+    text.is_some_and(|t| {
+        let t = t.trim_start();
+        HOOK_TEXT_PREFIXES.iter().any(|p| t.starts_with(p))
     })
 }
 
@@ -252,13 +762,23 @@ fn parse_message_content(content: &Option<RawMessageContent>) -> (Vec<ToolCall>,
 
             match item_type {
                 Some("tool_use") => {
+                    let id = obj
+                        .get("id")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .to_string();
                     let name = obj
                         .get("name")
                         .and_then(|v| v.as_str())
                         .unwrap_or("unknown")
                         .to_string();
                     let input = obj.get("input").cloned().unwrap_or(serde_json::Value::Null);
-                    tool_calls.push(ToolCall { name, input });
+                    tool_calls.push(ToolCall {
+                        id,
+                        name,
+                        input,
+                        parallel_group: 0,
+                    });
                 }
                 Some("tool_result") => {
                     let tool_use_id = obj
@@ -290,16 +810,219 @@ fn parse_message_content(content: &Option<RawMessageContent>) -> (Vec<ToolCall>,
     (tool_calls, tool_results)
 }
 
-/// Load all sessions, optionally filtered by project
-pub fn load_sessions(filter_project: Option<&Path>) -> Vec<Session> {
-    let files = find_session_files(filter_project);
+/// Load all sessions, optionally filtered by project. Subagent transcripts
+/// are included only with `include_subagents`.
+pub fn load_sessions(filter_project: Option<&Path>, include_subagents: bool) -> Vec<Session> {
+    let files = find_session_files(filter_project, include_subagents);
+    let mut sessions = parse_transcripts(&files);
+    if STRICT.load(Ordering::Relaxed) {
+        let mut corrupt: Vec<&Session> = sessions
+            .iter()
+            .filter(|s| has_many_skipped_lines(s))
+            .collect();
+        corrupt.sort_by(|a, b| a.jsonl_path.cmp(&b.jsonl_path));
+        for session in corrupt {
+            eprintln!(
+                "Warning: skipped {} of {} lines in {} (malformed JSON)",
+                session.skipped_lines,
+                session.line_count,
+                session.jsonl_path.display()
+            );
+        }
+    }
+    if include_subagents {
+        inherit_from_parents(&mut sessions);
+    }
+
+    crate::exclusions::apply_exclusions(&mut sessions, &crate::exclusions::load_exclusions());
+
+    if ANONYMIZE_PROJECTS.load(Ordering::Relaxed) {
+        anonymize_projects(&mut sessions);
+    }
+    if MASK_SECRETS.load(Ordering::Relaxed) {
+        sessions
+            .par_iter_mut()
+            .for_each(crate::redact::mask_session);
+    }
+    sessions
+}
 
+/// Parse transcript files in parallel, dropping any that fail to parse.
+/// The order of the result is unspecified; callers sort as they need.
+fn parse_transcripts(files: &[PathBuf]) -> Vec<Session> {
     files
-        .iter()
-        .filter_map(|path| parse_session_file(path))
+        .par_iter()
+        .filter_map(|path| parse_transcript(path))
         .collect()
 }
 
+/// Letter suffix for the n-th pseudonym: a..z, then aa, ab, ...
+fn pseudonym(mut n: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push((b'a' + (n % 26) as u8) as char);
+        if n < 26 {
+            break;
+        }
+        n = n / 26 - 1;
+    }
+    format!("project-{}", letters.iter().rev().collect::<String>())
+}
+
+/// Replace each distinct project with a stable pseudonym ("project-a",
+/// "project-b", ...). Letters are assigned in hash order rather than name
+/// order, so the mapping is deterministic without hinting at the real names.
+pub fn anonymize_projects(sessions: &mut [Session]) {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+
+    let hash = |project: &str| {
+        let mut hasher = DefaultHasher::new();
+        project.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    let mut projects: Vec<&str> = sessions
+        .iter()
+        .map(|s| s.project.as_str())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    projects.sort_by_key(|p| (hash(p), *p));
+
+    let names: HashMap<String, String> = projects
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (p.to_string(), pseudonym(i)))
+        .collect();
+
+    for session in sessions.iter_mut() {
+        if let Some(name) = names.get(&session.project) {
+            // Tool inputs name files under the real project dir too
+            for call in session.messages.iter_mut().flat_map(|m| &mut m.tool_calls) {
+                anonymize_json(&mut call.input, &session.project, name);
+            }
+            session.project = name.clone();
+        }
+    }
+}
+
+/// Swap the `project` dir for `name` in every string of a tool input, at
+/// path component boundaries only
+fn anonymize_json(value: &mut serde_json::Value, project: &str, name: &str) {
+    match value {
+        serde_json::Value::String(s) if s == project => *s = name.to_string(),
+        serde_json::Value::String(s) => {
+            let dir = format!("{}/", project);
+            if s.contains(&dir) {
+                *s = s.replace(&dir, &format!("{}/", name));
+            }
+        }
+        serde_json::Value::Array(items) => items
+            .iter_mut()
+            .for_each(|v| anonymize_json(v, project, name)),
+        serde_json::Value::Object(map) => map
+            .values_mut()
+            .for_each(|v| anonymize_json(v, project, name)),
+        _ => {}
+    }
+}
+
+/// A session is scripted/automated when none of its user messages carry a
+/// text prompt, e.g. sessions started by hooks or scripts. Hook-injected
+/// messages don't count as prompts.
+pub fn is_scripted(session: &Session) -> bool {
+    !session
+        .messages
+        .iter()
+        .any(|m| m.msg_type == MessageType::User && m.text_content.is_some() && !m.is_hook)
+}
+
+/// Name of the file in the aist config dir that adds to or overrides the
+/// default tool aliases, as a JSON object of `"variant": "Canonical"`
+const TOOL_ALIASES_FILE: &str = "tool-aliases.json";
+
+/// Built-in tool variant -> canonical name table (keys are lowercase)
+pub fn default_tool_aliases() -> HashMap<String, String> {
+    [
+        ("bash", "Bash"),
+        ("bashoutput", "Bash"),
+        ("killbash", "Bash"),
+        ("killshell", "Bash"),
+        ("multiedit", "Edit"),
+        ("read", "Read"),
+        ("write", "Write"),
+        ("edit", "Edit"),
+        ("grep", "Grep"),
+        ("glob", "Glob"),
+    ]
+    .iter()
+    .map(|(variant, canonical)| (variant.to_string(), canonical.to_string()))
+    .collect()
+}
+
+/// Default tool aliases merged with any user overrides from the config dir
+pub fn load_tool_aliases() -> HashMap<String, String> {
+    let mut aliases = default_tool_aliases();
+    let path = crate::state::config_dir().join(TOOL_ALIASES_FILE);
+    if let Ok(content) = std::fs::read_to_string(path) {
+        if let Ok(overrides) = serde_json::from_str::<HashMap<String, String>>(&content) {
+            aliases.extend(overrides.into_iter().map(|(k, v)| (k.to_lowercase(), v)));
+        }
+    }
+    aliases
+}
+
+/// Canonical name for a tool: MCP server prefixes (`mcp__server__tool`) are
+/// stripped, then the alias table is consulted case-insensitively
+pub fn canonical_tool_name(name: &str, aliases: &HashMap<String, String>) -> String {
+    let base = match name.strip_prefix("mcp__") {
+        Some(rest) => rest.split_once("__").map(|(_, tool)| tool).unwrap_or(rest),
+        None => name,
+    };
+    aliases
+        .get(&base.to_lowercase())
+        .cloned()
+        .unwrap_or_else(|| base.to_string())
+}
+
+/// Rewrite every tool call name to its canonical form so variants are
+/// counted and classified together
+pub fn normalize_tool_names(sessions: &mut [Session], aliases: &HashMap<String, String>) {
+    for session in sessions {
+        for message in &mut session.messages {
+            for tool_call in &mut message.tool_calls {
+                tool_call.name = canonical_tool_name(&tool_call.name, aliases);
+            }
+        }
+    }
+}
+
+/// Drop calls to the given tools, and their results, as if they were never
+/// invoked. Only tool counts and classification change: message timestamps
+/// are kept, so session durations are unaffected.
+pub fn exclude_tools(sessions: &mut [Session], tools: &[String]) {
+    if tools.is_empty() {
+        return;
+    }
+
+    for session in sessions {
+        let mut excluded_ids: HashSet<String> = HashSet::new();
+        for message in &mut session.messages {
+            message.tool_calls.retain(|tc| {
+                if tools.contains(&tc.name) {
+                    excluded_ids.insert(tc.id.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+            message
+                .tool_results
+                .retain(|tr| !excluded_ids.contains(&tr.tool_use_id));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -315,10 +1038,484 @@ mod tests {
         );
     }
 
+    fn write_fixture(name: &str, lines: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join("aist-parser-tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.jsonl", name));
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_session_tokens_sum_per_message_usage() {
+        let path = write_fixture(
+            "per-turn-usage",
+            &[
+                r#"{"type":"user","timestamp":"2026-01-13T10:00:00Z","sessionId":"u1","message":{"role":"user","content":"fix it"}}"#,
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:00:05Z","sessionId":"u1","message":{"id":"msg_1","role":"assistant","content":"looking","usage":{"input_tokens":1200,"output_tokens":300,"cache_read_input_tokens":40000}}}"#,
+                r#"{"type":"user","timestamp":"2026-01-13T10:01:00Z","sessionId":"u1","message":{"role":"user","content":"now test"}}"#,
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:01:05Z","sessionId":"u1","message":{"id":"msg_2","role":"assistant","content":"done","usage":{"input_tokens":800,"output_tokens":150,"cache_creation_input_tokens":2000}}}"#,
+            ],
+        );
+
+        let session = parse_session_file(&path).unwrap();
+        let per_turn: Vec<(u64, u64)> = session
+            .messages
+            .iter()
+            .map(|m| (m.token_input(), m.token_output()))
+            .collect();
+
+        assert_eq!(per_turn, [(0, 0), (41_200, 300), (0, 0), (2_800, 150)]);
+        let sum: u64 = per_turn.iter().map(|(i, o)| i + o).sum();
+        assert_eq!(crate::cost::session_tokens(&session), sum);
+    }
+
+    #[test]
+    fn test_branch_switch_records_every_branch() {
+        let path = write_fixture(
+            "branch-switch",
+            &[
+                r#"{"type":"user","timestamp":"2026-01-13T10:00:00Z","sessionId":"b1","gitBranch":"main","message":{"role":"user","content":"start a fix"}}"#,
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:01:00Z","sessionId":"b1","gitBranch":"fix/login","message":{"role":"assistant","content":"switched"}}"#,
+                r#"{"type":"user","timestamp":"2026-01-13T10:02:00Z","sessionId":"b1","gitBranch":"main","message":{"role":"user","content":"back"}}"#,
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:03:00Z","sessionId":"b1","gitBranch":"fix/login","message":{"role":"assistant","content":"again"}}"#,
+            ],
+        );
+
+        let session = parse_session_file(&path).unwrap();
+
+        assert_eq!(session.git_branches, ["main", "fix/login"]);
+        assert_eq!(session.git_branch.as_deref(), Some("fix/login"));
+        assert!(session.on_branch("main"));
+        assert!(!session.on_branch("develop"));
+    }
+
+    #[test]
+    fn test_malformed_lines_are_counted() {
+        let path = write_fixture(
+            "garbage",
+            &[
+                r#"{"type":"user","timestamp":"2026-01-13T10:00:00Z","sessionId":"m1","message":{"role":"user","content":"hi"}}"#,
+                "{not json",
+                "",
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:01:00Z","sessionId":"m1","message":{"role":"assistant","content":"hello"}}"#,
+                "garbage garbage",
+                r#"{"type":"user","timestamp":"#,
+            ],
+        );
+
+        let session = parse_session_file(&path).unwrap();
+
+        assert_eq!(session.skipped_lines, 3);
+        assert_eq!(session.line_count, 5);
+        assert!(has_many_skipped_lines(&session));
+    }
+
+    #[test]
+    fn test_summary_timestamp_does_not_extend_session() {
+        let path = write_fixture(
+            "compacted",
+            &[
+                r#"{"type":"user","timestamp":"2026-01-13T10:00:00Z","sessionId":"c1","message":{"role":"user","content":"fix the bug"}}"#,
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:20:00Z","sessionId":"c1","message":{"role":"assistant","content":"done"}}"#,
+                r#"{"type":"summary","timestamp":"2026-01-14T09:00:00Z","summary":"Fixed the bug"}"#,
+            ],
+        );
+
+        let session = parse_session_file(&path).unwrap();
+
+        assert!(session.was_compacted);
+        assert_eq!(session.messages.len(), 3);
+        let minutes = (session.end_time.unwrap() - session.start_time.unwrap()).num_minutes();
+        assert_eq!(minutes, 20);
+        assert!(session.messages[2].timestamp.is_none());
+    }
+
+    #[test]
+    fn test_compacted_session_has_no_summary_gap() {
+        let path = write_fixture(
+            "compacted-mid",
+            &[
+                r#"{"type":"user","timestamp":"2026-01-13T10:00:00Z","sessionId":"c2","message":{"role":"user","content":"fix the bug"}}"#,
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:01:00Z","sessionId":"c2","message":{"role":"assistant","content":"looking"}}"#,
+                r#"{"type":"summary","timestamp":"2026-01-20T09:00:00Z","summary":"Investigating the bug"}"#,
+                r#"{"type":"user","timestamp":"2026-01-13T10:02:00Z","sessionId":"c2","message":{"role":"user","content":"continue"}}"#,
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:03:00Z","sessionId":"c2","message":{"role":"assistant","content":"done"}}"#,
+            ],
+        );
+
+        let session = parse_session_file(&path).unwrap();
+        let bottlenecks = crate::bottlenecks::detect_all(std::slice::from_ref(&session));
+
+        assert!(!bottlenecks
+            .iter()
+            .any(|b| matches!(b, crate::bottlenecks::Bottleneck::LongGap(_))));
+        let efficiency = crate::bottlenecks::session_efficiency(&session, &bottlenecks);
+        assert_eq!(efficiency, 100.0);
+    }
+
+    #[test]
+    fn test_streamed_assistant_lines_coalesce() {
+        let path = write_fixture(
+            "streamed",
+            &[
+                r#"{"type":"user","timestamp":"2026-01-13T10:00:00Z","sessionId":"s1","message":{"role":"user","content":"fix the bug"}}"#,
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:00:05Z","sessionId":"s1","message":{"id":"msg_1","role":"assistant","content":[{"type":"tool_use","id":"t1","name":"Read","input":{}}]}}"#,
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:00:06Z","sessionId":"s1","message":{"id":"msg_1","role":"assistant","content":[{"type":"tool_use","id":"t2","name":"Grep","input":{}}]}}"#,
+            ],
+        );
+
+        let session = parse_session_file(&path).unwrap();
+        let assistant: Vec<_> = session
+            .messages
+            .iter()
+            .filter(|m| m.msg_type == MessageType::Assistant)
+            .collect();
+
+        assert_eq!(session.messages.len(), 2);
+        assert_eq!(assistant.len(), 1);
+        let names: Vec<&str> = assistant[0]
+            .tool_calls
+            .iter()
+            .map(|tc| tc.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Read", "Grep"]);
+        // Both halves of the streamed turn ran in parallel
+        assert!(assistant[0]
+            .tool_calls
+            .iter()
+            .all(|tc| tc.parallel_group == 1));
+    }
+
+    #[test]
+    fn test_source_dispatch_by_format() {
+        let claude = write_fixture(
+            "dispatch-claude",
+            &[
+                r#"{"type":"user","timestamp":"2026-01-13T10:00:00Z","sessionId":"c1","cwd":"/w/app","message":{"content":"fix it"}}"#,
+            ],
+        );
+        let generic = write_fixture(
+            "dispatch-generic",
+            &[
+                r#"{"role":"user","timestamp":"2026-01-13T10:00:00Z","session":"g1","cwd":"/w/other","text":"add a test"}"#,
+                r#"{"role":"assistant","timestamp":"2026-01-13T10:01:00Z","tool":"Edit","input":{"file_path":"/w/other/a.rs"}}"#,
+                r#"{"role":"user","timestamp":"2026-01-13T10:02:00Z","text":"build failed","error":true}"#,
+            ],
+        );
+
+        let first_line = |line: &str| serde_json::from_str::<serde_json::Value>(line).unwrap();
+        assert!(ClaudeCodeSource.detect(&first_line(r#"{"type":"user","sessionId":"c1"}"#)));
+        assert!(!GenericJsonlSource.detect(&first_line(r#"{"type":"user","sessionId":"c1"}"#)));
+        assert!(GenericJsonlSource.detect(&first_line(r#"{"role":"user"}"#)));
+
+        let claude = parse_transcript(&claude).unwrap();
+        assert_eq!(claude.session_id, "c1");
+        assert_eq!(claude.project, "/w/app");
+        assert_eq!(claude.messages[0].text_content.as_deref(), Some("fix it"));
+
+        let generic = parse_transcript(&generic).unwrap();
+        assert_eq!(generic.session_id, "g1");
+        assert_eq!(generic.project, "/w/other");
+        assert_eq!(generic.messages.len(), 3);
+        assert_eq!(generic.messages[1].msg_type, MessageType::Assistant);
+        assert_eq!(generic.messages[1].tool_calls[0].name, "Edit");
+        assert!(generic.messages[2].tool_results[0].is_error);
+        assert_eq!(
+            generic.messages[2].tool_results[0].tool_use_id,
+            generic.messages[1].tool_calls[0].id
+        );
+        assert_eq!(
+            generic.end_time.unwrap() - generic.start_time.unwrap(),
+            chrono::Duration::minutes(2)
+        );
+    }
+
+    #[test]
+    fn test_parallel_parse_matches_sequential() {
+        let mut files: Vec<PathBuf> = (0..12)
+            .map(|i| {
+                let line = format!(
+                    r#"{{"type":"user","timestamp":"2026-01-13T10:{:02}:00Z","sessionId":"par{}","message":{{"content":"task {}"}}}}"#,
+                    i, i, i
+                );
+                write_fixture(&format!("parallel-{}", i), &[&line])
+            })
+            .collect();
+        // Files that fail to open are still dropped
+        files.push(PathBuf::from("/nonexistent/aist/missing.jsonl"));
+
+        let ids = |sessions: Vec<Session>| -> HashSet<String> {
+            sessions.into_iter().map(|s| s.session_id).collect()
+        };
+        let sequential = ids(files.iter().filter_map(|p| parse_transcript(p)).collect());
+        let parallel = ids(parse_transcripts(&files));
+
+        assert_eq!(parallel.len(), 12);
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn test_models_used_deduped_per_session() {
+        let path = write_fixture(
+            "models",
+            &[
+                r#"{"type":"user","timestamp":"2026-01-13T10:00:00Z","sessionId":"s6","message":{"role":"user","content":"hi","model":"ignored"}}"#,
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:00:05Z","sessionId":"s6","message":{"id":"m1","model":"claude-opus-4-5","content":[{"type":"text","text":"Hello"}]}}"#,
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:00:09Z","sessionId":"s6","message":{"id":"m2","model":"claude-opus-4-5","content":[{"type":"text","text":"Again"}]}}"#,
+            ],
+        );
+
+        let session = parse_session_file(&path).unwrap();
+
+        assert_eq!(session.models_used, vec!["claude-opus-4-5".to_string()]);
+        assert_eq!(session.messages[0].model, None);
+        assert_eq!(
+            session.messages[1].model.as_deref(),
+            Some("claude-opus-4-5")
+        );
+    }
+
+    #[test]
+    fn test_usage_keeps_latest_streamed_line() {
+        let path = write_fixture(
+            "usage",
+            &[
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:00:05Z","sessionId":"s5","message":{"id":"msg_1","model":"claude-sonnet-4-5","usage":{"input_tokens":10,"output_tokens":1},"content":[{"type":"text","text":"Let me look"}]}}"#,
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:00:06Z","sessionId":"s5","message":{"id":"msg_1","model":"claude-sonnet-4-5","usage":{"input_tokens":10,"output_tokens":250,"cache_read_input_tokens":4000},"content":[{"type":"tool_use","id":"t1","name":"Read","input":{}}]}}"#,
+            ],
+        );
+
+        let session = parse_session_file(&path).unwrap();
+
+        assert_eq!(session.messages.len(), 1);
+        assert_eq!(
+            session.messages[0].model.as_deref(),
+            Some("claude-sonnet-4-5")
+        );
+        assert_eq!(
+            session.messages[0].usage,
+            Some(TokenUsage {
+                input_tokens: 10,
+                output_tokens: 250,
+                cache_write_tokens: 0,
+                cache_read_tokens: 4000,
+            })
+        );
+    }
+
+    #[test]
+    fn test_is_scripted() {
+        let prompted = write_fixture(
+            "prompted",
+            &[
+                r#"{"type":"user","timestamp":"2026-01-13T10:00:00Z","sessionId":"s3","message":{"content":"fix the login bug"}}"#,
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:00:05Z","sessionId":"s3","message":{"content":[{"type":"text","text":"On it"}]}}"#,
+            ],
+        );
+        let scripted = write_fixture(
+            "scripted",
+            &[
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:00:00Z","sessionId":"s4","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{}}]}}"#,
+                r#"{"type":"user","timestamp":"2026-01-13T10:00:05Z","sessionId":"s4","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"ok"}]}}"#,
+            ],
+        );
+
+        let prompted = parse_session_file(&prompted).unwrap();
+        let scripted = parse_session_file(&scripted).unwrap();
+
+        assert_eq!(
+            prompted.messages[0].text_content.as_deref(),
+            Some("fix the login bug")
+        );
+        assert!(!is_scripted(&prompted));
+        assert!(scripted.messages[1].text_content.is_none());
+        assert!(is_scripted(&scripted));
+    }
+
+    #[test]
+    fn test_hook_injection_not_a_prompt() {
+        let path = write_fixture(
+            "hooked",
+            &[
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:00:00Z","sessionId":"s5","message":{"content":[{"type":"text","text":"Done"}]}}"#,
+                r#"{"type":"user","timestamp":"2026-01-13T10:00:05Z","sessionId":"s5","message":{"content":"Stop hook feedback:\nclippy found 2 warnings"}}"#,
+                r#"{"type":"system","subtype":"stop_hook_summary","timestamp":"2026-01-13T10:00:06Z","sessionId":"s5"}"#,
+            ],
+        );
+
+        let session = parse_session_file(&path).unwrap();
+
+        assert!(!session.messages[0].is_hook);
+        assert!(session.messages[1].is_hook);
+        assert!(session.messages[2].is_hook);
+        assert!(is_scripted(&session));
+        let metrics = crate::metrics::calculate_session_metrics(&session);
+        assert_eq!(metrics.user_messages, 0);
+        assert_eq!(metrics.hook_injections, 2);
+    }
+
+    #[test]
+    fn test_anonymize_projects_stable_and_distinct() {
+        let session = |id: &str, project: &str| Session {
+            session_id: id.to_string(),
+            project: project.to_string(),
+            jsonl_path: PathBuf::from("/test/session.jsonl"),
+            ..Default::default()
+        };
+        let mut sessions = vec![
+            session("s1", "/work/acme-billing"),
+            session("s2", "/work/secret-client"),
+            session("s3", "/work/acme-billing"),
+        ];
+
+        sessions[1].messages.push(Message {
+            tool_calls: vec![ToolCall {
+                id: "t1".to_string(),
+                name: "Bash".to_string(),
+                input: serde_json::json!({
+                    "command": "cd /work/secret-client/web && npm test",
+                    "file_path": "/work/secret-client/src/lib.rs",
+                    "other": "/work/secret-client-old/a.rs"
+                }),
+                parallel_group: 0,
+            }],
+            ..Default::default()
+        });
+
+        anonymize_projects(&mut sessions);
+
+        let input = &sessions[1].messages[0].tool_calls[0].input;
+        let name = &sessions[1].project;
+        assert_eq!(input["file_path"], format!("{}/src/lib.rs", name));
+        assert_eq!(input["command"], format!("cd {}/web && npm test", name));
+        // A sibling dir sharing the prefix is a different project
+        assert_eq!(input["other"], "/work/secret-client-old/a.rs");
+        assert_eq!(sessions[0].project, sessions[2].project);
+        assert_ne!(sessions[0].project, sessions[1].project);
+        assert!(sessions.iter().all(|s| s.project.starts_with("project-")));
+        assert!(!sessions.iter().any(|s| s.project.contains("acme")));
+    }
+
+    #[test]
+    fn test_pseudonym_sequence() {
+        assert_eq!(pseudonym(0), "project-a");
+        assert_eq!(pseudonym(25), "project-z");
+        assert_eq!(pseudonym(26), "project-aa");
+        assert_eq!(pseudonym(27), "project-ab");
+    }
+
+    #[test]
+    fn test_normalize_tool_names_merges_variants() {
+        let path = write_fixture(
+            "variants",
+            &[
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:00:00Z","sessionId":"s6","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{}},{"type":"tool_use","id":"t2","name":"BashOutput","input":{}},{"type":"tool_use","id":"t3","name":"mcp__shell__bash","input":{}},{"type":"tool_use","id":"t4","name":"mcp__db__query","input":{}}]}}"#,
+            ],
+        );
+        let mut sessions = vec![parse_session_file(&path).unwrap()];
+
+        normalize_tool_names(&mut sessions, &default_tool_aliases());
+        let metrics = crate::metrics::calculate_session_metrics(&sessions[0]);
+
+        assert_eq!(metrics.tool_counts.get("Bash"), Some(&3));
+        assert_eq!(metrics.tool_counts.get("query"), Some(&1));
+        assert!(!metrics.tool_counts.contains_key("BashOutput"));
+        assert!(!metrics.tool_counts.contains_key("mcp__shell__bash"));
+    }
+
+    #[test]
+    fn test_exclude_tools() {
+        let path = write_fixture(
+            "exclude",
+            &[
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:00:00Z","sessionId":"s2","message":{"id":"m1","content":[{"type":"tool_use","id":"t1","name":"mcp__db__query","input":{}},{"type":"tool_use","id":"t2","name":"Read","input":{}}]}}"#,
+                r#"{"type":"user","timestamp":"2026-01-13T10:30:00Z","sessionId":"s2","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"boom","is_error":true}]}}"#,
+            ],
+        );
+        let mut sessions = vec![parse_session_file(&path).unwrap()];
+
+        exclude_tools(&mut sessions, &["mcp__db__query".to_string()]);
+        let metrics = crate::metrics::calculate_session_metrics(&sessions[0]);
+
+        assert!(!metrics.tool_counts.contains_key("mcp__db__query"));
+        assert_eq!(metrics.tool_counts.get("Read"), Some(&1));
+        assert_eq!(metrics.error_count, 0);
+        assert_eq!(metrics.duration_minutes, 30.0);
+    }
+
+    #[test]
+    fn test_exclude_raw_variant_before_merging() {
+        let path = write_fixture(
+            "exclude-variant",
+            &[
+                r#"{"type":"assistant","timestamp":"2026-01-13T10:00:00Z","sessionId":"s7","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{}},{"type":"tool_use","id":"t2","name":"BashOutput","input":{}}]}}"#,
+            ],
+        );
+        let mut sessions = vec![parse_session_file(&path).unwrap()];
+
+        exclude_tools(&mut sessions, &["BashOutput".to_string()]);
+        normalize_tool_names(&mut sessions, &default_tool_aliases());
+        let metrics = crate::metrics::calculate_session_metrics(&sessions[0]);
+
+        assert_eq!(metrics.tool_counts.get("Bash"), Some(&1));
+    }
+
+    #[test]
+    fn test_find_files_by_glob() {
+        let dir = std::env::temp_dir().join("aist-glob-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("archive")).unwrap();
+        let line = r#"{"type":"user","timestamp":"2026-01-13T10:00:00Z","sessionId":"g1","message":{"content":"hi"}}"#;
+        std::fs::write(dir.join("archive").join("a.jsonl"), line).unwrap();
+        std::fs::write(dir.join("archive").join("b.jsonl"), line).unwrap();
+        std::fs::write(dir.join("archive").join("notes.txt"), "skip me").unwrap();
+
+        let pattern = format!("{}/**/*", dir.display());
+        let files = find_files_by_glob(&pattern);
+
+        assert_eq!(files.len(), 2);
+        assert!(files[0].ends_with("archive/a.jsonl"));
+        let session = parse_session_file(&files[1]).unwrap();
+        assert_eq!(session.session_id, "g1");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_subagent_transcripts_opt_in() {
+        let dir = std::env::temp_dir().join("aist-subagent-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let project_dir = dir.join("-w-app");
+        std::fs::create_dir_all(project_dir.join("p1").join("subagents")).unwrap();
+        std::fs::write(
+            project_dir.join("p1.jsonl"),
+            r#"{"type":"user","timestamp":"2026-01-13T10:00:00Z","sessionId":"p1","cwd":"/w/app","gitBranch":"feature","message":{"content":"hi"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            project_dir.join("p1").join("subagents").join("agent-a.jsonl"),
+            r#"{"type":"user","timestamp":"2026-01-13T10:01:00Z","sessionId":"agent-a","message":{"content":"search"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(walk_projects_dir(&dir, None, false).len(), 1);
+        let files = walk_projects_dir(&dir, None, true);
+        assert_eq!(files.len(), 2);
+
+        let mut sessions = parse_transcripts(&files);
+        inherit_from_parents(&mut sessions);
+        let agent = sessions.iter().find(|s| s.session_id == "agent-a").unwrap();
+        assert_eq!(agent.project, "/w/app");
+        assert_eq!(agent.git_branch.as_deref(), Some("feature"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_load_sessions_returns_vec() {
         // Just verify it doesn't crash and returns a Vec
-        let sessions = load_sessions(None);
+        let sessions = load_sessions(None, false);
         // sessions may be empty if ~/.claude doesn't exist, that's fine
         // This test just ensures the function runs without panicking
         let _ = sessions;
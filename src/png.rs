@@ -0,0 +1,56 @@
+/// Rasterize an SVG to PNG bytes, at the SVG's own size
+#[cfg(feature = "png")]
+pub fn svg_to_png(svg: &str) -> Result<Vec<u8>, String> {
+    use resvg::{tiny_skia, usvg};
+
+    let mut options = usvg::Options::default();
+    options.fontdb_mut().load_system_fonts();
+    let tree = usvg::Tree::from_str(svg, &options).map_err(|e| format!("Invalid SVG: {}", e))?;
+
+    let size = tree.size().to_int_size();
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| "SVG has no drawable area".to_string())?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+    pixmap
+        .encode_png()
+        .map_err(|e| format!("Failed to encode PNG: {}", e))
+}
+
+/// Rasterize an SVG to PNG bytes (unavailable without the `png` feature)
+#[cfg(not(feature = "png"))]
+pub fn svg_to_png(_svg: &str) -> Result<Vec<u8>, String> {
+    Err("aist was built without PNG support (rebuild with --features png)".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_flamegraph_png_has_valid_header() {
+        use crate::flamegraph::{render_svg, ColorMap, Theme};
+        use crate::parser::Session;
+        use chrono::{TimeZone, Utc};
+
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0).unwrap();
+        let session = Session {
+            session_id: "png-session".to_string(),
+            project: "/test/project".to_string(),
+            jsonl_path: std::path::PathBuf::from("/test/session.jsonl"),
+            start_time: Some(start),
+            end_time: Some(start + chrono::Duration::minutes(30)),
+            ..Default::default()
+        };
+        let svg = render_svg(&[session], None, Theme::Light, &ColorMap::default()).unwrap();
+
+        let png = super::svg_to_png(&svg).unwrap();
+
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    }
+
+    #[cfg(not(feature = "png"))]
+    #[test]
+    fn test_png_without_feature_errors() {
+        assert!(super::svg_to_png("<svg/>").is_err());
+    }
+}
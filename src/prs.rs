@@ -1,9 +1,12 @@
-use crate::flamegraph::{extract_spans, ActivityType};
-use crate::github::{load_current_repo_cache, PrMapping, RepoCache};
+use crate::flamegraph::{extract_spans, ActivityType, DEFAULT_GAP_SECONDS};
+use crate::github::{
+    load_all_caches, load_current_repo_cache, print_cache_notes, PrMapping, PrState, RepoCache,
+};
+use crate::metrics::{round_percentages, tail_chars, truncate_chars};
 use crate::parser::Session;
 use chrono::{DateTime, Local, Utc};
 use colored::Colorize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Time metrics for a single GitHub PR
 #[derive(Debug, Clone)]
@@ -12,35 +15,32 @@ pub struct PrMetrics {
     pub pr_number: u32,
     pub title: String,
     pub branch: String,
+    /// Full duration of every session on the PR's branch. Transcripts don't
+    /// record how long each branch was checked out, so a session that moved
+    /// between PR branches adds its whole duration to each PR and totals
+    /// across PRs can exceed the real time spent.
     pub total_minutes: f64,
     pub session_count: usize,
     pub merged_at: Option<String>,
     pub closed_issues: Vec<u32>,
 }
 
-/// Calculate time spent per PR by matching sessions to PR branches
+/// Calculate time spent per PR by matching sessions to PR branches. A
+/// session that switched between PR branches counts in full toward each of
+/// them (see `PrMetrics::total_minutes`).
 pub fn calculate_pr_metrics(sessions: &[Session], cache: &RepoCache) -> Vec<PrMetrics> {
-    // Build branch -> PR mapping
-    let branch_to_pr: HashMap<&str, &PrMapping> = cache
-        .prs
-        .iter()
-        .map(|pr| (pr.branch.as_str(), pr))
-        .collect();
+    let branch_to_pr = cache.branch_to_pr();
 
     // Build PR -> (minutes, session_count)
     let mut pr_metrics: HashMap<u32, (f64, usize)> = HashMap::new();
 
     for session in sessions {
-        let branch = match &session.git_branch {
-            Some(b) => b.as_str(),
-            None => continue,
-        };
-
-        // Find the PR for this branch
-        let pr = match branch_to_pr.get(branch) {
-            Some(pr) => pr,
-            None => continue,
-        };
+        // Find the PRs for this session's branches
+        let prs: Vec<&PrMapping> = session
+            .branches()
+            .iter()
+            .filter_map(|b| branch_to_pr.get(b).copied())
+            .collect();
 
         // Calculate session duration
         let duration_minutes = match (session.start_time, session.end_time) {
@@ -48,10 +48,12 @@ pub fn calculate_pr_metrics(sessions: &[Session], cache: &RepoCache) -> Vec<PrMe
             _ => 0.0,
         };
 
-        // Add time to this PR
-        let entry = pr_metrics.entry(pr.pr_number).or_insert((0.0, 0));
-        entry.0 += duration_minutes;
-        entry.1 += 1;
+        // Add time to these PRs
+        for pr in prs {
+            let entry = pr_metrics.entry(pr.pr_number).or_insert((0.0, 0));
+            entry.0 += duration_minutes;
+            entry.1 += 1;
+        }
     }
 
     // Convert to Vec with PR info
@@ -80,11 +82,178 @@ pub fn calculate_pr_metrics(sessions: &[Session], cache: &RepoCache) -> Vec<PrMe
         b.total_minutes
             .partial_cmp(&a.total_minutes)
             .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.pr_number.cmp(&b.pr_number))
     });
 
     metrics
 }
 
+/// PR metrics labeled with the repo they belong to
+#[derive(Debug, Clone)]
+pub struct RepoPrMetrics {
+    pub repo: String,
+    pub metrics: PrMetrics,
+}
+
+/// Calculate PR metrics for every cache and merge them, sorted by time
+pub fn calculate_all_repo_pr_metrics(
+    sessions: &[Session],
+    caches: &[RepoCache],
+) -> Vec<RepoPrMetrics> {
+    let mut all: Vec<RepoPrMetrics> = caches
+        .iter()
+        .flat_map(|cache| {
+            let repo = format!("{}/{}", cache.owner, cache.repo);
+            calculate_pr_metrics(sessions, cache)
+                .into_iter()
+                .map(move |metrics| RepoPrMetrics {
+                    repo: repo.clone(),
+                    metrics,
+                })
+        })
+        .collect();
+
+    all.sort_by(|a, b| {
+        b.metrics
+            .total_minutes
+            .partial_cmp(&a.metrics.total_minutes)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.repo.cmp(&b.repo))
+            .then_with(|| a.metrics.pr_number.cmp(&b.metrics.pr_number))
+    });
+
+    all
+}
+
+/// A session whose time doesn't map to any synced PR
+#[derive(Debug, Clone)]
+pub struct OrphanSession {
+    pub session_id: String,
+    pub project: String,
+    pub branch: Option<String>,
+    pub minutes: f64,
+}
+
+/// Find sessions with no branch, or a branch that matches no PR in any
+/// cache, longest first. Zero-length sessions are skipped.
+pub fn find_orphan_sessions(sessions: &[Session], caches: &[RepoCache]) -> Vec<OrphanSession> {
+    let pr_branches: HashSet<&str> = caches
+        .iter()
+        .flat_map(|c| &c.prs)
+        .map(|pr| pr.branch.as_str())
+        .collect();
+
+    let mut orphans: Vec<OrphanSession> = sessions
+        .iter()
+        .filter(|s| !s.branches().iter().any(|b| pr_branches.contains(b)))
+        .filter_map(|s| {
+            let minutes = match (s.start_time, s.end_time) {
+                (Some(start), Some(end)) => (end - start).num_minutes() as f64,
+                _ => 0.0,
+            };
+            (minutes > 0.0).then(|| OrphanSession {
+                session_id: s.session_id.clone(),
+                project: s.project.clone(),
+                branch: s.git_branch.clone(),
+                minutes,
+            })
+        })
+        .collect();
+
+    orphans.sort_by(|a, b| {
+        b.minutes
+            .partial_cmp(&a.minutes)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.session_id.cmp(&b.session_id))
+    });
+    orphans
+}
+
+/// List sessions that aren't attributed to any PR, with the unattributed total
+pub fn list_orphans(sessions: &[Session], limit: usize) {
+    let caches = load_all_caches();
+    if caches.is_empty() {
+        println!(
+            "{}: No GitHub caches found. Run `aist sync` first.",
+            "Error".red()
+        );
+        return;
+    }
+    for cache in &caches {
+        print_cache_notes(cache);
+    }
+
+    let orphans = find_orphan_sessions(sessions, &caches);
+    if orphans.is_empty() {
+        println!("{}", "Every session maps to a synced PR.".green());
+        return;
+    }
+
+    let total_time: f64 = sessions
+        .iter()
+        .filter_map(|s| match (s.start_time, s.end_time) {
+            (Some(start), Some(end)) => Some((end - start).num_minutes() as f64),
+            _ => None,
+        })
+        .sum();
+    let orphan_time: f64 = orphans.iter().map(|o| o.minutes).sum();
+    let orphan_pct = round_percentages(&[orphan_time, total_time - orphan_time])[0];
+
+    // Header
+    println!("{}", "UNATTRIBUTED SESSIONS".bold());
+    println!("{}", "═".repeat(80));
+    println!(
+        "{} sessions | {} of {} total ({}%) not linked to any PR\n",
+        orphans.len().to_string().bold(),
+        format_duration(orphan_time).bold(),
+        format_duration(total_time),
+        orphan_pct
+    );
+
+    // Column headers
+    println!(
+        "{:<12} {:<25} {:<30} {:>10}",
+        "SESSION".dimmed(),
+        "PROJECT".dimmed(),
+        "BRANCH".dimmed(),
+        "TIME".dimmed()
+    );
+    println!("{}", "─".repeat(80).dimmed());
+
+    for o in orphans.iter().take(limit) {
+        let session_short: String = o.session_id.chars().take(10).collect();
+        let project = o.project.rsplit('/').next().unwrap_or(&o.project);
+        let project_short: String = project.chars().take(24).collect();
+        let branch = match &o.branch {
+            Some(b) => b.chars().take(29).collect::<String>(),
+            None => "(no branch)".to_string(),
+        };
+        println!(
+            "{:<12} {:<25} {:<30} {:>10}",
+            session_short,
+            project_short,
+            branch,
+            format_duration(o.minutes)
+        );
+    }
+
+    if orphans.len() > limit {
+        println!(
+            "{}",
+            format!("... and {} more (use --limit)", orphans.len() - limit).dimmed()
+        );
+    }
+
+    println!("{}", "─".repeat(80).dimmed());
+    println!(
+        "{:<12} {:<25} {:<30} {:>10}",
+        "TOTAL".bold(),
+        "",
+        "",
+        format_duration(orphan_time).bold()
+    );
+}
+
 /// Format duration in minutes to human-readable string
 fn format_duration(minutes: f64) -> String {
     if minutes >= 60.0 {
@@ -109,6 +278,7 @@ pub fn list_prs(sessions: &[Session]) {
             return;
         }
     };
+    print_cache_notes(&cache);
 
     let metrics = calculate_pr_metrics(sessions, &cache);
 
@@ -148,11 +318,7 @@ pub fn list_prs(sessions: &[Session]) {
 
     // List PRs
     for m in &metrics {
-        let title_display = if m.title.len() > 43 {
-            format!("{}...", &m.title[..40])
-        } else {
-            m.title.clone()
-        };
+        let title_display = truncate_chars(&m.title, 43);
 
         let issues_str = if m.closed_issues.is_empty() {
             "-".to_string()
@@ -190,6 +356,110 @@ pub fn list_prs(sessions: &[Session]) {
     );
 }
 
+/// List PRs across every synced repo cache with per-repo subtotals
+pub fn list_all_repo_prs(sessions: &[Session]) {
+    let caches = load_all_caches();
+    if caches.is_empty() {
+        println!(
+            "{}: No GitHub caches found. Run `aist sync` first.",
+            "Error".red()
+        );
+        return;
+    }
+    for cache in &caches {
+        print_cache_notes(cache);
+    }
+
+    let metrics = calculate_all_repo_pr_metrics(sessions, &caches);
+
+    if metrics.is_empty() {
+        println!("{}", "No PRs found with matching sessions.".yellow());
+        println!(
+            "{}",
+            "Tip: Make sure PR branches match session git branches.".dimmed()
+        );
+        return;
+    }
+
+    let total_time: f64 = metrics.iter().map(|m| m.metrics.total_minutes).sum();
+    let total_sessions: usize = metrics.iter().map(|m| m.metrics.session_count).sum();
+
+    // Header
+    println!("{}", "PRS BY TIME (ALL REPOS)".bold());
+    println!("{}", "═".repeat(90));
+    println!(
+        "{} PRs | {} repos | {} sessions | {} total\n",
+        metrics.len().to_string().bold(),
+        caches.len().to_string().bold(),
+        total_sessions.to_string().bold(),
+        format_duration(total_time).bold()
+    );
+
+    // Column headers
+    println!(
+        "{:<22} {:<8} {:<36} {:>10} {:>10}",
+        "REPO".dimmed(),
+        "PR".dimmed(),
+        "TITLE".dimmed(),
+        "TIME".dimmed(),
+        "SESSIONS".dimmed()
+    );
+    println!("{}", "─".repeat(90).dimmed());
+
+    for m in &metrics {
+        let repo_display = truncate_chars(&m.repo, 20);
+        let title_display = truncate_chars(&m.metrics.title, 34);
+
+        println!(
+            "{:<22} #{:<7} {:<36} {:>10} {:>10}",
+            repo_display,
+            m.metrics.pr_number,
+            title_display,
+            format_duration(m.metrics.total_minutes),
+            m.metrics.session_count
+        );
+    }
+
+    println!("{}", "─".repeat(90).dimmed());
+
+    // Per-repo subtotals
+    let mut subtotals: HashMap<&str, (f64, usize, usize)> = HashMap::new();
+    for m in &metrics {
+        let entry = subtotals.entry(m.repo.as_str()).or_insert((0.0, 0, 0));
+        entry.0 += m.metrics.total_minutes;
+        entry.1 += m.metrics.session_count;
+        entry.2 += 1;
+    }
+    let mut subtotals: Vec<_> = subtotals.into_iter().collect();
+    subtotals.sort_by(|(repo_a, (mins_a, _, _)), (repo_b, (mins_b, _, _))| {
+        mins_b
+            .partial_cmp(mins_a)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| repo_a.cmp(repo_b))
+    });
+
+    for (repo, (minutes, sessions, prs)) in &subtotals {
+        println!(
+            "{:<22} {:<8} {:<36} {:>10} {:>10}",
+            repo,
+            "",
+            format!("{} PRs", prs).dimmed(),
+            format_duration(*minutes),
+            sessions
+        );
+    }
+
+    println!("{}", "─".repeat(90).dimmed());
+    println!(
+        "{:<22} {:<8} {:<36} {:>10} {:>10}",
+        "TOTAL".bold(),
+        "",
+        "",
+        format_duration(total_time).bold(),
+        total_sessions.to_string().bold()
+    );
+}
+
 /// Session info for a specific PR
 #[derive(Debug)]
 struct PrSession<'a> {
@@ -210,6 +480,7 @@ pub fn show_pr_detail(pr_number: u32, sessions: &[Session]) {
             return;
         }
     };
+    print_cache_notes(&cache);
 
     // Find the PR
     let pr = cache.prs.iter().find(|p| p.pr_number == pr_number);
@@ -230,7 +501,7 @@ pub fn show_pr_detail(pr_number: u32, sessions: &[Session]) {
     // Find sessions matching this PR's branch
     let mut pr_sessions: Vec<PrSession> = sessions
         .iter()
-        .filter(|s| s.git_branch.as_deref() == Some(&pr.branch))
+        .filter(|s| s.on_branch(&pr.branch))
         .map(|s| {
             let duration = match (s.start_time, s.end_time) {
                 (Some(start), Some(end)) => (end - start).num_minutes() as f64,
@@ -244,17 +515,17 @@ pub fn show_pr_detail(pr_number: u32, sessions: &[Session]) {
         .collect();
 
     // Sort by start time
-    pr_sessions.sort_by(|a, b| a.session.start_time.cmp(&b.session.start_time));
+    pr_sessions.sort_by_key(|s| s.session.start_time);
 
     // Calculate totals
     let total_time: f64 = pr_sessions.iter().map(|s| s.duration_minutes).sum();
     let session_count = pr_sessions.len();
 
     // Determine status
-    let status = if pr.merged_at.is_some() {
-        "Merged".green()
-    } else {
-        "Open".yellow()
+    let status = match pr.status() {
+        PrState::Merged => "Merged".green(),
+        PrState::Open => "Open".yellow(),
+        PrState::Closed => "Closed".red(),
     };
 
     // Print header
@@ -326,6 +597,127 @@ pub fn show_pr_detail(pr_number: u32, sessions: &[Session]) {
 
     // Activity breakdown
     print_activity_breakdown(&pr_sessions);
+
+    // Files touched
+    let sessions: Vec<&Session> = pr_sessions.iter().map(|s| s.session).collect();
+    print_file_activity(&file_activity(&sessions));
+}
+
+/// Edits to one file across a set of sessions
+#[derive(Debug, Clone)]
+struct FileActivity {
+    path: String,
+    edit_count: usize,
+    first_touch: Option<DateTime<Utc>>,
+    last_touch: Option<DateTime<Utc>>,
+}
+
+/// Merge Write/Edit calls across sessions into per-file edit counts with
+/// first and last touch times, most-edited first. Paths are shown relative
+/// to the session's project when inside it.
+fn file_activity(sessions: &[&Session]) -> Vec<FileActivity> {
+    let mut by_path: HashMap<String, FileActivity> = HashMap::new();
+
+    for session in sessions {
+        for message in &session.messages {
+            for tc in &message.tool_calls {
+                if !matches!(tc.name.as_str(), "Edit" | "Write" | "NotebookEdit") {
+                    continue;
+                }
+                let Some(path) = tc
+                    .input
+                    .get("file_path")
+                    .or_else(|| tc.input.get("notebook_path"))
+                    .and_then(|v| v.as_str())
+                else {
+                    continue;
+                };
+
+                let display = shorten_file_path(path, &session.project);
+                let entry = by_path
+                    .entry(display.clone())
+                    .or_insert_with(|| FileActivity {
+                        path: display,
+                        edit_count: 0,
+                        first_touch: None,
+                        last_touch: None,
+                    });
+                entry.edit_count += 1;
+                if let Some(ts) = message.timestamp {
+                    entry.first_touch = Some(entry.first_touch.map_or(ts, |t| t.min(ts)));
+                    entry.last_touch = Some(entry.last_touch.map_or(ts, |t| t.max(ts)));
+                }
+            }
+        }
+    }
+
+    let mut files: Vec<FileActivity> = by_path.into_values().collect();
+    files.sort_by(|a, b| {
+        b.edit_count
+            .cmp(&a.edit_count)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    files
+}
+
+/// Path relative to the project, or with the home dir as ~
+fn shorten_file_path(path: &str, project: &str) -> String {
+    let home = dirs::home_dir()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_default();
+    shorten_file_path_in(path, project, &home)
+}
+
+/// `shorten_file_path` with an explicit home dir. Only a leading home dir
+/// becomes ~, and only at a path component boundary.
+fn shorten_file_path_in(path: &str, project: &str, home: &str) -> String {
+    if let Some(rel) = path.strip_prefix(project).and_then(|p| p.strip_prefix('/')) {
+        return rel.to_string();
+    }
+    match path.strip_prefix(home) {
+        Some(rest) if !home.is_empty() && rest.starts_with('/') => format!("~{}", rest),
+        _ => path.to_string(),
+    }
+}
+
+fn print_file_activity(files: &[FileActivity]) {
+    if files.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("{}", "FILES CHANGED".bold());
+    println!("{}", "─".repeat(70).dimmed());
+    println!(
+        "{:<36} {:>5} {:>13} {:>13}",
+        "FILE".dimmed(),
+        "EDITS".dimmed(),
+        "FIRST".dimmed(),
+        "LAST".dimmed()
+    );
+
+    let max_files = 20;
+    for f in files.iter().take(max_files) {
+        let path_display = tail_chars(&f.path, 36);
+        let touch = |ts: Option<DateTime<Utc>>| {
+            ts.map(|t| t.with_timezone(&Local).format("%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "-".to_string())
+        };
+        println!(
+            "{:<36} {:>5} {:>13} {:>13}",
+            path_display,
+            f.edit_count,
+            touch(f.first_touch),
+            touch(f.last_touch)
+        );
+    }
+
+    if files.len() > max_files {
+        println!(
+            "{}",
+            format!("... and {} more files", files.len() - max_files).dimmed()
+        );
+    }
 }
 
 /// Format timestamp for display
@@ -344,7 +736,7 @@ fn print_activity_breakdown(pr_sessions: &[PrSession]) {
     let mut total_span_time = 0.0;
 
     for pr_session in pr_sessions {
-        let spans = extract_spans(pr_session.session);
+        let spans = extract_spans(pr_session.session, DEFAULT_GAP_SECONDS);
         for span in spans {
             let duration_mins = (span.end - span.start).num_seconds() as f64 / 60.0;
             *time_by_activity.entry(span.activity).or_insert(0.0) += duration_mins;
@@ -359,11 +751,17 @@ fn print_activity_breakdown(pr_sessions: &[PrSession]) {
 
     // Sort by time descending
     let mut activities: Vec<_> = time_by_activity.into_iter().collect();
-    activities.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    activities.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+
+    let minutes: Vec<f64> = activities.iter().map(|(_, m)| *m).collect();
+    let percentages = round_percentages(&minutes);
 
     // Print each activity with a simple bar
-    for (activity, minutes) in &activities {
-        let percentage = (*minutes / total_span_time * 100.0) as usize;
+    for ((activity, minutes), percentage) in activities.iter().zip(percentages) {
         let bar_width = (percentage / 2).clamp(1, 30);
         let bar: String = "█".repeat(bar_width);
 
@@ -412,16 +810,23 @@ mod tests {
             git_branch: branch.map(|s| s.to_string()),
             start_time: Some(start),
             end_time: Some(end),
-            messages: vec![],
+            ..Default::default()
         }
     }
 
     fn make_cache(prs: Vec<PrMapping>) -> RepoCache {
         RepoCache {
+            host: Default::default(),
+            domain: None,
             owner: "test".to_string(),
             repo: "repo".to_string(),
             prs,
             synced_at: "2026-01-01T00:00:00Z".to_string(),
+            truncated: false,
+            issue_titles: HashMap::new(),
+            state: None,
+            limit: None,
+            ttl_hours: None,
         }
     }
 
@@ -440,6 +845,7 @@ mod tests {
                 branch: "feature/auth".to_string(),
                 closed_issues: vec![1, 2],
                 merged_at: Some("2026-01-01".to_string()),
+                state: None,
             },
             PrMapping {
                 pr_number: 11,
@@ -447,6 +853,7 @@ mod tests {
                 branch: "fix/bug".to_string(),
                 closed_issues: vec![3],
                 merged_at: None,
+                state: None,
             },
         ]);
 
@@ -464,6 +871,158 @@ mod tests {
         assert_eq!(metrics[1].session_count, 1);
     }
 
+    #[test]
+    fn test_session_matches_pr_on_earlier_branch() {
+        // Started on the PR branch, then checked out main
+        let mut session = make_session("s1", Some("main"), 30);
+        session.git_branches = vec!["feature/auth".to_string(), "main".to_string()];
+
+        let cache = make_cache(vec![PrMapping {
+            pr_number: 10,
+            title: "Add authentication".to_string(),
+            branch: "feature/auth".to_string(),
+            closed_issues: vec![],
+            merged_at: None,
+            state: None,
+        }]);
+
+        let metrics = calculate_pr_metrics(&[session], &cache);
+
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].pr_number, 10);
+        assert_eq!(metrics[0].total_minutes, 30.0);
+    }
+
+    #[test]
+    fn test_session_on_two_pr_branches_counts_toward_both() {
+        let mut session = make_session("s1", Some("feature/export"), 40);
+        session.git_branches = vec!["feature/auth".to_string(), "feature/export".to_string()];
+        let pr = |number, branch: &str| PrMapping {
+            pr_number: number,
+            title: format!("PR {}", number),
+            branch: branch.to_string(),
+            closed_issues: vec![],
+            merged_at: None,
+            state: None,
+        };
+        let cache = make_cache(vec![pr(10, "feature/auth"), pr(11, "feature/export")]);
+
+        let metrics = calculate_pr_metrics(&[session], &cache);
+
+        // The 40 minute session is double-counted: 80 minutes across both PRs
+        assert_eq!(metrics.len(), 2);
+        assert!(metrics.iter().all(|m| m.total_minutes == 40.0));
+        assert!(metrics.iter().all(|m| m.session_count == 1));
+    }
+
+    #[test]
+    fn test_find_orphan_sessions() {
+        let sessions = vec![
+            make_session("matched", Some("feature/auth"), 30),
+            make_session("unmatched", Some("spike/idea"), 45),
+            make_session("detached", None, 20),
+            make_session("empty", Some("spike/idea"), 0),
+        ];
+        let cache = make_cache(vec![PrMapping {
+            pr_number: 10,
+            title: "Add authentication".to_string(),
+            branch: "feature/auth".to_string(),
+            closed_issues: vec![],
+            merged_at: None,
+            state: None,
+        }]);
+
+        let orphans = find_orphan_sessions(&sessions, &[cache]);
+
+        let ids: Vec<&str> = orphans.iter().map(|o| o.session_id.as_str()).collect();
+        assert_eq!(ids, vec!["unmatched", "detached"]);
+        assert_eq!(orphans[0].branch.as_deref(), Some("spike/idea"));
+        assert_eq!(orphans[0].minutes, 45.0);
+    }
+
+    fn edits(session: &mut Session, minute: i64, paths: &[&str]) {
+        use crate::parser::{Message, MessageType, ToolCall};
+        let ts = session.start_time.unwrap() + chrono::Duration::minutes(minute);
+        session.messages.push(Message {
+            msg_type: MessageType::Assistant,
+            timestamp: Some(ts),
+            tool_calls: paths
+                .iter()
+                .map(|p| ToolCall {
+                    id: format!("t-{}", p),
+                    name: "Edit".to_string(),
+                    input: serde_json::json!({ "file_path": p }),
+                    parallel_group: 0,
+                })
+                .collect(),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_shorten_file_path_only_strips_leading_home() {
+        let home = "/home/al";
+        assert_eq!(
+            shorten_file_path_in("/w/app/src/main.rs", "/w/app", home),
+            "src/main.rs"
+        );
+        assert_eq!(
+            shorten_file_path_in("/home/al/notes.md", "/w/app", home),
+            "~/notes.md"
+        );
+        assert_eq!(
+            shorten_file_path_in("/home/alice/notes.md", "/w/app", home),
+            "/home/alice/notes.md"
+        );
+        assert_eq!(
+            shorten_file_path_in("/backup/home/al/x.rs", "/w/app", home),
+            "/backup/home/al/x.rs"
+        );
+    }
+
+    #[test]
+    fn test_file_activity_merges_sessions() {
+        let mut first = make_session("s1", Some("feature/auth"), 60);
+        edits(
+            &mut first,
+            5,
+            &["/test/project/src/auth.rs", "/test/project/src/lib.rs"],
+        );
+        edits(&mut first, 20, &["/test/project/src/auth.rs"]);
+        let mut second = make_session("s2", Some("feature/auth"), 60);
+        edits(&mut second, 40, &["/test/project/src/auth.rs"]);
+
+        let files = file_activity(&[&first, &second]);
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "src/auth.rs");
+        assert_eq!(files[0].edit_count, 3);
+        let start = first.start_time.unwrap();
+        assert_eq!(
+            files[0].first_touch,
+            Some(start + chrono::Duration::minutes(5))
+        );
+        assert_eq!(
+            files[0].last_touch,
+            Some(start + chrono::Duration::minutes(40))
+        );
+        assert_eq!(files[1].path, "src/lib.rs");
+        assert_eq!(files[1].edit_count, 1);
+    }
+
+    #[test]
+    fn test_file_activity_hides_anonymized_project_dir() {
+        let mut session = make_session("s1", Some("feature/auth"), 60);
+        edits(&mut session, 5, &["/test/project/src/auth.rs"]);
+        let mut sessions = [session];
+        crate::parser::anonymize_projects(&mut sessions);
+
+        let files = file_activity(&[&sessions[0]]);
+
+        assert_eq!(files[0].path, "src/auth.rs");
+        assert!(!files.iter().any(|f| f.path.contains("/test/project")));
+    }
+
     #[test]
     fn test_calculate_pr_metrics_no_branch() {
         let sessions = vec![make_session("s1", None, 30)];
@@ -474,6 +1033,7 @@ mod tests {
             branch: "feature/x".to_string(),
             closed_issues: vec![1],
             merged_at: None,
+            state: None,
         }]);
 
         let metrics = calculate_pr_metrics(&sessions, &cache);
@@ -490,6 +1050,7 @@ mod tests {
             branch: "feature/x".to_string(),
             closed_issues: vec![1],
             merged_at: None,
+            state: None,
         }]);
 
         let metrics = calculate_pr_metrics(&sessions, &cache);
@@ -507,6 +1068,7 @@ mod tests {
             branch: "feature/x".to_string(),
             closed_issues: vec![], // No linked issues
             merged_at: None,
+            state: None,
         }]);
 
         let metrics = calculate_pr_metrics(&sessions, &cache);
@@ -515,6 +1077,56 @@ mod tests {
         assert_eq!(metrics[0].total_minutes, 30.0);
     }
 
+    #[test]
+    fn test_calculate_all_repo_pr_metrics_merges_caches() {
+        let dir = std::env::temp_dir().join("aist-prs-all-repos-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut api = make_cache(vec![PrMapping {
+            pr_number: 3,
+            title: "API auth".to_string(),
+            branch: "feature/auth".to_string(),
+            closed_issues: vec![],
+            merged_at: None,
+            state: None,
+        }]);
+        api.repo = "api".to_string();
+        let mut web = make_cache(vec![PrMapping {
+            pr_number: 7,
+            title: "Web fix".to_string(),
+            branch: "fix/css".to_string(),
+            closed_issues: vec![],
+            merged_at: None,
+            state: None,
+        }]);
+        web.repo = "web".to_string();
+        for cache in [&api, &web] {
+            std::fs::write(
+                dir.join(format!("{}-{}.json", cache.owner, cache.repo)),
+                serde_json::to_string(cache).unwrap(),
+            )
+            .unwrap();
+        }
+
+        let caches = crate::github::load_caches_from(&dir);
+        assert_eq!(caches.len(), 2);
+
+        let sessions = vec![
+            make_session("s1", Some("feature/auth"), 20),
+            make_session("s2", Some("fix/css"), 50),
+        ];
+        let merged = calculate_all_repo_pr_metrics(&sessions, &caches);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].repo, "test/web");
+        assert_eq!(merged[0].metrics.pr_number, 7);
+        assert_eq!(merged[1].repo, "test/api");
+        assert_eq!(merged[1].metrics.total_minutes, 20.0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_format_duration() {
         assert_eq!(format_duration(30.0), "30m");
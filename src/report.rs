@@ -1,10 +1,13 @@
 use crate::bottlenecks::{self, Bottleneck};
+use crate::cost;
 use crate::metrics::{self, format_duration, ProjectMetrics};
 use crate::parser::Session;
-use chrono::{Datelike, Utc};
+use crate::team::TeamComparison;
+use chrono::{Datelike, NaiveDate, Utc};
 use colored::Colorize;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
 
 /// Report data structure for JSON output
 #[derive(Debug, Serialize)]
@@ -19,6 +22,48 @@ pub struct Report {
     pub top_bottlenecks: Vec<BottleneckSummary>,
     pub by_project: Vec<ProjectReport>,
     pub recommendations: Vec<String>,
+    /// Estimated time recovered by eliminating the costliest bottleneck types
+    pub savings: Vec<String>,
+    /// Deltas against the previous period, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comparison: Option<PeriodComparison>,
+    /// Standing against an anonymized team bundle, when one is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team: Option<TeamComparison>,
+}
+
+/// The previous period's headline numbers and how tool usage shifted since
+#[derive(Debug, Serialize)]
+pub struct PeriodComparison {
+    pub previous_session_count: usize,
+    pub previous_hours: f64,
+    pub previous_efficiency_percent: f64,
+    /// Tools whose call count moved the most, largest change first
+    pub tool_deltas: Vec<ToolDelta>,
+    /// Change in each headline metric
+    pub delta: ReportDelta,
+}
+
+/// Change in headline metrics from the previous period. Every delta is None
+/// when the previous period had no sessions, since there's no baseline.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ReportDelta {
+    pub sessions: Option<i64>,
+    pub hours: Option<f64>,
+    /// Change in efficiency, in percentage points
+    pub efficiency_points: Option<f64>,
+    /// Change in count per bottleneck type seen in either period
+    pub bottleneck_counts: Vec<(String, Option<i64>)>,
+}
+
+/// Change in one tool's call count between two periods
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ToolDelta {
+    pub tool: String,
+    pub previous: usize,
+    pub current: usize,
+    /// Percent change, or None when the tool wasn't used before
+    pub change_percent: Option<f64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -49,6 +94,21 @@ pub struct ProjectReport {
 /// Generate a report for the given sessions
 pub fn generate_report(sessions: &[Session], period: &str) -> Report {
     let filtered = metrics::filter_by_period(sessions, period);
+    build_report(filtered, period)
+}
+
+/// Generate a report for sessions started between two dates (inclusive),
+/// titled with the range instead of a period
+pub fn generate_report_for_range(
+    sessions: &[Session],
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+) -> Report {
+    let filtered = metrics::filter_by_date_range(sessions, since, until);
+    build_report(filtered, &metrics::date_range_label(since, until))
+}
+
+fn build_report(filtered: Vec<Session>, period: &str) -> Report {
     let aggregated = metrics::aggregate_metrics(&filtered);
     let bottlenecks = bottlenecks::detect_all(&filtered);
 
@@ -58,20 +118,8 @@ pub fn generate_report(sessions: &[Session], period: &str) -> Report {
 
     // Calculate time breakdown
     let time_breakdown = calculate_time_breakdown(&bottlenecks, aggregated.total_duration_minutes);
-
-    // Calculate efficiency
-    let wasted_time = time_breakdown.error_loop_minutes
-        + time_breakdown.exploration_minutes
-        + time_breakdown.edit_thrashing_minutes
-        + time_breakdown.long_gap_minutes;
-
-    let efficiency_percent = if aggregated.total_duration_minutes > 0.0 {
-        ((aggregated.total_duration_minutes - wasted_time) / aggregated.total_duration_minutes
-            * 100.0)
-            .clamp(0.0, 100.0)
-    } else {
-        100.0
-    };
+    let efficiency_percent =
+        calculate_efficiency(&time_breakdown, aggregated.total_duration_minutes);
 
     // Summarize bottlenecks by type
     let top_bottlenecks = summarize_bottlenecks(&bottlenecks);
@@ -80,7 +128,8 @@ pub fn generate_report(sessions: &[Session], period: &str) -> Report {
     let by_project = calculate_project_reports(&filtered, &aggregated.by_project);
 
     // Generate recommendations
-    let recommendations = generate_recommendations(&bottlenecks);
+    let recommendations = generate_recommendations(&bottlenecks, period);
+    let savings = estimate_savings(&top_bottlenecks, period);
 
     Report {
         period: period.to_string(),
@@ -93,6 +142,192 @@ pub fn generate_report(sessions: &[Session], period: &str) -> Report {
         top_bottlenecks,
         by_project,
         recommendations,
+        savings,
+        comparison: None,
+        team: None,
+    }
+}
+
+/// Share of total time not lost to bottlenecks
+fn calculate_efficiency(time_breakdown: &TimeBreakdown, total_minutes: f64) -> f64 {
+    let wasted_time = time_breakdown.error_loop_minutes
+        + time_breakdown.exploration_minutes
+        + time_breakdown.edit_thrashing_minutes
+        + time_breakdown.long_gap_minutes;
+
+    if total_minutes > 0.0 {
+        ((total_minutes - wasted_time) / total_minutes * 100.0).clamp(0.0, 100.0)
+    } else {
+        100.0
+    }
+}
+
+/// Compare the report's period with the one before it. Returns None for
+/// `all`, which has no previous period.
+pub fn compare_with_previous(
+    current: &Report,
+    sessions: &[Session],
+    period: &str,
+) -> Option<PeriodComparison> {
+    let previous = metrics::filter_previous_period(sessions, period)?;
+    let previous_metrics = metrics::aggregate_metrics(&previous);
+    let current_metrics = metrics::aggregate_metrics(&metrics::filter_by_period(sessions, period));
+    let previous_report = build_report(previous, period);
+
+    Some(PeriodComparison {
+        previous_session_count: previous_report.session_count,
+        previous_hours: previous_report.total_hours,
+        previous_efficiency_percent: previous_report.efficiency_percent,
+        tool_deltas: tool_deltas(&previous_metrics, &current_metrics, 5),
+        delta: compare_reports(current, &previous_report),
+    })
+}
+
+/// Headline deltas between two reports, current minus previous
+pub fn compare_reports(current: &Report, previous: &Report) -> ReportDelta {
+    let has_baseline = previous.session_count > 0;
+    let count_of = |report: &Report, kind: &str| {
+        report
+            .top_bottlenecks
+            .iter()
+            .find(|b| b.bottleneck_type == kind)
+            .map_or(0, |b| b.count as i64)
+    };
+
+    let mut kinds: Vec<&str> = Vec::new();
+    for b in current
+        .top_bottlenecks
+        .iter()
+        .chain(&previous.top_bottlenecks)
+    {
+        if !kinds.contains(&b.bottleneck_type.as_str()) {
+            kinds.push(&b.bottleneck_type);
+        }
+    }
+    let bottleneck_counts = kinds
+        .into_iter()
+        .map(|kind| {
+            let change = count_of(current, kind) - count_of(previous, kind);
+            (kind.to_string(), has_baseline.then_some(change))
+        })
+        .collect();
+
+    ReportDelta {
+        sessions: has_baseline
+            .then_some(current.session_count as i64 - previous.session_count as i64),
+        hours: has_baseline.then_some(current.total_hours - previous.total_hours),
+        efficiency_points: has_baseline
+            .then_some(current.efficiency_percent - previous.efficiency_percent),
+        bottleneck_counts,
+    }
+}
+
+/// "↑3" or "↓1.5h", green when the move is an improvement, or "n/a" without
+/// a baseline. `up_is_good` is None for metrics that are neither good nor bad.
+fn format_delta(
+    delta: Option<f64>,
+    precision: usize,
+    unit: &str,
+    up_is_good: Option<bool>,
+) -> String {
+    let Some(delta) = delta else {
+        return "n/a".dimmed().to_string();
+    };
+    let magnitude = format!("{:.*}", precision, delta.abs());
+    if magnitude.parse::<f64>() == Ok(0.0) {
+        return format!("±0{}", unit).dimmed().to_string();
+    }
+    let arrow = if delta > 0.0 { "↑" } else { "↓" };
+    let text = format!("{}{}{}", arrow, magnitude, unit);
+    match up_is_good {
+        Some(good) if good == (delta > 0.0) => text.green().to_string(),
+        Some(_) => text.red().to_string(),
+        None => text.cyan().to_string(),
+    }
+}
+
+/// The "VS PREVIOUS PERIOD" section of the text report
+fn render_comparison(report: &Report, c: &PeriodComparison) -> String {
+    let mut out = String::new();
+    let d = &c.delta;
+
+    out.push_str(&format!("{}\n", "VS PREVIOUS PERIOD".bold()));
+    out.push_str(&format!("{}\n", "─".repeat(40)));
+    out.push_str(&format!(
+        "Sessions: {} → {} {} | Time: {:.1}h → {:.1}h {} | Efficiency: {:.0}% → {:.0}% {}\n",
+        c.previous_session_count,
+        report.session_count,
+        format_delta(d.sessions.map(|n| n as f64), 0, "", None),
+        c.previous_hours,
+        report.total_hours,
+        format_delta(d.hours, 1, "h", None),
+        c.previous_efficiency_percent,
+        report.efficiency_percent,
+        format_delta(d.efficiency_points, 0, " pts", Some(true)),
+    ));
+    if !d.bottleneck_counts.is_empty() {
+        let counts: Vec<String> = d
+            .bottleneck_counts
+            .iter()
+            .map(|(kind, change)| {
+                let change = format_delta(change.map(|n| n as f64), 0, "", Some(false));
+                format!("{} {}", kind.to_lowercase(), change)
+            })
+            .collect();
+        out.push_str(&format!("Bottlenecks: {}\n", counts.join(", ")));
+    }
+    if !c.tool_deltas.is_empty() {
+        let movers: Vec<String> = c.tool_deltas.iter().map(format_tool_delta).collect();
+        out.push_str(&format!("Tool mix: {}\n", movers.join(", ")));
+    }
+    out.push('\n');
+    out
+}
+
+/// The `limit` tools whose call counts changed most between two periods
+pub fn tool_deltas(
+    previous: &metrics::AggregatedMetrics,
+    current: &metrics::AggregatedMetrics,
+    limit: usize,
+) -> Vec<ToolDelta> {
+    let tools: HashSet<&String> = previous
+        .tool_counts
+        .keys()
+        .chain(current.tool_counts.keys())
+        .collect();
+
+    let mut deltas: Vec<ToolDelta> = tools
+        .into_iter()
+        .filter_map(|tool| {
+            let before = previous.tool_counts.get(tool).copied().unwrap_or(0);
+            let after = current.tool_counts.get(tool).copied().unwrap_or(0);
+            if before == after {
+                return None;
+            }
+            let change_percent =
+                (before > 0).then(|| (after as f64 - before as f64) / before as f64 * 100.0);
+            Some(ToolDelta {
+                tool: tool.clone(),
+                previous: before,
+                current: after,
+                change_percent,
+            })
+        })
+        .collect();
+
+    deltas.sort_by(|a, b| {
+        let change = |d: &ToolDelta| d.current.abs_diff(d.previous);
+        change(b).cmp(&change(a)).then_with(|| a.tool.cmp(&b.tool))
+    });
+    deltas.truncate(limit);
+    deltas
+}
+
+/// "Bash +40%", "Read -15%", or "Grep new" for a tool that wasn't used before
+fn format_tool_delta(delta: &ToolDelta) -> String {
+    match delta.change_percent {
+        Some(pct) => format!("{} {:+.0}%", delta.tool, pct),
+        None => format!("{} new", delta.tool),
     }
 }
 
@@ -105,9 +340,15 @@ fn calculate_time_breakdown(bottlenecks: &[Bottleneck], total_minutes: f64) -> T
     for b in bottlenecks {
         match b {
             Bottleneck::ErrorLoop(e) => error_loop_minutes += e.duration_minutes,
+            // Flaky tools are failure time too, just not consecutive
+            Bottleneck::FlakyTool(f) => error_loop_minutes += f.duration_minutes,
+            Bottleneck::TestFailureLoop(t) => error_loop_minutes += t.duration_minutes,
             Bottleneck::ExplorationSpiral(e) => exploration_minutes += e.duration_minutes,
             Bottleneck::EditThrashing(e) => edit_thrashing_minutes += e.duration_minutes,
             Bottleneck::LongGap(g) => long_gap_minutes += g.gap_minutes,
+            Bottleneck::ApprovalStall(a) => long_gap_minutes += a.wait_minutes,
+            // Breaks between sessions aren't part of any session's time
+            Bottleneck::ContextSwitch(_) => {}
         }
     }
 
@@ -143,6 +384,10 @@ fn summarize_bottlenecks(bottlenecks: &[Bottleneck]) -> Vec<BottleneckSummary> {
             Bottleneck::ExplorationSpiral(e) => ("Exploration spirals", e.duration_minutes),
             Bottleneck::EditThrashing(e) => ("Edit thrashing", e.duration_minutes),
             Bottleneck::LongGap(g) => ("Long gaps", g.gap_minutes),
+            Bottleneck::FlakyTool(f) => ("Flaky tools", f.duration_minutes),
+            Bottleneck::TestFailureLoop(t) => ("Test failure loops", t.duration_minutes),
+            Bottleneck::ApprovalStall(a) => ("Approval stalls", a.wait_minutes),
+            Bottleneck::ContextSwitch(c) => ("Context switching", c.break_minutes),
         };
         let entry = by_type.entry(type_name).or_insert((0, 0.0));
         entry.0 += 1;
@@ -157,6 +402,10 @@ fn summarize_bottlenecks(bottlenecks: &[Bottleneck]) -> Vec<BottleneckSummary> {
                 "Exploration spirals" => format!("{} search sessions without edits", count),
                 "Edit thrashing" => format!("{} files edited repeatedly", count),
                 "Long gaps" => format!("{} pauses over 5 minutes", count),
+                "Flaky tools" => format!("{} tools alternating pass/fail", count),
+                "Test failure loops" => format!("{} runs of repeated test failures", count),
+                "Approval stalls" => format!("{} tool calls waiting on approval", count),
+                "Context switching" => format!("{} stretches hopping between 3+ projects", count),
                 _ => format!("{} occurrences", count),
             };
             BottleneckSummary {
@@ -173,6 +422,7 @@ fn summarize_bottlenecks(bottlenecks: &[Bottleneck]) -> Vec<BottleneckSummary> {
         b.total_minutes
             .partial_cmp(&a.total_minutes)
             .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.bottleneck_type.cmp(&b.bottleneck_type))
     });
 
     summaries.into_iter().take(5).collect()
@@ -218,40 +468,273 @@ fn calculate_project_reports(
         b.hours
             .partial_cmp(&a.hours)
             .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.name.cmp(&b.name))
     });
 
     reports
 }
 
-fn generate_recommendations(bottlenecks: &[Bottleneck]) -> Vec<String> {
-    let mut recommendations = Vec::new();
-    let mut has_error_loops = false;
-    let mut has_exploration = false;
-    let mut has_thrashing = false;
-    let mut has_gaps = false;
+/// A problem area ranked by how much time it cost
+struct Problem {
+    minutes: f64,
+    count: usize,
+    sessions: HashSet<String>,
+}
+
+impl Problem {
+    fn new() -> Self {
+        Problem {
+            minutes: 0.0,
+            count: 0,
+            sessions: HashSet::new(),
+        }
+    }
+
+    fn add(&mut self, b: &Bottleneck) {
+        self.minutes += b.wasted_minutes();
+        self.count += 1;
+        self.sessions.insert(b.session_id().to_string());
+    }
+}
+
+/// Build the top 3 recommendations, ranked by total wasted minutes
+fn generate_recommendations(bottlenecks: &[Bottleneck], period: &str) -> Vec<String> {
+    let mut error_loops_by_tool: HashMap<&str, Problem> = HashMap::new();
+    let mut exploration = Problem::new();
+    let mut thrashing = Problem::new();
+    let mut gaps = Problem::new();
+    // Ordered by tool so equal-cost recommendations come out the same every run
+    let mut flaky_by_tool: BTreeMap<&str, Problem> = BTreeMap::new();
+    let mut test_loops = Problem::new();
+    let mut test_commands: HashMap<&str, usize> = HashMap::new();
+    let mut approvals = Problem::new();
+    let mut approval_tools: HashMap<&str, usize> = HashMap::new();
+    let mut switching = Problem::new();
+    let mut most_projects = 0;
+
+    // Worst offenders: most-searched files, most-edited file, longest gap
+    let mut searched_files: HashMap<&str, usize> = HashMap::new();
+    let mut spirals_by_project: HashMap<&str, Problem> = HashMap::new();
+    let mut worst_thrash: Option<(&str, usize)> = None;
+    let mut longest_gap = 0.0_f64;
 
     for b in bottlenecks {
         match b {
-            Bottleneck::ErrorLoop(_) => has_error_loops = true,
-            Bottleneck::ExplorationSpiral(_) => has_exploration = true,
-            Bottleneck::EditThrashing(_) => has_thrashing = true,
-            Bottleneck::LongGap(_) => has_gaps = true,
+            Bottleneck::ErrorLoop(e) => error_loops_by_tool
+                .entry(e.tool_name.as_str())
+                .or_insert_with(Problem::new)
+                .add(b),
+            Bottleneck::ExplorationSpiral(e) => {
+                exploration.add(b);
+                spirals_by_project
+                    .entry(e.project_path.as_str())
+                    .or_insert_with(Problem::new)
+                    .add(b);
+                for file in &e.files_searched {
+                    *searched_files.entry(file.as_str()).or_insert(0) += 1;
+                }
+            }
+            Bottleneck::EditThrashing(e) => {
+                thrashing.add(b);
+                if worst_thrash.is_none_or(|(_, count)| e.edit_count > count) {
+                    worst_thrash = Some((e.file_path.as_str(), e.edit_count));
+                }
+            }
+            Bottleneck::LongGap(g) => {
+                gaps.add(b);
+                longest_gap = longest_gap.max(g.gap_minutes);
+            }
+            Bottleneck::FlakyTool(f) => flaky_by_tool
+                .entry(f.tool_name.as_str())
+                .or_insert_with(Problem::new)
+                .add(b),
+            Bottleneck::TestFailureLoop(t) => {
+                test_loops.add(b);
+                *test_commands.entry(t.command.as_str()).or_insert(0) += 1;
+            }
+            Bottleneck::ApprovalStall(a) => {
+                approvals.add(b);
+                *approval_tools.entry(a.tool_name.as_str()).or_insert(0) += 1;
+            }
+            Bottleneck::ContextSwitch(c) => {
+                switching.add(b);
+                most_projects = most_projects.max(c.projects.len());
+            }
         }
     }
 
-    if has_error_loops {
-        recommendations.push("Check PATH and dependencies for failing tools".to_string());
+    let when = period_phrase(period);
+    let mut ranked: Vec<(f64, String)> = Vec::new();
+
+    for (tool, p) in &error_loops_by_tool {
+        let tool_display = if *tool == "unknown" { "Tool" } else { tool };
+        ranked.push((
+            p.minutes,
+            format!(
+                "{} error loops cost you {} {} across {} ({} loops) - check your PATH and dependencies",
+                tool_display,
+                format_duration(p.minutes),
+                when,
+                plural(p.sessions.len(), "session"),
+                p.count
+            ),
+        ));
     }
-    if has_exploration {
-        recommendations.push("Add better context to CLAUDE.md to reduce search time".to_string());
+
+    for (tool, p) in &flaky_by_tool {
+        ranked.push((
+            p.minutes,
+            format!(
+                "Flaky {} results cost you {} {} across {} - stabilize the environment (pin versions, fix shared state)",
+                tool,
+                format_duration(p.minutes),
+                when,
+                plural(p.sessions.len(), "session")
+            ),
+        ));
     }
-    if has_thrashing {
-        recommendations.push("Break down complex changes into smaller, focused tasks".to_string());
+
+    if test_loops.count > 0 {
+        let command = test_commands
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(c, _)| c)
+            .unwrap_or("tests");
+        ranked.push((
+            test_loops.minutes,
+            format!(
+                "Rerunning failing `{}` cost you {} {} across {} - run just the failing test and read its full output before editing",
+                command,
+                format_duration(test_loops.minutes),
+                when,
+                plural(test_loops.sessions.len(), "session")
+            ),
+        ));
+    }
+
+    if approvals.count > 0 {
+        let tool = approval_tools
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(t, _)| t)
+            .unwrap_or("tools");
+        ranked.push((
+            approvals.minutes,
+            format!(
+                "Waiting on tool approval cost you {} {} across {} (mostly {}) - allow-list the tools you always approve",
+                format_duration(approvals.minutes),
+                when,
+                plural(approvals.sessions.len(), "session"),
+                tool
+            ),
+        ));
+    }
+
+    if switching.count > 0 {
+        ranked.push((
+            switching.minutes,
+            format!(
+                "Back-to-back sessions hopped between up to {} projects {} {} - batch work per project to skip the re-orienting",
+                most_projects,
+                plural(switching.count, "time"),
+                when
+            ),
+        ));
     }
-    if has_gaps {
-        recommendations.push("Review blocked sessions - unclear requirements?".to_string());
+
+    if exploration.count > 0 {
+        let mut files: Vec<_> = searched_files.into_iter().collect();
+        files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let names: Vec<String> = files
+            .iter()
+            .take(2)
+            .map(|(f, _)| file_name(f).to_string())
+            .collect();
+
+        // Tailor the hint to the project that keeps spiralling, if any
+        let repeat_project = spirals_by_project
+            .iter()
+            .filter(|(_, p)| p.count >= 2)
+            .max_by(|a, b| {
+                a.1.minutes
+                    .partial_cmp(&b.1.minutes)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then_with(|| b.0.cmp(a.0))
+            })
+            .map(|(path, _)| *path);
+
+        let hint = match (repeat_project, names.is_empty()) {
+            (Some(path), _) if has_claude_md(Path::new(path)) => {
+                if names.is_empty() {
+                    format!(
+                        "expand the CLAUDE.md in {} with file hints",
+                        file_name(path)
+                    )
+                } else {
+                    format!(
+                        "expand the CLAUDE.md in {} with file hints for {}",
+                        file_name(path),
+                        names.join(", ")
+                    )
+                }
+            }
+            (Some(path), true) => format!("create a CLAUDE.md in {}", file_name(path)),
+            (Some(path), false) => format!(
+                "create a CLAUDE.md in {} pointing at {}",
+                file_name(path),
+                names.join(", ")
+            ),
+            (None, true) => "add better context to CLAUDE.md".to_string(),
+            (None, false) => format!("add file hints for {} to CLAUDE.md", names.join(", ")),
+        };
+        ranked.push((
+            exploration.minutes,
+            format!(
+                "Exploration spirals cost you {} {} across {} - {}",
+                format_duration(exploration.minutes),
+                when,
+                plural(exploration.sessions.len(), "session"),
+                hint
+            ),
+        ));
     }
 
+    if let Some((file, edits)) = worst_thrash {
+        ranked.push((
+            thrashing.minutes,
+            format!(
+                "Edit thrashing cost you {} {} across {} (worst: {} edited {} times) - break changes into smaller, focused tasks",
+                format_duration(thrashing.minutes),
+                when,
+                plural(thrashing.sessions.len(), "session"),
+                file_name(file),
+                edits
+            ),
+        ));
+    }
+
+    if gaps.count > 0 {
+        ranked.push((
+            gaps.minutes,
+            format!(
+                "Long gaps cost you {} {} across {} (longest {}) - review blocked sessions, unclear requirements?",
+                format_duration(gaps.minutes),
+                when,
+                plural(gaps.sessions.len(), "session"),
+                format_duration(longest_gap)
+            ),
+        ));
+    }
+
+    // Worst offenders first; tie-break on text so output is stable
+    ranked.sort_by(|a, b| {
+        b.0.partial_cmp(&a.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.1.cmp(&b.1))
+    });
+
+    let mut recommendations: Vec<String> = ranked.into_iter().take(3).map(|(_, r)| r).collect();
+
     if recommendations.is_empty() {
         recommendations.push("No significant bottlenecks detected - keep it up!".to_string());
     }
@@ -259,6 +742,74 @@ fn generate_recommendations(bottlenecks: &[Bottleneck]) -> Vec<String> {
     recommendations
 }
 
+/// Projected savings from eliminating each of the top two bottleneck types.
+/// This assumes wasted time would not be spent elsewhere, so it's an upper
+/// bound rather than a forecast.
+fn estimate_savings(summaries: &[BottleneckSummary], period: &str) -> Vec<String> {
+    let rate = match period {
+        "day" => "/day".to_string(),
+        "week" => "/week".to_string(),
+        "month" => "/month".to_string(),
+        "quarter" => "/quarter".to_string(),
+        "year" => "/year".to_string(),
+        p if metrics::period_length(p).is_some() => format!(" per {}", p),
+        _ => " overall".to_string(),
+    };
+
+    summaries
+        .iter()
+        .filter(|s| s.total_minutes >= 1.0)
+        .take(2)
+        .map(|s| {
+            format!(
+                "Fixing {} could save ~{}{}",
+                s.bottleneck_type.to_lowercase(),
+                format_duration(s.total_minutes),
+                rate
+            )
+        })
+        .collect()
+}
+
+/// Whether a CLAUDE.md exists at the project path or any parent up to the
+/// repo root
+fn has_claude_md(project_path: &Path) -> bool {
+    for dir in project_path.ancestors() {
+        if dir.join("CLAUDE.md").exists() {
+            return true;
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    false
+}
+
+fn period_phrase(period: &str) -> String {
+    match period {
+        "day" => "today".to_string(),
+        "week" => "this week".to_string(),
+        "month" => "in the last 30 days".to_string(),
+        "quarter" => "in the last 90 days".to_string(),
+        "year" => "in the last 365 days".to_string(),
+        p if metrics::period_length(p).is_some() => format!("in the last {}", p),
+        _ => "overall".to_string(),
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+/// Last path component, for compact display of file paths
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
 fn extract_project_name(project_path: &str) -> String {
     project_path
         .trim_end_matches('/')
@@ -270,108 +821,111 @@ fn extract_project_name(project_path: &str) -> String {
 
 /// Print report in text format
 pub fn print_text_report(report: &Report) {
-    let period_display = match report.period.as_str() {
-        "day" => "Today".to_string(),
-        "week" => format!("Week {}, {}", report.week_number, report.year),
-        "month" => "Last 30 days".to_string(),
-        "all" => "All time".to_string(),
-        _ => report.period.clone(),
-    };
+    print!("{}", render_text_report(report));
+}
+
+/// Render the text report to a string
+pub fn render_text_report(report: &Report) -> String {
+    let mut out = String::new();
 
     // Header
-    println!(
-        "{}",
-        format!("AI SESSION REPORT: {}", period_display).bold()
-    );
-    println!("{}", "━".repeat(50));
-    println!();
+    out.push_str(&format!(
+        "{}\n",
+        format!("AI SESSION REPORT: {}", period_display(report)).bold()
+    ));
+    out.push_str(&format!("{}\n", "━".repeat(50)));
+    out.push('\n');
 
     // Summary line
-    println!(
-        "Sessions: {} | Time: {} | Efficiency: {}",
+    out.push_str(&format!(
+        "Sessions: {} | Time: {} | Efficiency: {}\n",
         report.session_count.to_string().bold(),
         format!("{:.1}h", report.total_hours).bold(),
         format!("{:.0}%", report.efficiency_percent)
             .color(efficiency_color(report.efficiency_percent))
             .bold()
-    );
-    println!();
+    ));
+    out.push('\n');
 
     // Time breakdown with ASCII bar chart
-    println!("{}", "TIME BREAKDOWN".bold());
-    println!("{}", "─".repeat(40));
-
-    let total = report.time_breakdown.productive_minutes
-        + report.time_breakdown.error_loop_minutes
-        + report.time_breakdown.exploration_minutes
-        + report.time_breakdown.edit_thrashing_minutes
-        + report.time_breakdown.long_gap_minutes;
-
-    if total > 0.0 {
-        print_bar(
-            "Productive",
-            report.time_breakdown.productive_minutes,
-            total,
-            "green",
-        );
-        if report.time_breakdown.error_loop_minutes > 0.0 {
-            print_bar(
-                "Error loops",
-                report.time_breakdown.error_loop_minutes,
-                total,
-                "red",
-            );
-        }
-        if report.time_breakdown.exploration_minutes > 0.0 {
-            print_bar(
-                "Exploration",
-                report.time_breakdown.exploration_minutes,
-                total,
-                "yellow",
-            );
-        }
-        if report.time_breakdown.edit_thrashing_minutes > 0.0 {
-            print_bar(
-                "Edit thrash",
-                report.time_breakdown.edit_thrashing_minutes,
-                total,
-                "magenta",
-            );
-        }
-        if report.time_breakdown.long_gap_minutes > 0.0 {
-            print_bar(
-                "Long gaps",
-                report.time_breakdown.long_gap_minutes,
-                total,
-                "blue",
-            );
+    out.push_str(&format!("{}\n", "TIME BREAKDOWN".bold()));
+    out.push_str(&format!("{}\n", "─".repeat(40)));
+
+    let rows = time_breakdown_rows(&report.time_breakdown);
+    if !rows.is_empty() {
+        for (label, value, percent, color) in rows {
+            out.push_str(&format_bar(label, value, percent, color));
         }
     } else {
-        println!("{}", "No time data available".dimmed());
+        out.push_str(&format!("{}\n", "No time data available".dimmed()));
     }
-    println!();
+    out.push('\n');
 
     // Top bottlenecks
     if !report.top_bottlenecks.is_empty() {
-        println!("{}", "TOP BOTTLENECKS".bold());
-        println!("{}", "─".repeat(40));
+        out.push_str(&format!("{}\n", "TOP BOTTLENECKS".bold()));
+        out.push_str(&format!("{}\n", "─".repeat(40)));
 
         for (i, b) in report.top_bottlenecks.iter().enumerate() {
-            println!(
-                "{}. {} ({}) - {}",
+            out.push_str(&format!(
+                "{}. {} ({}) - {}\n",
                 i + 1,
                 b.bottleneck_type.yellow(),
                 format_duration(b.total_minutes),
                 b.description.dimmed()
-            );
+            ));
         }
-        println!();
+        out.push('\n');
+    }
+
+    // Potential savings
+    if !report.savings.is_empty() {
+        out.push_str(&format!(
+            "{} {}\n",
+            "POTENTIAL SAVINGS".bold(),
+            "(estimate)".dimmed()
+        ));
+        out.push_str(&format!("{}\n", "─".repeat(40)));
+        for line in &report.savings {
+            out.push_str(&format!("{} {}\n", "→".green(), line));
+        }
+        out.push('\n');
+    }
+
+    // Previous period comparison
+    if let Some(c) = &report.comparison {
+        out.push_str(&render_comparison(report, c));
+    }
+
+    // Team benchmark
+    if let Some(t) = &report.team {
+        out.push_str(&format!("{}\n", "VS TEAM".bold()));
+        out.push_str(&format!("{}\n", "─".repeat(40)));
+        out.push_str(&format!(
+            "Your efficiency: {:.0}% (team median {:.0}%, {:.0}th percentile of {})\n",
+            report.efficiency_percent,
+            t.team_median_efficiency,
+            t.efficiency_percentile,
+            t.member_count
+        ));
+        let relative = match t.relative_cost {
+            Some(r) if r >= 1.0 => format!(", {:.0}% higher", (r - 1.0) * 100.0),
+            Some(r) => format!(", {:.0}% lower", (1.0 - r) * 100.0),
+            None => String::new(),
+        };
+        out.push_str(&format!(
+            "Cost per session: {} (team median {}{})\n",
+            cost::format_cost(t.cost_per_session),
+            cost::format_cost(t.team_median_cost_per_session),
+            relative
+        ));
+        out.push('\n');
     }
 
     // By project
     if !report.by_project.is_empty() {
-        println!("{}", "BY PROJECT".bold());
-        println!("{}", "─".repeat(40));
+        out.push_str(&format!("{}\n", "BY PROJECT".bold()));
+        out.push_str(&format!("{}\n", "─".repeat(40)));
 
         for p in report.by_project.iter().take(5) {
             let name_display = if p.name.len() > 20 {
@@ -379,35 +933,180 @@ pub fn print_text_report(report: &Report) {
             } else {
                 p.name.clone()
             };
-            println!(
-                "{:<20} {:>2} sessions, {:>5}, {}",
+            out.push_str(&format!(
+                "{:<20} {:>2} sessions, {:>5}, {}\n",
                 name_display,
                 p.session_count,
                 format!("{:.1}h", p.hours),
                 format!("{:.0}% eff", p.efficiency_percent)
                     .color(efficiency_color(p.efficiency_percent))
-            );
+            ));
         }
 
         if report.by_project.len() > 5 {
-            println!(
-                "{}",
+            out.push_str(&format!(
+                "{}\n",
                 format!("... and {} more projects", report.by_project.len() - 5).dimmed()
-            );
+            ));
         }
-        println!();
+        out.push('\n');
     }
 
     // Recommendations
-    println!("{}", "RECOMMENDATIONS".bold());
-    println!("{}", "─".repeat(40));
+    out.push_str(&format!("{}\n", "RECOMMENDATIONS".bold()));
+    out.push_str(&format!("{}\n", "─".repeat(40)));
+    for rec in &report.recommendations {
+        out.push_str(&format!("{} {}\n", "→".cyan(), rec));
+    }
+
+    out
+}
+
+/// "Week 3, 2026", "Last 30 days", "All time", ...
+fn period_display(report: &Report) -> String {
+    match report.period.as_str() {
+        "day" => "Today".to_string(),
+        "week" => format!("Week {}, {}", report.week_number, report.year),
+        "month" => "Last 30 days".to_string(),
+        "quarter" => "Last 90 days".to_string(),
+        "year" => "Last 365 days".to_string(),
+        "all" => "All time".to_string(),
+        p if metrics::period_length(p).is_some() => format!("Last {}", p),
+        _ => report.period.clone(),
+    }
+}
+
+/// (label, minutes, percent, color) for each time category to show.
+/// Productive is always shown; other categories only when present. Empty
+/// when there's no time data at all.
+fn time_breakdown_rows(tb: &TimeBreakdown) -> Vec<(&'static str, f64, usize, &'static str)> {
+    let rows = [
+        ("Productive", tb.productive_minutes, "green"),
+        ("Error loops", tb.error_loop_minutes, "red"),
+        ("Exploration", tb.exploration_minutes, "yellow"),
+        ("Edit thrash", tb.edit_thrashing_minutes, "magenta"),
+        ("Long gaps", tb.long_gap_minutes, "blue"),
+    ];
+    let minutes: Vec<f64> = rows.iter().map(|(_, m, _)| *m).collect();
+    if minutes.iter().sum::<f64>() <= 0.0 {
+        return vec![];
+    }
+    let percents = metrics::round_percentages(&minutes);
+
+    rows.into_iter()
+        .zip(percents)
+        .enumerate()
+        .filter(|(i, ((_, value, _), _))| *i == 0 || *value > 0.0)
+        .map(|(_, ((label, value, color), percent))| (label, value, percent, color))
+        .collect()
+}
+
+/// Print report as Markdown
+pub fn print_markdown_report(report: &Report) {
+    print!("{}", render_markdown_report(report));
+}
+
+/// Render the report as Markdown for pasting into an issue or PR
+pub fn render_markdown_report(report: &Report) -> String {
+    let mut out = String::new();
+    let cell = |text: &str| text.replace('|', "\\|");
+
+    out.push_str(&format!(
+        "## AI Session Report: {}\n\n",
+        period_display(report)
+    ));
+    out.push_str(&format!(
+        "**Sessions:** {} | **Time:** {:.1}h | **Efficiency:** {:.0}%\n\n",
+        report.session_count, report.total_hours, report.efficiency_percent
+    ));
+
+    out.push_str("## Time Breakdown\n\n");
+    let rows = time_breakdown_rows(&report.time_breakdown);
+    if rows.is_empty() {
+        out.push_str("No time data available\n\n");
+    } else {
+        out.push_str("| Category | Time | Share |\n|---|---:|---:|\n");
+        for (label, value, percent, _) in &rows {
+            out.push_str(&format!(
+                "| {} | {} | {}% |\n",
+                label,
+                format_duration(*value),
+                percent
+            ));
+        }
+        out.push_str("\n```\n");
+        for (label, _, percent, _) in &rows {
+            let filled = (percent * 20 / 100).min(20);
+            out.push_str(&format!(
+                "{:<12} {}{} {:>3}%\n",
+                label,
+                "█".repeat(filled),
+                "░".repeat(20 - filled),
+                percent
+            ));
+        }
+        out.push_str("```\n\n");
+    }
+
+    if !report.top_bottlenecks.is_empty() {
+        out.push_str("## Top Bottlenecks\n\n");
+        for b in &report.top_bottlenecks {
+            out.push_str(&format!(
+                "- **{}** ({}) - {}\n",
+                b.bottleneck_type,
+                format_duration(b.total_minutes),
+                b.description
+            ));
+        }
+        out.push('\n');
+    }
+
+    if !report.savings.is_empty() {
+        out.push_str("## Potential Savings (estimate)\n\n");
+        for line in &report.savings {
+            out.push_str(&format!("- {}\n", line));
+        }
+        out.push('\n');
+    }
+
+    if let Some(c) = &report.comparison {
+        out.push_str("## vs Previous Period\n\n");
+        out.push_str("| Metric | Previous | Current |\n|---|---:|---:|\n");
+        out.push_str(&format!(
+            "| Sessions | {} | {} |\n| Time | {:.1}h | {:.1}h |\n| Efficiency | {:.0}% | {:.0}% |\n\n",
+            c.previous_session_count,
+            report.session_count,
+            c.previous_hours,
+            report.total_hours,
+            c.previous_efficiency_percent,
+            report.efficiency_percent
+        ));
+    }
+
+    if !report.by_project.is_empty() {
+        out.push_str("## By Project\n\n");
+        out.push_str("| Project | Sessions | Time | Efficiency |\n|---|---:|---:|---:|\n");
+        for p in &report.by_project {
+            out.push_str(&format!(
+                "| {} | {} | {:.1}h | {:.0}% |\n",
+                cell(&p.name),
+                p.session_count,
+                p.hours,
+                p.efficiency_percent
+            ));
+        }
+        out.push('\n');
+    }
+
+    out.push_str("## Recommendations\n\n");
     for rec in &report.recommendations {
-        println!("{} {}", "→".cyan(), rec);
+        out.push_str(&format!("- {}\n", rec));
     }
+
+    out
 }
 
-fn print_bar(label: &str, value: f64, total: f64, color: &str) {
-    let percent = (value / total * 100.0) as usize;
+fn format_bar(label: &str, value: f64, percent: usize, color: &str) -> String {
     let bar_width = 20;
     let filled = (percent * bar_width / 100).min(bar_width);
     let empty = bar_width - filled;
@@ -422,13 +1121,13 @@ fn print_bar(label: &str, value: f64, total: f64, color: &str) {
         _ => bar.normal(),
     };
 
-    println!(
-        "{:<12} {} {:>3}% ({})",
+    format!(
+        "{:<12} {} {:>3}% ({})\n",
         label,
         colored_bar,
         percent,
         format_duration(value)
-    );
+    )
 }
 
 fn efficiency_color(percent: f64) -> colored::Color {
@@ -443,15 +1142,21 @@ fn efficiency_color(percent: f64) -> colored::Color {
 
 /// Print report as JSON
 pub fn print_json_report(report: &Report) {
-    match serde_json::to_string_pretty(report) {
+    match render_json_report(report) {
         Ok(json) => println!("{}", json),
-        Err(e) => eprintln!("Error serializing report: {}", e),
+        Err(e) => eprintln!("{}", e),
     }
 }
 
+/// Render the report as pretty-printed JSON
+pub fn render_json_report(report: &Report) -> Result<String, String> {
+    serde_json::to_string_pretty(report).map_err(|e| format!("Error serializing report: {}", e))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bottlenecks::{EditThrashing, ErrorLoop, ExplorationSpiral, FlakyTool, LongGap};
     use crate::parser::{Message, MessageType};
     use chrono::TimeZone;
     use std::path::PathBuf;
@@ -471,16 +1176,15 @@ mod tests {
                 Message {
                     msg_type: MessageType::User,
                     timestamp: Some(start),
-                    tool_calls: vec![],
-                    tool_results: vec![],
+                    ..Default::default()
                 },
                 Message {
                     msg_type: MessageType::Assistant,
                     timestamp: Some(end),
-                    tool_calls: vec![],
-                    tool_results: vec![],
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         }
     }
 
@@ -494,6 +1198,117 @@ mod tests {
         assert!(report.efficiency_percent >= 0.0 && report.efficiency_percent <= 100.0);
     }
 
+    #[test]
+    fn test_report_for_date_range_is_titled_with_range() {
+        let sessions = vec![create_test_session()];
+        let since = NaiveDate::from_ymd_opt(2026, 1, 5);
+        let until = NaiveDate::from_ymd_opt(2026, 1, 18);
+
+        let report = generate_report_for_range(&sessions, since, until);
+
+        assert_eq!(report.session_count, 1);
+        assert!(render_text_report(&report).contains("AI SESSION REPORT: 2026-01-05 to 2026-01-18"));
+    }
+
+    #[test]
+    fn test_compare_reports_delta_math() {
+        let summary = |kind: &str, count| BottleneckSummary {
+            bottleneck_type: kind.to_string(),
+            count,
+            total_minutes: 0.0,
+            description: String::new(),
+        };
+        let mut previous = generate_report(&[], "week");
+        previous.session_count = 8;
+        previous.total_hours = 6.5;
+        previous.efficiency_percent = 70.0;
+        previous.top_bottlenecks = vec![summary("Error loops", 5), summary("Long gaps", 2)];
+        let mut current = generate_report(&[], "week");
+        current.session_count = 10;
+        current.total_hours = 5.0;
+        current.efficiency_percent = 78.0;
+        current.top_bottlenecks = vec![summary("Error loops", 3), summary("Edit thrashing", 1)];
+
+        let delta = compare_reports(&current, &previous);
+
+        assert_eq!(delta.sessions, Some(2));
+        assert_eq!(delta.hours, Some(-1.5));
+        assert_eq!(delta.efficiency_points, Some(8.0));
+        assert_eq!(
+            delta.bottleneck_counts,
+            vec![
+                ("Error loops".to_string(), Some(-2)),
+                ("Edit thrashing".to_string(), Some(1)),
+                ("Long gaps".to_string(), Some(-2)),
+            ]
+        );
+
+        // Nothing to compare against
+        let empty = generate_report(&[], "week");
+        let delta = compare_reports(&current, &empty);
+        assert_eq!(delta.sessions, None);
+        assert_eq!(delta.efficiency_points, None);
+        assert!(delta.bottleneck_counts.iter().all(|(_, c)| c.is_none()));
+        assert_eq!(
+            format_delta(delta.hours, 1, "h", None),
+            "n/a".dimmed().to_string()
+        );
+    }
+
+    #[test]
+    fn test_markdown_report() {
+        let sessions = vec![create_test_session()];
+        let report = generate_report(&sessions, "all");
+
+        let md = render_markdown_report(&report);
+
+        assert!(md.starts_with("## AI Session Report: All time"));
+        assert!(md.contains("## Time Breakdown"));
+        assert!(md.contains("| Category | Time | Share |"));
+        assert!(md.contains("| Project | Sessions | Time | Efficiency |"));
+        assert!(md.contains("```\nProductive"));
+        assert!(!md.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_tool_deltas_between_periods() {
+        let counts = |pairs: &[(&str, usize)]| -> HashMap<String, usize> {
+            pairs.iter().map(|(t, n)| (t.to_string(), *n)).collect()
+        };
+        let mut previous = metrics::aggregate_metrics(&[]);
+        previous.tool_counts = counts(&[("Bash", 50), ("Read", 100), ("Edit", 30)]);
+        let mut current = metrics::aggregate_metrics(&[]);
+        current.tool_counts = counts(&[("Bash", 70), ("Read", 85), ("Edit", 30), ("Grep", 4)]);
+
+        let deltas = tool_deltas(&previous, &current, 5);
+
+        assert_eq!(
+            deltas,
+            vec![
+                ToolDelta {
+                    tool: "Bash".to_string(),
+                    previous: 50,
+                    current: 70,
+                    change_percent: Some(40.0),
+                },
+                ToolDelta {
+                    tool: "Read".to_string(),
+                    previous: 100,
+                    current: 85,
+                    change_percent: Some(-15.0),
+                },
+                ToolDelta {
+                    tool: "Grep".to_string(),
+                    previous: 0,
+                    current: 4,
+                    change_percent: None,
+                },
+            ]
+        );
+        let formatted: Vec<String> = deltas.iter().map(format_tool_delta).collect();
+        assert_eq!(formatted, vec!["Bash +40%", "Read -15%", "Grep new"]);
+    }
+
     #[test]
     fn test_generate_report_empty() {
         let sessions: Vec<Session> = vec![];
@@ -516,15 +1331,192 @@ mod tests {
     #[test]
     fn test_generate_recommendations_empty() {
         let bottlenecks: Vec<Bottleneck> = vec![];
-        let recs = generate_recommendations(&bottlenecks);
+        let recs = generate_recommendations(&bottlenecks, "week");
         assert_eq!(recs.len(), 1);
         assert!(recs[0].contains("No significant bottlenecks"));
     }
 
+    fn error_loop(session_id: &str, tool: &str, minutes: f64) -> Bottleneck {
+        Bottleneck::ErrorLoop(ErrorLoop {
+            session_id: session_id.to_string(),
+            project: "my-project".to_string(),
+            tool_name: tool.to_string(),
+            failure_count: 3,
+            start_time: None,
+            end_time: None,
+            duration_minutes: minutes,
+            error_samples: vec![],
+            error_bytes: 0,
+            refeed_count: 0,
+            refeed_cost: 0.0,
+        })
+    }
+
+    fn long_gap(session_id: &str, minutes: f64) -> Bottleneck {
+        Bottleneck::LongGap(LongGap {
+            session_id: session_id.to_string(),
+            project: "my-project".to_string(),
+            gap_minutes: minutes,
+            before_timestamp: None,
+            after_timestamp: None,
+        })
+    }
+
+    fn exploration(session_id: &str, project_path: &Path, minutes: f64) -> Bottleneck {
+        Bottleneck::ExplorationSpiral(ExplorationSpiral {
+            session_id: session_id.to_string(),
+            project: "my-project".to_string(),
+            project_path: project_path.to_string_lossy().to_string(),
+            read_count: 12,
+            grep_count: 4,
+            duration_minutes: minutes,
+            start_time: None,
+            files_searched: vec!["/repo/src/router.rs".to_string()],
+        })
+    }
+
+    #[test]
+    fn test_exploration_recommendation_checks_claude_md() {
+        let project = std::env::temp_dir().join("aist-report-claude-md");
+        let _ = std::fs::remove_dir_all(&project);
+        std::fs::create_dir_all(project.join(".git")).unwrap();
+        let bottlenecks = vec![
+            exploration("s1", &project, 15.0),
+            exploration("s2", &project, 12.0),
+        ];
+
+        let recs = generate_recommendations(&bottlenecks, "week");
+        assert!(recs[0].contains("create a CLAUDE.md in aist-report-claude-md"));
+        assert!(recs[0].contains("router.rs"));
+
+        std::fs::write(project.join("CLAUDE.md"), "# Project notes").unwrap();
+        let recs = generate_recommendations(&bottlenecks, "week");
+        assert!(recs[0].contains("expand the CLAUDE.md in aist-report-claude-md"));
+        assert!(recs[0].contains("file hints for router.rs"));
+
+        let _ = std::fs::remove_dir_all(&project);
+    }
+
+    #[test]
+    fn test_generate_recommendations_quantified() {
+        let bottlenecks = vec![
+            error_loop("s1", "Bash", 30.0),
+            error_loop("s2", "Bash", 17.0),
+            long_gap("s1", 12.0),
+        ];
+        let recs = generate_recommendations(&bottlenecks, "week");
+
+        assert_eq!(recs.len(), 2);
+        assert!(recs[0].starts_with("Bash error loops"));
+        assert!(recs[0].contains("47m this week"));
+        assert!(recs[0].contains("2 sessions"));
+        assert!(recs[0].contains("2 loops"));
+        assert!(recs[1].contains("Long gaps cost you 12m"));
+        assert!(recs[1].contains("1 session "));
+    }
+
+    #[test]
+    fn test_generate_recommendations_top_three_by_minutes() {
+        let bottlenecks = vec![
+            error_loop("s1", "Bash", 5.0),
+            error_loop("s1", "Read", 40.0),
+            error_loop("s2", "Grep", 20.0),
+            long_gap("s3", 10.0),
+            Bottleneck::EditThrashing(EditThrashing {
+                session_id: "s4".to_string(),
+                project: "my-project".to_string(),
+                file_path: "~/src/main.rs".to_string(),
+                edit_count: 9,
+                duration_minutes: 25.0,
+                start_time: None,
+            }),
+        ];
+        let recs = generate_recommendations(&bottlenecks, "all");
+
+        assert_eq!(recs.len(), 3);
+        assert!(recs[0].starts_with("Read error loops"));
+        assert!(recs[1].contains("main.rs edited 9 times"));
+        assert!(recs[2].starts_with("Grep error loops"));
+    }
+
+    #[test]
+    fn test_flaky_recommendations_ordered_by_tool() {
+        let flaky = |tool: &str| {
+            Bottleneck::FlakyTool(FlakyTool {
+                session_id: "s1".to_string(),
+                project: "my-project".to_string(),
+                tool_name: tool.to_string(),
+                flips: 3,
+                failure_count: 2,
+                result_count: 4,
+                duration_minutes: 8.0,
+                start_time: None,
+            })
+        };
+        let bottlenecks = vec![flaky("WebFetch"), flaky("Bash"), flaky("Read")];
+        let recs = generate_recommendations(&bottlenecks, "all");
+
+        assert!(recs[0].starts_with("Flaky Bash results"));
+        assert!(recs[1].starts_with("Flaky Read results"));
+        assert!(recs[2].starts_with("Flaky WebFetch results"));
+    }
+
+    #[test]
+    fn test_estimate_savings_names_top_category() {
+        let bottlenecks = vec![
+            error_loop("s1", "Bash", 30.0),
+            error_loop("s2", "Bash", 17.0),
+            long_gap("s1", 12.0),
+            long_gap("s2", 0.2),
+        ];
+        let summaries = summarize_bottlenecks(&bottlenecks);
+        let savings = estimate_savings(&summaries, "week");
+
+        assert_eq!(savings.len(), 2);
+        assert_eq!(savings[0], "Fixing error loops could save ~47m/week");
+        assert!(savings[1].starts_with("Fixing long gaps could save ~12m"));
+        assert!(estimate_savings(&[], "week").is_empty());
+    }
+
     #[test]
     fn test_efficiency_color() {
         assert_eq!(efficiency_color(85.0), colored::Color::Green);
         assert_eq!(efficiency_color(70.0), colored::Color::Yellow);
         assert_eq!(efficiency_color(50.0), colored::Color::Red);
     }
+
+    #[test]
+    fn test_tied_projects_sort_by_name() {
+        let names = [
+            "delta", "alpha", "echo", "charlie", "bravo", "golf", "foxtrot",
+        ];
+        let build = || -> HashMap<String, ProjectMetrics> {
+            names
+                .iter()
+                .map(|n| {
+                    let metrics = ProjectMetrics {
+                        session_count: 1,
+                        total_duration_minutes: 60.0,
+                        ..Default::default()
+                    };
+                    (n.to_string(), metrics)
+                })
+                .collect()
+        };
+
+        // Each map gets its own random hash seed, so iteration order differs
+        let first: Vec<String> = calculate_project_reports(&[], &build())
+            .into_iter()
+            .map(|r| r.name)
+            .collect();
+        let second: Vec<String> = calculate_project_reports(&[], &build())
+            .into_iter()
+            .map(|r| r.name)
+            .collect();
+
+        let mut sorted = names.map(String::from).to_vec();
+        sorted.sort();
+        assert_eq!(first, sorted);
+        assert_eq!(first, second);
+    }
 }
@@ -0,0 +1,112 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Root of aist's config state (repo caches, settings, tags)
+pub fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("aist")
+}
+
+/// Root of aist's disposable cache state
+pub fn cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("aist")
+}
+
+/// Every directory aist writes to. Claude's own transcripts are never listed.
+pub fn state_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![config_dir(), cache_dir()];
+    dirs.dedup();
+    dirs
+}
+
+/// A piece of aist-created state on disk
+#[derive(Debug, Clone)]
+pub struct StateEntry {
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+/// Find which of the given state directories exist, with their sizes
+pub fn find_state(dirs: &[PathBuf]) -> Vec<StateEntry> {
+    dirs.iter()
+        .filter(|dir| dir.exists() && !is_claude_path(dir))
+        .map(|dir| StateEntry {
+            path: dir.clone(),
+            bytes: dir_size(dir),
+        })
+        .collect()
+}
+
+/// Delete the given state entries, returning the bytes reclaimed
+pub fn remove_state(entries: &[StateEntry]) -> Result<u64, String> {
+    let mut reclaimed = 0;
+
+    for entry in entries {
+        if is_claude_path(&entry.path) {
+            return Err(format!(
+                "Refusing to remove {}: it holds Claude transcripts",
+                entry.path.display()
+            ));
+        }
+
+        let result = if entry.path.is_dir() {
+            fs::remove_dir_all(&entry.path)
+        } else {
+            fs::remove_file(&entry.path)
+        };
+        result.map_err(|e| format!("Failed to remove {}: {}", entry.path.display(), e))?;
+        reclaimed += entry.bytes;
+    }
+
+    Ok(reclaimed)
+}
+
+/// Total size of all files under a path
+fn dir_size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Whether a path is inside the read-only `~/.claude` directory
+fn is_claude_path(path: &Path) -> bool {
+    match dirs::home_dir() {
+        Some(home) => path.starts_with(home.join(".claude")),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_and_remove_state() {
+        let root = std::env::temp_dir().join("aist-state-test");
+        let _ = fs::remove_dir_all(&root);
+        let config = root.join("config").join("aist");
+        let missing = root.join("cache").join("aist");
+        fs::create_dir_all(config.join("repos")).unwrap();
+        fs::write(config.join("repos").join("o-r.json"), "0123456789").unwrap();
+        fs::write(config.join("tags.json"), "{}").unwrap();
+
+        let entries = find_state(&[config.clone(), missing]);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, config);
+        assert_eq!(entries[0].bytes, 12);
+
+        let reclaimed = remove_state(&entries).unwrap();
+        assert_eq!(reclaimed, 12);
+        assert!(!config.exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::cost;
+use crate::error::AistError;
+use crate::metrics;
+use crate::parser::Session;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Anonymized per-member numbers from a team, one entry per person.
+/// Loaded from the JSON file given to `report --team-bundle`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TeamBundle {
+    pub members: Vec<MemberStats>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct MemberStats {
+    pub efficiency_percent: f64,
+    pub cost_per_session: f64,
+}
+
+/// Where an individual sits against the team bundle
+#[derive(Debug, Serialize)]
+pub struct TeamComparison {
+    pub member_count: usize,
+    /// Share of team members below your efficiency, ties counting half
+    pub efficiency_percentile: f64,
+    pub team_median_efficiency: f64,
+    pub cost_per_session: f64,
+    pub team_median_cost_per_session: f64,
+    /// Your cost per session divided by the team median (1.0 = same)
+    pub relative_cost: Option<f64>,
+}
+
+/// Read a team bundle, rejecting one with no members
+pub fn load_team_bundle(path: &Path) -> Result<TeamBundle, AistError> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| AistError::Io(format!("Failed to read team bundle {}", path.display()), e))?;
+    let bundle: TeamBundle = serde_json::from_str(&content)
+        .map_err(|e| AistError::Parse(format!("Invalid team bundle: {}", e)))?;
+    if bundle.members.is_empty() {
+        return Err(AistError::Parse("Team bundle has no members".to_string()));
+    }
+    Ok(bundle)
+}
+
+/// Percentile rank of `value` within `distribution`: the share of values
+/// below it, counting ties as half
+pub fn percentile_rank(value: f64, distribution: &[f64]) -> f64 {
+    if distribution.is_empty() {
+        return 0.0;
+    }
+    let below = distribution.iter().filter(|v| **v < value).count() as f64;
+    let equal = distribution.iter().filter(|v| **v == value).count() as f64;
+    (below + equal / 2.0) / distribution.len() as f64 * 100.0
+}
+
+fn median(values: &[f64]) -> f64 {
+    metrics::percentile(values, 50.0)
+}
+
+/// Compare your efficiency and per-session cost for `sessions` with the team
+pub fn compare_to_team(
+    efficiency_percent: f64,
+    sessions: &[Session],
+    bundle: &TeamBundle,
+) -> TeamComparison {
+    let efficiencies: Vec<f64> = bundle
+        .members
+        .iter()
+        .map(|m| m.efficiency_percent)
+        .collect();
+    let costs: Vec<f64> = bundle.members.iter().map(|m| m.cost_per_session).collect();
+
+    let cost_per_session = if sessions.is_empty() {
+        0.0
+    } else {
+        sessions.iter().map(cost::calculate_cost).sum::<f64>() / sessions.len() as f64
+    };
+    let team_median_cost_per_session = median(&costs);
+
+    TeamComparison {
+        member_count: bundle.members.len(),
+        efficiency_percentile: percentile_rank(efficiency_percent, &efficiencies),
+        team_median_efficiency: median(&efficiencies),
+        cost_per_session,
+        team_median_cost_per_session,
+        relative_cost: (team_median_cost_per_session > 0.0)
+            .then(|| cost_per_session / team_median_cost_per_session),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_against_team() {
+        let team = [50.0, 60.0, 68.0, 75.0, 90.0];
+        assert_eq!(percentile_rank(72.0, &team), 60.0);
+        assert_eq!(percentile_rank(68.0, &team), 50.0);
+        assert_eq!(percentile_rank(40.0, &team), 0.0);
+        assert_eq!(percentile_rank(95.0, &team), 100.0);
+    }
+
+    #[test]
+    fn test_compare_to_team_medians() {
+        let bundle = TeamBundle {
+            members: [(50.0, 1.0), (68.0, 2.0), (90.0, 4.0)]
+                .iter()
+                .map(|&(efficiency_percent, cost_per_session)| MemberStats {
+                    efficiency_percent,
+                    cost_per_session,
+                })
+                .collect(),
+        };
+
+        let comparison = compare_to_team(72.0, &[], &bundle);
+
+        assert_eq!(comparison.member_count, 3);
+        assert_eq!(comparison.team_median_efficiency, 68.0);
+        assert_eq!(comparison.team_median_cost_per_session, 2.0);
+        assert!((comparison.efficiency_percentile - 66.67).abs() < 0.01);
+        assert_eq!(comparison.relative_cost, Some(0.0));
+    }
+}
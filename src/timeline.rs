@@ -1,12 +1,24 @@
 use crate::parser::Session;
 use chrono::{DateTime, Local, Utc};
 use colored::Colorize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// How event timestamps are shown in the timeline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimestampFormat {
+    /// Local wall-clock time, `HH:MM:SS`
+    Clock,
+    /// Local date and time, `YYYY-MM-DD HH:MM:SS`
+    Full,
+    /// Time since session start, `+mm:ss`
+    Elapsed,
+}
 
 /// Print a visual timeline for a session
-pub fn print_timeline(session: &Session) {
+pub fn print_timeline(session: &Session, ts_format: TimestampFormat) {
     print_session_header(session);
-    print_timeline_events(session);
+    print_timeline_events(session, ts_format);
     print_summary(session);
 }
 
@@ -56,10 +68,21 @@ fn get_tool_icon(tool_name: &str) -> &'static str {
     }
 }
 
-/// Format timestamp for display
-fn format_timestamp(ts: &DateTime<Utc>) -> String {
+/// Format timestamp for display; `start` anchors elapsed deltas
+fn format_timestamp(
+    ts: &DateTime<Utc>,
+    ts_format: TimestampFormat,
+    start: Option<DateTime<Utc>>,
+) -> String {
     let local: DateTime<Local> = ts.with_timezone(&Local);
-    local.format("%H:%M:%S").to_string()
+    match ts_format {
+        TimestampFormat::Clock => local.format("%H:%M:%S").to_string(),
+        TimestampFormat::Full => local.format("%Y-%m-%d %H:%M:%S").to_string(),
+        TimestampFormat::Elapsed => {
+            let secs = start.map(|s| (*ts - s).num_seconds().max(0)).unwrap_or(0);
+            format!("+{:02}:{:02}", secs / 60, secs % 60)
+        }
+    }
 }
 
 /// Extract a short description for a tool call
@@ -156,7 +179,7 @@ struct TimelineEvent {
 }
 
 /// Print the timeline events
-fn print_timeline_events(session: &Session) {
+fn print_timeline_events(session: &Session, ts_format: TimestampFormat) {
     println!("{}", "TIMELINE".bold());
     println!("{}", "\u{2500}".repeat(60).dimmed());
 
@@ -223,14 +246,14 @@ fn print_timeline_events(session: &Session) {
     }
 
     // Sort events by timestamp
-    events.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    events.sort_by_key(|e| e.timestamp);
 
     // Mark successful bash commands (those not followed by errors)
     mark_successful_bash_commands(&mut events);
 
     // Print events
     for event in &events {
-        let ts_str = format_timestamp(&event.timestamp);
+        let ts_str = format_timestamp(&event.timestamp, ts_format, session.start_time);
         let icon = event.icon;
 
         let desc = if event.is_error {
@@ -266,56 +289,229 @@ fn mark_successful_bash_commands(events: &mut [TimelineEvent]) {
     }
 }
 
-/// Print summary statistics
-fn print_summary(session: &Session) {
-    println!("{}", "SUMMARY".bold());
-    println!("{}", "\u{2500}".repeat(60).dimmed());
+/// Headline numbers for a session, shared by the printed summary and
+/// `timeline --summary-json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineSummary {
+    pub session_id: String,
+    pub tool_calls: usize,
+    pub tool_counts: BTreeMap<String, usize>,
+    pub errors: usize,
+    pub files_touched: usize,
+    pub duration_minutes: f64,
+    pub longest_gap_minutes: f64,
+}
 
-    // Count tool calls by type
-    let mut tool_counts: HashMap<String, usize> = HashMap::new();
-    let mut error_count = 0;
-    let mut files_touched: std::collections::HashSet<String> = std::collections::HashSet::new();
+/// Count tool calls, errors, and files touched, plus the session's
+/// duration and longest pause between messages
+pub fn compute_timeline_summary(session: &Session) -> TimelineSummary {
+    let mut tool_counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut errors = 0;
+    let mut files_touched: HashSet<&str> = HashSet::new();
 
     for message in &session.messages {
         for tool_call in &message.tool_calls {
             *tool_counts.entry(tool_call.name.clone()).or_insert(0) += 1;
 
-            // Track files touched
             if let Some(path) = tool_call.input.get("file_path").and_then(|v| v.as_str()) {
-                files_touched.insert(path.to_string());
+                files_touched.insert(path);
             }
         }
 
-        for tool_result in &message.tool_results {
-            if tool_result.is_error {
-                error_count += 1;
-            }
-        }
+        errors += message.tool_results.iter().filter(|r| r.is_error).count();
     }
 
-    // Total tool calls
-    let total_calls: usize = tool_counts.values().sum();
-
-    // Format tool breakdown
-    let mut breakdown_parts: Vec<String> = Vec::new();
-    for (name, count) in &tool_counts {
-        breakdown_parts.push(format!("{}: {}", name, count));
+    let timestamps: Vec<DateTime<Utc>> = session
+        .messages
+        .iter()
+        .filter_map(|m| m.timestamp)
+        .collect();
+    let longest_gap_minutes = timestamps
+        .windows(2)
+        .map(|w| (w[1] - w[0]).num_seconds() as f64 / 60.0)
+        .fold(0.0, f64::max);
+
+    TimelineSummary {
+        session_id: session.session_id.clone(),
+        tool_calls: tool_counts.values().sum(),
+        tool_counts,
+        errors,
+        files_touched: files_touched.len(),
+        duration_minutes: crate::exclusions::active_minutes(session),
+        longest_gap_minutes,
     }
-    breakdown_parts.sort();
-    let breakdown = breakdown_parts.join(", ");
+}
 
-    println!("{}: {} ({})", "Tool calls".dimmed(), total_calls, breakdown);
+/// Print summary statistics
+fn print_summary(session: &Session) {
+    println!("{}", "SUMMARY".bold());
+    println!("{}", "\u{2500}".repeat(60).dimmed());
+
+    let summary = compute_timeline_summary(session);
+
+    let breakdown = summary
+        .tool_counts
+        .iter()
+        .map(|(name, count)| format!("{}: {}", name, count))
+        .collect::<Vec<_>>()
+        .join(", ");
+    println!(
+        "{}: {} ({})",
+        "Tool calls".dimmed(),
+        summary.tool_calls,
+        breakdown
+    );
 
     // Errors
-    let error_status = if error_count > 0 {
-        format!("{} (check timeline for details)", error_count)
+    let error_status = if summary.errors > 0 {
+        format!("{} (check timeline for details)", summary.errors)
     } else {
         "0".to_string()
     };
     println!("{}: {}", "Errors".dimmed(), error_status);
 
     // Files touched
-    println!("{}: {}", "Files touched".dimmed(), files_touched.len());
+    println!("{}: {}", "Files touched".dimmed(), summary.files_touched);
+
+    // Momentum
+    if let Some((sparkline, bucket_minutes, edits)) = momentum_sparkline(session) {
+        println!(
+            "{}: {} {}",
+            "Momentum".dimmed(),
+            sparkline.green(),
+            format!("({}m buckets, {} edits)", bucket_minutes, edits).dimmed()
+        );
+    }
+
+    // Quality trend
+    if let Some(trend) = quality_trend(session) {
+        let label = match trend.direction {
+            TrendDirection::Improving => "improving".green(),
+            TrendDirection::Steady => "steady".normal(),
+            TrendDirection::Declining => "declining".red(),
+        };
+        let note = if trend.started_well && trend.ended_in_error_loop {
+            format!(" {}", "(started well, ended in an error loop)".dimmed())
+        } else {
+            String::new()
+        };
+        println!("{}: {}{}", "Trend".dimmed(), label, note);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TrendDirection {
+    Improving,
+    Steady,
+    Declining,
+}
+
+/// How a session's productivity moved over its duration
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct QualityTrend {
+    direction: TrendDirection,
+    started_well: bool,
+    ended_in_error_loop: bool,
+}
+
+/// Rolling productivity (edits minus errors) over the ordered messages,
+/// comparing the opening window with the closing one. Needs at least four
+/// edits or errors to say anything.
+fn quality_trend(session: &Session) -> Option<QualityTrend> {
+    let scores: Vec<i64> = session
+        .messages
+        .iter()
+        .filter_map(|message| {
+            let edits = message
+                .tool_calls
+                .iter()
+                .filter(|tc| matches!(tc.name.as_str(), "Edit" | "Write" | "NotebookEdit"))
+                .count() as i64;
+            let errors = message.tool_results.iter().filter(|tr| tr.is_error).count() as i64;
+            (edits > 0 || errors > 0).then_some(edits - errors)
+        })
+        .collect();
+
+    if scores.len() < 4 {
+        return None;
+    }
+
+    let window = (scores.len() / 4).max(2);
+    let rolling: Vec<i64> = scores.windows(window).map(|w| w.iter().sum()).collect();
+    let first = rolling[0];
+    let last = rolling[rolling.len() - 1];
+
+    // Require a shift of at least half a point per message in the window
+    let threshold = (window as i64 / 2).max(1);
+    let direction = if last - first >= threshold {
+        TrendDirection::Improving
+    } else if first - last >= threshold {
+        TrendDirection::Declining
+    } else {
+        TrendDirection::Steady
+    };
+
+    let ended_in_error_loop =
+        scores.len() >= 3 && scores[scores.len() - 3..].iter().all(|&s| s < 0);
+
+    Some(QualityTrend {
+        direction,
+        started_well: first > 0,
+        ended_in_error_loop,
+    })
+}
+
+const SPARK_CHARS: [char; 8] = [
+    '\u{2581}', '\u{2582}', '\u{2583}', '\u{2584}', '\u{2585}', '\u{2586}', '\u{2587}', '\u{2588}',
+];
+
+/// Build a sparkline of Edit/Write events binned across the session.
+/// Returns (sparkline, bucket size in minutes, edit count).
+fn momentum_sparkline(session: &Session) -> Option<(String, usize, usize)> {
+    let (start, end) = match (session.start_time, session.end_time) {
+        (Some(s), Some(e)) => (s, e),
+        _ => return None,
+    };
+    let duration_minutes = (end - start).num_minutes().max(1) as usize;
+
+    // Pick the smallest bucket that keeps the line at most 30 characters wide
+    let bucket_minutes = [1, 5, 10, 15, 30, 60, 120]
+        .into_iter()
+        .find(|b| duration_minutes.div_ceil(*b) <= 30)
+        .unwrap_or(240);
+    let bucket_count = duration_minutes.div_ceil(bucket_minutes);
+
+    let mut buckets = vec![0usize; bucket_count];
+    let mut edits = 0;
+    for message in &session.messages {
+        let Some(ts) = message.timestamp else {
+            continue;
+        };
+        for tool_call in &message.tool_calls {
+            if matches!(tool_call.name.as_str(), "Edit" | "Write" | "NotebookEdit") {
+                let idx = (ts - start).num_minutes().max(0) as usize / bucket_minutes;
+                buckets[idx.min(bucket_count - 1)] += 1;
+                edits += 1;
+            }
+        }
+    }
+
+    // Too few edits to show a rhythm - draw a flat line
+    if edits < 2 {
+        return Some((
+            SPARK_CHARS[0].to_string().repeat(bucket_count),
+            bucket_minutes,
+            edits,
+        ));
+    }
+
+    let max = *buckets.iter().max().unwrap_or(&1);
+    let sparkline = buckets
+        .iter()
+        .map(|&count| SPARK_CHARS[(count * 7).div_ceil(max).min(7)])
+        .collect();
+
+    Some((sparkline, bucket_minutes, edits))
 }
 
 /// Find a session by ID (supports partial match)
@@ -340,6 +536,8 @@ pub fn get_latest_session(sessions: &[Session]) -> Option<&Session> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::{Message, MessageType, ToolCall, ToolResult};
+    use chrono::{Duration, TimeZone};
 
     #[test]
     fn test_get_tool_icon() {
@@ -358,6 +556,27 @@ mod tests {
         assert!(short.len() <= 50 || short.contains("test.rs"));
     }
 
+    #[test]
+    fn test_format_timestamp_modes() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 13, 10, 0, 0).unwrap();
+        let ts = start + Duration::seconds(125);
+        let local = ts.with_timezone(&Local);
+
+        assert_eq!(
+            format_timestamp(&ts, TimestampFormat::Clock, Some(start)),
+            local.format("%H:%M:%S").to_string()
+        );
+
+        let full = format_timestamp(&ts, TimestampFormat::Full, Some(start));
+        assert_eq!(full, local.format("%Y-%m-%d %H:%M:%S").to_string());
+        assert!(full.starts_with(&local.format("%Y-%m-%d ").to_string()));
+
+        assert_eq!(
+            format_timestamp(&ts, TimestampFormat::Elapsed, Some(start)),
+            "+02:05"
+        );
+    }
+
     #[test]
     fn test_get_tool_description() {
         let input = serde_json::json!({"file_path": "/tmp/test.rs"});
@@ -371,6 +590,135 @@ mod tests {
         assert!(bash_desc.contains("cargo build"));
     }
 
+    fn edit_message(ts: DateTime<Utc>) -> Message {
+        Message {
+            msg_type: MessageType::Assistant,
+            timestamp: Some(ts),
+            tool_calls: vec![ToolCall {
+                id: "t1".to_string(),
+                name: "Edit".to_string(),
+                input: serde_json::json!({"file_path": "/tmp/test.rs"}),
+                parallel_group: 0,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn session_with_messages(minutes: i64, messages: Vec<Message>) -> Session {
+        let start = Utc.with_ymd_and_hms(2026, 1, 13, 10, 0, 0).unwrap();
+        Session {
+            session_id: "momentum".to_string(),
+            project: "/test".to_string(),
+            jsonl_path: std::path::PathBuf::from("/test.jsonl"),
+            git_branch: None,
+            start_time: Some(start),
+            end_time: Some(start + Duration::minutes(minutes)),
+            messages,
+            models_used: vec![],
+            excluded_windows: vec![],
+            was_compacted: false,
+            line_count: 0,
+            skipped_lines: 0,
+            git_branches: vec![],
+        }
+    }
+
+    #[test]
+    fn test_summary_json_round_trip() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 13, 10, 0, 0).unwrap();
+        let mut failed = edit_message(start + Duration::minutes(12));
+        failed.msg_type = MessageType::User;
+        failed.tool_calls.clear();
+        failed.tool_results = vec![ToolResult {
+            tool_use_id: "t1".to_string(),
+            content: "error".to_string(),
+            is_error: true,
+        }];
+        let messages = vec![
+            edit_message(start + Duration::minutes(1)),
+            edit_message(start + Duration::minutes(2)),
+            failed,
+        ];
+        let session = session_with_messages(20, messages);
+
+        let json = serde_json::to_string(&compute_timeline_summary(&session)).unwrap();
+        let summary: TimelineSummary = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(summary.session_id, "momentum");
+        assert_eq!(summary.tool_calls, 2);
+        assert_eq!(summary.tool_counts["Edit"], 2);
+        assert_eq!(summary.errors, 1);
+        assert_eq!(summary.files_touched, 1);
+        assert_eq!(summary.duration_minutes, 20.0);
+        assert_eq!(summary.longest_gap_minutes, 10.0);
+    }
+
+    #[test]
+    fn test_momentum_sparkline_multi_edit() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 13, 10, 0, 0).unwrap();
+        let messages = vec![
+            edit_message(start + Duration::minutes(1)),
+            edit_message(start + Duration::minutes(25)),
+            edit_message(start + Duration::minutes(26)),
+            edit_message(start + Duration::minutes(27)),
+        ];
+        let session = session_with_messages(30, messages);
+
+        let (sparkline, bucket_minutes, edits) = momentum_sparkline(&session).unwrap();
+
+        assert_eq!(bucket_minutes, 1);
+        assert_eq!(edits, 4);
+        assert_eq!(sparkline.chars().count(), 30);
+        assert!(sparkline.contains('\u{2588}'));
+    }
+
+    fn error_message(ts: DateTime<Utc>) -> Message {
+        Message {
+            msg_type: MessageType::User,
+            timestamp: Some(ts),
+            tool_results: vec![ToolResult {
+                tool_use_id: "t2".to_string(),
+                content: "error: command failed".to_string(),
+                is_error: true,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_quality_trend_declining_into_error_loop() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 13, 10, 0, 0).unwrap();
+        let mut messages: Vec<Message> = (0..4)
+            .map(|i| edit_message(start + Duration::minutes(i)))
+            .collect();
+        messages.extend((4..8).map(|i| error_message(start + Duration::minutes(i))));
+        let session = session_with_messages(10, messages);
+
+        let trend = quality_trend(&session).unwrap();
+
+        assert_eq!(trend.direction, TrendDirection::Declining);
+        assert!(trend.started_well);
+        assert!(trend.ended_in_error_loop);
+    }
+
+    #[test]
+    fn test_quality_trend_needs_enough_events() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 13, 10, 0, 0).unwrap();
+        let session = session_with_messages(5, vec![edit_message(start), error_message(start)]);
+        assert!(quality_trend(&session).is_none());
+    }
+
+    #[test]
+    fn test_momentum_sparkline_few_edits_is_flat() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 13, 10, 0, 0).unwrap();
+        let session = session_with_messages(120, vec![edit_message(start)]);
+
+        let (sparkline, bucket_minutes, _) = momentum_sparkline(&session).unwrap();
+
+        assert_eq!(bucket_minutes, 5);
+        assert!(sparkline.chars().all(|c| c == '\u{2581}'));
+    }
+
     #[test]
     fn test_find_session_by_id() {
         let sessions = vec![
@@ -378,19 +726,13 @@ mod tests {
                 session_id: "abc123def".to_string(),
                 project: "/test".to_string(),
                 jsonl_path: std::path::PathBuf::from("/test.jsonl"),
-                git_branch: None,
-                start_time: None,
-                end_time: None,
-                messages: vec![],
+                ..Default::default()
             },
             Session {
                 session_id: "xyz789ghi".to_string(),
                 project: "/test2".to_string(),
                 jsonl_path: std::path::PathBuf::from("/test2.jsonl"),
-                git_branch: None,
-                start_time: None,
-                end_time: None,
-                messages: vec![],
+                ..Default::default()
             },
         ];
 
@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Run the built `aist` binary with `args`, returning its stdout
+fn aist(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_aist"))
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "aist {:?} failed", args);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_cost_with_no_sessions() {
+    let stdout = aist(&[
+        "--transcripts",
+        "/nonexistent/aist-cli-tests/*.jsonl",
+        "cost",
+        "--histogram",
+    ]);
+    assert_eq!(stdout.trim(), "No sessions found.");
+}
+
+/// A fresh dir with one Claude Code transcript per project, stored under the
+/// encoded project dir names Claude uses
+fn transcripts_dir(name: &str, projects: &[&str]) -> PathBuf {
+    let root = std::env::temp_dir().join("aist-cli-tests").join(format!(
+        "{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&root);
+    for (i, project) in projects.iter().enumerate() {
+        let dir = root.join(project.replace('/', "-"));
+        std::fs::create_dir_all(&dir).unwrap();
+        let lines = [
+            format!(
+                r#"{{"type":"user","timestamp":"2026-01-13T10:00:00Z","sessionId":"s{i}","cwd":"{project}","message":{{"role":"user","content":"fix it"}}}}"#
+            ),
+            format!(
+                r#"{{"type":"assistant","timestamp":"2026-01-13T10:05:00Z","sessionId":"s{i}","cwd":"{project}","message":{{"id":"m{i}","content":[{{"type":"text","text":"done"}}]}}}}"#
+            ),
+        ];
+        std::fs::write(dir.join(format!("s{}.jsonl", i)), lines.join("\n")).unwrap();
+    }
+    root
+}
+
+#[test]
+fn test_report_project_filter_narrows_sessions() {
+    let root = transcripts_dir("report-project", &["/w/app", "/w/other"]);
+    let glob = format!("{}/*/*.jsonl", root.display());
+    let session_count = |extra: &[&str]| {
+        let mut args = vec!["--transcripts", &glob, "report", "-p", "all", "-f", "json"];
+        args.extend_from_slice(extra);
+        let report: serde_json::Value = serde_json::from_str(&aist(&args)).unwrap();
+        report["session_count"].as_u64().unwrap()
+    };
+
+    assert_eq!(session_count(&[]), 2);
+    assert_eq!(session_count(&["--project", "/w/app"]), 1);
+}